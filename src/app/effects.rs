@@ -0,0 +1,145 @@
+//! Side effects requested by the reducer and their executor
+
+use super::App;
+use crate::config::Config;
+use crate::history::History;
+use crate::scheduler::Schedule;
+use crate::worker::Command;
+use std::path::{Path, PathBuf};
+use tokio::sync::mpsc::UnboundedSender;
+
+/// Something the reducer wants done outside of the UI state
+#[derive(Clone, Debug, PartialEq)]
+pub enum Effect {
+    /// Run a backend command on the worker
+    Command(Command),
+    /// Open a file or folder with the system handler
+    Open { path: PathBuf, what: &'static str },
+    /// Copy text to the clipboard
+    Copy { label: String, text: String },
+    /// Write the schedule file
+    SaveSchedule(String),
+    /// Write the download history file
+    SaveHistory(String),
+    /// Write statistics CSV files, given as (file name, content)
+    ExportStats(Vec<(&'static str, String)>),
+    /// Write the download list as CSV
+    ExportCsv { path: PathBuf, content: String },
+}
+
+/// The outside world as seen by the executor
+pub trait Backend {
+    /// Start a backend command; its result arrives later as a `WorkerEvent`
+    fn send(&mut self, command: Command);
+    fn open(&mut self, path: &Path) -> Result<(), String>;
+    fn copy(&mut self, text: &str) -> Result<(), String>;
+    fn save_schedule(&mut self, content: &str) -> Result<(), String>;
+    fn save_history(&mut self, content: &str) -> Result<(), String>;
+    /// Write the files into a new export directory, returning its path
+    fn export_stats(&mut self, files: &[(&'static str, String)]) -> Result<PathBuf, String>;
+    fn export_file(&mut self, path: &Path, content: &str) -> Result<(), String>;
+}
+
+/// Run effects, recording their immediate outcome in the app state
+pub fn execute(app: &mut App, effects: Vec<Effect>, backend: &mut dyn Backend) {
+    for effect in effects {
+        match effect {
+            Effect::Command(command) => {
+                app.busy_gids
+                    .extend(command.gids().into_iter().map(String::from));
+                backend.send(command);
+            }
+            Effect::Open { path, what } => {
+                if let Err(e) = backend.open(&path) {
+                    app.set_status(format!("Failed to open {}: {}", what, e));
+                }
+            }
+            Effect::Copy { label, text } => match backend.copy(&text) {
+                Ok(()) => app.set_status(format!("{} copied to clipboard", label)),
+                // Without a clipboard, show the text so it can be copied by hand
+                Err(_) => app.set_status(format!("{}: {}", label, text)),
+            },
+            Effect::SaveSchedule(content) => {
+                if let Err(e) = backend.save_schedule(&content) {
+                    app.set_status(format!("Failed to save schedule: {}", e));
+                }
+            }
+            Effect::SaveHistory(content) => {
+                if let Err(e) = backend.save_history(&content) {
+                    app.set_status(format!("Failed to save history: {}", e));
+                }
+            }
+            Effect::ExportStats(files) => match backend.export_stats(&files) {
+                Ok(dir) => app.set_status(format!("Statistics exported to {}", dir.display())),
+                Err(e) => app.set_status(format!("Failed to export statistics: {}", e)),
+            },
+            Effect::ExportCsv { path, content } => match backend.export_file(&path, &content) {
+                Ok(()) => app.set_status(format!("Downloads exported to {}", path.display())),
+                Err(e) => app.set_status(format!("Failed to export downloads: {}", e)),
+            },
+        }
+    }
+}
+
+/// Backend used by the application: the worker channel plus the desktop
+pub struct SystemBackend {
+    commands: UnboundedSender<Command>,
+}
+
+impl SystemBackend {
+    pub fn new(commands: UnboundedSender<Command>) -> Self {
+        Self { commands }
+    }
+}
+
+impl Backend for SystemBackend {
+    fn send(&mut self, command: Command) {
+        // The worker only stops when the runtime shuts down
+        let _ = self.commands.send(command);
+    }
+
+    fn open(&mut self, path: &Path) -> Result<(), String> {
+        open::that(path).map_err(|e| e.to_string())
+    }
+
+    #[cfg(feature = "clipboard")]
+    fn copy(&mut self, text: &str) -> Result<(), String> {
+        arboard::Clipboard::new()
+            .and_then(|mut ctx| ctx.set_text(text.to_string()))
+            .map_err(|e| e.to_string())
+    }
+
+    #[cfg(not(feature = "clipboard"))]
+    fn copy(&mut self, _text: &str) -> Result<(), String> {
+        Err("clipboard support not enabled".to_string())
+    }
+
+    fn save_schedule(&mut self, content: &str) -> Result<(), String> {
+        write_file(&Schedule::path(), content)
+    }
+
+    fn save_history(&mut self, content: &str) -> Result<(), String> {
+        write_file(&History::path(), content)
+    }
+
+    fn export_stats(&mut self, files: &[(&'static str, String)]) -> Result<PathBuf, String> {
+        let stamp = chrono::Local::now().format("%Y%m%d-%H%M%S");
+        let dir = Config::data_dir().join(format!("stats-{}", stamp));
+        for (name, content) in files {
+            write_file(&dir.join(name), content)?;
+        }
+        Ok(dir)
+    }
+
+    fn export_file(&mut self, path: &Path, content: &str) -> Result<(), String> {
+        write_file(path, content)
+    }
+}
+
+/// Write a file the app keeps, creating its directory first
+fn write_file(path: &Path, content: &str) -> Result<(), String> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    }
+    std::fs::write(path, content).map_err(|e| e.to_string())
+}
@@ -0,0 +1,2655 @@
+//! Application state machine
+//!
+//! [`App`] owns all UI state. Input is turned into a [`KeyAction`] by the
+//! [`InputHandler`] and applied by [`App::update`], which only mutates state
+//! and returns the side effects to perform. Effects are run separately by
+//! [`effects::execute`] against a [`effects::Backend`], so the reducer can be tested
+//! without aria2 or a terminal.
+//!
+//! - `effects.rs` - Effect types, the `Backend` trait and the effect executor
+
+pub mod effects;
+
+pub use effects::{execute, Effect, SystemBackend};
+
+use crate::bandwidth::Bandwidth;
+use crate::events::DownloadEvent;
+use crate::history::{visible_history, History, HistoryEntry};
+use crate::hooks::HookOutcome;
+use crate::input::{InputHandler, KeyAction};
+use crate::models::{
+    AppSettings, BatchAction, ConfirmAction, Download, DownloadState, InputMode, LogEntry,
+};
+use crate::palette::{self, PaletteCommand};
+use crate::scheduler::{self, ScheduleRule, Scheduler};
+use crate::server::browser::{BrowserDownload, BrowserEvent};
+use crate::stats::{Resolution, Stats};
+use crate::ui::theme::{Theme, Themes};
+use crate::ui::widgets::ContextMenu;
+use crate::ui::{
+    filter_by_search, render_app_full, render_popup, render_size_warning, visible_downloads,
+    AppState, HitAreas, LimitScope, PopupType, ScheduleEdit, ScheduleState, SortOrder,
+    SpeedLimitState, COMPLETED_TAB, HISTORY_TAB,
+};
+use crate::usage::{QuotaAlert, QuotaUse};
+use crate::worker::{Command, WorkerEvent};
+use chrono::NaiveDateTime;
+use crossterm::event::{KeyModifiers, MouseButton, MouseEvent, MouseEventKind};
+use ratatui::widgets::ListState;
+use ratatui::Frame;
+use std::collections::VecDeque;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+// Minimum terminal size requirements
+pub const MIN_WIDTH: u16 = 60;
+pub const MIN_HEIGHT: u16 = 15;
+
+/// How long a status message stays visible
+const STATUS_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Longest gap between the clicks of a double-click
+const DOUBLE_CLICK: Duration = Duration::from_millis(400);
+
+/// Number of entries kept in the log view
+const LOG_CAPACITY: usize = 500;
+
+/// Complete UI state
+pub struct App {
+    pub downloads: Vec<Download>, // Latest snapshot from the download manager
+    pub cursor: Option<String>,   // GID of the download under the cursor
+    pub list_state: ListState,    // Row of the cursor, kept in sync with `cursor`
+    pub current_tab: usize,
+    pub input: InputHandler,
+    pub status_message: String,
+    status_expires: Option<Instant>,
+    pub sort_order: SortOrder,
+    pub sort_ascending: bool,
+    pub help_scroll: usize,
+    pub speed_limit_state: SpeedLimitState,
+    pub download_limit: u64,
+    pub upload_limit: u64,
+    pub selected_gids: Vec<String>, // Downloads marked for batch operations
+    pub pending_confirm: Option<ConfirmAction>,
+    pub busy_gids: Vec<String>, // Downloads with a backend command in flight
+    pub hook_failed_gids: Vec<String>, // Downloads whose hook command failed
+    pub log: Vec<LogEntry>,     // Oldest first, at most LOG_CAPACITY entries
+    pub log_scroll: usize,
+    pub scheduler: Scheduler,
+    pub schedule_state: ScheduleState,
+    pub schedule_note: String, // Next scheduled change, empty if nothing is planned
+    pub clock: NaiveDateTime,  // Local time of the last schedule check
+    pub bandwidth: Bandwidth,
+    pub profile_note: String, // Active speed profile and its next change
+    pub quota: Vec<QuotaUse>, // Data-cap quotas and how much of each is used
+    pub history: History,     // Finished and failed downloads, shown in the History tab
+    pub stats: Stats,         // Long-term statistics, refreshed while the dashboard is open
+    pub stats_resolution: Resolution, // Bucket size of the dashboard's throughput chart
+    pub browser_queue: VecDeque<BrowserDownload>, // Browser downloads awaiting confirmation
+    pub settings: AppSettings,
+    pub menu: Option<ContextMenu>, // Context menu of a right-clicked download
+    pub hits: HitAreas,            // Clickable areas of the last frame
+    pub last_click: Option<(Instant, usize)>, // Time and row of the last left click
+    pub details_open: bool,        // Details overlay shown over the list on narrow terminals
+    pub themes: Themes,            // Built-in and user themes to switch between
+    pub command_error: Option<String>, // Why the last command line failed
+    pub command_hint: String,      // Completions offered by Tab
+    pub tick: usize,
+    pub too_small: bool, // Terminal is below the minimum size
+    pub should_quit: bool,
+}
+
+impl Default for App {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl App {
+    pub fn new() -> Self {
+        let mut list_state = ListState::default();
+        list_state.select(Some(0));
+
+        Self {
+            downloads: Vec::new(),
+            cursor: None,
+            list_state,
+            current_tab: 0,
+            input: InputHandler::new(),
+            status_message: String::new(),
+            status_expires: None,
+            sort_order: SortOrder::Name,
+            sort_ascending: true,
+            help_scroll: 0,
+            speed_limit_state: SpeedLimitState::default(),
+            download_limit: 0,
+            upload_limit: 0,
+            selected_gids: Vec::new(),
+            pending_confirm: None,
+            busy_gids: Vec::new(),
+            hook_failed_gids: Vec::new(),
+            log: Vec::new(),
+            log_scroll: 0,
+            scheduler: Scheduler::default(),
+            schedule_state: ScheduleState::default(),
+            schedule_note: String::new(),
+            clock: chrono::Local::now().naive_local(),
+            bandwidth: Bandwidth::default(),
+            profile_note: String::new(),
+            quota: Vec::new(),
+            history: History::default(),
+            stats: Stats::default(),
+            stats_resolution: Resolution::default(),
+            browser_queue: VecDeque::new(),
+            settings: AppSettings::default(),
+            menu: None,
+            hits: HitAreas::default(),
+            last_click: None,
+            details_open: false,
+            themes: Themes::default(),
+            command_error: None,
+            command_hint: String::new(),
+            tick: 0,
+            too_small: false,
+            should_quit: false,
+        }
+    }
+
+    /// Draw with the theme called `name`; false if there is no such theme
+    pub fn set_theme(&mut self, name: &str) -> bool {
+        match self.themes.get(name) {
+            Some(theme) => {
+                Theme::set(theme);
+                self.settings.theme = name.to_string();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Show a message in the status bar for a few seconds
+    pub fn set_status(&mut self, message: impl Into<String>) {
+        self.status_message = message.into();
+        self.status_expires = Some(Instant::now() + STATUS_TIMEOUT);
+    }
+
+    /// Append a message to the log view
+    pub fn push_log(&mut self, message: impl Into<String>, is_error: bool) {
+        if self.log.len() >= LOG_CAPACITY {
+            self.log.remove(0);
+        }
+        self.log.push(LogEntry {
+            time: clock_time(),
+            message: message.into(),
+            is_error,
+        });
+    }
+
+    /// Advance timers: expire the status message and surface queued confirmations
+    pub fn on_tick(&mut self, now: Instant) {
+        self.tick = self.tick.wrapping_add(1);
+
+        if self.status_expires.is_some_and(|at| now >= at) {
+            self.status_message.clear();
+            self.status_expires = None;
+        }
+
+        if self.pending_confirm.is_none() && self.input.mode == InputMode::Normal {
+            if let Some(download) = self.browser_queue.pop_front() {
+                self.confirm(ConfirmAction::AddFromBrowser(download));
+            }
+        }
+    }
+
+    /// Handle a key press, returning the effects to execute
+    pub fn handle_key(&mut self, key: &crossterm::event::KeyEvent) -> Vec<Effect> {
+        // Only allow quitting while the terminal is too small
+        if self.too_small {
+            if self.input.keymap.action(InputMode::Normal, key) == Some(KeyAction::Quit) {
+                self.should_quit = true;
+            }
+            return Vec::new();
+        }
+
+        let action = self.input.handle_key(key);
+        self.update(action)
+    }
+
+    /// Handle a mouse event, returning the effects to execute
+    ///
+    /// Clicks are matched against the areas of the last frame.
+    pub fn handle_mouse(&mut self, event: &MouseEvent, now: Instant) -> Vec<Effect> {
+        if self.too_small {
+            return Vec::new();
+        }
+        let (x, y) = (event.column, event.row);
+        let action = match (self.input.mode, event.kind) {
+            (InputMode::Normal, MouseEventKind::Down(MouseButton::Left)) => {
+                return self.click(x, y, event.modifiers, now);
+            }
+            (InputMode::Normal, MouseEventKind::Down(MouseButton::Right)) => {
+                self.open_menu(x, y);
+                return Vec::new();
+            }
+            (InputMode::Normal, MouseEventKind::ScrollUp) if self.hits.in_list(x, y) => {
+                KeyAction::MoveUp
+            }
+            (InputMode::Normal, MouseEventKind::ScrollDown) if self.hits.in_list(x, y) => {
+                KeyAction::MoveDown
+            }
+            (InputMode::Help, MouseEventKind::ScrollUp) => KeyAction::HelpScrollUp,
+            (InputMode::Help, MouseEventKind::ScrollDown) => KeyAction::HelpScrollDown,
+            (InputMode::Log, MouseEventKind::ScrollUp) => KeyAction::LogScrollUp,
+            (InputMode::Log, MouseEventKind::ScrollDown) => KeyAction::LogScrollDown,
+            (InputMode::Confirmation, MouseEventKind::Down(MouseButton::Left)) => {
+                match self.hits.button_at(x, y) {
+                    Some(true) => KeyAction::ConfirmYes,
+                    Some(false) => KeyAction::ConfirmNo,
+                    None => KeyAction::None,
+                }
+            }
+            (InputMode::Menu, MouseEventKind::Down(MouseButton::Left)) => {
+                match (self.hits.menu_item_at(x, y), &mut self.menu) {
+                    (Some(item), Some(menu)) => {
+                        menu.selected = item;
+                        KeyAction::MenuSelect
+                    }
+                    _ => KeyAction::MenuClose,
+                }
+            }
+            (InputMode::Menu, MouseEventKind::Down(MouseButton::Right)) => {
+                self.update(KeyAction::MenuClose);
+                self.open_menu(x, y);
+                return Vec::new();
+            }
+            _ => KeyAction::None,
+        };
+        self.update(action)
+    }
+
+    /// Left click in normal mode: switch tabs, move the cursor, select with
+    /// Ctrl (one row) or Shift (a range), open the file on a double-click
+    fn click(&mut self, x: u16, y: u16, modifiers: KeyModifiers, now: Instant) -> Vec<Effect> {
+        if let Some(tab) = self.hits.tab_at(x, y) {
+            return self.update(KeyAction::SelectTab(tab));
+        }
+        let Some(row) = self.hits.row_at(x, y) else {
+            return Vec::new();
+        };
+        self.sync_cursor();
+
+        let double = self
+            .last_click
+            .is_some_and(|(at, last)| last == row && now.duration_since(at) <= DOUBLE_CLICK);
+        self.last_click = (!double).then_some((now, row));
+
+        if modifiers.contains(KeyModifiers::CONTROL) {
+            self.move_cursor(row);
+            return self.update(KeyAction::ToggleSelect);
+        }
+        if modifiers.contains(KeyModifiers::SHIFT) {
+            let from = self.list_state.selected().unwrap_or(row);
+            let range = from.min(row)..=from.max(row);
+            let gids = gids_of(
+                self.visible()
+                    .get(range)
+                    .unwrap_or_default()
+                    .iter()
+                    .copied(),
+            );
+            for gid in gids {
+                if !self.selected_gids.contains(&gid) {
+                    self.selected_gids.push(gid);
+                }
+            }
+            self.move_cursor(row);
+            return Vec::new();
+        }
+
+        self.move_cursor(row);
+        if double {
+            return self.update(KeyAction::OpenFile);
+        }
+        Vec::new()
+    }
+
+    /// Right click: move the cursor to the row and open its context menu
+    fn open_menu(&mut self, x: u16, y: u16) {
+        let Some(row) = self.hits.row_at(x, y) else {
+            return;
+        };
+        self.sync_cursor();
+        self.move_cursor(row);
+        if self.current_tab == HISTORY_TAB {
+            return;
+        }
+        let Some(download) = self.selected_download() else {
+            return;
+        };
+        let pause = if download.status == DownloadState::Paused {
+            "Resume"
+        } else {
+            "Pause"
+        };
+
+        // A click outside the selection acts on that download alone
+        if self
+            .cursor
+            .as_ref()
+            .is_some_and(|gid| !self.selected_gids.contains(gid))
+        {
+            self.selected_gids.clear();
+        }
+        self.menu = Some(ContextMenu {
+            x,
+            y,
+            items: vec![
+                (pause.to_string(), KeyAction::PauseResume),
+                ("Delete".to_string(), KeyAction::Delete),
+                ("Open folder".to_string(), KeyAction::OpenFolder),
+            ],
+            selected: 0,
+        });
+        self.input.enter_menu_mode();
+    }
+
+    /// Handle pasted text
+    pub fn handle_paste(&mut self, data: &str) {
+        self.input.handle_paste(data);
+    }
+
+    /// Apply the result of a backend command
+    pub fn handle_worker_event(&mut self, event: WorkerEvent) {
+        match event {
+            WorkerEvent::Done { gids, message } => {
+                for gid in gids {
+                    if let Some(pos) = self.busy_gids.iter().position(|g| *g == gid) {
+                        self.busy_gids.remove(pos);
+                    }
+                }
+                if let Some(message) = message {
+                    self.set_status(message);
+                }
+            }
+            WorkerEvent::SpeedLimits(dl, ul) => {
+                self.download_limit = dl;
+                self.upload_limit = ul;
+                if self.input.mode == InputMode::SpeedLimit {
+                    self.speed_limit_state.download_limit = dl;
+                    self.speed_limit_state.upload_limit = ul;
+                }
+            }
+        }
+    }
+
+    /// Report download lifecycle events in the status bar
+    ///
+    /// Finished downloads are added to the history, which is then saved.
+    pub fn handle_download_event(&mut self, event: DownloadEvent) -> Vec<Effect> {
+        let name = event.download().map(|d| d.name.clone()).unwrap_or_default();
+        let mut effects = Vec::new();
+        match event {
+            DownloadEvent::Completed(download) => {
+                self.set_status(format!("Completed: {}", name));
+                effects.extend(self.record_history(&download));
+            }
+            DownloadEvent::Failed(download) => {
+                // Only published once the automatic retries have run out
+                effects.extend(self.record_history(&download));
+                self.set_status(match &download.error_message {
+                    Some(e) => format!("Failed: {}: {}", name, e),
+                    None => format!("Failed: {}", name),
+                });
+            }
+            DownloadEvent::Stalled(_) => {
+                let message = format!("Stalled: {} (no data for a while)", name);
+                self.push_log(message.clone(), true);
+                self.set_status(message);
+            }
+            DownloadEvent::BackendDown => self.set_status("Lost connection to aria2c"),
+            DownloadEvent::BackendUp => self.set_status("Reconnected to aria2c"),
+            DownloadEvent::Quota(alert) => {
+                let message = alert.to_string();
+                self.push_log(message.clone(), matches!(alert, QuotaAlert::Reached(_)));
+                self.set_status(message);
+            }
+            _ => {}
+        }
+        effects
+    }
+
+    /// Add a finished download to the history
+    fn record_history(&mut self, download: &Download) -> Option<Effect> {
+        let entry = HistoryEntry::from_download(download, chrono::Local::now());
+        self.history
+            .record(entry)
+            .then(|| Effect::SaveHistory(self.history.to_json()))
+    }
+
+    /// Log the outcome of a hook command and flag failures on the download's row
+    pub fn handle_hook_outcome(&mut self, outcome: HookOutcome) {
+        let mut message = format!(
+            "hook {} for {}: {}",
+            outcome.program, outcome.name, outcome.status
+        );
+        if !outcome.output.is_empty() {
+            message.push('\n');
+            message.push_str(&outcome.output);
+        }
+        self.push_log(message, !outcome.success);
+
+        if !outcome.success {
+            self.set_status(format!(
+                "Hook {} failed for {}: {} (press e for the log)",
+                outcome.program, outcome.name, outcome.status
+            ));
+            if let Some(gid) = outcome.gid {
+                if !self.hook_failed_gids.contains(&gid) {
+                    self.hook_failed_gids.push(gid);
+                }
+            }
+        }
+    }
+
+    /// Run scheduled actions that are due at `now` (local time)
+    pub fn check_schedule(&mut self, now: NaiveDateTime) -> Vec<Effect> {
+        self.clock = now;
+        let commands = self.scheduler.check(now);
+        for command in &commands {
+            let message = match command {
+                Command::PauseAll => "Schedule: pausing all downloads".to_string(),
+                Command::ResumeAll => "Schedule: resuming all downloads".to_string(),
+                Command::Resume(gid) => format!("Schedule: starting {}", self.name_of(gid)),
+                Command::Pause(gid) => format!("Schedule: pausing {}", self.name_of(gid)),
+                _ => continue,
+            };
+            self.push_log(message, false);
+        }
+        self.schedule_note = self.scheduler.next_change(now).unwrap_or_default();
+
+        let mut effects: Vec<Effect> = commands.into_iter().map(Effect::Command).collect();
+        effects.extend(self.apply_bandwidth());
+        effects
+    }
+
+    /// Switch speed-limit profiles when the schedule or turtle mode asks for it
+    fn apply_bandwidth(&mut self) -> Option<Effect> {
+        let current = (self.download_limit, self.upload_limit);
+        let change = self.bandwidth.check(self.clock, current);
+        if change.is_some() {
+            let message = match self.bandwidth.active() {
+                Some(profile) => format!("Speed profile: {}", profile.describe()),
+                None => "Speed profile: back to manual limits".to_string(),
+            };
+            self.push_log(message, false);
+        }
+
+        self.profile_note = match self.bandwidth.active() {
+            Some(profile) => match self.bandwidth.next_change(self.clock) {
+                Some((at, next)) => format!(
+                    "{}, {} {}",
+                    profile.name,
+                    next,
+                    scheduler::format_moment(at, self.clock)
+                ),
+                None => profile.name.clone(),
+            },
+            None => String::new(),
+        };
+
+        let (download, upload) = change?;
+        self.download_limit = download;
+        self.upload_limit = upload;
+        Some(Effect::Command(Command::SetSpeedLimits {
+            download,
+            upload,
+        }))
+    }
+
+    /// Apply an event from the browser endpoint
+    pub fn handle_browser_event(&mut self, event: BrowserEvent) {
+        match event {
+            BrowserEvent::Added(what) => self.set_status(format!("Browser: added {}", what)),
+            BrowserEvent::Failed(e) => self.set_status(format!("Browser: failed to add: {}", e)),
+            BrowserEvent::Pending(download) => {
+                self.set_status(format!(
+                    "Browser: {} awaiting confirmation",
+                    download.describe()
+                ));
+                self.browser_queue.push_back(download);
+            }
+        }
+    }
+
+    /// Apply a key action to the state, returning the effects to execute
+    pub fn update(&mut self, action: KeyAction) -> Vec<Effect> {
+        let mut effects = Vec::new();
+        self.sync_cursor();
+
+        match action {
+            // ============ Normal Mode Actions ============
+            KeyAction::EnterEditMode => {
+                self.input.enter_edit_mode();
+            }
+            KeyAction::Quit => {
+                self.confirm(ConfirmAction::Quit);
+            }
+            KeyAction::SelectTab(tab) => {
+                self.current_tab = tab;
+                self.cursor = None;
+                self.list_state.select(Some(0));
+                self.selected_gids.clear();
+            }
+            KeyAction::MoveUp => {
+                let i = self.list_state.selected().unwrap_or(0);
+                if i > 0 {
+                    self.move_cursor(i - 1);
+                }
+            }
+            KeyAction::MoveDown => {
+                let i = self.list_state.selected().unwrap_or(0);
+                if i < self.visible_count().saturating_sub(1) {
+                    self.move_cursor(i + 1);
+                }
+            }
+            KeyAction::MoveToTop => {
+                self.move_cursor(0);
+            }
+            KeyAction::MoveToBottom => {
+                let count = self.visible_count();
+                if count > 0 {
+                    self.move_cursor(count - 1);
+                }
+            }
+            KeyAction::PageUp => {
+                let i = self.list_state.selected().unwrap_or(0);
+                self.move_cursor(i.saturating_sub(10));
+            }
+            KeyAction::PageDown => {
+                let i = self.list_state.selected().unwrap_or(0);
+                let new_i = (i + 10).min(self.visible_count().saturating_sub(1));
+                self.move_cursor(new_i);
+            }
+            KeyAction::ToggleDetails => {
+                self.details_open = !self.details_open;
+            }
+
+            // ============ Search Actions ============
+            KeyAction::EnterSearchMode => {
+                self.input.enter_search_mode();
+            }
+            KeyAction::ClearSearch => {
+                self.input.clear_search();
+            }
+            KeyAction::SearchSubmit | KeyAction::SearchCancel => {
+                self.input.exit_to_normal();
+            }
+
+            // ============ Help Actions ============
+            KeyAction::ShowHelp => {
+                self.input.enter_help_mode();
+                self.help_scroll = 0;
+            }
+            KeyAction::HelpClose => {
+                self.input.exit_to_normal();
+            }
+            KeyAction::HelpScrollUp => {
+                self.help_scroll = self.help_scroll.saturating_sub(1);
+            }
+            KeyAction::HelpScrollDown => {
+                self.help_scroll += 1;
+            }
+
+            // ============ Command Line Actions ============
+            KeyAction::EnterCommandMode => {
+                self.input.enter_command_mode("");
+                self.command_error = None;
+                self.command_hint.clear();
+            }
+            KeyAction::CommandEdited => {
+                self.command_error = None;
+                self.command_hint.clear();
+            }
+            KeyAction::CommandComplete => {
+                let themes: Vec<&str> = self.themes.names().collect();
+                let completion = palette::complete(&self.input.command_buffer, &themes);
+                self.input.command_buffer = completion.line;
+                self.command_hint = completion.candidates.join("  ");
+                self.command_error = None;
+            }
+            KeyAction::CommandCancel => {
+                self.input.exit_to_normal();
+            }
+            KeyAction::CommandSubmit => {
+                let line = self.input.command_buffer.trim().to_string();
+                self.input.push_history(&line);
+                self.input.exit_to_normal();
+                if !line.is_empty() {
+                    match PaletteCommand::parse(&line).and_then(|c| self.run_command(c)) {
+                        Ok(more) => effects.extend(more),
+                        // Keep the line so it can be fixed
+                        Err(e) => {
+                            self.input.enter_command_mode(&line);
+                            self.command_error = Some(e);
+                        }
+                    }
+                }
+            }
+
+            // ============ Log Actions ============
+            KeyAction::ShowLog => {
+                self.input.enter_log_mode();
+                self.log_scroll = 0;
+            }
+            KeyAction::LogClose => {
+                self.input.exit_to_normal();
+            }
+            KeyAction::LogScrollUp => {
+                self.log_scroll += 1;
+            }
+            KeyAction::LogScrollDown => {
+                self.log_scroll = self.log_scroll.saturating_sub(1);
+            }
+
+            // ============ Statistics Actions ============
+            KeyAction::ShowStats => {
+                self.input.enter_stats_mode();
+            }
+            KeyAction::StatsClose => {
+                self.input.exit_to_normal();
+            }
+            KeyAction::StatsCycleResolution => {
+                self.stats_resolution = self.stats_resolution.next();
+            }
+            KeyAction::StatsExport => {
+                effects.push(Effect::ExportStats(self.stats.csv_files()));
+            }
+
+            // ============ Schedule Actions ============
+            KeyAction::ShowSchedule => {
+                self.schedule_state = ScheduleState::default();
+                self.input.enter_schedule_mode();
+            }
+            KeyAction::ScheduleClose => {
+                self.input.exit_to_normal();
+            }
+            KeyAction::ScheduleUp => {
+                let rows = self.schedule_rows();
+                self.schedule_state.move_selection(-1, rows);
+            }
+            KeyAction::ScheduleDown => {
+                let rows = self.schedule_rows();
+                self.schedule_state.move_selection(1, rows);
+            }
+            KeyAction::ScheduleAdd => {
+                self.edit_schedule(ScheduleEdit::Rule(None), String::new());
+            }
+            KeyAction::ScheduleEdit => {
+                let index = self.schedule_state.selected;
+                match self.scheduler.schedule.rules.get(index) {
+                    Some(rule) => {
+                        let text = rule.to_string();
+                        self.edit_schedule(ScheduleEdit::Rule(Some(index)), text);
+                    }
+                    None => {
+                        self.schedule_state.error =
+                            Some("Only rules can be edited; use s or p to change a timer".into());
+                    }
+                }
+            }
+            KeyAction::ScheduleDelete => {
+                let index = self.schedule_state.selected;
+                let schedule = &mut self.scheduler.schedule;
+                if index < schedule.rules.len() {
+                    let rule = schedule.rules.remove(index);
+                    self.scheduler.rules_changed();
+                    effects.push(Effect::SaveSchedule(self.scheduler.schedule.to_toml()));
+                    self.push_log(format!("Schedule: removed rule {}", rule), false);
+                } else if index - schedule.rules.len() < schedule.timers.len() {
+                    schedule.timers.remove(index - schedule.rules.len());
+                }
+                let rows = self.schedule_rows();
+                self.schedule_state.move_selection(0, rows);
+            }
+            KeyAction::ScheduleStartAt | KeyAction::ScheduleStopAt => {
+                let gids = gids_of(self.copy_targets());
+                if gids.is_empty() {
+                    self.schedule_state.error = Some("No download selected".into());
+                } else if matches!(action, KeyAction::ScheduleStartAt) {
+                    self.edit_schedule(ScheduleEdit::StartAt(gids), String::new());
+                } else {
+                    self.edit_schedule(ScheduleEdit::StopAt(gids), String::new());
+                }
+            }
+            KeyAction::ScheduleSubmit => {
+                effects.extend(self.submit_schedule_edit());
+            }
+            KeyAction::ScheduleCancel => {
+                self.schedule_state.editing = None;
+                self.schedule_state.error = None;
+                self.input.enter_schedule_mode();
+            }
+
+            // ============ Appearance ============
+            KeyAction::CycleTheme => {
+                let next = self.themes.next(&self.settings.theme).to_string();
+                self.set_theme(&next);
+                self.set_status(format!("Theme: {}", next));
+            }
+
+            // ============ Speed Limit Actions ============
+            KeyAction::ToggleTurtle => {
+                self.bandwidth.turtle = !self.bandwidth.turtle;
+                effects.extend(self.apply_bandwidth());
+                match self.bandwidth.active().filter(|_| self.bandwidth.turtle) {
+                    Some(profile) => {
+                        self.set_status(format!("Turtle mode on: {}", profile.describe()))
+                    }
+                    None => self.set_status("Turtle mode off"),
+                }
+            }
+            KeyAction::ShowSpeedLimit => {
+                // Show the last known limits until aria2 answers
+                let targets = self.copy_targets();
+                let label = match targets.as_slice() {
+                    [download] => download.name.clone(),
+                    _ => format!("{} downloads", targets.len()),
+                };
+                let caps = targets.first().map_or((0, 0), |d| {
+                    (d.options.download_limit, d.options.upload_limit)
+                });
+                let gids = targets.iter().filter_map(|d| d.gid.clone()).collect();
+                self.speed_limit_state =
+                    SpeedLimitState::new(self.download_limit, self.upload_limit)
+                        .with_targets(gids, label, caps);
+                effects.push(Effect::Command(Command::FetchSpeedLimits));
+                self.input.enter_speed_limit_mode();
+            }
+            KeyAction::SpeedLimitConfirm
+                if self.speed_limit_state.scope == LimitScope::Selected =>
+            {
+                let state = &self.speed_limit_state;
+                effects.push(Effect::Command(Command::SetDownloadLimits {
+                    gids: state.targets.clone(),
+                    download: state.download_cap,
+                    upload: state.upload_cap,
+                }));
+                self.input.exit_to_normal();
+            }
+            KeyAction::SpeedLimitConfirm => {
+                self.download_limit = self.speed_limit_state.download_limit;
+                self.upload_limit = self.speed_limit_state.upload_limit;
+                effects.push(Effect::Command(Command::SetSpeedLimits {
+                    download: self.download_limit,
+                    upload: self.upload_limit,
+                }));
+                self.input.exit_to_normal();
+            }
+            KeyAction::SpeedLimitCancel => {
+                self.input.exit_to_normal();
+            }
+            KeyAction::SpeedLimitToggleField => {
+                self.speed_limit_state.toggle_field();
+            }
+            KeyAction::SpeedLimitToggleScope => {
+                self.speed_limit_state.toggle_scope();
+            }
+            KeyAction::SpeedLimitIncrease => {
+                self.speed_limit_state.increase_limit();
+            }
+            KeyAction::SpeedLimitDecrease => {
+                self.speed_limit_state.decrease_limit();
+            }
+
+            // ============ Sorting Actions ============
+            KeyAction::CycleSort => {
+                self.sort_order = self.sort_order.next();
+                self.set_status(format!("Sort by: {}", self.sort_order.as_str()));
+            }
+            KeyAction::ToggleSortDirection => {
+                self.sort_ascending = !self.sort_ascending;
+                let dir = if self.sort_ascending {
+                    "Ascending"
+                } else {
+                    "Descending"
+                };
+                self.set_status(format!("Sort direction: {}", dir));
+            }
+
+            // ============ Input Editing ============
+            KeyAction::SubmitInput => {
+                if !self.input.get_input().is_empty() {
+                    let url = self.input.take_input();
+                    effects.push(Effect::Command(Command::Add(url)));
+                }
+                self.input.exit_edit_mode();
+            }
+            KeyAction::CancelInput => {
+                self.input.exit_edit_mode();
+            }
+            KeyAction::ClearAll => {
+                self.input.buffer.clear();
+            }
+            KeyAction::DeleteChar
+            | KeyAction::DeleteWord
+            | KeyAction::MoveCursorLeft
+            | KeyAction::MoveCursorRight
+            | KeyAction::MoveCursorStart
+            | KeyAction::MoveCursorEnd
+            | KeyAction::SearchDeleteChar => {
+                // Already handled in input handler
+            }
+
+            // ============ Download Management ============
+            KeyAction::PauseResume => {
+                if !self.selected_gids.is_empty() {
+                    // Resume if everything is paused, otherwise pause the rest
+                    let targets = self.selected_downloads();
+                    let effect = if targets.iter().all(|d| d.status == DownloadState::Paused) {
+                        batch_effect(BatchAction::Resume, gids_of(targets))
+                    } else {
+                        let running = targets
+                            .into_iter()
+                            .filter(|d| d.status != DownloadState::Paused);
+                        batch_effect(BatchAction::Pause, gids_of(running))
+                    };
+                    effects.extend(effect);
+                } else if let Some((gid, download)) = self.selected_with_gid() {
+                    let command = if download.status == DownloadState::Paused {
+                        Command::Resume(gid)
+                    } else {
+                        Command::Pause(gid)
+                    };
+                    effects.push(Effect::Command(command));
+                }
+            }
+            KeyAction::PauseAll => {
+                effects.push(Effect::Command(Command::PauseAll));
+            }
+            KeyAction::ResumeAll => {
+                effects.push(Effect::Command(Command::ResumeAll));
+            }
+            KeyAction::Delete => {
+                if !self.selected_gids.is_empty() {
+                    let gids = gids_of(self.selected_downloads());
+                    if !gids.is_empty() {
+                        self.confirm(ConfirmAction::Batch(BatchAction::Remove, gids));
+                    }
+                } else if let Some((gid, download)) = self.selected_with_gid() {
+                    effects.push(Effect::Command(Command::Remove {
+                        gid,
+                        name: download.name.clone(),
+                    }));
+
+                    // Move the cursor to a neighbour of the deleted download
+                    let selected_idx = self.list_state.selected().unwrap_or(0);
+                    if selected_idx > 0 {
+                        self.move_cursor(selected_idx - 1);
+                    } else if self.visible_count() > 1 {
+                        self.move_cursor(1);
+                    } else {
+                        self.cursor = None;
+                        self.list_state.select(None);
+                    }
+                }
+            }
+            KeyAction::DeleteFile => {
+                if !self.selected_gids.is_empty() {
+                    let gids = gids_of(self.selected_downloads());
+                    if !gids.is_empty() {
+                        self.confirm(ConfirmAction::Batch(BatchAction::DeleteFile, gids));
+                    }
+                } else if let Some((gid, _)) = self.selected_with_gid() {
+                    self.confirm(ConfirmAction::DeleteFile(gid));
+                }
+            }
+            KeyAction::RetryDownload if self.current_tab == HISTORY_TAB => {
+                match self.selected_history().map(|e| e.url.clone()) {
+                    Some(Some(url)) => effects.push(Effect::Command(Command::Add(url))),
+                    Some(None) => self.set_status("URL not available"),
+                    None => {}
+                }
+            }
+            KeyAction::RetryDownload => {
+                if !self.selected_gids.is_empty() {
+                    let targets = self.selected_downloads();
+                    let gids = gids_of(
+                        targets
+                            .into_iter()
+                            .filter(|d| d.status == DownloadState::Error),
+                    );
+                    if gids.is_empty() {
+                        self.set_status("Can only retry failed downloads");
+                    } else {
+                        self.confirm(ConfirmAction::Batch(BatchAction::Retry, gids));
+                    }
+                } else if let Some(download) = self.selected_download() {
+                    if download.status == DownloadState::Error {
+                        if let Some(gid) = download.gid.clone() {
+                            self.confirm(ConfirmAction::RetryDownload(gid));
+                        }
+                    } else {
+                        self.set_status("Can only retry failed downloads");
+                    }
+                }
+            }
+            KeyAction::PurgeCompleted => {
+                if self.current_tab == COMPLETED_TAB {
+                    self.confirm(ConfirmAction::PurgeCompleted);
+                } else {
+                    self.set_status("Switch to Completed tab to purge");
+                }
+            }
+
+            // ============ Queue Management ============
+            KeyAction::MoveQueueUp => {
+                if !self.selected_gids.is_empty() {
+                    let gids = gids_of(self.selected_downloads());
+                    effects.extend(batch_effect(BatchAction::MoveUp, gids));
+                } else if let Some((gid, _)) = self.selected_with_gid() {
+                    effects.push(Effect::Command(Command::MoveUp(gid)));
+                }
+            }
+            KeyAction::MoveQueueDown => {
+                if !self.selected_gids.is_empty() {
+                    let gids = gids_of(self.selected_downloads());
+                    effects.extend(batch_effect(BatchAction::MoveDown, gids));
+                } else if let Some((gid, _)) = self.selected_with_gid() {
+                    effects.push(Effect::Command(Command::MoveDown(gid)));
+                }
+            }
+
+            // ============ File Operations ============
+            KeyAction::OpenFile
+            | KeyAction::OpenFolder
+            | KeyAction::CopyUrl
+            | KeyAction::CopyPath
+                if self.current_tab == HISTORY_TAB =>
+            {
+                effects.extend(self.history_action(&action));
+            }
+            KeyAction::OpenFile => {
+                if let Some(download) = self.selected_download() {
+                    match &download.file_path {
+                        Some(path) => effects.push(Effect::Open {
+                            path: PathBuf::from(path),
+                            what: "file",
+                        }),
+                        None => self.set_status("File path not available"),
+                    }
+                }
+            }
+            KeyAction::OpenFolder => {
+                if let Some(download) = self.selected_download() {
+                    let folder = match &download.file_path {
+                        Some(path) => std::path::Path::new(path).parent().map(PathBuf::from),
+                        // Open default download directory
+                        None => Some(default_download_dir()),
+                    };
+                    if let Some(path) = folder {
+                        effects.push(Effect::Open {
+                            path,
+                            what: "folder",
+                        });
+                    }
+                }
+            }
+            KeyAction::CopyUrl => {
+                let urls: Vec<String> = self
+                    .copy_targets()
+                    .into_iter()
+                    .filter_map(|d| d.url.clone())
+                    .collect();
+                match copy_effect("URL", "URLs", urls) {
+                    Some(effect) => effects.push(effect),
+                    None => self.set_status("URL not available"),
+                }
+            }
+            KeyAction::CopyPath => {
+                let paths: Vec<String> = self
+                    .copy_targets()
+                    .into_iter()
+                    .filter_map(|d| d.file_path.clone())
+                    .collect();
+                match copy_effect("Path", "Paths", paths) {
+                    Some(effect) => effects.push(effect),
+                    None => self.set_status("Path not available"),
+                }
+            }
+
+            // ============ Selection (Batch Operations) ============
+            KeyAction::ToggleSelect => {
+                if let Some(gid) = self.cursor.clone() {
+                    if self.selected_gids.contains(&gid) {
+                        self.selected_gids.retain(|g| *g != gid);
+                    } else {
+                        self.selected_gids.push(gid);
+                    }
+                }
+            }
+            KeyAction::SelectAll => {
+                self.selected_gids = gids_of(self.visible());
+                self.set_status(format!("Selected {} items", self.selected_gids.len()));
+            }
+            KeyAction::DeselectAll => {
+                self.selected_gids.clear();
+                self.set_status("Selection cleared");
+            }
+
+            // ============ Confirmation Mode ============
+            KeyAction::ConfirmYes => {
+                if let Some(action) = self.pending_confirm.take() {
+                    match action {
+                        ConfirmAction::Quit => self.should_quit = true,
+                        ConfirmAction::DeleteFile(gid) => {
+                            effects.push(Effect::Command(Command::DeleteFile(gid)));
+                        }
+                        ConfirmAction::PurgeCompleted => {
+                            effects.push(Effect::Command(Command::PurgeCompleted));
+                            self.list_state.select(None);
+                        }
+                        ConfirmAction::RetryDownload(gid) => {
+                            effects.push(Effect::Command(Command::Retry(gid)));
+                        }
+                        ConfirmAction::AddFromBrowser(download) => {
+                            effects.push(Effect::Command(Command::AddFromBrowser(download)));
+                        }
+                        ConfirmAction::Batch(action, gids) => {
+                            if matches!(action, BatchAction::Remove | BatchAction::DeleteFile) {
+                                self.selected_gids.clear();
+                            }
+                            effects.push(Effect::Command(Command::Batch(action, gids)));
+                        }
+                    }
+                }
+                self.input.exit_to_normal();
+            }
+            KeyAction::ConfirmNo => {
+                self.pending_confirm = None;
+                self.input.exit_to_normal();
+            }
+
+            // ============ Context Menu ============
+            KeyAction::MenuUp => {
+                if let Some(menu) = &mut self.menu {
+                    menu.selected = menu.selected.saturating_sub(1);
+                }
+            }
+            KeyAction::MenuDown => {
+                if let Some(menu) = &mut self.menu {
+                    menu.selected = (menu.selected + 1).min(menu.items.len().saturating_sub(1));
+                }
+            }
+            KeyAction::MenuSelect => {
+                self.input.exit_to_normal();
+                let action = self
+                    .menu
+                    .take()
+                    .and_then(|menu| menu.items.get(menu.selected).map(|(_, a)| a.clone()));
+                if let Some(action) = action {
+                    return self.update(action);
+                }
+            }
+            KeyAction::MenuClose => {
+                self.menu = None;
+                self.input.exit_to_normal();
+            }
+
+            KeyAction::None => {}
+        }
+
+        // Sorting, searching and tab changes move rows around the cursor
+        self.sync_cursor();
+        effects
+    }
+
+    /// Render the whole UI
+    pub fn draw(&mut self, f: &mut Frame) {
+        let size = f.size();
+
+        // Check terminal size
+        self.too_small = size.width < MIN_WIDTH || size.height < MIN_HEIGHT;
+        if self.too_small {
+            render_size_warning(f, size, MIN_WIDTH, MIN_HEIGHT, size.width, size.height);
+            return;
+        }
+
+        self.sync_cursor();
+        let state = AppState {
+            downloads: &self.downloads,
+            current_tab: self.current_tab,
+            input_text: self.input.get_input(),
+            input_mode: self.input.mode,
+            status_message: &self.status_message,
+            search_query: self.input.get_search_query(),
+            sort_order: self.sort_order,
+            sort_ascending: self.sort_ascending,
+            help_scroll: self.help_scroll,
+            speed_limit_state: if self.input.mode == InputMode::SpeedLimit {
+                Some(&self.speed_limit_state)
+            } else {
+                None
+            },
+            download_limit: self.download_limit,
+            upload_limit: self.upload_limit,
+            selected_gids: &self.selected_gids,
+            busy_gids: &self.busy_gids,
+            hook_failed_gids: &self.hook_failed_gids,
+            log: &self.log,
+            log_scroll: self.log_scroll,
+            schedule: matches!(
+                self.input.mode,
+                InputMode::Schedule | InputMode::ScheduleEdit
+            )
+            .then_some(&self.scheduler.schedule),
+            schedule_state: Some(&self.schedule_state),
+            schedule_input: &self.input.schedule_buffer,
+            schedule_note: &self.schedule_note,
+            profile_note: &self.profile_note,
+            quota: &self.quota,
+            history: &self.history.entries,
+            stats: (self.input.mode == InputMode::Stats)
+                .then_some((&self.stats, self.stats_resolution)),
+            keymap: &self.input.keymap,
+            menu: self.menu.as_ref(),
+            show_details: self.details_open,
+            command_input: &self.input.command_buffer,
+            command_error: self.command_error.as_deref(),
+            command_hint: &self.command_hint,
+            tick: self.tick,
+        };
+
+        self.hits = render_app_full(f, state, &mut self.list_state);
+
+        // Show confirmation popup if pending
+        if let Some(action) = &self.pending_confirm {
+            let (title, message) = confirm_text(action, &self.downloads);
+            self.hits.buttons =
+                render_popup(f, size, title, &message, PopupType::Confirmation, true);
+        }
+    }
+
+    /// Rows in the schedule popup: rules followed by download timers
+    fn schedule_rows(&self) -> usize {
+        let schedule = &self.scheduler.schedule;
+        schedule.rules.len() + schedule.timers.len()
+    }
+
+    /// Open the text field of the schedule popup
+    fn edit_schedule(&mut self, edit: ScheduleEdit, text: String) {
+        self.schedule_state.editing = Some(edit);
+        self.schedule_state.error = None;
+        self.input.enter_schedule_edit_mode(&text);
+    }
+
+    /// Apply the text typed in the schedule popup
+    ///
+    /// Invalid input keeps the field open with the error shown below it.
+    fn submit_schedule_edit(&mut self) -> Vec<Effect> {
+        let Some(edit) = self.schedule_state.editing.clone() else {
+            return Vec::new();
+        };
+        let text = self.input.schedule_buffer.trim().to_string();
+        let mut effects = Vec::new();
+
+        let starting = matches!(edit, ScheduleEdit::StartAt(_));
+        match edit {
+            ScheduleEdit::Rule(index) => {
+                let rule = match ScheduleRule::parse(&text) {
+                    Ok(rule) => rule,
+                    Err(e) => {
+                        self.schedule_state.error = Some(e);
+                        return effects;
+                    }
+                };
+                let rules = &mut self.scheduler.schedule.rules;
+                let message = format!("Schedule: rule {}", rule);
+                match index {
+                    Some(i) if i < rules.len() => rules[i] = rule,
+                    _ => {
+                        rules.push(rule);
+                        self.schedule_state.selected = rules.len() - 1;
+                    }
+                }
+                self.scheduler.rules_changed();
+                effects.push(Effect::SaveSchedule(self.scheduler.schedule.to_toml()));
+                self.push_log(message, false);
+            }
+            ScheduleEdit::StartAt(gids) | ScheduleEdit::StopAt(gids) => {
+                let at = if text.is_empty() {
+                    None
+                } else {
+                    match scheduler::next_time_of_day(&text, self.clock) {
+                        Ok(at) => Some(at),
+                        Err(e) => {
+                            self.schedule_state.error = Some(e);
+                            return effects;
+                        }
+                    }
+                };
+
+                for gid in gids {
+                    let name = self.name_of(&gid);
+                    let running = self.downloads.iter().any(|d| {
+                        d.gid.as_ref() == Some(&gid)
+                            && matches!(d.status, DownloadState::Active | DownloadState::Waiting)
+                    });
+                    let timer = self.scheduler.schedule.timer_mut(&gid, &name);
+                    if starting {
+                        timer.start_at = at;
+                        // Hold the download until its time comes
+                        if at.is_some() && running {
+                            effects.push(Effect::Command(Command::Pause(gid)));
+                        }
+                    } else {
+                        timer.stop_at = at;
+                    }
+                }
+                self.scheduler
+                    .schedule
+                    .timers
+                    .retain(|t| t.start_at.is_some() || t.stop_at.is_some());
+            }
+        }
+
+        self.schedule_note = self.scheduler.next_change(self.clock).unwrap_or_default();
+        self.schedule_state.editing = None;
+        self.schedule_state.error = None;
+        self.input.enter_schedule_mode();
+        effects
+    }
+
+    /// Name of the download with the given GID, or the GID itself
+    fn name_of(&self, gid: &str) -> String {
+        self.downloads
+            .iter()
+            .find(|d| d.gid.as_deref() == Some(gid))
+            .map(|d| d.name.clone())
+            .unwrap_or_else(|| gid.to_string())
+    }
+
+    /// Run a command from the command line
+    ///
+    /// Errors leave the state untouched and are shown under the command line.
+    fn run_command(&mut self, command: PaletteCommand) -> Result<Vec<Effect>, String> {
+        let mut effects = Vec::new();
+        match command {
+            PaletteCommand::Add { url, options } => {
+                effects.push(Effect::Command(Command::AddWithOptions(url, options)));
+            }
+            PaletteCommand::Limit { download, upload } => {
+                self.download_limit = download.unwrap_or(self.download_limit);
+                self.upload_limit = upload.unwrap_or(self.upload_limit);
+                effects.push(Effect::Command(Command::SetSpeedLimits {
+                    download: self.download_limit,
+                    upload: self.upload_limit,
+                }));
+            }
+            PaletteCommand::Pause(query) => {
+                let gids = gids_of(
+                    self.command_targets(query.as_deref())?
+                        .into_iter()
+                        .filter(|d| !d.status.is_stopped() && d.status != DownloadState::Paused),
+                );
+                match batch_effect(BatchAction::Pause, gids) {
+                    Some(effect) => effects.push(effect),
+                    None => self.set_status("Nothing to pause"),
+                }
+            }
+            PaletteCommand::Resume(query) => {
+                let gids = gids_of(
+                    self.command_targets(query.as_deref())?
+                        .into_iter()
+                        .filter(|d| d.status == DownloadState::Paused),
+                );
+                match batch_effect(BatchAction::Resume, gids) {
+                    Some(effect) => effects.push(effect),
+                    None => self.set_status("Nothing to resume"),
+                }
+            }
+            PaletteCommand::Sort { order, ascending } => {
+                self.sort_order = order;
+                self.sort_ascending = ascending.unwrap_or(self.sort_ascending);
+                let dir = if self.sort_ascending {
+                    "ascending"
+                } else {
+                    "descending"
+                };
+                self.set_status(format!("Sort by: {} ({})", order.as_str(), dir));
+            }
+            PaletteCommand::Set { name, value } => {
+                effects.push(Effect::Command(Command::SetOption { name, value }));
+            }
+            PaletteCommand::Purge => {
+                self.confirm(ConfirmAction::PurgeCompleted);
+            }
+            PaletteCommand::Theme(name) => {
+                if !self.set_theme(&name) {
+                    return Err(format!("unknown theme '{}'", name));
+                }
+                self.set_status(format!("Theme: {}", name));
+            }
+            PaletteCommand::ExportCsv(path) => {
+                effects.push(Effect::ExportCsv {
+                    path,
+                    content: palette::downloads_csv(&self.downloads),
+                });
+            }
+            PaletteCommand::Action(action) => {
+                effects = self.update(action);
+            }
+        }
+        Ok(effects)
+    }
+
+    /// Downloads matching a search query from any tab, or the selection
+    fn command_targets(&self, query: Option<&str>) -> Result<Vec<&Download>, String> {
+        let targets = match query {
+            Some(query) => {
+                let all: Vec<&Download> = self.downloads.iter().collect();
+                filter_by_search(&all, query)
+            }
+            None => self.copy_targets(),
+        };
+        if targets.is_empty() {
+            return Err(match query {
+                Some(query) => format!("no downloads match '{}'", query),
+                None => "no download selected".to_string(),
+            });
+        }
+        Ok(targets)
+    }
+
+    /// Ask the user to confirm an action
+    fn confirm(&mut self, action: ConfirmAction) {
+        self.pending_confirm = Some(action);
+        self.input.enter_confirmation_mode();
+    }
+
+    /// Downloads in the list, in display order
+    fn visible(&self) -> Vec<&Download> {
+        visible_downloads(
+            &self.downloads,
+            self.current_tab,
+            self.input.get_search_query(),
+            self.sort_order,
+            self.sort_ascending,
+        )
+    }
+
+    /// Number of rows in the list
+    fn visible_count(&self) -> usize {
+        if self.current_tab == HISTORY_TAB {
+            self.visible_history().len()
+        } else {
+            self.visible().len()
+        }
+    }
+
+    /// History entries in the list, newest first
+    fn visible_history(&self) -> Vec<&HistoryEntry> {
+        visible_history(&self.history.entries, self.input.get_search_query())
+    }
+
+    /// The history entry under the cursor
+    fn selected_history(&self) -> Option<&HistoryEntry> {
+        let row = self.list_state.selected()?;
+        self.visible_history().get(row).copied()
+    }
+
+    /// Open or copy from the history entry under the cursor
+    fn history_action(&mut self, action: &KeyAction) -> Option<Effect> {
+        let entry = self.selected_history()?;
+        let path = entry.path.as_deref().map(PathBuf::from);
+        let effect = match action {
+            KeyAction::OpenFile => path.map(|path| Effect::Open { path, what: "file" }),
+            KeyAction::OpenFolder => path
+                .and_then(|p| p.parent().map(PathBuf::from))
+                .map(|path| Effect::Open {
+                    path,
+                    what: "folder",
+                }),
+            KeyAction::CopyUrl => copy_effect("URL", "URLs", entry.url.iter().cloned().collect()),
+            _ => copy_effect("Path", "Paths", entry.path.iter().cloned().collect()),
+        };
+        if effect.is_none() {
+            let missing = if matches!(action, KeyAction::CopyUrl) {
+                "URL not available"
+            } else {
+                "File path not available"
+            };
+            self.set_status(missing);
+        }
+        effect
+    }
+
+    /// Put the cursor on the download at `index` in the list
+    fn move_cursor(&mut self, index: usize) {
+        self.cursor = self.visible().get(index).and_then(|d| d.gid.clone());
+        self.list_state.select(Some(index));
+    }
+
+    /// Re-resolve the cursor row after the list changed
+    ///
+    /// The cursor follows its download through refreshes, sorting and
+    /// filtering. If the download left the list, the cursor stays on the
+    /// same row, now showing a neighbour. Selected downloads that are gone
+    /// are dropped from the selection.
+    fn sync_cursor(&mut self) {
+        if self.current_tab == HISTORY_TAB {
+            // History entries have no GID; the cursor is just a row
+            let row = match self.visible_count() {
+                0 => None,
+                len => Some(self.list_state.selected().unwrap_or(0).min(len - 1)),
+            };
+            self.cursor = None;
+            self.list_state.select(row);
+            return;
+        }
+        let visible = self.visible();
+        let row = self
+            .cursor
+            .as_ref()
+            .and_then(|gid| visible.iter().position(|d| d.gid.as_ref() == Some(gid)))
+            .or_else(|| match visible.len() {
+                0 => None,
+                len => Some(self.list_state.selected().unwrap_or(0).min(len - 1)),
+            });
+        let cursor = row.and_then(|i| visible[i].gid.clone());
+
+        self.cursor = cursor;
+        self.list_state.select(row);
+
+        let downloads = &self.downloads;
+        self.selected_gids
+            .retain(|gid| downloads.iter().any(|d| d.gid.as_ref() == Some(gid)));
+    }
+
+    /// The download under the cursor
+    fn selected_download(&self) -> Option<&Download> {
+        let gid = self.cursor.as_ref()?;
+        self.downloads.iter().find(|d| d.gid.as_ref() == Some(gid))
+    }
+
+    /// Downloads in the multi-selection that are in the list, in display order
+    fn selected_downloads(&self) -> Vec<&Download> {
+        self.visible()
+            .into_iter()
+            .filter(|d| {
+                d.gid
+                    .as_ref()
+                    .is_some_and(|gid| self.selected_gids.contains(gid))
+            })
+            .collect()
+    }
+
+    /// The multi-selection if there is one, otherwise the download under the cursor
+    fn copy_targets(&self) -> Vec<&Download> {
+        if self.selected_gids.is_empty() {
+            self.selected_download().into_iter().collect()
+        } else {
+            self.selected_downloads()
+        }
+    }
+
+    /// The download under the cursor together with its GID
+    fn selected_with_gid(&self) -> Option<(String, &Download)> {
+        let download = self.selected_download()?;
+        Some((download.gid.clone()?, download))
+    }
+}
+
+/// Title and message for a confirmation popup
+fn confirm_text(action: &ConfirmAction, downloads: &[Download]) -> (&'static str, String) {
+    match action {
+        ConfirmAction::Quit => {
+            let active_count = downloads.iter().filter(|d| !d.status.is_stopped()).count();
+
+            let msg = if active_count > 0 {
+                format!(
+                    "You have {} active/queued download(s).\n\n\
+                    Quitting will cancel all downloads.\n\n\
+                    Are you sure you want to quit?",
+                    active_count
+                )
+            } else {
+                "Are you sure you want to quit?".to_string()
+            };
+            ("Confirm Quit", msg)
+        }
+        ConfirmAction::DeleteFile(gid) => {
+            let name = downloads
+                .iter()
+                .find(|d| d.gid.as_ref() == Some(gid))
+                .map(|d| d.name.clone())
+                .unwrap_or_else(|| "Unknown".to_string());
+            (
+                "Delete File",
+                format!(
+                    "Are you sure you want to delete this file from disk?\n\n\
+                    File: {}\n\n\
+                    This cannot be undone!",
+                    name
+                ),
+            )
+        }
+        ConfirmAction::PurgeCompleted => (
+            "Purge Completed",
+            "Are you sure you want to remove all completed downloads from the list?".to_string(),
+        ),
+        ConfirmAction::RetryDownload(_) => {
+            ("Retry Download", "Retry this failed download?".to_string())
+        }
+        ConfirmAction::AddFromBrowser(download) => {
+            let mut msg = format!("The browser sent a download:\n\n{}", download.describe());
+            if let Some(referer) = &download.referer {
+                msg.push_str(&format!("\n\nFrom: {}", referer));
+            }
+            msg.push_str("\n\nAdd it?");
+            ("Browser Download", msg)
+        }
+        ConfirmAction::Batch(action, gids) => {
+            let count = gids.len();
+            match action {
+                BatchAction::Remove => (
+                    "Remove Downloads",
+                    format!("Remove {} download(s) from the list?", count),
+                ),
+                BatchAction::DeleteFile => (
+                    "Delete Files",
+                    format!(
+                        "Are you sure you want to delete {} file(s) from disk?\n\n\
+                        This cannot be undone!",
+                        count
+                    ),
+                ),
+                BatchAction::Retry => (
+                    "Retry Downloads",
+                    format!("Retry {} failed download(s)?", count),
+                ),
+                _ => ("Confirm", format!("Apply to {} download(s)?", count)),
+            }
+        }
+    }
+}
+
+/// GIDs of the given downloads, skipping any not yet known to aria2
+fn gids_of<'a>(downloads: impl IntoIterator<Item = &'a Download>) -> Vec<String> {
+    downloads
+        .into_iter()
+        .filter_map(|d| d.gid.clone())
+        .collect()
+}
+
+/// Command effect for a batch action, or nothing if there are no targets
+fn batch_effect(action: BatchAction, gids: Vec<String>) -> Option<Effect> {
+    if gids.is_empty() {
+        None
+    } else {
+        Some(Effect::Command(Command::Batch(action, gids)))
+    }
+}
+
+/// Clipboard effect for one or more values, joined by newlines
+fn copy_effect(singular: &str, plural: &str, values: Vec<String>) -> Option<Effect> {
+    let label = match values.len() {
+        0 => return None,
+        1 => singular.to_string(),
+        n => format!("{} {}", n, plural),
+    };
+    Some(Effect::Copy {
+        label,
+        text: values.join("\n"),
+    })
+}
+
+/// Local time of day as `HH:MM:SS`
+fn clock_time() -> String {
+    chrono::Local::now().format("%H:%M:%S").to_string()
+}
+
+fn default_download_dir() -> PathBuf {
+    dirs::download_dir()
+        .or_else(|| dirs::home_dir().map(|p| p.join("Downloads")))
+        .unwrap_or_else(|| PathBuf::from("./Downloads"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::effects::Backend;
+    use super::*;
+    use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+    use ratatui::{backend::TestBackend, layout::Rect, Terminal};
+    use std::path::Path;
+
+    /// Backend that records effects instead of performing them
+    #[derive(Default)]
+    struct FakeBackend {
+        commands: Vec<Command>,
+        opened: Vec<PathBuf>,
+        saved_schedules: Vec<String>,
+        saved_histories: Vec<String>,
+        exported: Vec<&'static str>,
+        files: Vec<(PathBuf, String)>,
+    }
+
+    impl Backend for FakeBackend {
+        fn send(&mut self, command: Command) {
+            self.commands.push(command);
+        }
+
+        fn open(&mut self, path: &Path) -> Result<(), String> {
+            self.opened.push(path.to_path_buf());
+            Ok(())
+        }
+
+        fn copy(&mut self, _text: &str) -> Result<(), String> {
+            Err("no clipboard".to_string())
+        }
+
+        fn save_schedule(&mut self, content: &str) -> Result<(), String> {
+            self.saved_schedules.push(content.to_string());
+            Ok(())
+        }
+
+        fn save_history(&mut self, content: &str) -> Result<(), String> {
+            self.saved_histories.push(content.to_string());
+            Ok(())
+        }
+
+        fn export_stats(&mut self, files: &[(&'static str, String)]) -> Result<PathBuf, String> {
+            self.exported.extend(files.iter().map(|(name, _)| *name));
+            Ok(PathBuf::from("/tmp/stats"))
+        }
+
+        fn export_file(&mut self, path: &Path, content: &str) -> Result<(), String> {
+            self.files.push((path.to_path_buf(), content.to_string()));
+            Ok(())
+        }
+    }
+
+    fn create_test_download(gid: &str, name: &str, status: DownloadState) -> Download {
+        Download {
+            gid: Some(gid.to_string()),
+            name: name.to_string(),
+            url: Some(format!("https://example.com/{}", name)),
+            status,
+            ..Default::default()
+        }
+    }
+
+    fn test_app() -> App {
+        let mut app = App::new();
+        app.downloads = vec![
+            create_test_download("g1", "alpha.iso", DownloadState::Active),
+            create_test_download("g2", "beta.zip", DownloadState::Active),
+            create_test_download("g3", "gamma.tar", DownloadState::Complete),
+            create_test_download("g4", "delta.bin", DownloadState::Paused),
+        ];
+        app.downloads[1].progress = 0.4;
+        app.downloads[2].progress = 1.0;
+        app.downloads[3].progress = 0.5; // Paused downloads are queued however far they got
+        app
+    }
+
+    /// Feed key presses through the input handler, reducer and executor
+    fn press(app: &mut App, backend: &mut FakeBackend, keys: &[KeyCode]) {
+        for code in keys {
+            let effects = app.handle_key(&KeyEvent::new(*code, KeyModifiers::NONE));
+            execute(app, effects, backend);
+        }
+    }
+
+    /// Select the first two rows of the current tab
+    const SELECT_BOTH: [KeyCode; 3] = [KeyCode::Char('v'), KeyCode::Char('j'), KeyCode::Char('v')];
+
+    fn type_text(app: &mut App, backend: &mut FakeBackend, text: &str) {
+        let keys: Vec<KeyCode> = text.chars().map(KeyCode::Char).collect();
+        press(app, backend, &keys);
+    }
+
+    /// Open the command line, type `line` and run it
+    fn run_command(app: &mut App, backend: &mut FakeBackend, line: &str) {
+        press(app, backend, &[KeyCode::Char(':')]);
+        type_text(app, backend, line);
+        press(app, backend, &[KeyCode::Enter]);
+    }
+
+    fn screen(app: &mut App) -> String {
+        screen_sized(app, 120, 40)
+    }
+
+    fn screen_sized(app: &mut App, width: u16, height: u16) -> String {
+        let mut terminal = Terminal::new(TestBackend::new(width, height)).unwrap();
+        terminal.draw(|f| app.draw(f)).unwrap();
+        let buffer = terminal.backend().buffer();
+        let mut text = String::new();
+        for y in 0..buffer.area.height {
+            for x in 0..buffer.area.width {
+                text.push_str(buffer.get(x, y).symbol());
+            }
+            text.push('\n');
+        }
+        text
+    }
+
+    #[test]
+    fn test_rebound_keys() {
+        let mut app = test_app();
+        let mut backend = FakeBackend::default();
+        let config: crate::config::KeysConfig =
+            toml::from_str("[normal]\ndelete = \"D\"\npause = \"z\"").unwrap();
+        app.input.keymap = crate::keymap::Keymap::from_config(&config).unwrap();
+
+        // The old keys do nothing
+        press(
+            &mut app,
+            &mut backend,
+            &[KeyCode::Char(' '), KeyCode::Char('d')],
+        );
+        assert!(backend.commands.is_empty());
+
+        press(&mut app, &mut backend, &[KeyCode::Char('z')]);
+        assert_eq!(backend.commands, vec![Command::Pause("g1".to_string())]);
+        assert!(screen(&mut app).contains("z pause"));
+
+        press(&mut app, &mut backend, &[KeyCode::Char('?')]);
+        assert!(screen(&mut app).contains("D                   Remove download from list"));
+    }
+
+    #[test]
+    fn test_cycle_theme() {
+        let mut app = test_app();
+
+        app.handle_key(&KeyEvent::new(KeyCode::Char('t'), KeyModifiers::CONTROL));
+        assert_eq!(app.settings.theme, "light");
+        assert_eq!(Theme::current(), app.themes.get("light").unwrap());
+        assert!(screen(&mut app).contains("Theme: light"));
+
+        assert!(!app.set_theme("missing"));
+        assert!(app.set_theme("dark"));
+        assert_eq!(
+            Theme::current().text,
+            ratatui::style::Color::Rgb(204, 224, 208)
+        );
+    }
+
+    /// Send a mouse event at the middle of `area`
+    fn click(
+        app: &mut App,
+        backend: &mut FakeBackend,
+        kind: MouseEventKind,
+        area: Rect,
+        modifiers: KeyModifiers,
+        now: Instant,
+    ) {
+        let event = MouseEvent {
+            kind,
+            column: area.x + area.width / 2,
+            row: area.y + area.height / 2,
+            modifiers,
+        };
+        let effects = app.handle_mouse(&event, now);
+        execute(app, effects, backend);
+    }
+
+    const LEFT: MouseEventKind = MouseEventKind::Down(MouseButton::Left);
+
+    #[test]
+    fn test_mouse_tabs_rows_and_selection() {
+        let mut app = test_app();
+        let mut backend = FakeBackend::default();
+        let now = Instant::now();
+        screen(&mut app);
+
+        // Active tab lists alpha and beta
+        let row = app.hits.rows[1].0;
+        click(&mut app, &mut backend, LEFT, row, KeyModifiers::NONE, now);
+        assert_eq!(app.cursor.as_deref(), Some("g2"));
+
+        let first = app.hits.rows[0].0;
+        click(
+            &mut app,
+            &mut backend,
+            LEFT,
+            first,
+            KeyModifiers::CONTROL,
+            now,
+        );
+        assert_eq!(app.selected_gids, vec!["g1"]);
+        click(&mut app, &mut backend, LEFT, row, KeyModifiers::SHIFT, now);
+        assert_eq!(app.selected_gids, vec!["g1", "g2"]);
+        assert_eq!(app.cursor.as_deref(), Some("g2"));
+
+        // The wheel moves the cursor while over the list
+        let list = app.hits.list;
+        let scroll_up = MouseEventKind::ScrollUp;
+        click(
+            &mut app,
+            &mut backend,
+            scroll_up,
+            list,
+            KeyModifiers::NONE,
+            now,
+        );
+        assert_eq!(app.cursor.as_deref(), Some("g1"));
+
+        let tab = app.hits.tabs[2].0;
+        click(&mut app, &mut backend, LEFT, tab, KeyModifiers::NONE, now);
+        assert_eq!(app.current_tab, 2);
+        assert!(screen(&mut app).contains("gamma.tar"));
+    }
+
+    #[test]
+    fn test_double_click_opens_file() {
+        let mut app = test_app();
+        let mut backend = FakeBackend::default();
+        app.downloads[0].file_path = Some("/tmp/alpha.iso".to_string());
+        let now = Instant::now();
+        screen(&mut app);
+
+        let row = app.hits.rows[0].0;
+        click(&mut app, &mut backend, LEFT, row, KeyModifiers::NONE, now);
+        let later = now + Duration::from_secs(1);
+        click(&mut app, &mut backend, LEFT, row, KeyModifiers::NONE, later);
+        assert!(backend.opened.is_empty());
+
+        let soon = later + Duration::from_millis(200);
+        click(&mut app, &mut backend, LEFT, row, KeyModifiers::NONE, soon);
+        assert_eq!(backend.opened, vec![PathBuf::from("/tmp/alpha.iso")]);
+    }
+
+    #[test]
+    fn test_context_menu() {
+        let mut app = test_app();
+        let mut backend = FakeBackend::default();
+        let now = Instant::now();
+        screen(&mut app);
+
+        let row = app.hits.rows[1].0;
+        let right = MouseEventKind::Down(MouseButton::Right);
+        click(&mut app, &mut backend, right, row, KeyModifiers::NONE, now);
+        assert_eq!(app.input.mode, InputMode::Menu);
+        assert_eq!(app.cursor.as_deref(), Some("g2"));
+        let text = screen(&mut app);
+        assert!(text.contains("Pause") && text.contains("Open folder"));
+
+        // Keys work in the menu too
+        press(&mut app, &mut backend, &[KeyCode::Esc]);
+        assert_eq!(app.input.mode, InputMode::Normal);
+        assert!(app.menu.is_none());
+
+        click(&mut app, &mut backend, right, row, KeyModifiers::NONE, now);
+        screen(&mut app);
+        let pause = app.hits.menu[0];
+        click(&mut app, &mut backend, LEFT, pause, KeyModifiers::NONE, now);
+        assert_eq!(app.input.mode, InputMode::Normal);
+        assert_eq!(backend.commands, vec![Command::Pause("g2".to_string())]);
+    }
+
+    #[test]
+    fn test_mouse_confirmation_and_help() {
+        let mut app = test_app();
+        let mut backend = FakeBackend::default();
+        let now = Instant::now();
+
+        press(&mut app, &mut backend, &[KeyCode::Char('q')]);
+        screen(&mut app);
+        let (no, _) = app.hits.buttons[1];
+        click(&mut app, &mut backend, LEFT, no, KeyModifiers::NONE, now);
+        assert!(!app.should_quit);
+        assert_eq!(app.input.mode, InputMode::Normal);
+
+        press(&mut app, &mut backend, &[KeyCode::Char('q')]);
+        screen(&mut app);
+        let (yes, answer) = app.hits.buttons[0];
+        assert!(answer);
+        click(&mut app, &mut backend, LEFT, yes, KeyModifiers::NONE, now);
+        assert!(app.should_quit);
+
+        let mut app = test_app();
+        press(&mut app, &mut backend, &[KeyCode::Char('?')]);
+        let down = MouseEventKind::ScrollDown;
+        click(
+            &mut app,
+            &mut backend,
+            down,
+            Rect::default(),
+            KeyModifiers::NONE,
+            now,
+        );
+        assert_eq!(app.help_scroll, 1);
+    }
+
+    #[test]
+    fn test_add_download() {
+        let mut app = test_app();
+        let mut backend = FakeBackend::default();
+
+        press(&mut app, &mut backend, &[KeyCode::Char('i')]);
+        assert_eq!(app.input.mode, InputMode::Editing);
+        type_text(&mut app, &mut backend, "https://a.com/x.iso");
+        press(&mut app, &mut backend, &[KeyCode::Enter]);
+
+        assert_eq!(app.input.mode, InputMode::Normal);
+        assert_eq!(
+            backend.commands,
+            vec![Command::Add("https://a.com/x.iso".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_pause_marks_row_busy_until_done() {
+        let mut app = test_app();
+        let mut backend = FakeBackend::default();
+
+        // Active tab shows alpha and beta
+        press(
+            &mut app,
+            &mut backend,
+            &[KeyCode::Char('j'), KeyCode::Char(' ')],
+        );
+        assert_eq!(backend.commands, vec![Command::Pause("g2".to_string())]);
+        assert_eq!(app.busy_gids, vec!["g2"]);
+
+        app.handle_worker_event(WorkerEvent::Done {
+            gids: vec!["g2".to_string()],
+            message: Some("Paused".to_string()),
+        });
+        assert!(app.busy_gids.is_empty());
+        assert_eq!(app.status_message, "Paused");
+    }
+
+    #[test]
+    fn test_resume_from_queue() {
+        let mut app = test_app();
+        let mut backend = FakeBackend::default();
+
+        press(
+            &mut app,
+            &mut backend,
+            &[KeyCode::Char('2'), KeyCode::Char(' ')],
+        );
+        assert_eq!(backend.commands, vec![Command::Resume("g4".to_string())]);
+    }
+
+    #[test]
+    fn test_pause_applies_to_selection() {
+        let mut app = test_app();
+        let mut backend = FakeBackend::default();
+
+        press(&mut app, &mut backend, &SELECT_BOTH);
+        press(&mut app, &mut backend, &[KeyCode::Char(' ')]);
+        let gids = vec!["g1".to_string(), "g2".to_string()];
+        assert_eq!(
+            backend.commands,
+            vec![Command::Batch(BatchAction::Pause, gids.clone())]
+        );
+        assert_eq!(app.busy_gids, gids);
+
+        app.handle_worker_event(WorkerEvent::Done {
+            gids,
+            message: Some("Paused 2".to_string()),
+        });
+        assert!(app.busy_gids.is_empty());
+        assert_eq!(app.status_message, "Paused 2");
+    }
+
+    #[test]
+    fn test_bulk_remove_confirms_with_count() {
+        let mut app = test_app();
+        let mut backend = FakeBackend::default();
+
+        press(&mut app, &mut backend, &SELECT_BOTH);
+        press(&mut app, &mut backend, &[KeyCode::Char('d')]);
+        assert!(backend.commands.is_empty());
+        assert!(screen(&mut app).contains("Remove 2 download(s) from the list?"));
+
+        press(&mut app, &mut backend, &[KeyCode::Char('y')]);
+        assert_eq!(
+            backend.commands,
+            vec![Command::Batch(
+                BatchAction::Remove,
+                vec!["g1".to_string(), "g2".to_string()]
+            )]
+        );
+        assert!(app.selected_gids.is_empty());
+    }
+
+    #[test]
+    fn test_bulk_retry_needs_failed_downloads() {
+        let mut app = test_app();
+        let mut backend = FakeBackend::default();
+
+        press(&mut app, &mut backend, &SELECT_BOTH);
+        press(&mut app, &mut backend, &[KeyCode::Char('r')]);
+        assert_eq!(app.status_message, "Can only retry failed downloads");
+        assert_eq!(app.pending_confirm, None);
+    }
+
+    #[test]
+    fn test_copy_selection_joins_lines() {
+        let mut app = test_app();
+        let mut backend = FakeBackend::default();
+
+        press(&mut app, &mut backend, &SELECT_BOTH);
+        press(&mut app, &mut backend, &[KeyCode::Char('c')]);
+        assert_eq!(
+            app.status_message,
+            "2 URLs: https://example.com/alpha.iso\nhttps://example.com/beta.zip"
+        );
+    }
+
+    #[test]
+    fn test_cursor_follows_download_across_sort() {
+        let mut app = test_app();
+        let mut backend = FakeBackend::default();
+
+        press(&mut app, &mut backend, &[KeyCode::Char('j')]);
+        assert_eq!(app.cursor.as_deref(), Some("g2"));
+
+        // Descending by name puts beta first; the cursor moves with it
+        press(&mut app, &mut backend, &[KeyCode::Char('S')]);
+        assert_eq!(app.list_state.selected(), Some(0));
+        press(&mut app, &mut backend, &[KeyCode::Char(' ')]);
+        assert_eq!(backend.commands, vec![Command::Pause("g2".to_string())]);
+    }
+
+    #[test]
+    fn test_selection_survives_refresh() {
+        let mut app = test_app();
+        let mut backend = FakeBackend::default();
+
+        press(
+            &mut app,
+            &mut backend,
+            &[KeyCode::Char('j'), KeyCode::Char('v')],
+        );
+        assert_eq!(app.selected_gids, vec!["g2"]);
+
+        // A new download sorts before the selected one
+        let mut new = create_test_download("g5", "aardvark.bin", DownloadState::Active);
+        new.progress = 0.1;
+        app.downloads.push(new);
+        screen(&mut app);
+        assert_eq!(app.list_state.selected(), Some(2));
+
+        press(&mut app, &mut backend, &[KeyCode::Char(' ')]);
+        assert_eq!(
+            backend.commands,
+            vec![Command::Batch(BatchAction::Pause, vec!["g2".to_string()])]
+        );
+    }
+
+    #[test]
+    fn test_cursor_moves_to_neighbour_when_download_leaves() {
+        let mut app = test_app();
+        let mut backend = FakeBackend::default();
+
+        // alpha completes and leaves the Active tab
+        app.downloads[0].status = DownloadState::Complete;
+        app.downloads[0].progress = 1.0;
+        press(&mut app, &mut backend, &[KeyCode::Char(' ')]);
+        assert_eq!(app.cursor.as_deref(), Some("g2"));
+        assert_eq!(backend.commands, vec![Command::Pause("g2".to_string())]);
+    }
+
+    #[test]
+    fn test_quit_requires_confirmation() {
+        let mut app = test_app();
+        let mut backend = FakeBackend::default();
+
+        press(&mut app, &mut backend, &[KeyCode::Char('q')]);
+        assert_eq!(app.pending_confirm, Some(ConfirmAction::Quit));
+        assert!(screen(&mut app).contains("Confirm Quit"));
+
+        press(&mut app, &mut backend, &[KeyCode::Char('n')]);
+        assert_eq!(app.pending_confirm, None);
+        assert!(!app.should_quit);
+
+        press(
+            &mut app,
+            &mut backend,
+            &[KeyCode::Char('q'), KeyCode::Char('y')],
+        );
+        assert!(app.should_quit);
+        assert!(backend.commands.is_empty());
+    }
+
+    #[test]
+    fn test_purge_only_on_completed_tab() {
+        let mut app = test_app();
+        let mut backend = FakeBackend::default();
+
+        press(&mut app, &mut backend, &[KeyCode::Char('X')]);
+        assert_eq!(app.status_message, "Switch to Completed tab to purge");
+        assert_eq!(app.pending_confirm, None);
+
+        press(
+            &mut app,
+            &mut backend,
+            &[KeyCode::Char('3'), KeyCode::Char('X'), KeyCode::Char('y')],
+        );
+        assert_eq!(backend.commands, vec![Command::PurgeCompleted]);
+    }
+
+    #[test]
+    fn test_copy_without_clipboard_shows_text() {
+        let mut app = test_app();
+        let mut backend = FakeBackend::default();
+
+        press(&mut app, &mut backend, &[KeyCode::Char('c')]);
+        assert_eq!(app.status_message, "URL: https://example.com/alpha.iso");
+    }
+
+    #[test]
+    fn test_browser_download_waits_for_confirmation() {
+        let mut app = test_app();
+        let mut backend = FakeBackend::default();
+        let download = BrowserDownload {
+            url: Some("https://a.com/x.iso".to_string()),
+            ..Default::default()
+        };
+
+        app.handle_browser_event(BrowserEvent::Pending(download.clone()));
+        app.on_tick(Instant::now());
+        assert_eq!(app.input.mode, InputMode::Confirmation);
+        assert!(screen(&mut app).contains("Browser Download"));
+
+        press(&mut app, &mut backend, &[KeyCode::Char('y')]);
+        assert_eq!(backend.commands, vec![Command::AddFromBrowser(download)]);
+    }
+
+    #[test]
+    fn test_download_events_reach_status_bar() {
+        let mut app = test_app();
+
+        let mut failed = app.downloads[0].clone();
+        failed.error_message = Some("Resource not found".to_string());
+        app.handle_download_event(DownloadEvent::Failed(failed));
+        assert_eq!(app.status_message, "Failed: alpha.iso: Resource not found");
+
+        app.handle_download_event(DownloadEvent::Completed(app.downloads[2].clone()));
+        assert_eq!(app.status_message, "Completed: gamma.tar");
+
+        // Routine transitions stay quiet
+        app.handle_download_event(DownloadEvent::Started(app.downloads[1].clone()));
+        assert_eq!(app.status_message, "Completed: gamma.tar");
+
+        app.handle_download_event(DownloadEvent::Stalled(app.downloads[1].clone()));
+        assert_eq!(
+            app.status_message,
+            "Stalled: beta.zip (no data for a while)"
+        );
+        assert!(app.log[0].is_error);
+    }
+
+    #[test]
+    fn test_hook_failure_is_logged_and_flagged() {
+        let mut app = test_app();
+        let mut backend = FakeBackend::default();
+
+        app.handle_hook_outcome(HookOutcome {
+            gid: Some("g1".to_string()),
+            name: "alpha.iso".to_string(),
+            program: "clamscan".to_string(),
+            success: false,
+            status: "exit 1".to_string(),
+            output: "alpha.iso: Infected".to_string(),
+        });
+
+        assert_eq!(app.hook_failed_gids, vec!["g1"]);
+        assert!(app.log[0].is_error);
+        assert_eq!(
+            app.log[0].message,
+            "hook clamscan for alpha.iso: exit 1\nalpha.iso: Infected"
+        );
+        assert!(screen(&mut app).contains("[hook failed]"));
+
+        press(&mut app, &mut backend, &[KeyCode::Char('e')]);
+        assert_eq!(app.input.mode, InputMode::Log);
+        assert!(screen(&mut app).contains("alpha.iso: Infected"));
+    }
+
+    #[test]
+    fn test_quota_alerts_and_gauge() {
+        use crate::usage::QuotaPeriod;
+
+        let mut app = test_app();
+        let usage = QuotaUse {
+            period: QuotaPeriod::Monthly,
+            used: 50 * 1024 * 1024 * 1024,
+            limit: 50 * 1024 * 1024 * 1024,
+        };
+        app.handle_download_event(DownloadEvent::Quota(QuotaAlert::Reached(usage)));
+        assert_eq!(
+            app.status_message,
+            "Monthly quota of 50.00 GB used up: downloads paused"
+        );
+        assert!(app.log[0].is_error);
+
+        app.quota = vec![usage];
+        assert!(screen(&mut app).contains("Month ████████ 100%"));
+    }
+
+    #[test]
+    fn test_finished_downloads_go_to_history() {
+        let mut app = test_app();
+        let mut backend = FakeBackend::default();
+
+        let mut done = app.downloads[2].clone();
+        done.file_path = Some("/tmp/gamma.tar".to_string());
+        let effects = app.handle_download_event(DownloadEvent::Completed(done.clone()));
+        execute(&mut app, effects, &mut backend);
+        assert_eq!(backend.saved_histories.len(), 1);
+
+        // The same completion reported again is not recorded twice
+        let effects = app.handle_download_event(DownloadEvent::Completed(done));
+        assert!(effects.is_empty());
+
+        press(&mut app, &mut backend, &[KeyCode::Char('4')]);
+        assert!(screen(&mut app).contains("History [1/1]"));
+
+        press(&mut app, &mut backend, &[KeyCode::Char('r')]);
+        assert!(matches!(
+            backend.commands.last(),
+            Some(Command::Add(url)) if url == "https://example.com/gamma.tar"
+        ));
+        press(&mut app, &mut backend, &[KeyCode::Char('O')]);
+        assert_eq!(backend.opened, vec![PathBuf::from("/tmp")]);
+    }
+
+    #[test]
+    fn test_stats_dashboard() {
+        let mut app = test_app();
+        let mut backend = FakeBackend::default();
+        app.stats
+            .record(chrono::Local::now().naive_local(), (4096, 0));
+
+        press(&mut app, &mut backend, &[KeyCode::Char('a')]);
+        assert_eq!(app.input.mode, InputMode::Stats);
+        assert!(screen(&mut app).contains("Throughput per minute"));
+
+        press(&mut app, &mut backend, &[KeyCode::Char('r')]);
+        assert_eq!(app.stats_resolution, Resolution::Hour);
+        assert!(screen(&mut app).contains("Throughput per hour"));
+
+        press(&mut app, &mut backend, &[KeyCode::Char('e')]);
+        assert_eq!(
+            backend.exported,
+            vec!["throughput.csv", "domains.csv", "types.csv"]
+        );
+        assert_eq!(app.status_message, "Statistics exported to /tmp/stats");
+
+        press(&mut app, &mut backend, &[KeyCode::Esc]);
+        assert_eq!(app.input.mode, InputMode::Normal);
+    }
+
+    /// Local time on Monday 2024-01-01
+    fn monday(hour: u32, minute: u32) -> NaiveDateTime {
+        chrono::NaiveDate::from_ymd_opt(2024, 1, 1)
+            .unwrap()
+            .and_hms_opt(hour, minute, 0)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_schedule_rule_is_validated_and_saved() {
+        let mut app = test_app();
+        let mut backend = FakeBackend::default();
+
+        press(
+            &mut app,
+            &mut backend,
+            &[KeyCode::Char('t'), KeyCode::Char('a')],
+        );
+        assert_eq!(app.input.mode, InputMode::ScheduleEdit);
+        type_text(&mut app, &mut backend, "daily 01:00-07:00 resum");
+        press(&mut app, &mut backend, &[KeyCode::Enter]);
+        assert_eq!(app.input.mode, InputMode::ScheduleEdit);
+        assert!(app.schedule_state.error.is_some());
+        assert!(backend.saved_schedules.is_empty());
+
+        press(
+            &mut app,
+            &mut backend,
+            &[KeyCode::Char('e'), KeyCode::Enter],
+        );
+        assert_eq!(app.input.mode, InputMode::Schedule);
+        assert_eq!(app.scheduler.schedule.rules.len(), 1);
+        assert_eq!(
+            backend.saved_schedules,
+            vec!["rules = [\"daily 01:00-07:00 resume\"]\n"]
+        );
+        assert!(screen(&mut app).contains("daily 01:00-07:00 resume"));
+
+        press(&mut app, &mut backend, &[KeyCode::Char('d'), KeyCode::Esc]);
+        assert!(app.scheduler.schedule.rules.is_empty());
+        assert_eq!(backend.saved_schedules.len(), 2);
+        assert_eq!(app.input.mode, InputMode::Normal);
+    }
+
+    #[test]
+    fn test_start_at_holds_download_until_its_time() {
+        let mut app = test_app();
+        let mut backend = FakeBackend::default();
+        app.clock = monday(0, 0);
+
+        press(
+            &mut app,
+            &mut backend,
+            &[KeyCode::Char('t'), KeyCode::Char('s')],
+        );
+        type_text(&mut app, &mut backend, "03:00");
+        press(&mut app, &mut backend, &[KeyCode::Enter]);
+        assert_eq!(backend.commands, vec![Command::Pause("g1".to_string())]);
+        assert_eq!(app.schedule_note, "start alpha.iso 03:00");
+
+        press(&mut app, &mut backend, &[KeyCode::Esc]);
+        assert!(screen(&mut app).contains("Next: start alpha.iso 03:00"));
+
+        assert!(app.check_schedule(monday(2, 59)).is_empty());
+        let effects = app.check_schedule(monday(3, 0));
+        assert_eq!(
+            effects,
+            vec![Effect::Command(Command::Resume("g1".to_string()))]
+        );
+        assert_eq!(app.log[0].message, "Schedule: starting alpha.iso");
+        assert!(app.schedule_note.is_empty());
+    }
+
+    #[test]
+    fn test_speed_limit_for_selected_download() {
+        let mut app = test_app();
+        let mut backend = FakeBackend::default();
+
+        press(
+            &mut app,
+            &mut backend,
+            &[KeyCode::Char('l'), KeyCode::Char('s'), KeyCode::Right],
+        );
+        assert!(screen(&mut app).contains("Limits for alpha.iso"));
+        press(&mut app, &mut backend, &[KeyCode::Enter]);
+        assert_eq!(
+            backend.commands,
+            vec![
+                Command::FetchSpeedLimits,
+                Command::SetDownloadLimits {
+                    gids: vec!["g1".to_string()],
+                    download: 1024 * 1024,
+                    upload: 0
+                }
+            ]
+        );
+        assert_eq!(app.download_limit, 0); // Global limits untouched
+
+        app.downloads[0].options.download_limit = 1024 * 1024;
+        assert!(screen(&mut app).contains("(cap 1.0 MB/s)"));
+    }
+
+    #[test]
+    fn test_turtle_mode_restores_manual_limits() {
+        let mut app = test_app();
+        let mut backend = FakeBackend::default();
+        app.download_limit = 2048;
+
+        press(&mut app, &mut backend, &[KeyCode::Char('T')]);
+        assert_eq!(
+            backend.commands,
+            vec![Command::SetSpeedLimits {
+                download: 100 * 1024,
+                upload: 20 * 1024
+            }]
+        );
+        assert_eq!(app.profile_note, "Turtle");
+        assert!(screen(&mut app).contains("Limits: Turtle"));
+
+        press(&mut app, &mut backend, &[KeyCode::Char('T')]);
+        assert_eq!(
+            backend.commands[1],
+            Command::SetSpeedLimits {
+                download: 2048,
+                upload: 0
+            }
+        );
+        assert!(app.profile_note.is_empty());
+        assert_eq!(app.status_message, "Turtle mode off");
+    }
+
+    #[test]
+    fn test_status_message_expires() {
+        let mut app = test_app();
+        app.set_status("Hello");
+
+        app.on_tick(Instant::now());
+        assert_eq!(app.status_message, "Hello");
+
+        app.on_tick(Instant::now() + STATUS_TIMEOUT);
+        assert!(app.status_message.is_empty());
+    }
+
+    #[test]
+    fn test_render_lists_current_tab() {
+        let mut app = test_app();
+        let mut backend = FakeBackend::default();
+
+        let text = screen(&mut app);
+        assert!(text.contains("alpha.iso"));
+        assert!(text.contains("beta.zip"));
+        assert!(!text.contains("gamma.tar"));
+
+        press(&mut app, &mut backend, &[KeyCode::Char('3')]);
+        let text = screen(&mut app);
+        assert!(text.contains("gamma.tar"));
+        assert!(!text.contains("alpha.iso"));
+    }
+
+    #[test]
+    fn test_command_line_runs_commands() {
+        let mut app = test_app();
+        let mut backend = FakeBackend::default();
+
+        run_command(&mut app, &mut backend, "limit down 2M");
+        assert_eq!(app.input.mode, InputMode::Normal);
+        assert_eq!(app.download_limit, 2 * 1024 * 1024);
+        assert_eq!(
+            backend.commands.pop(),
+            Some(Command::SetSpeedLimits {
+                download: 2 * 1024 * 1024,
+                upload: 0,
+            })
+        );
+
+        // Pause takes a search query over every tab
+        run_command(&mut app, &mut backend, "pause status:active");
+        assert_eq!(
+            backend.commands.pop(),
+            Some(Command::Batch(
+                BatchAction::Pause,
+                vec!["g1".to_string(), "g2".to_string()]
+            ))
+        );
+        run_command(&mut app, &mut backend, "resume delta");
+        assert_eq!(
+            backend.commands.pop(),
+            Some(Command::Batch(BatchAction::Resume, vec!["g4".to_string()]))
+        );
+
+        run_command(&mut app, &mut backend, "sort size desc");
+        assert_eq!(app.sort_order, SortOrder::Size);
+        assert!(!app.sort_ascending);
+
+        run_command(
+            &mut app,
+            &mut backend,
+            "add https://a.com/x.iso --dir /tmp --out y.iso",
+        );
+        assert_eq!(
+            backend.commands.pop(),
+            Some(Command::AddWithOptions(
+                "https://a.com/x.iso".to_string(),
+                crate::models::DownloadOptions {
+                    dir: Some("/tmp".to_string()),
+                    out: Some("y.iso".to_string()),
+                    ..Default::default()
+                }
+            ))
+        );
+
+        run_command(&mut app, &mut backend, "set max-concurrent-downloads 3");
+        assert_eq!(
+            backend.commands.pop(),
+            Some(Command::SetOption {
+                name: "max-concurrent-downloads".to_string(),
+                value: "3".to_string(),
+            })
+        );
+
+        run_command(&mut app, &mut backend, "export csv /tmp/list.csv");
+        let (path, content) = backend.files.pop().unwrap();
+        assert_eq!(path, PathBuf::from("/tmp/list.csv"));
+        assert_eq!(content.lines().count(), 5);
+        assert!(app.status_message.contains("/tmp/list.csv"));
+
+        run_command(&mut app, &mut backend, "purge");
+        assert_eq!(app.input.mode, InputMode::Confirmation);
+        assert_eq!(app.pending_confirm, Some(ConfirmAction::PurgeCompleted));
+    }
+
+    #[test]
+    fn test_command_line_errors_keep_it_open() {
+        let mut app = test_app();
+        let mut backend = FakeBackend::default();
+
+        run_command(&mut app, &mut backend, "pause nothing-like-this");
+        assert_eq!(app.input.mode, InputMode::Command);
+        assert_eq!(app.input.command_buffer, "pause nothing-like-this");
+        assert_eq!(
+            app.command_error.as_deref(),
+            Some("no downloads match 'nothing-like-this'")
+        );
+        assert!(screen(&mut app).contains("no downloads match"));
+        assert!(backend.commands.is_empty());
+
+        // Typing clears the error; Esc closes the line
+        press(&mut app, &mut backend, &[KeyCode::Backspace]);
+        assert_eq!(app.command_error, None);
+        press(&mut app, &mut backend, &[KeyCode::Esc]);
+        assert_eq!(app.input.mode, InputMode::Normal);
+
+        run_command(&mut app, &mut backend, "theme no-such-theme");
+        assert_eq!(
+            app.command_error.as_deref(),
+            Some("unknown theme 'no-such-theme'")
+        );
+    }
+
+    #[test]
+    fn test_command_line_history_and_completion() {
+        let mut app = test_app();
+        let mut backend = FakeBackend::default();
+
+        press(&mut app, &mut backend, &[KeyCode::Char(':')]);
+        type_text(&mut app, &mut backend, "so");
+        press(&mut app, &mut backend, &[KeyCode::Tab]);
+        assert_eq!(app.input.command_buffer, "sort");
+        assert_eq!(app.command_hint, "sort  sort_direction");
+        type_text(&mut app, &mut backend, " s");
+        press(&mut app, &mut backend, &[KeyCode::Tab]);
+        assert_eq!(app.input.command_buffer, "sort s");
+        assert!(app.command_hint.contains("size"));
+        assert!(app.command_hint.contains("speed"));
+        press(&mut app, &mut backend, &[KeyCode::Esc]);
+
+        run_command(&mut app, &mut backend, "sort speed");
+        press(&mut app, &mut backend, &[KeyCode::Char(':'), KeyCode::Up]);
+        assert_eq!(app.input.command_buffer, "sort speed");
+
+        // Names of normal-mode actions run them
+        press(&mut app, &mut backend, &[KeyCode::Esc]);
+        run_command(&mut app, &mut backend, "help");
+        assert_eq!(app.input.mode, InputMode::Help);
+    }
+
+    #[test]
+    fn test_small_terminal_only_allows_quit() {
+        let mut app = test_app();
+        let mut backend = FakeBackend::default();
+        let mut terminal = Terminal::new(TestBackend::new(50, 12)).unwrap();
+        terminal.draw(|f| app.draw(f)).unwrap();
+        assert!(app.too_small);
+
+        press(&mut app, &mut backend, &[KeyCode::Char('i')]);
+        assert_eq!(app.input.mode, InputMode::Normal);
+        press(&mut app, &mut backend, &[KeyCode::Char('q')]);
+        assert!(app.should_quit);
+    }
+
+    #[test]
+    fn test_compact_layout_on_small_terminals() {
+        let mut app = test_app();
+        let mut backend = FakeBackend::default();
+
+        // Tab strip, list and one-line bars instead of sidebar and banner
+        let text = screen_sized(&mut app, 60, 15);
+        assert!(!app.too_small);
+        assert!(text.contains("Active 2"));
+        assert!(text.contains("alpha.iso"));
+        assert!(text.contains("[Downloads]"));
+        assert!(!text.contains("Categories"));
+        assert!(!text.contains("Details"));
+        assert!(!text.contains("Add Download"));
+        assert_eq!(app.hits.tabs.len(), 4);
+
+        // Clicking the strip switches tabs
+        let tab = app.hits.tabs[2].0;
+        click(
+            &mut app,
+            &mut backend,
+            LEFT,
+            tab,
+            KeyModifiers::NONE,
+            Instant::now(),
+        );
+        assert_eq!(app.current_tab, 2);
+        assert!(screen_sized(&mut app, 60, 15).contains("gamma.tar"));
+
+        // Enter opens the details over the list, and closes them again
+        press(&mut app, &mut backend, &[KeyCode::Enter]);
+        let text = screen_sized(&mut app, 60, 15);
+        assert!(text.contains("Details"));
+        assert!(app.hits.rows.is_empty());
+        press(&mut app, &mut backend, &[KeyCode::Enter]);
+        screen_sized(&mut app, 60, 15);
+        assert!(!app.hits.rows.is_empty());
+
+        // The input field shows up while typing
+        press(&mut app, &mut backend, &[KeyCode::Char('i')]);
+        assert!(screen_sized(&mut app, 60, 15).contains("Add Download"));
+        press(&mut app, &mut backend, &[KeyCode::Esc]);
+
+        // Popups still open and close at the smallest size
+        for key in ['?', 'e', 'a', 't', 'l'] {
+            press(&mut app, &mut backend, &[KeyCode::Char(key)]);
+            assert_ne!(app.input.mode, InputMode::Normal);
+            screen_sized(&mut app, 60, 15);
+            press(&mut app, &mut backend, &[KeyCode::Esc]);
+            assert_eq!(app.input.mode, InputMode::Normal);
+        }
+    }
+
+    #[test]
+    fn test_full_layout_keeps_details_beside_the_list() {
+        let mut app = test_app();
+        let text = screen(&mut app);
+        assert!(text.contains("Categories"));
+        assert!(text.contains("TUI Downloader"));
+        assert!(text.contains("Details"));
+
+        // Wide but short: the banner goes, the sidebar and details stay
+        let text = screen_sized(&mut app, 100, 20);
+        assert!(text.contains("Categories"));
+        assert!(!text.contains("TUI Downloader"));
+        assert!(text.contains("Details"));
+    }
+}
@@ -19,6 +19,8 @@ pub struct Aria2Status {
     pub download_speed: String,
     #[serde(rename = "uploadSpeed")]
     pub upload_speed: String,
+    #[serde(rename = "uploadLength")]
+    pub upload_length: Option<String>,
     pub connections: String,
     #[serde(rename = "errorCode")]
     pub error_code: Option<String>,
@@ -31,6 +33,12 @@ pub struct Aria2Status {
     #[serde(rename = "numPeers")]
     pub num_peers: Option<String>,
     pub seeder: Option<String>,
+    /// Bytes hash-checked so far, present only while verifying
+    #[serde(rename = "verifiedLength")]
+    pub verified_length: Option<String>,
+    /// "true" while waiting for a hash check
+    #[serde(rename = "verifyIntegrityPending")]
+    pub verify_integrity_pending: Option<String>,
     /// Hexadecimal representation of download progress by piece
     pub bitfield: Option<String>,
     /// Total number of pieces
@@ -110,6 +118,17 @@ impl Aria2Manager {
         Ok(manager)
     }
 
+    /// Talk to an RPC endpoint that is already listening, without spawning aria2c
+    #[cfg(test)]
+    pub fn connect(rpc_url: String, secret: &str) -> Self {
+        Self {
+            process: Arc::new(Mutex::new(None)),
+            rpc_url,
+            secret: secret.to_string(),
+            client: reqwest::Client::new(),
+        }
+    }
+
     /// Spawns aria2c process with proper configuration
     async fn spawn_aria2c(&self) -> Result<(), Box<dyn std::error::Error>> {
         // Get or create Downloads directory
@@ -121,7 +140,7 @@ impl Aria2Manager {
         std::fs::create_dir_all(&download_dir)?;
 
         let child = Command::new("aria2c")
-            .args(&[
+            .args([
                 "--enable-rpc",
                 "--rpc-listen-all=false",
                 &format!("--rpc-listen-port={}", ARIA2C_RPC_PORT),
@@ -165,11 +184,43 @@ impl Aria2Manager {
         let mut rpc_params = vec![json!(format!("token:{}", self.secret))];
         rpc_params.extend(params);
 
+        self.send_request(method, rpc_params).await
+    }
+
+    /// Run several calls in one round trip with `system.multicall`
+    ///
+    /// Returns one result per call, in order.
+    pub async fn multicall(
+        &self,
+        calls: Vec<(&str, Vec<Value>)>,
+    ) -> Result<Vec<Result<Value, String>>, Box<dyn std::error::Error>> {
+        let calls: Vec<Value> = calls
+            .into_iter()
+            .map(|(method, params)| {
+                let mut rpc_params = vec![json!(format!("token:{}", self.secret))];
+                rpc_params.extend(params);
+                json!({ "methodName": method, "params": rpc_params })
+            })
+            .collect();
+        let count = calls.len();
+
+        let result = self
+            .send_request("system.multicall", vec![json!(calls)])
+            .await?;
+        Ok(parse_multicall(&result, count))
+    }
+
+    /// Send a JSON-RPC request with the given params as-is
+    async fn send_request(
+        &self,
+        method: &str,
+        params: Vec<Value>,
+    ) -> Result<Value, Box<dyn std::error::Error>> {
         let payload = json!({
             "jsonrpc": "2.0",
             "id": uuid::Uuid::new_v4().to_string(),
             "method": method,
-            "params": rpc_params,
+            "params": params,
         });
 
         let response = self
@@ -189,9 +240,15 @@ impl Aria2Manager {
     }
 
     /// Add a URL download
-    pub async fn add_uri(&self, uri: &str) -> Result<String, Box<dyn std::error::Error>> {
+    pub async fn add_uri(
+        &self,
+        uri: &str,
+        options: Value,
+    ) -> Result<String, Box<dyn std::error::Error>> {
         let uris = vec![json!(uri)];
-        let result = self.call_method("aria2.addUri", vec![json!(uris)]).await?;
+        let result = self
+            .call_method("aria2.addUri", vec![json!(uris), options])
+            .await?;
 
         Ok(result.as_str().unwrap_or("").to_string())
     }
@@ -200,13 +257,14 @@ impl Aria2Manager {
     pub async fn add_torrent(
         &self,
         torrent_path: &str,
+        options: Value,
     ) -> Result<String, Box<dyn std::error::Error>> {
         // Read torrent file and encode as base64
         let torrent_data = tokio::fs::read(torrent_path).await?;
         let encoded = base64::encode(&torrent_data);
 
         let result = self
-            .call_method("aria2.addTorrent", vec![json!(encoded)])
+            .call_method("aria2.addTorrent", vec![json!(encoded), json!([]), options])
             .await?;
 
         Ok(result.as_str().unwrap_or("").to_string())
@@ -216,12 +274,13 @@ impl Aria2Manager {
     pub async fn add_metalink(
         &self,
         metalink_path: &str,
+        options: Value,
     ) -> Result<String, Box<dyn std::error::Error>> {
         let metalink_data = tokio::fs::read(metalink_path).await?;
         let encoded = base64::encode(&metalink_data);
 
         let result = self
-            .call_method("aria2.addMetalink", vec![json!(encoded)])
+            .call_method("aria2.addMetalink", vec![json!(encoded), options])
             .await?;
 
         Ok(result.as_str().unwrap_or("").to_string())
@@ -249,6 +308,12 @@ impl Aria2Manager {
                         "numSeeders",
                         "numPeers",
                         "seeder",
+                        "uploadLength",
+                        "verifiedLength",
+                        "verifyIntegrityPending",
+                        "uploadLength",
+                        "verifiedLength",
+                        "verifyIntegrityPending",
                         "bitfield",
                         "numPieces"
                     ]),
@@ -286,6 +351,9 @@ impl Aria2Manager {
                     "numSeeders",
                     "numPeers",
                     "seeder",
+                    "uploadLength",
+                    "verifiedLength",
+                    "verifyIntegrityPending",
                     "bitfield",
                     "numPieces"
                 ])],
@@ -321,6 +389,12 @@ impl Aria2Manager {
                         "numSeeders",
                         "numPeers",
                         "seeder",
+                        "uploadLength",
+                        "verifiedLength",
+                        "verifyIntegrityPending",
+                        "uploadLength",
+                        "verifiedLength",
+                        "verifyIntegrityPending",
                         "bitfield",
                         "numPieces"
                     ]),
@@ -357,6 +431,12 @@ impl Aria2Manager {
                         "numSeeders",
                         "numPeers",
                         "seeder",
+                        "uploadLength",
+                        "verifiedLength",
+                        "verifyIntegrityPending",
+                        "uploadLength",
+                        "verifiedLength",
+                        "verifyIntegrityPending",
                         "bitfield",
                         "numPieces"
                     ]),
@@ -372,6 +452,14 @@ impl Aria2Manager {
         Ok(result.as_str().unwrap_or("").to_string())
     }
 
+    /// Pause a download without waiting for servers to be contacted
+    pub async fn force_pause(&self, gid: &str) -> Result<String, Box<dyn std::error::Error>> {
+        let result = self
+            .call_method("aria2.forcePause", vec![json!(gid)])
+            .await?;
+        Ok(result.as_str().unwrap_or("").to_string())
+    }
+
     /// Pause all downloads
     pub async fn pause_all(&self) -> Result<String, Box<dyn std::error::Error>> {
         let result = self.call_method("aria2.pauseAll", vec![]).await?;
@@ -478,6 +566,26 @@ impl Aria2Manager {
         self.call_method("aria2.getServers", vec![json!(gid)]).await
     }
 
+    /// Move the first URI of a download to the end, so the next mirror is tried first
+    pub async fn rotate_uris(&self, gid: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let result = self.call_method("aria2.getUris", vec![json!(gid)]).await?;
+        let uris: Vec<&str> = result
+            .as_array()
+            .map(|uris| uris.iter().filter_map(|u| u["uri"].as_str()).collect())
+            .unwrap_or_default();
+        if uris.len() < 2 {
+            return Err("No other mirror to switch to".into());
+        }
+
+        // Deleted URIs are removed before added ones are appended
+        self.call_method(
+            "aria2.changeUri",
+            vec![json!(gid), json!(1), json!([uris[0]]), json!([uris[0]])],
+        )
+        .await?;
+        Ok(())
+    }
+
     /// Set options for a specific download
     pub async fn change_option(
         &self,
@@ -541,3 +649,37 @@ mod base64 {
         String::from_utf8(buf).unwrap()
     }
 }
+
+/// Split a `system.multicall` result into per-call results
+///
+/// Successful calls are wrapped in a one-element array, failed ones are
+/// `{"code": .., "message": ..}` objects.
+fn parse_multicall(result: &Value, count: usize) -> Vec<Result<Value, String>> {
+    let items = result.as_array().cloned().unwrap_or_default();
+    (0..count)
+        .map(|i| match items.get(i) {
+            Some(Value::Array(values)) => Ok(values.first().cloned().unwrap_or(Value::Null)),
+            Some(fault) => Err(fault
+                .get("message")
+                .and_then(|m| m.as_str())
+                .unwrap_or("Unknown error")
+                .to_string()),
+            None => Err("Missing result".to_string()),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_multicall() {
+        let result = json!([["2089b05ecca3d829"], {"code": 1, "message": "GID not found"}]);
+        let parsed = parse_multicall(&result, 3);
+
+        assert_eq!(parsed[0], Ok(json!("2089b05ecca3d829")));
+        assert_eq!(parsed[1], Err("GID not found".to_string()));
+        assert!(parsed[2].is_err());
+    }
+}
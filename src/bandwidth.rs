@@ -0,0 +1,310 @@
+//! Named speed-limit profiles switched by time of day
+//!
+//! Profiles are pairs of global limits from `[[bandwidth.profiles]]`.
+//! `[[bandwidth.schedule]]` entries pick a profile for weekly time windows,
+//! and outside every window the `default` profile applies. Turtle mode
+//! overrides the schedule with the `turtle` profile until it is switched off.
+//! Without a schedule, limits set by hand are restored when turtle mode ends.
+
+use crate::config::BandwidthConfig;
+use crate::scheduler::TimeWindow;
+use crate::ui::format_speed;
+use crate::units::parse_speed_limit;
+use chrono::NaiveDateTime;
+
+/// Built-in profile without limits
+pub const UNLIMITED: &str = "Unlimited";
+
+/// Built-in profile for turtle mode
+pub const TURTLE: &str = "Turtle";
+
+const TURTLE_DOWNLOAD: u64 = 100 * 1024;
+const TURTLE_UPLOAD: u64 = 20 * 1024;
+
+/// Named pair of global limits in bytes per second (0 = unlimited)
+#[derive(Clone, Debug, PartialEq)]
+pub struct SpeedProfile {
+    pub name: String,
+    pub download: u64,
+    pub upload: u64,
+}
+
+impl SpeedProfile {
+    /// e.g. `Work hours (500.00 KB/s down, 50.00 KB/s up)`
+    pub fn describe(&self) -> String {
+        let limit = |bytes: u64| {
+            if bytes == 0 {
+                "unlimited".to_string()
+            } else {
+                format_speed(bytes)
+            }
+        };
+        format!(
+            "{} ({} down, {} up)",
+            self.name,
+            limit(self.download),
+            limit(self.upload)
+        )
+    }
+}
+
+/// Profiles, their schedule and turtle mode
+#[derive(Clone, Debug)]
+pub struct Bandwidth {
+    profiles: Vec<SpeedProfile>,
+    rules: Vec<(TimeWindow, String)>, // Window and the profile used inside it
+    default: String,
+    turtle_profile: String,
+    pub turtle: bool,
+    active: Option<String>, // Profile applied last, `None` while limits are set by hand
+    manual: (u64, u64),     // Limits set by hand before a profile took over
+}
+
+impl Default for Bandwidth {
+    fn default() -> Self {
+        Self::from_config(&BandwidthConfig::default()).expect("default profiles are valid")
+    }
+}
+
+impl Bandwidth {
+    /// Build from configuration, checking limits, windows and profile names
+    pub fn from_config(config: &BandwidthConfig) -> Result<Self, String> {
+        let mut profiles = vec![
+            SpeedProfile {
+                name: UNLIMITED.to_string(),
+                download: 0,
+                upload: 0,
+            },
+            SpeedProfile {
+                name: TURTLE.to_string(),
+                download: TURTLE_DOWNLOAD,
+                upload: TURTLE_UPLOAD,
+            },
+        ];
+        for entry in &config.profiles {
+            let limit = |text: &str| {
+                parse_speed_limit(text)
+                    .ok_or_else(|| format!("profile '{}': invalid limit '{}'", entry.name, text))
+            };
+            let profile = SpeedProfile {
+                name: entry.name.clone(),
+                download: limit(&entry.download)?,
+                upload: limit(&entry.upload)?,
+            };
+            // Configured profiles replace built-in ones of the same name
+            profiles.retain(|p| p.name != profile.name);
+            profiles.push(profile);
+        }
+
+        let known = |name: &str| {
+            if profiles.iter().any(|p| p.name == name) {
+                Ok(name.to_string())
+            } else {
+                Err(format!("unknown profile '{}'", name))
+            }
+        };
+        let rules = config
+            .schedule
+            .iter()
+            .map(|rule| {
+                let window = TimeWindow::parse(&rule.when)
+                    .map_err(|e| format!("schedule '{}': {}", rule.when, e))?;
+                Ok((window, known(&rule.profile)?))
+            })
+            .collect::<Result<_, String>>()?;
+
+        Ok(Self {
+            default: known(&config.default)?,
+            turtle_profile: known(&config.turtle)?,
+            profiles,
+            rules,
+            turtle: false,
+            active: None,
+            manual: (0, 0),
+        })
+    }
+
+    /// Profile by name
+    pub fn profile(&self, name: &str) -> Option<&SpeedProfile> {
+        self.profiles.iter().find(|p| p.name == name)
+    }
+
+    /// Profile applied last, `None` while limits are set by hand
+    pub fn active(&self) -> Option<&SpeedProfile> {
+        self.profile(self.active.as_deref()?)
+    }
+
+    /// Limits to apply at `now`, if the wanted profile changed
+    ///
+    /// `current` are the limits in effect; they are restored once no profile
+    /// is wanted any more.
+    pub fn check(&mut self, now: NaiveDateTime, current: (u64, u64)) -> Option<(u64, u64)> {
+        let wanted = self.wanted(now).map(str::to_string);
+        if wanted == self.active {
+            return None;
+        }
+        if self.active.is_none() {
+            self.manual = current;
+        }
+        self.active = wanted;
+        Some(match self.active() {
+            Some(profile) => (profile.download, profile.upload),
+            None => self.manual,
+        })
+    }
+
+    /// Next profile switch after `now` and the profile it switches to
+    ///
+    /// Turtle mode lasts until it is switched off, so it has no next change.
+    pub fn next_change(&self, now: NaiveDateTime) -> Option<(NaiveDateTime, &str)> {
+        if self.turtle {
+            return None;
+        }
+        let current = self.wanted(now);
+        let mut boundaries: Vec<NaiveDateTime> = self
+            .rules
+            .iter()
+            .flat_map(|(window, _)| window.boundaries(now))
+            .collect();
+        boundaries.sort();
+        boundaries
+            .into_iter()
+            .find(|t| self.wanted(*t) != current)
+            .and_then(|t| Some((t, self.wanted(t)?)))
+    }
+
+    /// Profile that should be in effect at `now`
+    fn wanted(&self, now: NaiveDateTime) -> Option<&str> {
+        if self.turtle {
+            return Some(&self.turtle_profile);
+        }
+        if self.rules.is_empty() {
+            return None;
+        }
+        let profile = self
+            .rules
+            .iter()
+            .find(|(window, _)| window.contains(now))
+            .map_or(&self.default, |(_, profile)| profile);
+        Some(profile)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use chrono::NaiveDate;
+
+    // 2024-01-01 is a Monday
+    fn at(day: u32, hour: u32, minute: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2024, 1, day)
+            .unwrap()
+            .and_hms_opt(hour, minute, 0)
+            .unwrap()
+    }
+
+    fn work_hours() -> Bandwidth {
+        let config = Config::parse(
+            r#"
+            [[bandwidth.profiles]]
+            name = "Work hours"
+            download = "500K"
+            upload = "50K"
+
+            [[bandwidth.schedule]]
+            when = "weekdays 09:00-17:00"
+            profile = "Work hours"
+            "#,
+        )
+        .unwrap();
+        Bandwidth::from_config(&config.bandwidth).unwrap()
+    }
+
+    #[test]
+    fn test_schedule_switches_profiles() {
+        let mut bandwidth = work_hours();
+
+        // The first check applies the profile for the current time
+        assert_eq!(
+            bandwidth.check(at(1, 10, 0), (0, 0)),
+            Some((500 * 1024, 50 * 1024))
+        );
+        assert_eq!(bandwidth.active().unwrap().name, "Work hours");
+        assert_eq!(bandwidth.check(at(1, 12, 0), (0, 0)), None);
+
+        assert_eq!(bandwidth.check(at(1, 17, 0), (0, 0)), Some((0, 0)));
+        assert_eq!(bandwidth.active().unwrap().name, UNLIMITED);
+    }
+
+    #[test]
+    fn test_next_change() {
+        let bandwidth = work_hours();
+        assert_eq!(
+            bandwidth.next_change(at(1, 10, 0)),
+            Some((at(1, 17, 0), UNLIMITED))
+        );
+        assert_eq!(
+            bandwidth.next_change(at(5, 18, 0)),
+            Some((at(8, 9, 0), "Work hours"))
+        );
+    }
+
+    #[test]
+    fn test_turtle_mode_restores_manual_limits() {
+        let mut bandwidth = Bandwidth::default();
+        assert_eq!(bandwidth.check(at(1, 10, 0), (2048, 1024)), None);
+
+        bandwidth.turtle = true;
+        assert_eq!(
+            bandwidth.check(at(1, 10, 0), (2048, 1024)),
+            Some((TURTLE_DOWNLOAD, TURTLE_UPLOAD))
+        );
+        assert_eq!(bandwidth.next_change(at(1, 10, 0)), None);
+
+        bandwidth.turtle = false;
+        assert_eq!(bandwidth.check(at(1, 10, 0), (0, 0)), Some((2048, 1024)));
+        assert!(bandwidth.active().is_none());
+    }
+
+    #[test]
+    fn test_turtle_mode_overrides_schedule() {
+        let mut bandwidth = work_hours();
+        bandwidth.check(at(1, 10, 0), (0, 0));
+
+        bandwidth.turtle = true;
+        assert_eq!(
+            bandwidth.check(at(1, 10, 0), (0, 0)),
+            Some((TURTLE_DOWNLOAD, TURTLE_UPLOAD))
+        );
+        bandwidth.turtle = false;
+        assert_eq!(
+            bandwidth.check(at(1, 10, 0), (0, 0)),
+            Some((500 * 1024, 50 * 1024))
+        );
+    }
+
+    #[test]
+    fn test_invalid_configuration() {
+        let parse = |toml: &str| Config::parse(toml).map(|_| ());
+        assert!(parse("[bandwidth]\ndefault = \"Nope\"").is_err());
+        assert!(parse("[[bandwidth.profiles]]\nname = \"x\"\ndownload = \"fast\"").is_err());
+        assert!(parse(
+            "[[bandwidth.schedule]]\nwhen = \"someday 01:00-02:00\"\nprofile = \"Turtle\""
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_describe() {
+        let bandwidth = Bandwidth::default();
+        assert_eq!(
+            bandwidth.profile(TURTLE).unwrap().describe(),
+            "Turtle (100.00 KB/s down, 20.00 KB/s up)"
+        );
+        assert_eq!(
+            bandwidth.profile(UNLIMITED).unwrap().describe(),
+            "Unlimited (unlimited down, unlimited up)"
+        );
+    }
+}
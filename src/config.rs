@@ -0,0 +1,562 @@
+//! User configuration loaded from `config.toml`
+//!
+//! The file lives in the platform config directory
+//! (`~/.config/tui-downloader/config.toml` on Linux). Every section is
+//! optional; missing keys fall back to their defaults.
+
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::net::{IpAddr, SocketAddr};
+use std::path::PathBuf;
+use std::time::Duration;
+
+const CONFIG_DIR: &str = "tui-downloader";
+const CONFIG_FILE: &str = "config.toml";
+
+/// Top-level configuration
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub browser: BrowserConfig,
+    pub api: ApiConfig,
+    pub metrics: MetricsConfig,
+    pub hooks: Vec<HookConfig>,
+    pub notifications: NotificationsConfig,
+    pub retry: RetryConfig,
+    pub watchdog: WatchdogConfig,
+    pub bandwidth: BandwidthConfig,
+    pub quota: QuotaConfig,
+    pub keys: KeysConfig,
+    pub ui: UiConfig,
+}
+
+/// Loopback endpoint used by browser extensions to hand over downloads
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct BrowserConfig {
+    pub enabled: bool,
+    pub port: u16,
+    pub token: String,
+    pub confirm: bool,       // Ask before adding downloads sent by the browser
+    pub cors_origin: String, // Web origin allowed to call the endpoint, none if empty
+}
+
+impl Default for BrowserConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            port: 6801,
+            token: String::new(),
+            confirm: false,
+            cors_origin: String::new(),
+        }
+    }
+}
+
+/// HTTP API for scripts and automation
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct ApiConfig {
+    pub enabled: bool,
+    pub bind: String, // Address to listen on, loopback by default
+    pub port: u16,
+    pub token: String,
+    pub cors_origin: String, // Web origin allowed to call the API, none if empty
+}
+
+impl Default for ApiConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            bind: "127.0.0.1".to_string(),
+            port: 6802,
+            token: String::new(),
+            cors_origin: String::new(),
+        }
+    }
+}
+
+impl ApiConfig {
+    /// Socket address to listen on
+    pub fn address(&self) -> Result<SocketAddr, Box<dyn std::error::Error>> {
+        socket_address("api", &self.bind, self.port)
+    }
+}
+
+/// Prometheus `/metrics` endpoint
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct MetricsConfig {
+    pub enabled: bool,
+    pub bind: String,
+    pub port: u16,
+    pub token: String, // Optional; required as a bearer token when set
+}
+
+impl Default for MetricsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            bind: "127.0.0.1".to_string(),
+            port: 9101,
+            token: String::new(),
+        }
+    }
+}
+
+impl MetricsConfig {
+    /// Socket address to listen on
+    pub fn address(&self) -> Result<SocketAddr, Box<dyn std::error::Error>> {
+        socket_address("metrics", &self.bind, self.port)
+    }
+}
+
+/// Automatic retries of failed downloads
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct RetryConfig {
+    pub max_attempts: u32,            // 0 disables automatic retries
+    pub initial_delay: u64,           // Seconds before the first retry
+    pub backoff: f64,                 // Delay multiplier for each further attempt
+    pub max_delay: u64,               // Upper bound for the delay, in seconds
+    pub transient_codes: Vec<String>, // aria2 exit codes worth retrying
+}
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            initial_delay: 10,
+            backoff: 2.0,
+            max_delay: 600,
+            // Unknown error, timeout, too slow, network problem, name resolution
+            // failed, bad HTTP response, server overloaded (503)
+            transient_codes: ["1", "2", "5", "6", "19", "22", "29"]
+                .iter()
+                .map(|c| c.to_string())
+                .collect(),
+        }
+    }
+}
+
+impl RetryConfig {
+    /// Whether a download that failed with `code` after `attempts` retries gets another one
+    pub fn should_retry(&self, code: Option<&str>, attempts: u32) -> bool {
+        attempts < self.max_attempts
+            && code.is_some_and(|code| self.transient_codes.iter().any(|c| c == code))
+    }
+
+    /// Wait before the retry that follows `attempts` earlier retries
+    pub fn delay(&self, attempts: u32) -> Duration {
+        let secs = self.initial_delay as f64 * self.backoff.powi(attempts as i32);
+        Duration::from_secs_f64(secs.min(self.max_delay as f64))
+    }
+}
+
+/// What the watchdog does with a stalled download
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StallAction {
+    #[default]
+    Notify, // Only flag it
+    Reconnect,    // Pause and unpause to open fresh connections
+    SwitchMirror, // Try the next URI first, then reconnect
+    Restart,      // Add it again, resuming the partial file
+}
+
+/// Detection of downloads that sit active without receiving data
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct WatchdogConfig {
+    pub enabled: bool,
+    pub min_speed: u64, // Bytes per second below which a download counts as slow
+    pub minutes: u64,   // How long it must stay slow to count as stalled
+    pub action: StallAction,
+}
+
+impl Default for WatchdogConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            min_speed: 1024,
+            minutes: 5,
+            action: StallAction::Notify,
+        }
+    }
+}
+
+impl WatchdogConfig {
+    /// How long a download must stay slow to count as stalled
+    pub fn window(&self) -> Duration {
+        Duration::from_secs(self.minutes * 60)
+    }
+}
+
+/// Named pair of global speed limits, one `[[bandwidth.profiles]]` entry
+#[derive(Clone, Debug, Deserialize)]
+pub struct SpeedProfileConfig {
+    pub name: String,
+    #[serde(default)]
+    pub download: String, // e.g. "500K" or "2M"; empty or "0" for unlimited
+    #[serde(default)]
+    pub upload: String,
+}
+
+/// Profile used during a weekly time window, one `[[bandwidth.schedule]]` entry
+#[derive(Clone, Debug, Deserialize)]
+pub struct BandwidthRuleConfig {
+    pub when: String, // e.g. "weekdays 09:00-17:00"
+    pub profile: String,
+}
+
+/// Speed-limit profiles, their weekly schedule and turtle mode
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct BandwidthConfig {
+    pub profiles: Vec<SpeedProfileConfig>,
+    pub schedule: Vec<BandwidthRuleConfig>,
+    pub default: String, // Profile outside every window of the schedule
+    pub turtle: String,  // Profile used by turtle mode
+}
+
+impl Default for BandwidthConfig {
+    fn default() -> Self {
+        Self {
+            profiles: Vec::new(),
+            schedule: Vec::new(),
+            default: crate::bandwidth::UNLIMITED.to_string(),
+            turtle: crate::bandwidth::TURTLE.to_string(),
+        }
+    }
+}
+
+/// Key bindings: `[keys.<mode>]` tables of `action = "key"` or `action = ["key", ...]`
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(transparent)]
+pub struct KeysConfig(pub BTreeMap<String, BTreeMap<String, KeyList>>);
+
+/// One key or several for an action; an empty list leaves it without a key
+#[derive(Clone, Debug, Deserialize)]
+#[serde(untagged)]
+pub enum KeyList {
+    One(String),
+    Many(Vec<String>),
+}
+
+/// Appearance
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct UiConfig {
+    pub theme: String, // A preset or the name of a file in the `themes` directory
+}
+
+impl Default for UiConfig {
+    fn default() -> Self {
+        Self {
+            theme: "dark".to_string(),
+        }
+    }
+}
+
+/// Data caps for metered connections
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct QuotaConfig {
+    pub daily: String, // e.g. "2G"; empty or "0" for no daily quota
+    pub monthly: String,
+    pub count_upload: bool, // Uploaded bytes count toward the quotas too
+    pub warn_at: Vec<u8>,   // Percentages of a quota that produce a warning
+}
+
+impl Default for QuotaConfig {
+    fn default() -> Self {
+        Self {
+            daily: String::new(),
+            monthly: String::new(),
+            count_upload: true,
+            warn_at: vec![80, 90],
+        }
+    }
+}
+
+/// Download event a hook runs on
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HookEvent {
+    Start,
+    Complete,
+    Error,
+}
+
+/// Command run on a download event, one `[[hooks]]` entry
+#[derive(Clone, Debug, Deserialize)]
+pub struct HookConfig {
+    pub event: HookEvent,
+    pub command: Vec<String>, // Program and arguments, with `{placeholders}`
+    #[serde(default)]
+    pub pattern: Option<String>, // Only run for names matching this glob
+    #[serde(default = "default_hook_timeout")]
+    pub timeout: u64, // Seconds before the command is killed
+}
+
+fn default_hook_timeout() -> u64 {
+    60
+}
+
+/// Event that can raise a notification
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NotifyEvent {
+    Complete,
+    Error,
+    QueueDrained, // Nothing is left running or waiting
+}
+
+/// Notifications for events that happen while the user looks elsewhere
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct NotificationsConfig {
+    pub desktop: Vec<NotifyEvent>,  // freedesktop notifications over D-Bus
+    pub terminal: Vec<NotifyEvent>, // OSC escape sequence understood by the terminal
+    pub bell: Vec<NotifyEvent>,
+    pub osc: u16,   // 9 (iTerm2, Windows Terminal, kitty) or 777 (rxvt, foot, Ghostty)
+    pub batch: u64, // Seconds to collect events into one notification
+}
+
+impl Default for NotificationsConfig {
+    fn default() -> Self {
+        Self {
+            desktop: Vec::new(),
+            terminal: Vec::new(),
+            bell: Vec::new(),
+            osc: 9,
+            batch: 3,
+        }
+    }
+}
+
+impl NotificationsConfig {
+    /// Whether any backend is configured
+    pub fn enabled(&self) -> bool {
+        !(self.desktop.is_empty() && self.terminal.is_empty() && self.bell.is_empty())
+    }
+}
+
+fn socket_address(
+    section: &str,
+    bind: &str,
+    port: u16,
+) -> Result<SocketAddr, Box<dyn std::error::Error>> {
+    let ip: IpAddr = bind
+        .parse()
+        .map_err(|_| format!("[{}] invalid bind address '{}'", section, bind))?;
+    Ok(SocketAddr::new(ip, port))
+}
+
+impl Config {
+    /// Directory holding the configuration file and files the app writes itself
+    pub fn dir() -> PathBuf {
+        dirs::config_dir()
+            .unwrap_or_else(|| PathBuf::from("."))
+            .join(CONFIG_DIR)
+    }
+
+    /// Directory for data the app keeps, such as the download history
+    pub fn data_dir() -> PathBuf {
+        dirs::data_dir()
+            .unwrap_or_else(|| PathBuf::from("."))
+            .join(CONFIG_DIR)
+    }
+
+    /// Path of the configuration file
+    pub fn path() -> PathBuf {
+        Self::dir().join(CONFIG_FILE)
+    }
+
+    /// Load configuration from the default path (defaults if the file is missing)
+    pub fn load() -> Result<Self, Box<dyn std::error::Error>> {
+        let path = Self::path();
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let content = std::fs::read_to_string(&path)?;
+        Self::parse(&content).map_err(|e| format!("{}: {}", path.display(), e).into())
+    }
+
+    /// Parse and validate configuration from TOML text
+    pub fn parse(content: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        let config: Config = toml::from_str(content)?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    fn validate(&self) -> Result<(), Box<dyn std::error::Error>> {
+        if self.browser.enabled && self.browser.token.is_empty() {
+            return Err("[browser] is enabled but no token is set".into());
+        }
+        if self.api.enabled {
+            if self.api.token.is_empty() {
+                return Err("[api] is enabled but no token is set".into());
+            }
+            self.api.address()?;
+        }
+        if self.metrics.enabled {
+            self.metrics.address()?;
+        }
+        if !matches!(self.notifications.osc, 9 | 777) {
+            return Err("[notifications] osc must be 9 or 777".into());
+        }
+        if self.watchdog.enabled && self.watchdog.minutes == 0 {
+            return Err("[watchdog] minutes must be at least 1".into());
+        }
+        if self.retry.backoff < 1.0 {
+            return Err("[retry] backoff must be at least 1.0".into());
+        }
+        crate::bandwidth::Bandwidth::from_config(&self.bandwidth)
+            .map_err(|e| format!("[bandwidth] {}", e))?;
+        crate::usage::Quota::from_config(&self.quota).map_err(|e| format!("[quota] {}", e))?;
+        crate::keymap::Keymap::from_config(&self.keys)?;
+        for hook in &self.hooks {
+            if hook
+                .command
+                .first()
+                .is_none_or(|program| program.is_empty())
+            {
+                return Err("[[hooks]] command must name a program".into());
+            }
+            if hook.timeout == 0 {
+                return Err("[[hooks]] timeout must be at least 1 second".into());
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_empty_config_uses_defaults() {
+        let config = Config::parse("").unwrap();
+        assert!(!config.browser.enabled);
+        assert_eq!(config.browser.port, 6801);
+        assert!(!config.browser.confirm);
+    }
+
+    #[test]
+    fn test_browser_section() {
+        let config = Config::parse(
+            r#"
+            [browser]
+            enabled = true
+            port = 7000
+            token = "secret"
+            confirm = true
+            "#,
+        )
+        .unwrap();
+        assert!(config.browser.enabled);
+        assert_eq!(config.browser.port, 7000);
+        assert_eq!(config.browser.token, "secret");
+        assert!(config.browser.confirm);
+    }
+
+    #[test]
+    fn test_browser_requires_token() {
+        assert!(Config::parse("[browser]\nenabled = true").is_err());
+    }
+
+    #[test]
+    fn test_api_section() {
+        let config = Config::parse("[api]\nenabled = true\ntoken = \"t\"").unwrap();
+        assert_eq!(config.api.address().unwrap().to_string(), "127.0.0.1:6802");
+
+        assert!(Config::parse("[api]\nenabled = true").is_err());
+        assert!(Config::parse("[api]\nenabled = true\ntoken = \"t\"\nbind = \"nope\"").is_err());
+    }
+
+    #[test]
+    fn test_hooks_section() {
+        let config = Config::parse(
+            r#"
+            [[hooks]]
+            event = "complete"
+            command = ["clamscan", "{path}"]
+            pattern = "*.iso"
+
+            [[hooks]]
+            event = "error"
+            command = ["notify-send", "{name} failed"]
+            timeout = 5
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.hooks.len(), 2);
+        assert_eq!(config.hooks[0].event, HookEvent::Complete);
+        assert_eq!(config.hooks[0].pattern.as_deref(), Some("*.iso"));
+        assert_eq!(config.hooks[0].timeout, 60);
+        assert_eq!(config.hooks[1].timeout, 5);
+
+        assert!(Config::parse("[[hooks]]\nevent = \"complete\"\ncommand = []").is_err());
+        assert!(Config::parse("[[hooks]]\nevent = \"paused\"\ncommand = [\"x\"]").is_err());
+    }
+
+    #[test]
+    fn test_notifications_section() {
+        let config = Config::parse("").unwrap();
+        assert!(!config.notifications.enabled());
+
+        let config = Config::parse(
+            r#"
+            [notifications]
+            desktop = ["complete", "error", "queue_drained"]
+            bell = ["error"]
+            osc = 777
+            "#,
+        )
+        .unwrap();
+        assert!(config.notifications.enabled());
+        assert_eq!(config.notifications.desktop[2], NotifyEvent::QueueDrained);
+        assert_eq!(config.notifications.bell, vec![NotifyEvent::Error]);
+        assert_eq!(config.notifications.batch, 3);
+
+        assert!(Config::parse("[notifications]\nosc = 8").is_err());
+        assert!(Config::parse("[notifications]\nbell = [\"paused\"]").is_err());
+    }
+
+    #[test]
+    fn test_retry_policy() {
+        let retry = Config::parse("").unwrap().retry;
+        assert!(retry.should_retry(Some("6"), 0));
+        assert!(!retry.should_retry(Some("3"), 0)); // 404 is permanent
+        assert!(!retry.should_retry(Some("6"), 3));
+        assert!(!retry.should_retry(None, 0));
+
+        assert_eq!(retry.delay(0), Duration::from_secs(10));
+        assert_eq!(retry.delay(2), Duration::from_secs(40));
+        assert_eq!(retry.delay(10), Duration::from_secs(600));
+
+        let retry = Config::parse("[retry]\nmax_attempts = 0").unwrap().retry;
+        assert!(!retry.should_retry(Some("6"), 0));
+        assert!(Config::parse("[retry]\nbackoff = 0.5").is_err());
+    }
+
+    #[test]
+    fn test_watchdog_section() {
+        let watchdog = Config::parse("").unwrap().watchdog;
+        assert!(watchdog.enabled);
+        assert_eq!(watchdog.action, StallAction::Notify);
+        assert_eq!(watchdog.window(), Duration::from_secs(300));
+
+        let watchdog = Config::parse("[watchdog]\nminutes = 2\naction = \"switch_mirror\"")
+            .unwrap()
+            .watchdog;
+        assert_eq!(watchdog.action, StallAction::SwitchMirror);
+        assert_eq!(watchdog.window(), Duration::from_secs(120));
+
+        assert!(Config::parse("[watchdog]\nminutes = 0").is_err());
+        assert!(Config::parse("[watchdog]\naction = \"panic\"").is_err());
+    }
+}
@@ -1,49 +1,135 @@
 use crate::aria2::{Aria2Manager, Aria2Status};
-use crate::models::{Download, DownloadType, GlobalStats};
-use std::collections::{HashMap, HashSet};
+use crate::config::{RetryConfig, StallAction, WatchdogConfig};
+use crate::events::{self, DownloadEvent};
+use crate::models::{
+    BatchAction, BatchResult, Download, DownloadOptions, DownloadState, DownloadType, GlobalStats,
+    StateTransition,
+};
+use crate::stats::Stats;
+use crate::usage::{Meter, Quota, QuotaUse, Usage};
+use indexmap::IndexMap;
+use serde_json::json;
+use std::collections::HashSet;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
-use tokio::sync::RwLock;
+use std::time::{Duration, Instant};
+use tokio::sync::{broadcast, RwLock};
 
 const MAX_SPEED_HISTORY: usize = 60; // Keep last 60 data points for graphing
+const EVENT_CAPACITY: usize = 256; // Events a slow subscriber may fall behind by
+const PAUSE_CHECKS: usize = 10; // Polls for a forced pause to take effect before reconnecting
+const USAGE_SAVE_INTERVAL: Duration = Duration::from_secs(60); // How often usage and stats are written
+
+/// What changed for one download during a poll
+#[derive(Default)]
+struct StatusUpdate {
+    transferred: (u64, u64), // Bytes (downloaded, uploaded) since the last poll
+    transition: Option<StateTransition>,
+    milestone: Option<u8>,
+    stalled: bool, // The download just became stalled
+}
 
 pub struct DownloadManager {
     aria2: Arc<Aria2Manager>,
-    downloads: Arc<RwLock<HashMap<String, Download>>>,
-    deleted_gids: Arc<RwLock<HashSet<String>>>, // Track deleted GIDs to prevent re-adding
+    downloads: Arc<RwLock<IndexMap<String, Download>>>, // Kept in the order they were added
+    deleted_gids: Arc<RwLock<HashSet<String>>>,         // Track deleted GIDs to prevent re-adding
     global_stats: Arc<RwLock<GlobalStats>>,
+    backend_healthy: AtomicBool, // Whether the last poll of aria2c succeeded
+    events: broadcast::Sender<DownloadEvent>,
+    retry: RetryConfig, // When failed downloads are retried automatically
+    watchdog: WatchdogConfig,
+    meter: RwLock<Meter>,                   // Data-cap usage and quotas
+    stats: RwLock<Stats>,                   // Long-term throughput and per-domain totals
+    usage_saved: std::sync::Mutex<Instant>, // When the usage and stats were last written
 }
 
 #[allow(dead_code)]
 impl DownloadManager {
     /// Create a new download manager
     pub async fn new() -> Result<Self, Box<dyn std::error::Error>> {
-        let aria2 = Arc::new(Aria2Manager::new().await?);
-        let downloads = Arc::new(RwLock::new(HashMap::new()));
+        Ok(Self::with_aria2(Aria2Manager::new().await?))
+    }
+
+    /// Create a download manager for an aria2c that is already running
+    fn with_aria2(aria2: Aria2Manager) -> Self {
+        let aria2 = Arc::new(aria2);
+        let downloads = Arc::new(RwLock::new(IndexMap::new()));
         let deleted_gids = Arc::new(RwLock::new(HashSet::new()));
         let global_stats = Arc::new(RwLock::new(GlobalStats::default()));
+        let (events, _) = broadcast::channel(EVENT_CAPACITY);
 
-        Ok(Self {
+        Self {
             aria2,
             downloads,
             deleted_gids,
             global_stats,
-        })
+            backend_healthy: AtomicBool::new(true),
+            events,
+            retry: RetryConfig::default(),
+            watchdog: WatchdogConfig::default(),
+            meter: RwLock::new(Meter::default()),
+            stats: RwLock::new(Stats::default()),
+            usage_saved: std::sync::Mutex::new(Instant::now()),
+        }
+    }
+
+    /// Continue the statistics saved by an earlier run
+    pub fn set_stats(&mut self, stats: Stats) {
+        *self.stats.get_mut() = stats;
+    }
+
+    /// Replace the automatic retry policy
+    pub fn set_retry_policy(&mut self, retry: RetryConfig) {
+        self.retry = retry;
+    }
+
+    /// Receive lifecycle events for all downloads from now on
+    pub fn subscribe(&self) -> broadcast::Receiver<DownloadEvent> {
+        self.events.subscribe()
+    }
+
+    fn publish(&self, event: DownloadEvent) {
+        // Sending only fails when nobody is subscribed
+        let _ = self.events.send(event);
+    }
+
+    /// Set the data-cap quotas and the usage carried over from earlier runs
+    pub fn set_quota(&mut self, quota: Quota, usage: Usage) {
+        *self.meter.get_mut() = Meter::new(quota, usage);
+    }
+
+    /// Replace the stalled-download watchdog settings
+    pub fn set_watchdog(&mut self, watchdog: WatchdogConfig) {
+        self.watchdog = watchdog;
     }
 
     /// Add a download from URL, torrent file, or magnet link
     pub async fn add_download(&self, input: &str) -> Result<String, Box<dyn std::error::Error>> {
+        self.add_download_with_options(input, &DownloadOptions::default())
+            .await
+    }
+
+    /// Add a download with extra aria2 options (output name, referer, headers...)
+    pub async fn add_download_with_options(
+        &self,
+        input: &str,
+        options: &DownloadOptions,
+    ) -> Result<String, Box<dyn std::error::Error>> {
+        let aria2_options = options.to_aria2();
+
         let gid = if input.starts_with("magnet:") {
             // Magnet link - treat as torrent
-            self.aria2.add_uri(input).await?
+            self.aria2.add_uri(input, aria2_options).await?
         } else if input.ends_with(".torrent") {
             // Torrent file path
-            self.aria2.add_torrent(input).await?
+            self.aria2.add_torrent(input, aria2_options).await?
         } else if input.ends_with(".metalink") || input.ends_with(".meta4") {
             // Metalink file
-            self.aria2.add_metalink(input).await?
+            self.aria2.add_metalink(input, aria2_options).await?
         } else {
             // Regular HTTP/HTTPS/FTP URL
-            self.aria2.add_uri(input).await?
+            self.aria2.add_uri(input, aria2_options).await?
         };
 
         // Create initial download entry
@@ -57,11 +143,14 @@ impl DownloadManager {
 
         let download = Download {
             gid: Some(gid.clone()),
-            name: extract_filename(input),
+            name: options
+                .out
+                .clone()
+                .unwrap_or_else(|| extract_filename(input)),
             url: Some(input.to_string()),
             progress: 0.0,
             speed: "0 B/s".to_string(),
-            status: "WAITING".to_string(),
+            status: DownloadState::Waiting,
             total_length: 0,
             completed_length: 0,
             download_type,
@@ -71,42 +160,171 @@ impl DownloadManager {
             connections: 0,
             file_path: None,
             error_message: None,
-            added_at: std::time::Instant::now(),
+            error_code: None,
+            uploaded_length: 0,
+            added_at: chrono::Local::now(),
             seeds: 0,
             peers: 0,
             bitfield: None,
             num_pieces: 0,
+            options: options.clone(),
+            retry_attempts: 0,
+            next_retry: None,
+            stalled: false,
+            slow_since: None,
         };
 
-        self.downloads.write().await.insert(gid.clone(), download);
+        self.downloads
+            .write()
+            .await
+            .insert(gid.clone(), download.clone());
+        self.publish(DownloadEvent::Added(download));
 
         Ok(gid)
     }
 
     /// Retry a failed download by re-adding it
     pub async fn retry_download(&self, gid: &str) -> Result<String, Box<dyn std::error::Error>> {
-        let download = self.downloads.read().await.get(gid).cloned();
+        self.readd(gid, 0).await
+    }
 
-        if let Some(download) = download {
-            if let Some(url) = &download.url {
-                // Remove the old download
-                self.remove_download(gid).await?;
+    /// Add a download again with its original options, in the same place in the list
+    ///
+    /// HTTP downloads are written to the same file with `continue` set, so
+    /// aria2 resumes the partial file instead of starting over. aria2 refuses a
+    /// second download of a file that is still being written, so the old GID is
+    /// removed first. Subscribers see an `Added` for the new GID but no
+    /// `Removed` for the old one.
+    async fn readd(&self, gid: &str, attempts: u32) -> Result<String, Box<dyn std::error::Error>> {
+        let download = self
+            .downloads
+            .read()
+            .await
+            .get(gid)
+            .cloned()
+            .ok_or("Download not found")?;
+        let url = download.url.clone().ok_or("No URL available for retry")?;
+
+        let mut options = download.options.clone();
+        options.resume = true;
+        if download.download_type == DownloadType::Http {
+            if let Some(path) = download.file_path.as_deref().map(Path::new) {
+                if options.dir.is_none() {
+                    options.dir = path.parent().map(|p| p.display().to_string());
+                }
+                if options.out.is_none() {
+                    options.out = path.file_name().map(|n| n.to_string_lossy().into_owned());
+                }
+            }
+        }
 
-                // Add it again
-                let new_gid = self.add_download(url).await?;
-                Ok(new_gid)
-            } else {
-                Err("No URL available for retry".into())
+        let index = self.downloads.read().await.get_index_of(gid);
+        // The new entry's Added stands for the old one, so no Removed is published
+        self.discard(gid).await;
+
+        let added = self
+            .add_download_with_options(&url, &options)
+            .await
+            .map_err(|e| e.to_string());
+        let mut downloads = self.downloads.write().await;
+        let new_gid = match added {
+            Ok(new_gid) => new_gid,
+            Err(e) => {
+                // Put it back as failed so it can still be retried or removed by hand
+                let mut download = download;
+                download.status = DownloadState::Error;
+                download.error_message = Some(e.clone());
+                let (new_index, _) = downloads.insert_full(gid.to_string(), download);
+                if let Some(index) = index {
+                    downloads.move_index(new_index, index);
+                }
+                return Err(e.into());
+            }
+        };
+        if let Some(new) = downloads.get_mut(&new_gid) {
+            new.retry_attempts = attempts;
+            // aria2 resumes the partial file, which must not count as new traffic
+            new.completed_length = download.completed_length;
+            new.uploaded_length = download.uploaded_length;
+        }
+        if let (Some(index), Some(new_index)) = (index, downloads.get_index_of(&new_gid)) {
+            downloads.move_index(new_index, index);
+        }
+        Ok(new_gid)
+    }
+
+    /// Apply the watchdog's action to a download that just stalled
+    async fn handle_stall(&self, gid: &str) -> Result<(), Box<dyn std::error::Error>> {
+        match self.watchdog.action {
+            StallAction::Notify => return Ok(()),
+            StallAction::Reconnect => self.reconnect(gid, false).await?,
+            StallAction::SwitchMirror => self.reconnect(gid, true).await?,
+            StallAction::Restart => {
+                let attempts = self.get_download(gid).await.map_or(0, |d| d.retry_attempts);
+                self.readd(gid, attempts).await?;
+                return Ok(());
             }
-        } else {
-            Err("Download not found".into())
         }
+
+        // Give the fresh connections a full window before judging again
+        if let Some(download) = self.downloads.write().await.get_mut(gid) {
+            download.stalled = false;
+            download.slow_since = None;
+        }
+        Ok(())
+    }
+
+    /// Pause and unpause a download so aria2 opens new connections
+    ///
+    /// With `switch_mirror` the current URI moves to the back first, if there is another one.
+    async fn reconnect(
+        &self,
+        gid: &str,
+        switch_mirror: bool,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        self.aria2.force_pause(gid).await?;
+        for _ in 0..PAUSE_CHECKS {
+            tokio::time::sleep(Duration::from_millis(200)).await;
+            if self.aria2.get_status(gid).await?.status != "paused" {
+                continue;
+            }
+            if switch_mirror {
+                // Without another mirror a reconnect is the best we can do
+                let _ = self.aria2.rotate_uris(gid).await;
+            }
+            self.aria2.unpause(gid).await?;
+            return Ok(());
+        }
+        Err("Download did not pause".into())
+    }
+
+    /// Plan the next automatic retry of a download that just failed, if the policy allows one
+    fn schedule_retry(&self, download: &mut Download) {
+        let retry = download.url.is_some()
+            && self
+                .retry
+                .should_retry(download.error_code.as_deref(), download.retry_attempts);
+        download.next_retry =
+            retry.then(|| Instant::now() + self.retry.delay(download.retry_attempts));
     }
 
     /// Update download information from aria2c
-    pub async fn update_downloads(&self) -> Result<(), Box<dyn std::error::Error>> {
+    ///
+    /// Returns the state changes seen since the previous update.
+    pub async fn update_downloads(
+        &self,
+    ) -> Result<Vec<StateTransition>, Box<dyn std::error::Error>> {
         // Get all active downloads
-        let active = self.aria2.get_active().await.unwrap_or_default();
+        let active = self.aria2.get_active().await.ok();
+        let healthy = active.is_some();
+        if self.backend_healthy.swap(healthy, Ordering::Relaxed) != healthy {
+            self.publish(if healthy {
+                DownloadEvent::BackendUp
+            } else {
+                DownloadEvent::BackendDown
+            });
+        }
+        let active = active.unwrap_or_default();
 
         // Get waiting downloads
         let waiting = self.aria2.get_waiting(0, 100).await.unwrap_or_default();
@@ -116,43 +334,162 @@ impl DownloadManager {
 
         let mut downloads = self.downloads.write().await;
         let deleted_gids = self.deleted_gids.read().await;
+        let mut transferred = (0, 0); // Bytes (downloaded, uploaded) since the last poll
+        let mut transitions = Vec::new();
+        let mut stalls = Vec::new();
+        let mut finished = Vec::new(); // Downloads that completed or failed for good
+
+        for status in active.into_iter().chain(waiting).chain(stopped) {
+            if deleted_gids.contains(&status.gid) {
+                continue;
+            }
+
+            let gid = status.gid.clone();
+            let update = self
+                .update_download_from_status(&mut downloads, status, &deleted_gids)
+                .await;
+            transferred.0 += update.transferred.0;
+            transferred.1 += update.transferred.1;
 
-        // Update active downloads
-        for status in active {
-            if !deleted_gids.contains(&status.gid) {
-                self.update_download_from_status(&mut downloads, status, &deleted_gids)
-                    .await;
+            let Some(download) = downloads.get_mut(&gid) else {
+                continue;
+            };
+            if let Some(percent) = update.milestone {
+                self.publish(DownloadEvent::Progress(download.clone(), percent));
+            }
+            if update.stalled {
+                self.publish(DownloadEvent::Stalled(download.clone()));
+                stalls.push(gid.clone());
+            }
+            if let Some(transition) = update.transition {
+                if transition.to == DownloadState::Error {
+                    self.schedule_retry(download);
+                }
+                // A failure with a retry scheduled is not reported until the retries run out
+                let retrying =
+                    transition.to == DownloadState::Error && download.next_retry.is_some();
+                let done = matches!(
+                    transition.to,
+                    DownloadState::Complete | DownloadState::Error
+                );
+                if done && !retrying {
+                    finished.push(download.clone());
+                }
+                if let Some(event) = events::for_transition(transition.from, download) {
+                    if !retrying {
+                        self.publish(event);
+                    }
+                }
+                transitions.push(transition);
             }
         }
 
-        // Update waiting downloads
-        for status in waiting {
-            if !deleted_gids.contains(&status.gid) {
-                self.update_download_from_status(&mut downloads, status, &deleted_gids)
-                    .await;
+        // Update global stats
+        self.update_global_stats(&downloads, transferred).await;
+
+        // Data-cap accounting
+        let (alerts, over_quota) = {
+            let mut meter = self.meter.write().await;
+            let alerts = meter.record(chrono::Local::now().date_naive(), transferred);
+            (alerts, meter.over_quota(transferred))
+        };
+
+        // Long-term statistics
+        {
+            let now = chrono::Local::now();
+            let mut stats = self.stats.write().await;
+            stats.record(now.naive_local(), transferred);
+            for download in &finished {
+                stats.record_finished(download, now);
             }
         }
 
-        // Update stopped downloads
-        for status in stopped {
-            if !deleted_gids.contains(&status.gid) {
-                self.update_download_from_status(&mut downloads, status, &deleted_gids)
-                    .await;
+        // Automatic retries that are due
+        let now = Instant::now();
+        let due: Vec<(String, u32)> = downloads
+            .iter()
+            .filter(|(_, d)| d.status == DownloadState::Error)
+            .filter(|(_, d)| d.next_retry.is_some_and(|at| at <= now))
+            .map(|(gid, d)| (gid.clone(), d.retry_attempts + 1))
+            .collect();
+        drop(downloads);
+        drop(deleted_gids);
+
+        for alert in alerts {
+            self.publish(DownloadEvent::Quota(alert));
+        }
+        if over_quota {
+            // Also catches downloads resumed by hand after the quota was used up
+            let _ = self.pause_all().await;
+        }
+        if self.usage_saved.lock().unwrap().elapsed() >= USAGE_SAVE_INTERVAL {
+            self.save_usage().await;
+        }
+
+        for gid in stalls {
+            // Failures leave the download flagged as stalled
+            let _ = self.handle_stall(&gid).await;
+        }
+
+        for (gid, attempts) in due {
+            if self.readd(&gid, attempts).await.is_err() {
+                // Leave it failed rather than trying again on every poll
+                let failed = self.downloads.write().await.get_mut(&gid).map(|download| {
+                    download.next_retry = None;
+                    download.clone()
+                });
+                if let Some(download) = failed {
+                    self.stats
+                        .write()
+                        .await
+                        .record_finished(&download, chrono::Local::now());
+                    self.publish(DownloadEvent::Failed(download));
+                }
             }
         }
 
-        // Update global stats
-        self.update_global_stats(&downloads).await;
+        Ok(transitions)
+    }
 
-        Ok(())
+    /// Write the data-cap usage and statistics to disk if they changed
+    ///
+    /// A failed write is tried again at the next interval; the totals stay in memory.
+    async fn save_usage(&self) {
+        *self.usage_saved.lock().unwrap() = Instant::now();
+        let _ = self.meter.write().await.save();
+        let _ = self.stats.write().await.save();
+    }
+
+    /// Long-term transfer statistics
+    pub async fn stats(&self) -> Stats {
+        self.stats.read().await.clone()
+    }
+
+    /// Data-cap quotas and how much of each is used
+    pub async fn quota_usage(&self) -> Vec<QuotaUse> {
+        self.meter.read().await.uses()
     }
 
-    async fn update_global_stats(&self, downloads: &HashMap<String, Download>) {
+    /// Bytes transferred today and this month
+    pub async fn usage(&self) -> Usage {
+        self.meter.read().await.usage.clone()
+    }
+
+    async fn update_global_stats(
+        &self,
+        downloads: &IndexMap<String, Download>,
+        transferred: (u64, u64),
+    ) {
         let mut stats = GlobalStats::default();
+        {
+            let previous = self.global_stats.read().await;
+            stats.total_downloaded = previous.total_downloaded + transferred.0;
+            stats.total_uploaded = previous.total_uploaded + transferred.1;
+        }
 
         for download in downloads.values() {
-            match download.status.as_str() {
-                "ACTIVE" => {
+            match download.status {
+                DownloadState::Active | DownloadState::Seeding | DownloadState::Verifying => {
                     stats.num_active += 1;
                     // Parse speed strings to get total speeds
                     if let Some(speed) = download.speed_history.last() {
@@ -162,10 +499,10 @@ impl DownloadManager {
                         stats.upload_speed += speed;
                     }
                 }
-                "WAITING" | "PAUSED" => stats.num_waiting += 1,
-                "COMPLETE" => stats.num_stopped += 1,
-                "ERROR" => stats.num_stopped += 1,
-                _ => {}
+                DownloadState::Waiting | DownloadState::Paused => stats.num_waiting += 1,
+                DownloadState::Complete | DownloadState::Error | DownloadState::Removed => {
+                    stats.num_stopped += 1
+                }
             }
         }
 
@@ -175,15 +512,29 @@ impl DownloadManager {
 
     async fn update_download_from_status(
         &self,
-        downloads: &mut HashMap<String, Download>,
+        downloads: &mut IndexMap<String, Download>,
         status: Aria2Status,
         deleted_gids: &tokio::sync::RwLockReadGuard<'_, HashSet<String>>,
-    ) {
+    ) -> StatusUpdate {
         // Skip if this download was deleted by user
         if deleted_gids.contains(&status.gid) {
-            return;
+            return StatusUpdate::default();
         }
 
+        let state = state_of(&status);
+        let previous = downloads.get(&status.gid).map(|d| d.status);
+        let transition = (previous != Some(state)).then(|| StateTransition {
+            gid: status.gid.clone(),
+            from: previous,
+            to: state,
+        });
+
+        let uploaded: u64 = status
+            .upload_length
+            .as_ref()
+            .and_then(|u| u.parse().ok())
+            .unwrap_or(0);
+
         if let Some(download) = downloads.get_mut(&status.gid) {
             // Update existing download
             let total: u64 = status.total_length.parse().unwrap_or(0);
@@ -191,6 +542,14 @@ impl DownloadManager {
             let speed: u64 = status.download_speed.parse().unwrap_or(0);
             let upload_speed: u64 = status.upload_speed.parse().unwrap_or(0);
 
+            let before = download.progress;
+
+            // Bytes transferred since the last update
+            let transferred = (
+                completed.saturating_sub(download.completed_length),
+                uploaded.saturating_sub(download.uploaded_length),
+            );
+
             download.progress = if total > 0 {
                 completed as f64 / total as f64
             } else {
@@ -198,13 +557,15 @@ impl DownloadManager {
             };
             download.speed = format_speed(speed);
             download.upload_speed = format_speed(upload_speed);
-            download.status = status.status.to_uppercase();
+            download.status = state;
             download.total_length = total;
             download.completed_length = completed;
             download.connections = status.connections.parse().unwrap_or(0);
 
             // Update error message if present
             download.error_message = status.error_message.clone();
+            download.error_code = status.error_code.clone().filter(|c| c != "0");
+            download.uploaded_length = uploaded;
 
             // Update seeds and peers from bittorrent info if available
             if let Some(ref bt_info) = status.bittorrent {
@@ -242,7 +603,7 @@ impl DownloadManager {
                     if !file.path.is_empty() {
                         download.file_path = Some(file.path.clone());
                         // Extract just the filename from the path
-                        if let Some(filename) = file.path.split('/').last() {
+                        if let Some(filename) = file.path.split('/').next_back() {
                             if !filename.is_empty() {
                                 download.name = filename.to_string();
                             }
@@ -250,6 +611,13 @@ impl DownloadManager {
                     }
                 }
             }
+
+            StatusUpdate {
+                transferred,
+                transition,
+                milestone: events::milestone_crossed(before, download.progress),
+                stalled: watch_stall(download, speed, &self.watchdog, Instant::now()),
+            }
         } else {
             // New download not added by us - add it
             let total: u64 = status.total_length.parse().unwrap_or(0);
@@ -265,7 +633,7 @@ impl DownloadManager {
                 if let Some(file) = files.first() {
                     if !file.path.is_empty() {
                         file_path = Some(file.path.clone());
-                        if let Some(filename) = file.path.split('/').last() {
+                        if let Some(filename) = file.path.split('/').next_back() {
                             if !filename.is_empty() {
                                 name = filename.to_string();
                             }
@@ -307,7 +675,7 @@ impl DownloadManager {
                     0.0
                 },
                 speed: format_speed(speed),
-                status: status.status.to_uppercase(),
+                status: state,
                 total_length: total,
                 completed_length: completed,
                 download_type,
@@ -317,18 +685,31 @@ impl DownloadManager {
                 connections: status.connections.parse().unwrap_or(0),
                 file_path,
                 error_message: status.error_message.clone(),
-                added_at: std::time::Instant::now(),
+                error_code: status.error_code.clone().filter(|c| c != "0"),
+                uploaded_length: uploaded,
+                added_at: chrono::Local::now(),
                 seeds,
                 peers,
                 bitfield: status.bitfield.clone(),
                 num_pieces,
+                options: DownloadOptions::default(),
+                retry_attempts: 0,
+                next_retry: None,
+                stalled: false,
+                slow_since: None,
             };
 
             downloads.insert(status.gid, download);
+
+            // Transfers that happened before we saw the download are not counted
+            StatusUpdate {
+                transition,
+                ..Default::default()
+            }
         }
     }
 
-    /// Get all downloads
+    /// Get all downloads in the order they were added
     pub async fn get_all_downloads(&self) -> Vec<Download> {
         self.downloads.read().await.values().cloned().collect()
     }
@@ -339,7 +720,7 @@ impl DownloadManager {
             .read()
             .await
             .values()
-            .filter(|d| d.progress > 0.0 && d.progress < 1.0)
+            .filter(|d| d.status.is_running() || d.status == DownloadState::Paused)
             .cloned()
             .collect()
     }
@@ -350,7 +731,7 @@ impl DownloadManager {
             .read()
             .await
             .values()
-            .filter(|d| d.status == "WAITING" || d.progress == 0.0)
+            .filter(|d| d.status == DownloadState::Waiting)
             .cloned()
             .collect()
     }
@@ -361,7 +742,7 @@ impl DownloadManager {
             .read()
             .await
             .values()
-            .filter(|d| d.progress >= 1.0 || d.status == "COMPLETE")
+            .filter(|d| d.status == DownloadState::Complete)
             .cloned()
             .collect()
     }
@@ -371,6 +752,11 @@ impl DownloadManager {
         self.global_stats.read().await.clone()
     }
 
+    /// Whether aria2c answered the last status poll
+    pub fn is_backend_healthy(&self) -> bool {
+        self.backend_healthy.load(Ordering::Relaxed)
+    }
+
     /// Pause a download
     pub async fn pause_download(&self, gid: &str) -> Result<(), Box<dyn std::error::Error>> {
         self.aria2.pause(gid).await?;
@@ -385,11 +771,19 @@ impl DownloadManager {
 
     /// Remove a download
     pub async fn remove_download(&self, gid: &str) -> Result<(), Box<dyn std::error::Error>> {
+        if let Some(download) = self.discard(gid).await {
+            self.publish(DownloadEvent::Removed(download));
+        }
+        Ok(())
+    }
+
+    /// Remove a download from aria2 and the list without publishing an event
+    async fn discard(&self, gid: &str) -> Option<Download> {
         // Mark as deleted FIRST to prevent re-adding during async operations
         self.deleted_gids.write().await.insert(gid.to_string());
 
         // Remove from our local storage
-        self.downloads.write().await.remove(gid);
+        let removed = self.downloads.write().await.shift_remove(gid);
 
         // Try to force remove from aria2c (might fail if already stopped)
         let _ = self.aria2.force_remove(gid).await;
@@ -400,7 +794,7 @@ impl DownloadManager {
         // Remove from aria2c's completed/stopped list
         let _ = self.aria2.remove_download_result(gid).await;
 
-        Ok(())
+        removed
     }
 
     /// Get download by GID
@@ -415,7 +809,7 @@ impl DownloadManager {
             .read()
             .await
             .iter()
-            .filter(|(_, d)| d.progress >= 1.0 || d.status == "COMPLETE")
+            .filter(|(_, d)| d.status == DownloadState::Complete)
             .map(|(gid, _)| gid.clone())
             .collect();
 
@@ -482,6 +876,52 @@ impl DownloadManager {
         Ok(())
     }
 
+    /// Change any of aria2's global options, e.g. `max-concurrent-downloads`
+    pub async fn set_global_option(
+        &self,
+        name: &str,
+        value: &str,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        self.aria2.set_global_option(name, value).await?;
+        Ok(())
+    }
+
+    /// Set speed limits for several downloads (0 = only the global limit applies)
+    ///
+    /// All downloads are changed in one `system.multicall` round trip.
+    pub async fn set_download_limits(
+        &self,
+        gids: &[String],
+        download: u64,
+        upload: u64,
+    ) -> Result<BatchResult, Box<dyn std::error::Error>> {
+        let options = json!({
+            "max-download-limit": download.to_string(),
+            "max-upload-limit": upload.to_string(),
+        });
+        let calls = gids
+            .iter()
+            .map(|gid| ("aria2.changeOption", vec![json!(gid), options.clone()]))
+            .collect();
+        let responses = self.aria2.multicall(calls).await?;
+
+        let mut result = BatchResult::default();
+        let mut downloads = self.downloads.write().await;
+        for (gid, response) in gids.iter().zip(responses) {
+            if response.is_err() {
+                result.failed += 1;
+                continue;
+            }
+            result.succeeded += 1;
+            // Kept with the options so a retry re-adds the download with the same limits
+            if let Some(d) = downloads.get_mut(gid) {
+                d.options.download_limit = download;
+                d.options.upload_limit = upload;
+            }
+        }
+        Ok(result)
+    }
+
     /// Get current speed limits
     pub async fn get_speed_limits(&self) -> Result<(u64, u64), Box<dyn std::error::Error>> {
         let options = self.aria2.get_global_option().await?;
@@ -513,6 +953,92 @@ impl DownloadManager {
         Ok(())
     }
 
+    /// Apply an action to several downloads
+    ///
+    /// aria2 calls are sent in a single `system.multicall` round trip.
+    pub async fn run_batch(
+        &self,
+        action: BatchAction,
+        gids: &[String],
+    ) -> Result<BatchResult, Box<dyn std::error::Error>> {
+        let mut result = BatchResult::default();
+
+        let rpc = |method: &'static str, extra: Vec<serde_json::Value>| {
+            gids.iter()
+                .map(|gid| {
+                    let mut params = vec![json!(gid)];
+                    params.extend(extra.iter().cloned());
+                    (method, params)
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let calls = match action {
+            BatchAction::Pause => rpc("aria2.pause", vec![]),
+            BatchAction::Resume => rpc("aria2.unpause", vec![]),
+            BatchAction::MoveUp => rpc("aria2.changePosition", vec![json!(-1), json!("POS_CUR")]),
+            BatchAction::MoveDown => rpc("aria2.changePosition", vec![json!(1), json!("POS_CUR")]),
+            BatchAction::Remove => {
+                // Mark as deleted FIRST to prevent re-adding during async operations
+                {
+                    let mut deleted_gids = self.deleted_gids.write().await;
+                    let mut downloads = self.downloads.write().await;
+                    for gid in gids {
+                        deleted_gids.insert(gid.clone());
+                        if let Some(download) = downloads.shift_remove(gid) {
+                            self.publish(DownloadEvent::Removed(download));
+                        }
+                    }
+                }
+
+                // forceRemove fails for downloads that already stopped and
+                // removeDownloadResult for ones still running, so either counts
+                let removed = self
+                    .aria2
+                    .multicall(rpc("aria2.forceRemove", vec![]))
+                    .await?;
+                tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+                let cleared = self
+                    .aria2
+                    .multicall(rpc("aria2.removeDownloadResult", vec![]))
+                    .await?;
+
+                for (removed, cleared) in removed.iter().zip(&cleared) {
+                    if removed.is_ok() || cleared.is_ok() {
+                        result.succeeded += 1;
+                    } else {
+                        result.failed += 1;
+                    }
+                }
+                return Ok(result);
+            }
+            BatchAction::DeleteFile | BatchAction::Retry => {
+                // Local file operations and re-adds, one download at a time
+                for gid in gids {
+                    let ok = match action {
+                        BatchAction::DeleteFile => self.delete_file(gid).await.is_ok(),
+                        _ => self.retry_download(gid).await.is_ok(),
+                    };
+                    if ok {
+                        result.succeeded += 1;
+                    } else {
+                        result.failed += 1;
+                    }
+                }
+                return Ok(result);
+            }
+        };
+
+        for call in self.aria2.multicall(calls).await? {
+            if call.is_ok() {
+                result.succeeded += 1;
+            } else {
+                result.failed += 1;
+            }
+        }
+        Ok(result)
+    }
+
     /// Pause all downloads
     pub async fn pause_all(&self) -> Result<(), Box<dyn std::error::Error>> {
         self.aria2.pause_all().await?;
@@ -527,10 +1053,41 @@ impl DownloadManager {
 
     /// Shutdown aria2c
     pub async fn shutdown(&self) -> Result<(), Box<dyn std::error::Error>> {
+        self.save_usage().await;
         self.aria2.shutdown().await
     }
 }
 
+/// Track how long a download has been slow; true when it has just become stalled
+fn watch_stall(
+    download: &mut Download,
+    speed: u64,
+    watchdog: &WatchdogConfig,
+    now: Instant,
+) -> bool {
+    if !watchdog.enabled || download.status != DownloadState::Active || speed >= watchdog.min_speed
+    {
+        download.slow_since = None;
+        download.stalled = false;
+        return false;
+    }
+
+    let since = *download.slow_since.get_or_insert(now);
+    if download.stalled || now.duration_since(since) < watchdog.window() {
+        return false;
+    }
+    download.stalled = true;
+    true
+}
+
+/// Lifecycle state of a download as reported by aria2
+fn state_of(status: &Aria2Status) -> DownloadState {
+    let seeder = status.seeder.as_deref() == Some("true");
+    let verifying = status.verified_length.is_some()
+        || status.verify_integrity_pending.as_deref() == Some("true");
+    DownloadState::from_aria2(&status.status, seeder, verifying)
+}
+
 /// Extract filename from URL or path
 fn extract_filename(input: &str) -> String {
     if input.starts_with("magnet:") {
@@ -552,7 +1109,7 @@ fn extract_filename(input: &str) -> String {
 
     // For URLs and file paths
     let path = input.split('?').next().unwrap_or(input);
-    let filename = path.split('/').last().unwrap_or("Unknown");
+    let filename = path.split('/').next_back().unwrap_or("Unknown");
 
     if filename.is_empty() {
         "Unknown".to_string()
@@ -578,8 +1135,6 @@ fn format_speed(speed_bytes: u64) -> String {
     }
 }
 
-/// Format file size in human-readable format
-
 mod urlencoding {
     pub fn decode(s: &str) -> Result<String, ()> {
         let mut result = String::new();
@@ -609,3 +1164,257 @@ mod urlencoding {
         Ok(result)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::server::http::{self, Response};
+    use crate::usage::Transfer;
+    use serde_json::Value;
+    use tokio::io::BufReader;
+    use tokio::net::TcpListener;
+
+    /// aria2 JSON-RPC endpoint that records the calls it receives, in order
+    struct FakeBackend {
+        url: String,
+        calls: Arc<std::sync::Mutex<Vec<(String, Value)>>>, // (method, params)
+    }
+
+    /// aria2's reply to one call; calls for the GID `missing` fail
+    fn answer(method: &str, params: &Value) -> Result<Value, Value> {
+        if params[1] == "missing" {
+            return Err(json!({ "code": 1, "message": "GID missing is not found" }));
+        }
+        Ok(match method {
+            "aria2.addUri" => json!("new"),
+            // The re-added download, resumed from 2700 of 3000 bytes
+            "aria2.tellActive" => json!([{
+                "gid": "new",
+                "status": "active",
+                "totalLength": "3000",
+                "completedLength": "2800",
+                "downloadSpeed": "100",
+                "uploadSpeed": "0",
+                "uploadLength": "50",
+                "connections": "1",
+            }]),
+            "aria2.tellWaiting" | "aria2.tellStopped" => json!([]),
+            _ => json!("OK"),
+        })
+    }
+
+    impl FakeBackend {
+        async fn start() -> Self {
+            let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let url = format!("http://{}/jsonrpc", listener.local_addr().unwrap());
+            let calls = Arc::new(std::sync::Mutex::new(Vec::new()));
+
+            let recorded = calls.clone();
+            tokio::spawn(async move {
+                while let Ok((stream, _)) = listener.accept().await {
+                    let (read_half, mut write_half) = stream.into_split();
+                    let mut reader = BufReader::new(read_half);
+                    let Ok(request) = http::read_request(&mut reader).await else {
+                        continue;
+                    };
+                    let body: Value = serde_json::from_slice(&request.body).unwrap();
+                    let record = |method: &Value, params: &Value| {
+                        let method = method.as_str().unwrap().to_string();
+                        let result = answer(&method, params);
+                        recorded.lock().unwrap().push((method, params.clone()));
+                        result
+                    };
+
+                    let reply = if body["method"] == "system.multicall" {
+                        let results: Vec<Value> = body["params"][0]
+                            .as_array()
+                            .unwrap()
+                            .iter()
+                            .map(|call| match record(&call["methodName"], &call["params"]) {
+                                Ok(value) => json!([value]),
+                                Err(fault) => fault,
+                            })
+                            .collect();
+                        json!({ "jsonrpc": "2.0", "id": body["id"], "result": results })
+                    } else {
+                        match record(&body["method"], &body["params"]) {
+                            Ok(value) => {
+                                json!({ "jsonrpc": "2.0", "id": body["id"], "result": value })
+                            }
+                            Err(fault) => {
+                                json!({ "jsonrpc": "2.0", "id": body["id"], "error": fault })
+                            }
+                        }
+                    };
+                    let mut response = Response::json(200, &reply);
+                    let _ = http::write_response(&mut write_half, &mut response, None).await;
+                }
+            });
+
+            Self { url, calls }
+        }
+
+        fn methods(&self) -> Vec<String> {
+            let calls = self.calls.lock().unwrap();
+            calls.iter().map(|(method, _)| method.clone()).collect()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_set_download_limits_in_one_round_trip() {
+        let backend = FakeBackend::start().await;
+        let manager =
+            DownloadManager::with_aria2(Aria2Manager::connect(backend.url.clone(), "secret"));
+        manager.downloads.write().await.insert(
+            "a".to_string(),
+            Download {
+                gid: Some("a".to_string()),
+                ..Default::default()
+            },
+        );
+
+        let gids = ["a".to_string(), "missing".to_string()];
+        let result = manager.set_download_limits(&gids, 1024, 0).await.unwrap();
+
+        assert_eq!(
+            result,
+            BatchResult {
+                succeeded: 1,
+                failed: 1
+            }
+        );
+        assert_eq!(
+            backend.methods(),
+            ["aria2.changeOption", "aria2.changeOption"]
+        );
+        assert_eq!(
+            backend.calls.lock().unwrap()[0].1[2]["max-download-limit"],
+            "1024"
+        );
+        let downloads = manager.downloads.read().await;
+        assert_eq!(downloads["a"].options.download_limit, 1024);
+    }
+
+    #[tokio::test]
+    async fn test_readd_does_not_count_the_partial_file_again() {
+        let backend = FakeBackend::start().await;
+        let manager =
+            DownloadManager::with_aria2(Aria2Manager::connect(backend.url.clone(), "secret"));
+        manager.downloads.write().await.insert(
+            "old".to_string(),
+            Download {
+                gid: Some("old".to_string()),
+                url: Some("https://example.com/a.iso".to_string()),
+                file_path: Some("/data/a.iso".to_string()),
+                status: DownloadState::Error,
+                total_length: 3000,
+                completed_length: 2700,
+                uploaded_length: 50,
+                ..Default::default()
+            },
+        );
+
+        manager.retry_download("old").await.unwrap();
+        manager.update_downloads().await.unwrap();
+
+        // Only the 100 bytes fetched after resuming are new
+        let expected = Transfer {
+            downloaded: 100,
+            uploaded: 0,
+        };
+        assert_eq!(manager.usage().await.today, expected);
+        assert_eq!(manager.get_global_stats().await.total_downloaded, 100);
+        assert_eq!(manager.stats().await.minutes[0].transfer, expected);
+    }
+
+    #[tokio::test]
+    async fn test_restart_removes_stalled_download_before_adding_it_again() {
+        let backend = FakeBackend::start().await;
+        let mut manager =
+            DownloadManager::with_aria2(Aria2Manager::connect(backend.url.clone(), "secret"));
+        manager.set_watchdog(WatchdogConfig {
+            action: StallAction::Restart,
+            ..Default::default()
+        });
+        manager.downloads.write().await.insert(
+            "old".to_string(),
+            Download {
+                gid: Some("old".to_string()),
+                url: Some("https://example.com/a.iso".to_string()),
+                file_path: Some("/data/a.iso".to_string()),
+                status: DownloadState::Active,
+                stalled: true,
+                ..Default::default()
+            },
+        );
+        let mut events = manager.subscribe();
+
+        manager.handle_stall("old").await.unwrap();
+
+        // aria2 refuses to add a download for a file another one is still writing
+        assert_eq!(
+            backend.methods(),
+            [
+                "aria2.forceRemove",
+                "aria2.removeDownloadResult",
+                "aria2.addUri"
+            ]
+        );
+        let options = backend.calls.lock().unwrap()[2].1[2].clone();
+        assert_eq!(
+            options,
+            json!({ "continue": "true", "dir": "/data", "out": "a.iso" })
+        );
+
+        let downloads = manager.downloads.read().await;
+        assert_eq!(downloads.keys().collect::<Vec<_>>(), ["new"]);
+        assert!(matches!(events.try_recv(), Ok(DownloadEvent::Added(_))));
+        assert!(events.try_recv().is_err());
+    }
+
+    #[test]
+    fn test_watch_stall() {
+        let watchdog = WatchdogConfig::default();
+        let start = Instant::now();
+        let mut download = Download {
+            status: DownloadState::Active,
+            ..Default::default()
+        };
+
+        assert!(!watch_stall(&mut download, 0, &watchdog, start));
+        assert!(!watch_stall(
+            &mut download,
+            100,
+            &watchdog,
+            start + Duration::from_secs(60)
+        ));
+        assert!(watch_stall(
+            &mut download,
+            0,
+            &watchdog,
+            start + Duration::from_secs(300)
+        ));
+        assert!(download.stalled);
+
+        // Reported once, cleared when data flows again
+        assert!(!watch_stall(
+            &mut download,
+            0,
+            &watchdog,
+            start + Duration::from_secs(301)
+        ));
+        assert!(!watch_stall(
+            &mut download,
+            50_000,
+            &watchdog,
+            start + Duration::from_secs(302)
+        ));
+        assert!(!download.stalled);
+        assert_eq!(download.slow_since, None);
+
+        // Paused downloads are not watched
+        download.status = DownloadState::Paused;
+        assert!(!watch_stall(&mut download, 0, &watchdog, start));
+        assert_eq!(download.slow_since, None);
+    }
+}
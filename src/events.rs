@@ -0,0 +1,168 @@
+//! Download lifecycle events
+//!
+//! [`DownloadManager`](crate::download_manager::DownloadManager) publishes a
+//! [`DownloadEvent`] on a broadcast channel whenever it notices a download
+//! change state between two polls of aria2. Subscribe with
+//! `DownloadManager::subscribe`.
+
+use crate::models::{Download, DownloadState};
+use crate::usage::QuotaAlert;
+
+/// Progress percentages that produce a [`DownloadEvent::Progress`] event
+pub const MILESTONES: [u8; 3] = [25, 50, 75];
+
+/// Something that happened to a download, with a snapshot taken at that moment
+#[derive(Clone, Debug)]
+pub enum DownloadEvent {
+    Added(Download),
+    Started(Download),
+    Paused(Download),
+    Resumed(Download),
+    /// Progress crossed one of the [`MILESTONES`]
+    Progress(Download, u8),
+    Completed(Download),
+    /// Failed for good; failures that will be retried automatically are not published
+    Failed(Download),
+    Removed(Download),
+    /// The watchdog found the download active but receiving (almost) nothing
+    Stalled(Download),
+    /// aria2c stopped answering status polls
+    BackendDown,
+    /// aria2c answers again after being down
+    BackendUp,
+    /// A data-cap quota crossed a warning threshold or was used up
+    Quota(QuotaAlert),
+}
+
+impl DownloadEvent {
+    /// The download the event is about, if any
+    pub fn download(&self) -> Option<&Download> {
+        match self {
+            DownloadEvent::Added(d)
+            | DownloadEvent::Started(d)
+            | DownloadEvent::Paused(d)
+            | DownloadEvent::Resumed(d)
+            | DownloadEvent::Progress(d, _)
+            | DownloadEvent::Completed(d)
+            | DownloadEvent::Failed(d)
+            | DownloadEvent::Removed(d)
+            | DownloadEvent::Stalled(d) => Some(d),
+            DownloadEvent::BackendDown | DownloadEvent::BackendUp | DownloadEvent::Quota(_) => None,
+        }
+    }
+
+    /// Short name, e.g. for logs and hook configuration
+    pub fn name(&self) -> &'static str {
+        match self {
+            DownloadEvent::Added(_) => "added",
+            DownloadEvent::Started(_) => "started",
+            DownloadEvent::Paused(_) => "paused",
+            DownloadEvent::Resumed(_) => "resumed",
+            DownloadEvent::Progress(_, _) => "progress",
+            DownloadEvent::Completed(_) => "completed",
+            DownloadEvent::Failed(_) => "failed",
+            DownloadEvent::Removed(_) => "removed",
+            DownloadEvent::Stalled(_) => "stalled",
+            DownloadEvent::BackendDown => "backend_down",
+            DownloadEvent::BackendUp => "backend_up",
+            DownloadEvent::Quota(_) => "quota",
+        }
+    }
+}
+
+/// Event for a change of state, `from` being `None` for a download seen for the first time
+pub fn for_transition(from: Option<DownloadState>, download: &Download) -> Option<DownloadEvent> {
+    use DownloadState::*;
+
+    let download = download.clone();
+    let event = match (from, download.status) {
+        (None, _) => DownloadEvent::Added(download),
+        (Some(Paused), Active) => DownloadEvent::Resumed(download),
+        (Some(Waiting) | Some(Verifying), Active) => DownloadEvent::Started(download),
+        (Some(_), Paused) => DownloadEvent::Paused(download),
+        // A torrent that starts seeding has finished downloading
+        (Some(Active) | Some(Waiting) | Some(Paused), Seeding) => {
+            DownloadEvent::Completed(download)
+        }
+        (Some(Seeding), Complete) => return None,
+        (Some(_), Complete) => DownloadEvent::Completed(download),
+        (Some(_), Error) => DownloadEvent::Failed(download),
+        (Some(_), Removed) => DownloadEvent::Removed(download),
+        _ => return None,
+    };
+    Some(event)
+}
+
+/// Highest milestone passed when progress moved from `before` to `after`
+pub fn milestone_crossed(before: f64, after: f64) -> Option<u8> {
+    MILESTONES
+        .iter()
+        .rev()
+        .find(|&&m| {
+            let threshold = m as f64 / 100.0;
+            before < threshold && after >= threshold
+        })
+        .copied()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn download(status: DownloadState) -> Download {
+        Download {
+            gid: Some("abc".to_string()),
+            name: "file.iso".to_string(),
+            status,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_for_transition() {
+        let name = |from, to| for_transition(from, &download(to)).map(|e| e.name());
+
+        assert_eq!(name(None, DownloadState::Active), Some("added"));
+        assert_eq!(
+            name(Some(DownloadState::Waiting), DownloadState::Active),
+            Some("started")
+        );
+        assert_eq!(
+            name(Some(DownloadState::Paused), DownloadState::Active),
+            Some("resumed")
+        );
+        assert_eq!(
+            name(Some(DownloadState::Active), DownloadState::Paused),
+            Some("paused")
+        );
+        assert_eq!(
+            name(Some(DownloadState::Active), DownloadState::Complete),
+            Some("completed")
+        );
+        assert_eq!(
+            name(Some(DownloadState::Active), DownloadState::Seeding),
+            Some("completed")
+        );
+        assert_eq!(
+            name(Some(DownloadState::Seeding), DownloadState::Complete),
+            None
+        );
+        assert_eq!(
+            name(Some(DownloadState::Active), DownloadState::Error),
+            Some("failed")
+        );
+        assert_eq!(
+            name(Some(DownloadState::Waiting), DownloadState::Verifying),
+            None
+        );
+    }
+
+    #[test]
+    fn test_milestone_crossed() {
+        assert_eq!(milestone_crossed(0.0, 0.1), None);
+        assert_eq!(milestone_crossed(0.2, 0.3), Some(25));
+        assert_eq!(milestone_crossed(0.2, 0.8), Some(75));
+        assert_eq!(milestone_crossed(0.5, 0.6), None);
+        assert_eq!(milestone_crossed(0.74, 0.75), Some(75));
+    }
+}
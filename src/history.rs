@@ -0,0 +1,204 @@
+//! Finished and failed downloads, kept across restarts
+//!
+//! Every download that completes, or fails without an automatic retry left,
+//! becomes a [`HistoryEntry`]. Entries live in `history.json` under the data
+//! directory (`~/.local/share/tui-downloader/` on Linux), so they outlast
+//! purging the Completed tab and restarting the app. The History tab lists
+//! them newest first.
+
+use crate::config::Config;
+use crate::models::{Download, DownloadState};
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+const HISTORY_FILE: &str = "history.json";
+
+/// Entries kept in the history file, oldest are dropped first
+const HISTORY_CAPACITY: usize = 1000;
+
+/// How a download ended
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HistoryResult {
+    Complete,
+    Error,
+}
+
+/// One finished or failed download
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    #[serde(default)]
+    pub gid: Option<String>,
+    pub name: String,
+    pub url: Option<String>,
+    pub path: Option<String>, // Final location of the file
+    pub size: u64,
+    pub started: DateTime<Local>,
+    pub finished: DateTime<Local>,
+    pub average_speed: u64, // Bytes per second between start and finish
+    pub result: HistoryResult,
+    pub error: Option<String>,
+}
+
+impl HistoryEntry {
+    /// Entry for a download that ended at `finished`
+    pub fn from_download(download: &Download, finished: DateTime<Local>) -> Self {
+        let (result, size) = match download.status {
+            DownloadState::Error => (HistoryResult::Error, download.completed_length),
+            _ => (HistoryResult::Complete, download.total_length),
+        };
+        let seconds = (finished - download.added_at).num_seconds().max(1) as u64;
+        Self {
+            gid: download.gid.clone(),
+            name: download.name.clone(),
+            url: download.url.clone(),
+            path: download.file_path.clone(),
+            size,
+            started: download.added_at,
+            finished,
+            average_speed: download.completed_length / seconds,
+            result,
+            error: download.error_message.clone(),
+        }
+    }
+
+    /// Whether the name or URL contains `query` (case-insensitive)
+    pub fn matches(&self, query: &str) -> bool {
+        let query = query.to_lowercase();
+        self.name.to_lowercase().contains(&query)
+            || self
+                .url
+                .as_ref()
+                .is_some_and(|url| url.to_lowercase().contains(&query))
+    }
+}
+
+/// All recorded downloads, oldest first
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct History {
+    pub entries: Vec<HistoryEntry>,
+}
+
+impl History {
+    /// Path of the history file
+    pub fn path() -> PathBuf {
+        Config::data_dir().join(HISTORY_FILE)
+    }
+
+    /// Load the saved history (empty if there is no file)
+    pub fn load() -> Result<Self, Box<dyn std::error::Error>> {
+        let path = Self::path();
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = std::fs::read_to_string(&path)?;
+        Self::parse(&content).map_err(|e| format!("{}: {}", path.display(), e).into())
+    }
+
+    /// Parse a saved history from JSON text
+    pub fn parse(content: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        Ok(Self {
+            entries: serde_json::from_str(content)?,
+        })
+    }
+
+    /// JSON text for the history file
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(&self.entries).unwrap_or_default()
+    }
+
+    /// Add an entry, returning whether the history changed
+    ///
+    /// A download that already completed is not recorded twice, e.g. when a
+    /// torrent stops seeding.
+    pub fn record(&mut self, entry: HistoryEntry) -> bool {
+        let duplicate = entry.result == HistoryResult::Complete
+            && entry.gid.is_some()
+            && self
+                .entries
+                .iter()
+                .any(|e| e.gid == entry.gid && e.result == HistoryResult::Complete);
+        if duplicate {
+            return false;
+        }
+        self.entries.push(entry);
+        if self.entries.len() > HISTORY_CAPACITY {
+            let excess = self.entries.len() - HISTORY_CAPACITY;
+            self.entries.drain(..excess);
+        }
+        true
+    }
+}
+
+/// Entries matching a search query, newest first
+pub fn visible_history<'a>(entries: &'a [HistoryEntry], query: &str) -> Vec<&'a HistoryEntry> {
+    entries
+        .iter()
+        .rev()
+        .filter(|e| query.is_empty() || e.matches(query))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn finished(name: &str, gid: &str, status: DownloadState) -> HistoryEntry {
+        let download = Download {
+            gid: Some(gid.to_string()),
+            name: name.to_string(),
+            url: Some(format!("https://example.com/{}", name)),
+            status,
+            total_length: 4096,
+            completed_length: 4096,
+            added_at: Local::now() - chrono::Duration::seconds(4),
+            ..Default::default()
+        };
+        HistoryEntry::from_download(&download, Local::now())
+    }
+
+    #[test]
+    fn test_entry_from_download() {
+        let entry = finished("big.iso", "g1", DownloadState::Complete);
+        assert_eq!(entry.result, HistoryResult::Complete);
+        assert_eq!(entry.size, 4096);
+        assert_eq!(entry.average_speed, 1024);
+        assert!(entry.started < entry.finished);
+    }
+
+    #[test]
+    fn test_completed_downloads_are_recorded_once() {
+        let mut history = History::default();
+        assert!(history.record(finished("a.iso", "g1", DownloadState::Complete)));
+        assert!(!history.record(finished("a.iso", "g1", DownloadState::Complete)));
+
+        // Failures are kept even when the same download fails again
+        assert!(history.record(finished("b.zip", "g2", DownloadState::Error)));
+        assert!(history.record(finished("b.zip", "g2", DownloadState::Error)));
+        assert_eq!(history.entries.len(), 3);
+    }
+
+    #[test]
+    fn test_history_round_trips_through_json() {
+        let mut history = History::default();
+        history.record(finished("a.iso", "g1", DownloadState::Complete));
+        assert_eq!(History::parse(&history.to_json()).unwrap(), history);
+    }
+
+    #[test]
+    fn test_visible_history_is_newest_first_and_searchable() {
+        let mut history = History::default();
+        history.record(finished("a.iso", "g1", DownloadState::Complete));
+        history.record(finished("b.zip", "g2", DownloadState::Complete));
+
+        let names: Vec<&str> = visible_history(&history.entries, "")
+            .iter()
+            .map(|e| e.name.as_str())
+            .collect();
+        assert_eq!(names, vec!["b.zip", "a.iso"]);
+
+        // URLs are searched too
+        assert_eq!(visible_history(&history.entries, "COM/A").len(), 1);
+    }
+}
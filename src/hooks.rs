@@ -0,0 +1,280 @@
+//! User commands run on download events
+//!
+//! Hooks are `[[hooks]]` entries in `config.toml`. Each one runs a command
+//! when a download starts, completes or fails, optionally only for downloads
+//! whose name matches a glob pattern. Commands run in the background with a
+//! timeout and report back with a [`HookOutcome`].
+
+use crate::config::{HookConfig, HookEvent};
+use crate::events::DownloadEvent;
+use crate::models::{Download, DownloadType};
+use std::path::Path;
+use std::process::Stdio;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{broadcast, mpsc};
+
+/// Result of running a hook command
+#[derive(Clone, Debug)]
+pub struct HookOutcome {
+    pub gid: Option<String>,
+    pub name: String,    // Name of the download
+    pub program: String, // First word of the command, for messages
+    pub success: bool,
+    pub status: String, // "exit 0", "timed out after 60s", ...
+    pub output: String, // Combined stdout and stderr
+}
+
+/// Run hooks for events from `events` until the channel closes
+pub fn spawn(
+    hooks: Vec<HookConfig>,
+    mut events: broadcast::Receiver<DownloadEvent>,
+) -> mpsc::UnboundedReceiver<HookOutcome> {
+    let (tx, rx) = mpsc::unbounded_channel();
+    if hooks.is_empty() {
+        return rx;
+    }
+
+    let hooks = Arc::new(hooks);
+    tokio::spawn(async move {
+        loop {
+            let event = match events.recv().await {
+                Ok(event) => event,
+                Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                Err(broadcast::error::RecvError::Closed) => break,
+            };
+            let (Some(kind), Some(download)) = (hook_event(&event), event.download()) else {
+                continue;
+            };
+
+            for hook in hooks.iter().filter(|h| applies(h, kind, &download.name)) {
+                let hook = hook.clone();
+                let download = download.clone();
+                let tx = tx.clone();
+                tokio::spawn(async move {
+                    let _ = tx.send(run(&hook, &download).await);
+                });
+            }
+        }
+    });
+    rx
+}
+
+/// Hook event a download event triggers, if any
+fn hook_event(event: &DownloadEvent) -> Option<HookEvent> {
+    match event {
+        DownloadEvent::Started(_) => Some(HookEvent::Start),
+        DownloadEvent::Completed(_) => Some(HookEvent::Complete),
+        DownloadEvent::Failed(_) => Some(HookEvent::Error),
+        _ => None,
+    }
+}
+
+fn applies(hook: &HookConfig, event: HookEvent, name: &str) -> bool {
+    hook.event == event
+        && hook
+            .pattern
+            .as_deref()
+            .is_none_or(|pattern| glob_match(pattern, name))
+}
+
+/// Run a hook for a download, killing it when the timeout runs out
+async fn run(hook: &HookConfig, download: &Download) -> HookOutcome {
+    let argv: Vec<String> = hook.command.iter().map(|a| expand(a, download)).collect();
+
+    let mut command = tokio::process::Command::new(&argv[0]);
+    command
+        .args(&argv[1..])
+        .stdin(Stdio::null())
+        .kill_on_drop(true);
+
+    let limit = Duration::from_secs(hook.timeout);
+    let (success, status, output) = match tokio::time::timeout(limit, command.output()).await {
+        Err(_) => (
+            false,
+            format!("timed out after {}s", hook.timeout),
+            String::new(),
+        ),
+        Ok(Err(e)) => (false, format!("failed to start: {}", e), String::new()),
+        Ok(Ok(out)) => {
+            let mut text = String::from_utf8_lossy(&out.stdout).into_owned();
+            text.push_str(&String::from_utf8_lossy(&out.stderr));
+            let status = match out.status.code() {
+                Some(code) => format!("exit {}", code),
+                None => "killed by signal".to_string(),
+            };
+            (out.status.success(), status, text.trim_end().to_string())
+        }
+    };
+
+    HookOutcome {
+        gid: download.gid.clone(),
+        name: download.name.clone(),
+        program: hook.command[0].clone(),
+        success,
+        status,
+        output,
+    }
+}
+
+/// Replace `{placeholders}` in a hook argument; unknown ones are kept as-is
+pub fn expand(template: &str, download: &Download) -> String {
+    let mut result = String::new();
+    let mut rest = template;
+
+    while let Some(start) = rest.find('{') {
+        let Some(len) = rest[start..].find('}') else {
+            break;
+        };
+        let end = start + len;
+
+        result.push_str(&rest[..start]);
+        match placeholder(&rest[start + 1..end], download) {
+            Some(value) => result.push_str(&value),
+            None => result.push_str(&rest[start..=end]),
+        }
+        rest = &rest[end + 1..];
+    }
+    result.push_str(rest);
+    result
+}
+
+fn placeholder(key: &str, download: &Download) -> Option<String> {
+    let path = download.file_path.as_deref().unwrap_or("");
+    let value = match key {
+        "path" => path.to_string(),
+        "name" => download.name.clone(),
+        "gid" => download.gid.clone().unwrap_or_default(),
+        "url" => download.url.clone().unwrap_or_default(),
+        "size" => download.total_length.to_string(),
+        "dir" => Path::new(path)
+            .parent()
+            .map(|p| p.display().to_string())
+            .unwrap_or_default(),
+        "type" => match download.download_type {
+            DownloadType::Http => "http",
+            DownloadType::Torrent => "torrent",
+            DownloadType::Metalink => "metalink",
+        }
+        .to_string(),
+        _ => return None,
+    };
+    Some(value)
+}
+
+/// Shell-style glob supporting `*` and `?`, ignoring ASCII case
+pub fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.to_ascii_lowercase().chars().collect();
+    let text: Vec<char> = text.to_ascii_lowercase().chars().collect();
+
+    let (mut p, mut t) = (0, 0);
+    let mut star: Option<(usize, usize)> = None; // Pattern index after `*`, text index it matched up to
+
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p + 1, t));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match star {
+                // Let the last `*` swallow one more character
+                Some((sp, st)) => {
+                    p = sp;
+                    t = st + 1;
+                    star = Some((sp, st + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn download() -> Download {
+        Download {
+            gid: Some("abc123".to_string()),
+            name: "ubuntu.iso".to_string(),
+            url: Some("https://example.com/ubuntu.iso".to_string()),
+            total_length: 4096,
+            file_path: Some("/data/isos/ubuntu.iso".to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_expand_placeholders() {
+        let d = download();
+        assert_eq!(expand("{path}", &d), "/data/isos/ubuntu.iso");
+        assert_eq!(expand("{dir}/done", &d), "/data/isos/done");
+        assert_eq!(
+            expand("{name} ({size} bytes)", &d),
+            "ubuntu.iso (4096 bytes)"
+        );
+        assert_eq!(expand("--gid={gid}", &d), "--gid=abc123");
+        assert_eq!(
+            expand("{type}:{url}", &d),
+            "http:https://example.com/ubuntu.iso"
+        );
+        assert_eq!(expand("{unknown} {", &d), "{unknown} {");
+    }
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match("*.iso", "ubuntu.iso"));
+        assert!(glob_match("*.ISO", "ubuntu.iso"));
+        assert!(glob_match("ubuntu-??.*", "ubuntu-22.iso"));
+        assert!(glob_match("*", ""));
+        assert!(glob_match("a*b*c", "axxbyyc"));
+        assert!(!glob_match("*.iso", "ubuntu.iso.part"));
+        assert!(!glob_match("a*b*c", "axxbyy"));
+    }
+
+    #[test]
+    fn test_hook_applies_to_event_and_pattern() {
+        let hook = HookConfig {
+            event: HookEvent::Complete,
+            command: vec!["true".to_string()],
+            pattern: Some("*.iso".to_string()),
+            timeout: 60,
+        };
+        assert!(applies(&hook, HookEvent::Complete, "a.iso"));
+        assert!(!applies(&hook, HookEvent::Error, "a.iso"));
+        assert!(!applies(&hook, HookEvent::Complete, "a.zip"));
+    }
+
+    #[tokio::test]
+    async fn test_run_reports_status_and_output() {
+        let hook = |command: &[&str], timeout| HookConfig {
+            event: HookEvent::Complete,
+            command: command.iter().map(|s| s.to_string()).collect(),
+            pattern: None,
+            timeout,
+        };
+
+        let outcome = run(&hook(&["sh", "-c", "echo {name}"], 5), &download()).await;
+        assert!(outcome.success);
+        assert_eq!(outcome.status, "exit 0");
+        assert_eq!(outcome.output, "ubuntu.iso");
+
+        let outcome = run(
+            &hook(&["sh", "-c", "echo oops >&2; exit 3"], 5),
+            &download(),
+        )
+        .await;
+        assert!(!outcome.success);
+        assert_eq!(outcome.status, "exit 3");
+        assert_eq!(outcome.output, "oops");
+
+        let outcome = run(&hook(&["sleep", "5"], 1), &download()).await;
+        assert!(!outcome.success);
+        assert_eq!(outcome.status, "timed out after 1s");
+    }
+}
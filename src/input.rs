@@ -1,7 +1,8 @@
+use crate::keymap::Keymap;
 use crate::models::InputMode;
 use crossterm::event::KeyModifiers;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 #[allow(dead_code)]
 pub enum KeyAction {
     // Normal mode actions
@@ -28,8 +29,27 @@ pub enum KeyAction {
     // Help
     ShowHelp,
 
+    // Command line
+    EnterCommandMode,
+
+    // Show or hide the details overlay on small terminals
+    ToggleDetails,
+
+    // Switch to the next theme
+    CycleTheme,
+
     // Speed limit
     ShowSpeedLimit,
+    ToggleTurtle,
+
+    // Log view
+    ShowLog,
+
+    // Statistics dashboard
+    ShowStats,
+
+    // Scheduler
+    ShowSchedule,
 
     // Retry failed download
     RetryDownload,
@@ -79,6 +99,7 @@ pub enum KeyAction {
     SpeedLimitConfirm,
     SpeedLimitCancel,
     SpeedLimitToggleField,
+    SpeedLimitToggleScope,
     SpeedLimitIncrease,
     SpeedLimitDecrease,
 
@@ -87,10 +108,44 @@ pub enum KeyAction {
     HelpScrollUp,
     HelpScrollDown,
 
+    // Log mode actions
+    LogClose,
+    LogScrollUp,
+    LogScrollDown,
+
+    // Stats mode actions
+    StatsClose,
+    StatsCycleResolution,
+    StatsExport,
+
+    // Schedule mode actions
+    ScheduleClose,
+    ScheduleUp,
+    ScheduleDown,
+    ScheduleAdd,
+    ScheduleEdit,
+    ScheduleDelete,
+    ScheduleStartAt,
+    ScheduleStopAt,
+    ScheduleSubmit,
+    ScheduleCancel,
+
+    // Command line actions
+    CommandSubmit,
+    CommandCancel,
+    CommandComplete,
+    CommandEdited, // Text changed, by typing or recalling the history
+
     // Confirmation actions
     ConfirmYes,
     ConfirmNo,
 
+    // Context menu actions
+    MenuUp,
+    MenuDown,
+    MenuSelect,
+    MenuClose,
+
     // No action
     None,
 }
@@ -101,6 +156,11 @@ pub struct InputHandler {
     pub search_query: String,
     pub cursor_position: usize,
     pub speed_limit_buffer: String,
+    pub schedule_buffer: String, // Rule or time being typed in the schedule popup
+    pub command_buffer: String,  // Command line, without the leading `:`
+    pub command_history: Vec<String>, // Commands run, oldest first
+    history_pos: Option<usize>,  // Entry recalled with Up/Down
+    pub keymap: Keymap,          // Keys of the modes that run commands
 }
 
 impl InputHandler {
@@ -111,6 +171,11 @@ impl InputHandler {
             search_query: String::new(),
             cursor_position: 0,
             speed_limit_buffer: String::new(),
+            schedule_buffer: String::new(),
+            command_buffer: String::new(),
+            command_history: Vec::new(),
+            history_pos: None,
+            keymap: Keymap::default(),
         }
     }
 
@@ -121,87 +186,24 @@ impl InputHandler {
             InputMode::Search => self.handle_search_mode(key),
             InputMode::SpeedLimit => self.handle_speed_limit_mode(key),
             InputMode::Help => self.handle_help_mode(key),
+            InputMode::Log => self.handle_log_mode(key),
+            InputMode::Stats => self.handle_stats_mode(key),
+            InputMode::Schedule => self.handle_schedule_mode(key),
+            InputMode::ScheduleEdit => self.handle_schedule_edit_mode(key),
             InputMode::Confirmation => self.handle_confirmation_mode(key),
+            InputMode::Menu => self.handle_menu_mode(key),
+            InputMode::Command => self.handle_command_mode(key),
             InputMode::Settings => self.handle_settings_mode(key),
         }
     }
 
     pub fn handle_normal_mode(&mut self, key: &crossterm::event::KeyEvent) -> KeyAction {
-        use crossterm::event::KeyCode;
-
-        // Check for modifier combinations first
-        if key.modifiers.contains(KeyModifiers::SHIFT) {
-            match key.code {
-                KeyCode::Delete => return KeyAction::DeleteFile,
-                KeyCode::Up | KeyCode::Char('K') => return KeyAction::MoveQueueUp,
-                KeyCode::Down | KeyCode::Char('J') => return KeyAction::MoveQueueDown,
-                KeyCode::Char('P') => return KeyAction::PauseAll,
-                KeyCode::Char('R') => return KeyAction::ResumeAll,
-                _ => {}
-            }
-        }
-
-        if key.modifiers.contains(KeyModifiers::CONTROL) {
-            match key.code {
-                KeyCode::Char('a') => return KeyAction::SelectAll,
-                KeyCode::Char('d') => return KeyAction::DeselectAll,
-                KeyCode::Char('u') => return KeyAction::PageUp,
-                _ => {}
-            }
-        }
-
-        match key.code {
-            // Basic actions
-            KeyCode::Char('i') | KeyCode::Char('I') => KeyAction::EnterEditMode,
-            KeyCode::Char('q') | KeyCode::Char('Q') => KeyAction::Quit,
-
-            // Tab selection
-            KeyCode::Char('1') => KeyAction::SelectTab(0),
-            KeyCode::Char('2') => KeyAction::SelectTab(1),
-            KeyCode::Char('3') => KeyAction::SelectTab(2),
-
-            // Navigation
-            KeyCode::Up | KeyCode::Char('k') => KeyAction::MoveUp,
-            KeyCode::Down | KeyCode::Char('j') => KeyAction::MoveDown,
-            KeyCode::Home | KeyCode::Char('g') => KeyAction::MoveToTop,
-            KeyCode::End | KeyCode::Char('G') => KeyAction::MoveToBottom,
-            KeyCode::PageUp => KeyAction::PageUp,
-            KeyCode::PageDown => KeyAction::PageDown,
-
-            // Download management
-            KeyCode::Char(' ') | KeyCode::Char('p') => KeyAction::PauseResume,
-            KeyCode::Char('d') => KeyAction::Delete,
-            KeyCode::Char('x') | KeyCode::Char('X') => KeyAction::PurgeCompleted,
-            KeyCode::Char('r') => KeyAction::RetryDownload,
-
-            // Search
-            KeyCode::Char('/') => KeyAction::EnterSearchMode,
-            KeyCode::Esc => KeyAction::ClearSearch,
-
-            // Help
-            KeyCode::Char('?') => KeyAction::ShowHelp,
-            KeyCode::F(1) => KeyAction::ShowHelp,
-
-            // Speed limit
-            KeyCode::Char('l') | KeyCode::Char('L') => KeyAction::ShowSpeedLimit,
-
-            // Open file/folder
-            KeyCode::Char('o') => KeyAction::OpenFile,
-            KeyCode::Char('O') => KeyAction::OpenFolder,
-
-            // Copy
-            KeyCode::Char('c') => KeyAction::CopyUrl,
-            KeyCode::Char('C') => KeyAction::CopyPath,
-
-            // Sorting
-            KeyCode::Char('s') => KeyAction::CycleSort,
-            KeyCode::Char('S') => KeyAction::ToggleSortDirection,
-
-            // Selection
-            KeyCode::Char('v') | KeyCode::Char('V') => KeyAction::ToggleSelect,
+        self.bound_action(key).unwrap_or(KeyAction::None)
+    }
 
-            _ => KeyAction::None,
-        }
+    /// Action the keymap binds to `key` in the current mode
+    fn bound_action(&self, key: &crossterm::event::KeyEvent) -> Option<KeyAction> {
+        self.keymap.action(self.mode, key)
     }
 
     pub fn handle_input_mode(&mut self, key: &crossterm::event::KeyEvent) -> KeyAction {
@@ -312,12 +314,10 @@ impl InputHandler {
     pub fn handle_speed_limit_mode(&mut self, key: &crossterm::event::KeyEvent) -> KeyAction {
         use crossterm::event::KeyCode;
 
+        if let Some(action) = self.bound_action(key) {
+            return action;
+        }
         match key.code {
-            KeyCode::Enter => KeyAction::SpeedLimitConfirm,
-            KeyCode::Esc => KeyAction::SpeedLimitCancel,
-            KeyCode::Tab | KeyCode::Up | KeyCode::Down => KeyAction::SpeedLimitToggleField,
-            KeyCode::Right => KeyAction::SpeedLimitIncrease,
-            KeyCode::Left => KeyAction::SpeedLimitDecrease,
             KeyCode::Backspace => {
                 self.speed_limit_buffer.pop();
                 KeyAction::None
@@ -333,28 +333,111 @@ impl InputHandler {
     }
 
     pub fn handle_help_mode(&mut self, key: &crossterm::event::KeyEvent) -> KeyAction {
+        self.bound_action(key).unwrap_or(KeyAction::None)
+    }
+
+    pub fn handle_log_mode(&mut self, key: &crossterm::event::KeyEvent) -> KeyAction {
+        self.bound_action(key).unwrap_or(KeyAction::None)
+    }
+
+    pub fn handle_stats_mode(&mut self, key: &crossterm::event::KeyEvent) -> KeyAction {
+        self.bound_action(key).unwrap_or(KeyAction::None)
+    }
+
+    pub fn handle_schedule_mode(&mut self, key: &crossterm::event::KeyEvent) -> KeyAction {
+        self.bound_action(key).unwrap_or(KeyAction::None)
+    }
+
+    pub fn handle_schedule_edit_mode(&mut self, key: &crossterm::event::KeyEvent) -> KeyAction {
         use crossterm::event::KeyCode;
 
         match key.code {
-            KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('?') | KeyCode::Enter => {
-                KeyAction::HelpClose
+            KeyCode::Enter => KeyAction::ScheduleSubmit,
+            KeyCode::Esc => KeyAction::ScheduleCancel,
+            KeyCode::Backspace => {
+                self.schedule_buffer.pop();
+                KeyAction::None
+            }
+            KeyCode::Char(c) => {
+                self.schedule_buffer.push(c);
+                KeyAction::None
             }
-            KeyCode::Up | KeyCode::Char('k') => KeyAction::HelpScrollUp,
-            KeyCode::Down | KeyCode::Char('j') => KeyAction::HelpScrollDown,
             _ => KeyAction::None,
         }
     }
 
     pub fn handle_confirmation_mode(&mut self, key: &crossterm::event::KeyEvent) -> KeyAction {
+        self.bound_action(key).unwrap_or(KeyAction::None)
+    }
+
+    pub fn handle_menu_mode(&mut self, key: &crossterm::event::KeyEvent) -> KeyAction {
+        self.bound_action(key).unwrap_or(KeyAction::None)
+    }
+
+    pub fn handle_command_mode(&mut self, key: &crossterm::event::KeyEvent) -> KeyAction {
         use crossterm::event::KeyCode;
 
+        if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('u') {
+            self.command_buffer.clear();
+            return KeyAction::CommandEdited;
+        }
         match key.code {
-            KeyCode::Char('y') | KeyCode::Char('Y') => KeyAction::ConfirmYes,
-            KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc => KeyAction::ConfirmNo,
+            KeyCode::Enter => KeyAction::CommandSubmit,
+            KeyCode::Esc => KeyAction::CommandCancel,
+            KeyCode::Tab => KeyAction::CommandComplete,
+            // Deleting the `:` leaves the command line, as in vi
+            KeyCode::Backspace if self.command_buffer.is_empty() => KeyAction::CommandCancel,
+            KeyCode::Backspace => {
+                self.command_buffer.pop();
+                KeyAction::CommandEdited
+            }
+            KeyCode::Up => {
+                self.recall_command(true);
+                KeyAction::CommandEdited
+            }
+            KeyCode::Down => {
+                self.recall_command(false);
+                KeyAction::CommandEdited
+            }
+            KeyCode::Char(c) => {
+                self.command_buffer.push(c);
+                KeyAction::CommandEdited
+            }
             _ => KeyAction::None,
         }
     }
 
+    /// Put an older or newer history entry on the command line
+    ///
+    /// Going past the newest entry leaves an empty line.
+    fn recall_command(&mut self, older: bool) {
+        let len = self.command_history.len();
+        let pos = match (self.history_pos, older) {
+            (None, true) if len > 0 => len - 1,
+            (None, _) => return,
+            (Some(i), true) => i.saturating_sub(1),
+            (Some(i), false) if i + 1 < len => i + 1,
+            (Some(_), false) => {
+                self.history_pos = None;
+                self.command_buffer.clear();
+                return;
+            }
+        };
+        self.history_pos = Some(pos);
+        self.command_buffer = self.command_history[pos].clone();
+    }
+
+    /// Remember a command that was run, skipping repeats of the last one
+    pub fn push_history(&mut self, line: &str) {
+        if line.is_empty() || self.command_history.last().is_some_and(|last| last == line) {
+            return;
+        }
+        if self.command_history.len() >= crate::palette::HISTORY_CAPACITY {
+            self.command_history.remove(0);
+        }
+        self.command_history.push(line.to_string());
+    }
+
     pub fn handle_settings_mode(&mut self, key: &crossterm::event::KeyEvent) -> KeyAction {
         use crossterm::event::KeyCode;
 
@@ -375,6 +458,13 @@ impl InputHandler {
             InputMode::Search => {
                 self.search_query.push_str(data);
             }
+            InputMode::ScheduleEdit => {
+                self.schedule_buffer.push_str(data.trim());
+            }
+            InputMode::Command => {
+                self.command_buffer
+                    .push_str(&data.replace(['\n', '\r'], " "));
+            }
             InputMode::SpeedLimit => {
                 // Only allow numeric pastes for speed limit
                 let cleaned: String = data
@@ -409,6 +499,35 @@ impl InputHandler {
         self.mode = InputMode::Help;
     }
 
+    pub fn enter_log_mode(&mut self) {
+        self.mode = InputMode::Log;
+    }
+
+    pub fn enter_stats_mode(&mut self) {
+        self.mode = InputMode::Stats;
+    }
+
+    pub fn enter_menu_mode(&mut self) {
+        self.mode = InputMode::Menu;
+    }
+
+    pub fn enter_schedule_mode(&mut self) {
+        self.mode = InputMode::Schedule;
+    }
+
+    /// Start typing in the schedule popup, pre-filled with `text`
+    pub fn enter_schedule_edit_mode(&mut self, text: &str) {
+        self.mode = InputMode::ScheduleEdit;
+        self.schedule_buffer = text.to_string();
+    }
+
+    /// Open the command line, pre-filled with `text`
+    pub fn enter_command_mode(&mut self, text: &str) {
+        self.mode = InputMode::Command;
+        self.command_buffer = text.to_string();
+        self.history_pos = None;
+    }
+
     pub fn enter_confirmation_mode(&mut self) {
         self.mode = InputMode::Confirmation;
     }
@@ -424,11 +543,9 @@ impl InputHandler {
     #[allow(dead_code)]
     pub fn delete_last_char(&mut self) {
         match self.mode {
-            InputMode::Editing => {
-                if self.cursor_position > 0 {
-                    self.cursor_position -= 1;
-                    self.buffer.remove(self.cursor_position);
-                }
+            InputMode::Editing if self.cursor_position > 0 => {
+                self.cursor_position -= 1;
+                self.buffer.remove(self.cursor_position);
             }
             InputMode::Search => {
                 self.search_query.pop();
@@ -568,6 +685,12 @@ mod tests {
 
         let action = handler.handle_key(&make_key_event(KeyCode::Char('l')));
         assert!(matches!(action, KeyAction::ShowSpeedLimit));
+
+        let action = handler.handle_key(&make_key_event(KeyCode::Char('e')));
+        assert!(matches!(action, KeyAction::ShowLog));
+
+        let action = handler.handle_key(&make_key_event(KeyCode::Char('t')));
+        assert!(matches!(action, KeyAction::ShowSchedule));
     }
 
     #[test]
@@ -596,6 +719,48 @@ mod tests {
         assert_eq!(handler.search_query, "tes");
     }
 
+    #[test]
+    fn test_schedule_edit_mode() {
+        let mut handler = InputHandler::new();
+        handler.enter_schedule_edit_mode("daily");
+        handler.handle_key(&make_key_event(KeyCode::Char(' ')));
+        handler.handle_paste(" 01:00-07:00 resume ");
+        assert_eq!(handler.schedule_buffer, "daily 01:00-07:00 resume");
+
+        handler.handle_key(&make_key_event(KeyCode::Backspace));
+        assert_eq!(handler.schedule_buffer, "daily 01:00-07:00 resum");
+
+        let action = handler.handle_key(&make_key_event(KeyCode::Enter));
+        assert!(matches!(action, KeyAction::ScheduleSubmit));
+    }
+
+    #[test]
+    fn test_command_mode_history() {
+        let mut handler = InputHandler::new();
+        handler.push_history("sort size");
+        handler.push_history("purge");
+        handler.push_history("purge");
+        assert_eq!(handler.command_history, vec!["sort size", "purge"]);
+
+        handler.enter_command_mode("");
+        handler.handle_key(&make_key_event(KeyCode::Up));
+        assert_eq!(handler.command_buffer, "purge");
+        handler.handle_key(&make_key_event(KeyCode::Up));
+        handler.handle_key(&make_key_event(KeyCode::Up));
+        assert_eq!(handler.command_buffer, "sort size");
+        handler.handle_key(&make_key_event(KeyCode::Down));
+        assert_eq!(handler.command_buffer, "purge");
+        handler.handle_key(&make_key_event(KeyCode::Down));
+        assert_eq!(handler.command_buffer, "");
+
+        // Backspace on an empty line leaves the command line
+        handler.handle_key(&make_key_event(KeyCode::Char('x')));
+        let action = handler.handle_key(&make_key_event(KeyCode::Backspace));
+        assert_eq!(action, KeyAction::CommandEdited);
+        let action = handler.handle_key(&make_key_event(KeyCode::Backspace));
+        assert_eq!(action, KeyAction::CommandCancel);
+    }
+
     #[test]
     fn test_take_input() {
         let mut handler = InputHandler::new();
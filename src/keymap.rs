@@ -0,0 +1,477 @@
+//! Key bindings for the modes that run commands
+//!
+//! Each [`InputMode`] that maps keys to commands has a table of default
+//! bindings below. `[keys.<mode>]` sections in the configuration rebind
+//! actions by name, replacing their default keys:
+//!
+//! ```toml
+//! [keys.normal]
+//! delete = "Shift+Delete"
+//! delete_file = []          # no key
+//! ```
+//!
+//! A key bound to two actions of the same mode, or a mode left without a way
+//! out, is rejected at startup. Modes for typing text (adding a URL,
+//! searching, editing a schedule rule) keep their fixed editing keys. The
+//! help popup and the shortcuts bar are generated from the active keymap.
+
+use crate::config::{KeyList, KeysConfig};
+use crate::input::KeyAction;
+use crate::models::InputMode;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use indexmap::IndexMap;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::OnceLock;
+
+/// An action's name in `[keys.<mode>]`, the action and its default keys
+type Binding = (&'static str, KeyAction, &'static [&'static str]);
+
+const NORMAL: &[Binding] = &[
+    ("add", KeyAction::EnterEditMode, &["i", "I"]),
+    ("quit", KeyAction::Quit, &["q", "Q"]),
+    ("tab_1", KeyAction::SelectTab(0), &["1"]),
+    ("tab_2", KeyAction::SelectTab(1), &["2"]),
+    ("tab_3", KeyAction::SelectTab(2), &["3"]),
+    ("tab_4", KeyAction::SelectTab(3), &["4"]),
+    ("up", KeyAction::MoveUp, &["k", "Up"]),
+    ("down", KeyAction::MoveDown, &["j", "Down"]),
+    ("top", KeyAction::MoveToTop, &["g", "Home"]),
+    ("bottom", KeyAction::MoveToBottom, &["G", "End"]),
+    ("page_up", KeyAction::PageUp, &["PageUp", "Ctrl+u"]),
+    ("page_down", KeyAction::PageDown, &["PageDown"]),
+    ("pause", KeyAction::PauseResume, &["Space", "p"]),
+    ("delete", KeyAction::Delete, &["d"]),
+    ("delete_file", KeyAction::DeleteFile, &["Shift+Delete"]),
+    ("purge", KeyAction::PurgeCompleted, &["x", "X"]),
+    ("retry", KeyAction::RetryDownload, &["r"]),
+    ("search", KeyAction::EnterSearchMode, &["/"]),
+    ("clear_search", KeyAction::ClearSearch, &["Esc"]),
+    ("help", KeyAction::ShowHelp, &["?", "F1"]),
+    ("command", KeyAction::EnterCommandMode, &[":"]),
+    ("details", KeyAction::ToggleDetails, &["Enter"]),
+    ("theme", KeyAction::CycleTheme, &["Ctrl+t"]),
+    ("speed_limit", KeyAction::ShowSpeedLimit, &["l", "L"]),
+    ("turtle", KeyAction::ToggleTurtle, &["T"]),
+    ("log", KeyAction::ShowLog, &["e"]),
+    ("stats", KeyAction::ShowStats, &["a"]),
+    ("schedule", KeyAction::ShowSchedule, &["t"]),
+    ("open_file", KeyAction::OpenFile, &["o"]),
+    ("open_folder", KeyAction::OpenFolder, &["O"]),
+    ("copy_url", KeyAction::CopyUrl, &["c"]),
+    ("copy_path", KeyAction::CopyPath, &["C"]),
+    ("sort", KeyAction::CycleSort, &["s"]),
+    ("sort_direction", KeyAction::ToggleSortDirection, &["S"]),
+    ("queue_up", KeyAction::MoveQueueUp, &["K", "Shift+Up"]),
+    ("queue_down", KeyAction::MoveQueueDown, &["J", "Shift+Down"]),
+    ("select", KeyAction::ToggleSelect, &["v", "V"]),
+    ("select_all", KeyAction::SelectAll, &["Ctrl+a"]),
+    ("deselect_all", KeyAction::DeselectAll, &["Ctrl+d"]),
+    ("pause_all", KeyAction::PauseAll, &["P"]),
+    ("resume_all", KeyAction::ResumeAll, &["R"]),
+];
+
+const SPEED_LIMIT: &[Binding] = &[
+    ("confirm", KeyAction::SpeedLimitConfirm, &["Enter"]),
+    ("cancel", KeyAction::SpeedLimitCancel, &["Esc"]),
+    (
+        "switch",
+        KeyAction::SpeedLimitToggleField,
+        &["Tab", "Up", "Down"],
+    ),
+    ("scope", KeyAction::SpeedLimitToggleScope, &["s"]),
+    ("increase", KeyAction::SpeedLimitIncrease, &["Right"]),
+    ("decrease", KeyAction::SpeedLimitDecrease, &["Left"]),
+];
+
+const HELP: &[Binding] = &[
+    ("close", KeyAction::HelpClose, &["Esc", "q", "?", "Enter"]),
+    ("scroll_up", KeyAction::HelpScrollUp, &["k", "Up"]),
+    ("scroll_down", KeyAction::HelpScrollDown, &["j", "Down"]),
+];
+
+const LOG: &[Binding] = &[
+    ("close", KeyAction::LogClose, &["Esc", "q", "e", "Enter"]),
+    ("scroll_up", KeyAction::LogScrollUp, &["k", "Up"]),
+    ("scroll_down", KeyAction::LogScrollDown, &["j", "Down"]),
+];
+
+const STATS: &[Binding] = &[
+    ("close", KeyAction::StatsClose, &["Esc", "q", "a"]),
+    ("resolution", KeyAction::StatsCycleResolution, &["r", "Tab"]),
+    ("export", KeyAction::StatsExport, &["e"]),
+];
+
+const SCHEDULE: &[Binding] = &[
+    ("close", KeyAction::ScheduleClose, &["Esc", "q", "t"]),
+    ("up", KeyAction::ScheduleUp, &["k", "Up"]),
+    ("down", KeyAction::ScheduleDown, &["j", "Down"]),
+    ("add", KeyAction::ScheduleAdd, &["a"]),
+    ("edit", KeyAction::ScheduleEdit, &["Enter", "e"]),
+    ("delete", KeyAction::ScheduleDelete, &["d", "Delete"]),
+    ("start_at", KeyAction::ScheduleStartAt, &["s"]),
+    ("stop_at", KeyAction::ScheduleStopAt, &["p"]),
+];
+
+const CONFIRM: &[Binding] = &[
+    ("yes", KeyAction::ConfirmYes, &["y", "Y"]),
+    ("no", KeyAction::ConfirmNo, &["n", "N", "Esc"]),
+];
+
+const MENU: &[Binding] = &[
+    ("up", KeyAction::MenuUp, &["k", "Up"]),
+    ("down", KeyAction::MenuDown, &["j", "Down"]),
+    ("select", KeyAction::MenuSelect, &["Enter", "Space"]),
+    ("close", KeyAction::MenuClose, &["Esc", "q"]),
+];
+
+/// Modes with configurable keys, by their name in `[keys.<mode>]`
+const MODES: &[(&str, InputMode, &[Binding])] = &[
+    ("normal", InputMode::Normal, NORMAL),
+    ("speed_limit", InputMode::SpeedLimit, SPEED_LIMIT),
+    ("help", InputMode::Help, HELP),
+    ("log", InputMode::Log, LOG),
+    ("stats", InputMode::Stats, STATS),
+    ("schedule", InputMode::Schedule, SCHEDULE),
+    ("confirm", InputMode::Confirmation, CONFIRM),
+    ("menu", InputMode::Menu, MENU),
+];
+
+/// Actions that must keep a key so the mode can be left
+const REQUIRED: &[KeyAction] = &[
+    KeyAction::Quit,
+    KeyAction::SpeedLimitCancel,
+    KeyAction::HelpClose,
+    KeyAction::LogClose,
+    KeyAction::StatsClose,
+    KeyAction::ScheduleClose,
+    KeyAction::ConfirmNo,
+    KeyAction::MenuClose,
+];
+
+/// Names of a mode's actions in `[keys.<mode>]`, with the actions
+pub fn actions(mode: InputMode) -> impl Iterator<Item = (&'static str, &'static KeyAction)> {
+    MODES
+        .iter()
+        .filter(move |(_, m, _)| *m == mode)
+        .flat_map(|(_, _, bindings)| bindings.iter().map(|(name, action, _)| (*name, action)))
+}
+
+/// A key with its modifiers
+///
+/// Shift is folded into letters and symbols (`G`, `?`), since terminals
+/// disagree on whether to report it; letters with Ctrl or Alt are lowercase.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Key {
+    pub code: KeyCode,
+    pub modifiers: KeyModifiers,
+}
+
+impl Key {
+    pub fn new(code: KeyCode, modifiers: KeyModifiers) -> Self {
+        let mut modifiers =
+            modifiers & (KeyModifiers::SHIFT | KeyModifiers::CONTROL | KeyModifiers::ALT);
+        let code = match code {
+            KeyCode::Char(c) => {
+                let shifted = modifiers.contains(KeyModifiers::SHIFT);
+                modifiers.remove(KeyModifiers::SHIFT);
+                if modifiers.intersects(KeyModifiers::CONTROL | KeyModifiers::ALT) {
+                    KeyCode::Char(c.to_ascii_lowercase())
+                } else if shifted {
+                    KeyCode::Char(c.to_ascii_uppercase())
+                } else {
+                    KeyCode::Char(c)
+                }
+            }
+            code => code,
+        };
+        Self { code, modifiers }
+    }
+
+    /// Key of a terminal event
+    pub fn from_event(event: &KeyEvent) -> Self {
+        Self::new(event.code, event.modifiers)
+    }
+
+    /// Parse a key such as `d`, `Space`, `Ctrl+u` or `Shift+Delete`
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let invalid = || format!("invalid key '{}'", text);
+        let mut modifiers = KeyModifiers::NONE;
+        let mut rest = text;
+        // `+` is a key too, so only strip prefixes that name a modifier
+        while let Some((prefix, tail)) = rest.split_once('+') {
+            let modifier = match prefix.to_lowercase().as_str() {
+                "ctrl" | "control" => KeyModifiers::CONTROL,
+                "alt" => KeyModifiers::ALT,
+                "shift" => KeyModifiers::SHIFT,
+                _ => break,
+            };
+            if tail.is_empty() {
+                break;
+            }
+            modifiers |= modifier;
+            rest = tail;
+        }
+
+        let code = match rest.to_lowercase().as_str() {
+            "space" => KeyCode::Char(' '),
+            "enter" | "return" => KeyCode::Enter,
+            "esc" | "escape" => KeyCode::Esc,
+            "tab" => KeyCode::Tab,
+            "backspace" => KeyCode::Backspace,
+            "delete" | "del" => KeyCode::Delete,
+            "insert" | "ins" => KeyCode::Insert,
+            "up" => KeyCode::Up,
+            "down" => KeyCode::Down,
+            "left" => KeyCode::Left,
+            "right" => KeyCode::Right,
+            "home" => KeyCode::Home,
+            "end" => KeyCode::End,
+            "pageup" | "pgup" => KeyCode::PageUp,
+            "pagedown" | "pgdn" => KeyCode::PageDown,
+            name if name.len() > 1 && name.starts_with('f') => match name[1..].parse() {
+                Ok(n @ 1..=24) => KeyCode::F(n),
+                _ => return Err(invalid()),
+            },
+            _ => {
+                let mut chars = rest.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(c), None) => KeyCode::Char(c),
+                    _ => return Err(invalid()),
+                }
+            }
+        };
+        Ok(Self::new(code, modifiers))
+    }
+}
+
+impl fmt::Display for Key {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (modifier, name) in [
+            (KeyModifiers::CONTROL, "Ctrl+"),
+            (KeyModifiers::ALT, "Alt+"),
+            (KeyModifiers::SHIFT, "Shift+"),
+        ] {
+            if self.modifiers.contains(modifier) {
+                f.write_str(name)?;
+            }
+        }
+        match self.code {
+            KeyCode::Char(' ') => f.write_str("Space"),
+            KeyCode::Char(c) if self.modifiers.is_empty() => write!(f, "{}", c),
+            KeyCode::Char(c) => write!(f, "{}", c.to_ascii_uppercase()),
+            KeyCode::F(n) => write!(f, "F{}", n),
+            KeyCode::Enter => f.write_str("Enter"),
+            KeyCode::Esc => f.write_str("Esc"),
+            KeyCode::Tab => f.write_str("Tab"),
+            KeyCode::Backspace => f.write_str("Backspace"),
+            KeyCode::Delete => f.write_str("Delete"),
+            KeyCode::Insert => f.write_str("Insert"),
+            KeyCode::Up => f.write_str("Up"),
+            KeyCode::Down => f.write_str("Down"),
+            KeyCode::Left => f.write_str("Left"),
+            KeyCode::Right => f.write_str("Right"),
+            KeyCode::Home => f.write_str("Home"),
+            KeyCode::End => f.write_str("End"),
+            KeyCode::PageUp => f.write_str("PageUp"),
+            KeyCode::PageDown => f.write_str("PageDown"),
+            code => write!(f, "{:?}", code),
+        }
+    }
+}
+
+/// Active key bindings of every mode with configurable keys
+#[derive(Clone, Debug)]
+pub struct Keymap {
+    modes: HashMap<InputMode, IndexMap<Key, KeyAction>>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Self::from_config(&KeysConfig::default()).expect("default key bindings conflict")
+    }
+}
+
+impl Keymap {
+    /// Defaults with the bindings from `[keys.<mode>]` sections applied
+    pub fn from_config(config: &KeysConfig) -> Result<Self, String> {
+        for (mode_name, actions) in &config.0 {
+            let Some((_, _, bindings)) = MODES.iter().find(|(name, _, _)| name == mode_name) else {
+                return Err(format!("[keys] unknown mode '{}'", mode_name));
+            };
+            if let Some(action) = actions
+                .keys()
+                .find(|action| !bindings.iter().any(|(name, _, _)| name == *action))
+            {
+                return Err(format!("[keys.{}] unknown action '{}'", mode_name, action));
+            }
+        }
+
+        let mut modes = HashMap::new();
+        for (mode_name, mode, bindings) in MODES {
+            let overrides = config.0.get(*mode_name);
+            let mut keys: IndexMap<Key, KeyAction> = IndexMap::new();
+            let mut names: HashMap<Key, &str> = HashMap::new();
+            for (name, action, defaults) in *bindings {
+                let texts: Vec<&str> = match overrides.and_then(|o| o.get(*name)) {
+                    Some(KeyList::One(text)) => vec![text.as_str()],
+                    Some(KeyList::Many(texts)) => texts.iter().map(String::as_str).collect(),
+                    None => defaults.to_vec(),
+                };
+                for text in texts {
+                    let key =
+                        Key::parse(text).map_err(|e| format!("[keys.{}] {}", mode_name, e))?;
+                    if let Some(other) = names.get(&key).filter(|other| *other != name) {
+                        return Err(format!(
+                            "[keys.{}] {} is bound to both {} and {}",
+                            mode_name, key, other, name
+                        ));
+                    }
+                    names.insert(key, name);
+                    keys.insert(key, action.clone());
+                }
+                if REQUIRED.contains(action) && !keys.values().any(|a| a == action) {
+                    return Err(format!("[keys.{}] {} needs a key", mode_name, name));
+                }
+            }
+            modes.insert(*mode, keys);
+        }
+        Ok(Self { modes })
+    }
+
+    /// The default bindings, shared
+    pub fn builtin() -> &'static Keymap {
+        static BUILTIN: OnceLock<Keymap> = OnceLock::new();
+        BUILTIN.get_or_init(Keymap::default)
+    }
+
+    /// Action bound to a key event in `mode`
+    pub fn action(&self, mode: InputMode, event: &KeyEvent) -> Option<KeyAction> {
+        self.modes.get(&mode)?.get(&Key::from_event(event)).cloned()
+    }
+
+    /// Keys bound to an action in `mode`, in the order they were given
+    pub fn keys(&self, mode: InputMode, action: &KeyAction) -> Vec<Key> {
+        self.modes
+            .get(&mode)
+            .map(|keys| {
+                keys.iter()
+                    .filter(|(_, a)| *a == action)
+                    .map(|(key, _)| *key)
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// The first key bound to an action, as shown in the shortcuts bar
+    pub fn label(&self, mode: InputMode, action: &KeyAction) -> Option<String> {
+        self.keys(mode, action).first().map(Key::to_string)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(code: KeyCode, modifiers: KeyModifiers) -> KeyEvent {
+        KeyEvent::new(code, modifiers)
+    }
+
+    fn keys_config(toml_text: &str) -> KeysConfig {
+        #[derive(serde::Deserialize)]
+        struct Wrapper {
+            keys: KeysConfig,
+        }
+        toml::from_str::<Wrapper>(toml_text).unwrap().keys
+    }
+
+    #[test]
+    fn test_parse_and_display_keys() {
+        for text in [
+            "d",
+            "G",
+            "?",
+            "Space",
+            "Ctrl+A",
+            "Shift+Delete",
+            "F1",
+            "PageUp",
+            "+",
+        ] {
+            assert_eq!(Key::parse(text).unwrap().to_string(), text);
+        }
+        assert_eq!(Key::parse("shift+k").unwrap(), Key::parse("K").unwrap());
+        assert_eq!(Key::parse("ctrl+U").unwrap(), Key::parse("Ctrl+u").unwrap());
+        assert!(Key::parse("Hyper+x").is_err());
+        assert!(Key::parse("F30").is_err());
+    }
+
+    #[test]
+    fn test_shift_is_folded_into_characters() {
+        let keymap = Keymap::default();
+        let shifted = event(KeyCode::Char('G'), KeyModifiers::SHIFT);
+        assert_eq!(
+            keymap.action(InputMode::Normal, &shifted),
+            Some(KeyAction::MoveToBottom)
+        );
+        let up = event(KeyCode::Up, KeyModifiers::SHIFT);
+        assert_eq!(
+            keymap.action(InputMode::Normal, &up),
+            Some(KeyAction::MoveQueueUp)
+        );
+    }
+
+    #[test]
+    fn test_rebinding_replaces_default_keys() {
+        let config = keys_config(
+            r#"
+            [keys.normal]
+            delete = "Shift+Delete"
+            delete_file = ["D", "Ctrl+x"]
+            "#,
+        );
+        let keymap = Keymap::from_config(&config).unwrap();
+        let d = event(KeyCode::Char('d'), KeyModifiers::NONE);
+        assert_eq!(keymap.action(InputMode::Normal, &d), None);
+        assert_eq!(
+            keymap.label(InputMode::Normal, &KeyAction::Delete),
+            Some("Shift+Delete".to_string())
+        );
+        assert_eq!(
+            keymap.keys(InputMode::Normal, &KeyAction::DeleteFile).len(),
+            2
+        );
+    }
+
+    #[test]
+    fn test_conflicts_are_rejected() {
+        let config = keys_config("[keys.normal]\ndelete_file = \"d\"");
+        assert_eq!(
+            Keymap::from_config(&config).unwrap_err(),
+            "[keys.normal] d is bound to both delete and delete_file"
+        );
+
+        // The same key may mean different things in different modes
+        let config = keys_config("[keys.help]\nclose = \"d\"");
+        assert!(Keymap::from_config(&config).is_ok());
+    }
+
+    #[test]
+    fn test_invalid_sections_are_rejected() {
+        for (toml_text, error) in [
+            ("[keys.nope]\nquit = \"q\"", "[keys] unknown mode 'nope'"),
+            (
+                "[keys.normal]\nfly = \"f\"",
+                "[keys.normal] unknown action 'fly'",
+            ),
+            ("[keys.help]\nclose = []", "[keys.help] close needs a key"),
+            (
+                "[keys.log]\nclose = \"Ctrl+\"",
+                "[keys.log] invalid key 'Ctrl+'",
+            ),
+        ] {
+            let config = keys_config(toml_text);
+            assert_eq!(Keymap::from_config(&config).unwrap_err(), error);
+        }
+    }
+}
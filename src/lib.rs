@@ -1,9 +1,27 @@
+pub mod app;
 pub mod aria2;
+pub mod bandwidth;
+pub mod config;
 pub mod download_manager;
+pub mod events;
+pub mod history;
+pub mod hooks;
 pub mod input;
+pub mod keymap;
 pub mod models;
+pub mod notifications;
+pub mod palette;
+pub mod query;
+pub mod scheduler;
+pub mod server;
+pub mod stats;
 pub mod ui;
+pub mod units;
+pub mod usage;
+pub mod worker;
 
 pub use aria2::Aria2Manager;
+pub use config::Config;
 pub use download_manager::DownloadManager;
+pub use events::DownloadEvent;
 pub use models::{Download, DownloadType, InputMode};
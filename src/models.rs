@@ -1,12 +1,14 @@
+use serde::{Deserialize, Serialize};
+
 /// Represents a download item with metadata
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize)]
 pub struct Download {
     pub gid: Option<String>,
     pub name: String,
     pub url: Option<String>,
     pub progress: f64,
     pub speed: String,
-    pub status: String,
+    pub status: DownloadState,
     pub total_length: u64,
     pub completed_length: u64,
     pub download_type: DownloadType,
@@ -16,12 +18,21 @@ pub struct Download {
     pub connections: u32,
     pub file_path: Option<String>,
     pub error_message: Option<String>,
-    #[allow(dead_code)]
-    pub added_at: std::time::Instant, // When the download was added
-    pub seeds: u32,               // For torrents: number of seeders
-    pub peers: u32,               // For torrents: number of peers
-    pub bitfield: Option<String>, // Hex string showing which pieces are downloaded
-    pub num_pieces: u32,          // Total number of pieces in the download
+    pub error_code: Option<String>, // aria2 exit status code for failed downloads
+    pub uploaded_length: u64,       // Bytes uploaded (torrents)
+    pub added_at: chrono::DateTime<chrono::Local>, // When the download was added
+    pub seeds: u32,                 // For torrents: number of seeders
+    pub peers: u32,                 // For torrents: number of peers
+    pub bitfield: Option<String>,   // Hex string showing which pieces are downloaded
+    pub num_pieces: u32,            // Total number of pieces in the download
+    #[serde(skip)]
+    pub options: DownloadOptions, // Options it was added with, reused on retry
+    pub retry_attempts: u32,        // Automatic retries so far
+    #[serde(skip)]
+    pub next_retry: Option<std::time::Instant>, // When the next automatic retry is due
+    pub stalled: bool,              // Active but below the watchdog's speed threshold for too long
+    #[serde(skip)]
+    pub slow_since: Option<std::time::Instant>, // Start of the current slow period
 }
 
 impl Default for Download {
@@ -32,7 +43,7 @@ impl Default for Download {
             url: None,
             progress: 0.0,
             speed: "0 B/s".to_string(),
-            status: "IDLE".to_string(),
+            status: DownloadState::Waiting,
             total_length: 0,
             completed_length: 0,
             download_type: DownloadType::Http,
@@ -42,17 +53,165 @@ impl Default for Download {
             connections: 0,
             file_path: None,
             error_message: None,
-            added_at: std::time::Instant::now(),
+            error_code: None,
+            uploaded_length: 0,
+            added_at: chrono::Local::now(),
             seeds: 0,
             peers: 0,
             bitfield: None,
             num_pieces: 0,
+            options: DownloadOptions::default(),
+            retry_attempts: 0,
+            next_retry: None,
+            stalled: false,
+            slow_since: None,
         }
     }
 }
 
-/// Type of download
+/// Per-download options passed to aria2 when a download is added
+#[derive(Clone, Debug, Default, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct DownloadOptions {
+    pub out: Option<String>, // Output file name
+    pub dir: Option<String>, // Output directory
+    pub referer: Option<String>,
+    pub user_agent: Option<String>,
+    pub headers: Vec<String>, // Extra HTTP headers, e.g. "Cookie: a=b"
+    pub resume: bool,         // Continue a partially downloaded file
+    pub download_limit: u64,  // Bytes per second for this download, 0 = only the global limit
+    pub upload_limit: u64,
+}
+
+impl DownloadOptions {
+    /// Convert to the aria2 `options` JSON object
+    pub fn to_aria2(&self) -> serde_json::Value {
+        let mut options = serde_json::Map::new();
+
+        if let Some(out) = &self.out {
+            options.insert("out".to_string(), out.clone().into());
+        }
+        if let Some(dir) = &self.dir {
+            options.insert("dir".to_string(), dir.clone().into());
+        }
+        if let Some(referer) = &self.referer {
+            options.insert("referer".to_string(), referer.clone().into());
+        }
+        if let Some(user_agent) = &self.user_agent {
+            options.insert("user-agent".to_string(), user_agent.clone().into());
+        }
+        if !self.headers.is_empty() {
+            options.insert("header".to_string(), self.headers.clone().into());
+        }
+        if self.resume {
+            options.insert("continue".to_string(), "true".into());
+        }
+        if self.download_limit > 0 {
+            options.insert(
+                "max-download-limit".to_string(),
+                self.download_limit.to_string().into(),
+            );
+        }
+        if self.upload_limit > 0 {
+            options.insert(
+                "max-upload-limit".to_string(),
+                self.upload_limit.to_string().into(),
+            );
+        }
+
+        serde_json::Value::Object(options)
+    }
+}
+
+/// Lifecycle state of a download
+///
+/// Derived from the aria2 status on every poll.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum DownloadState {
+    #[default]
+    Waiting,
+    Active,
+    Paused,
+    Seeding, // Finished torrent still uploading
+    Complete,
+    Error,
+    Removed,   // Stopped by a remove call made outside of the app
+    Verifying, // Hash check of existing data before downloading
+}
+
+impl DownloadState {
+    pub const ALL: [DownloadState; 8] = [
+        DownloadState::Waiting,
+        DownloadState::Active,
+        DownloadState::Paused,
+        DownloadState::Seeding,
+        DownloadState::Complete,
+        DownloadState::Error,
+        DownloadState::Removed,
+        DownloadState::Verifying,
+    ];
+
+    /// Map an aria2 status. `seeder` and `verifying` refine an active download.
+    pub fn from_aria2(status: &str, seeder: bool, verifying: bool) -> Self {
+        match status {
+            "active" if verifying => DownloadState::Verifying,
+            "active" if seeder => DownloadState::Seeding,
+            "active" => DownloadState::Active,
+            "paused" => DownloadState::Paused,
+            "complete" => DownloadState::Complete,
+            "error" => DownloadState::Error,
+            "removed" => DownloadState::Removed,
+            _ => DownloadState::Waiting,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DownloadState::Waiting => "WAITING",
+            DownloadState::Active => "ACTIVE",
+            DownloadState::Paused => "PAUSED",
+            DownloadState::Seeding => "SEEDING",
+            DownloadState::Complete => "COMPLETE",
+            DownloadState::Error => "ERROR",
+            DownloadState::Removed => "REMOVED",
+            DownloadState::Verifying => "VERIFYING",
+        }
+    }
+
+    /// Whether aria2 is transferring or checking data
+    pub fn is_running(&self) -> bool {
+        matches!(
+            self,
+            DownloadState::Active | DownloadState::Seeding | DownloadState::Verifying
+        )
+    }
+
+    /// Whether aria2 has stopped the download for good
+    pub fn is_stopped(&self) -> bool {
+        matches!(
+            self,
+            DownloadState::Complete | DownloadState::Error | DownloadState::Removed
+        )
+    }
+}
+
+impl std::fmt::Display for DownloadState {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A change of state seen between two polls of aria2
 #[derive(Clone, Debug, PartialEq)]
+pub struct StateTransition {
+    pub gid: String,
+    pub from: Option<DownloadState>, // None when the download was first seen
+    pub to: DownloadState,
+}
+
+/// Type of download
+#[derive(Clone, Debug, PartialEq, Serialize)]
 pub enum DownloadType {
     Http,
     Torrent,
@@ -60,26 +219,35 @@ pub enum DownloadType {
 }
 
 /// Input mode for the application
-#[derive(PartialEq, Clone, Copy, Debug)]
+#[derive(PartialEq, Eq, Hash, Clone, Copy, Debug, Default)]
 pub enum InputMode {
+    #[default]
     Normal,
     Editing,
     Search,
     SpeedLimit,
     Help,
+    Log,
+    Stats,
+    Schedule,
+    ScheduleEdit,
     Confirmation,
+    Menu,    // Context menu of a right-clicked download
+    Command, // Command line opened with `:`
     #[allow(dead_code)]
     Settings,
 }
 
-impl Default for InputMode {
-    fn default() -> Self {
-        InputMode::Normal
-    }
+/// Line in the log view
+#[derive(Clone, Debug, PartialEq)]
+pub struct LogEntry {
+    pub time: String, // Local time, `HH:MM:SS`
+    pub message: String,
+    pub is_error: bool,
 }
 
 /// Global statistics from aria2
-#[derive(Clone, Debug, Default)]
+#[derive(Clone, Debug, Default, Serialize)]
 pub struct GlobalStats {
     pub download_speed: u64,
     pub upload_speed: u64,
@@ -87,6 +255,8 @@ pub struct GlobalStats {
     pub num_waiting: u32,
     pub num_stopped: u32,
     pub num_stopped_total: u32,
+    pub total_downloaded: u64, // Bytes downloaded since startup
+    pub total_uploaded: u64,   // Bytes uploaded since startup
 }
 
 /// Sorting options for downloads
@@ -161,6 +331,58 @@ pub enum ConfirmAction {
     DeleteFile(String), // GID of download to delete
     PurgeCompleted,
     RetryDownload(String), // GID of download to retry
+    AddFromBrowser(crate::server::browser::BrowserDownload),
+    Batch(BatchAction, Vec<String>), // Action on several selected downloads
+}
+
+/// Per-download action applied to a multi-selection
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BatchAction {
+    Pause,
+    Resume,
+    Remove,
+    DeleteFile,
+    Retry,
+    MoveUp,
+    MoveDown,
+}
+
+impl BatchAction {
+    /// Past tense used in result summaries
+    pub fn past_tense(&self) -> &'static str {
+        match self {
+            BatchAction::Pause => "Paused",
+            BatchAction::Resume => "Resumed",
+            BatchAction::Remove => "Removed",
+            BatchAction::DeleteFile => "Deleted",
+            BatchAction::Retry => "Restarted",
+            BatchAction::MoveUp => "Moved up",
+            BatchAction::MoveDown => "Moved down",
+        }
+    }
+}
+
+/// Outcome of a batch action
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct BatchResult {
+    pub succeeded: usize,
+    pub failed: usize,
+}
+
+impl BatchResult {
+    /// Summary such as "Paused 12, 1 failed"
+    pub fn summary(&self, action: BatchAction) -> String {
+        if self.failed == 0 {
+            format!("{} {}", action.past_tense(), self.succeeded)
+        } else {
+            format!(
+                "{} {}, {} failed",
+                action.past_tense(),
+                self.succeeded,
+                self.failed
+            )
+        }
+    }
 }
 
 /// Speed limit settings
@@ -348,10 +570,91 @@ mod tests {
         assert_eq!(filtered.len(), 2);
     }
 
+    #[test]
+    fn test_batch_result_summary() {
+        let result = BatchResult {
+            succeeded: 12,
+            failed: 1,
+        };
+        assert_eq!(result.summary(BatchAction::Pause), "Paused 12, 1 failed");
+
+        let result = BatchResult {
+            succeeded: 3,
+            failed: 0,
+        };
+        assert_eq!(result.summary(BatchAction::Remove), "Removed 3");
+    }
+
+    #[test]
+    fn test_download_options_to_aria2() {
+        let options = DownloadOptions {
+            out: Some("file.iso".to_string()),
+            referer: Some("https://example.com/".to_string()),
+            headers: vec!["Cookie: a=b".to_string()],
+            ..Default::default()
+        };
+
+        let value = options.to_aria2();
+        assert_eq!(value["out"], "file.iso");
+        assert_eq!(value["referer"], "https://example.com/");
+        assert_eq!(value["header"][0], "Cookie: a=b");
+        assert!(value.get("dir").is_none());
+        assert!(value.get("user-agent").is_none());
+        assert!(value.get("continue").is_none());
+
+        let resume = DownloadOptions {
+            resume: true,
+            ..Default::default()
+        };
+        assert_eq!(resume.to_aria2()["continue"], "true");
+        assert!(value.get("max-download-limit").is_none());
+
+        let capped = DownloadOptions {
+            download_limit: 512 * 1024,
+            ..Default::default()
+        };
+        assert_eq!(capped.to_aria2()["max-download-limit"], "524288");
+        assert!(capped.to_aria2().get("max-upload-limit").is_none());
+    }
+
+    #[test]
+    fn test_download_state_from_aria2() {
+        assert_eq!(
+            DownloadState::from_aria2("active", false, false),
+            DownloadState::Active
+        );
+        assert_eq!(
+            DownloadState::from_aria2("active", true, false),
+            DownloadState::Seeding
+        );
+        assert_eq!(
+            DownloadState::from_aria2("active", false, true),
+            DownloadState::Verifying
+        );
+        assert_eq!(
+            DownloadState::from_aria2("paused", false, false),
+            DownloadState::Paused
+        );
+        assert_eq!(
+            DownloadState::from_aria2("removed", false, false),
+            DownloadState::Removed
+        );
+        assert_eq!(
+            DownloadState::from_aria2("waiting", false, false),
+            DownloadState::Waiting
+        );
+        assert!(DownloadState::Error.is_stopped());
+        assert!(DownloadState::Seeding.is_running());
+        assert_eq!(
+            serde_json::to_value(DownloadState::Complete).unwrap(),
+            "COMPLETE"
+        );
+    }
+
     #[test]
     fn test_download_default() {
         let download = Download::default();
-        assert_eq!(download.status, "IDLE");
+        assert_eq!(download.status, DownloadState::Waiting);
         assert_eq!(download.progress, 0.0);
     }
 }
@@ -0,0 +1,293 @@
+//! Notifications for finished and failed downloads
+//!
+//! Events are collected for a few seconds and summarised, so finishing fifty
+//! files raises one notification. Desktop notifications are sent from the
+//! notifier task (with the `notifications` feature). Terminal escapes and the
+//! bell go back to the UI loop as [`TerminalAlert`]s, because only the UI
+//! loop may write to the terminal.
+
+use crate::config::{NotificationsConfig, NotifyEvent};
+use crate::download_manager::DownloadManager;
+use crate::events::DownloadEvent;
+use crate::models::DownloadState;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{broadcast, mpsc};
+
+/// Whether this build can send desktop notifications
+pub const DESKTOP_SUPPORTED: bool = cfg!(feature = "notifications");
+
+/// Names listed in a notification body before "and N more"
+const MAX_NAMES: usize = 5;
+
+/// Something worth notifying about
+#[derive(Clone, Debug, PartialEq)]
+enum Notable {
+    Completed(String),
+    Failed(String),
+    QueueDrained,
+}
+
+impl Notable {
+    fn kind(&self) -> NotifyEvent {
+        match self {
+            Notable::Completed(_) => NotifyEvent::Complete,
+            Notable::Failed(_) => NotifyEvent::Error,
+            Notable::QueueDrained => NotifyEvent::QueueDrained,
+        }
+    }
+}
+
+/// Output for the UI loop to write to the terminal
+#[derive(Clone, Debug, PartialEq)]
+pub struct TerminalAlert {
+    pub osc: Option<String>, // Complete escape sequence
+    pub bell: bool,
+}
+
+impl TerminalAlert {
+    /// Bytes to write to the terminal
+    pub fn escape(&self) -> String {
+        let mut out = self.osc.clone().unwrap_or_default();
+        if self.bell {
+            out.push('\x07');
+        }
+        out
+    }
+}
+
+/// Watch download events and raise batched notifications
+///
+/// Failures to reach the desktop notification service are reported on `notices`.
+pub fn spawn(
+    config: NotificationsConfig,
+    dm: Arc<DownloadManager>,
+    notices: mpsc::UnboundedSender<String>,
+) -> mpsc::UnboundedReceiver<TerminalAlert> {
+    let (tx, rx) = mpsc::unbounded_channel();
+    if !config.enabled() {
+        return rx;
+    }
+
+    let mut events = dm.subscribe();
+    tokio::spawn(async move {
+        let window = Duration::from_secs(config.batch);
+        while let Some(first) = next_notable(&mut events).await {
+            let mut batch = vec![first];
+            let deadline = tokio::time::Instant::now() + window;
+            while let Ok(Some(notable)) =
+                tokio::time::timeout_at(deadline, next_notable(&mut events)).await
+            {
+                batch.push(notable);
+            }
+            if queue_drained(&dm).await {
+                batch.push(Notable::QueueDrained);
+            }
+
+            if let Some((title, body)) = summarize(&batch, &config.desktop) {
+                let result = tokio::task::spawn_blocking(move || show_desktop(&title, &body)).await;
+                if let Ok(Err(e)) = result {
+                    let _ = notices.send(format!("Desktop notification failed: {}", e));
+                }
+            }
+
+            let alert = TerminalAlert {
+                osc: summarize(&batch, &config.terminal)
+                    .map(|(title, body)| osc_sequence(config.osc, &title, &body)),
+                bell: batch.iter().any(|n| config.bell.contains(&n.kind())),
+            };
+            if (alert.osc.is_some() || alert.bell) && tx.send(alert).is_err() {
+                break;
+            }
+        }
+    });
+    rx
+}
+
+/// Wait for the next event worth notifying about; `None` once the channel closes
+async fn next_notable(events: &mut broadcast::Receiver<DownloadEvent>) -> Option<Notable> {
+    loop {
+        match events.recv().await {
+            Ok(DownloadEvent::Completed(d)) => return Some(Notable::Completed(d.name)),
+            Ok(DownloadEvent::Failed(d)) => return Some(Notable::Failed(d.name)),
+            Ok(_) | Err(broadcast::error::RecvError::Lagged(_)) => continue,
+            Err(broadcast::error::RecvError::Closed) => return None,
+        }
+    }
+}
+
+/// Nothing is left downloading or waiting (seeding torrents do not count)
+async fn queue_drained(dm: &DownloadManager) -> bool {
+    dm.get_all_downloads().await.iter().all(|d| {
+        !matches!(
+            d.status,
+            DownloadState::Active | DownloadState::Waiting | DownloadState::Verifying
+        )
+    })
+}
+
+/// Title and body for the events of the given kinds, `None` if there are none
+fn summarize(batch: &[Notable], kinds: &[NotifyEvent]) -> Option<(String, String)> {
+    let wanted: Vec<&Notable> = batch.iter().filter(|n| kinds.contains(&n.kind())).collect();
+    if wanted.is_empty() {
+        return None;
+    }
+
+    let mut completed = Vec::new();
+    let mut failed = Vec::new();
+    let mut drained = false;
+    for notable in wanted {
+        match notable {
+            Notable::Completed(name) => completed.push(name.as_str()),
+            Notable::Failed(name) => failed.push(name.as_str()),
+            Notable::QueueDrained => drained = true,
+        }
+    }
+
+    let mut parts = Vec::new();
+    match completed.len() {
+        0 => {}
+        1 => parts.push("Download complete".to_string()),
+        n => parts.push(format!("{} downloads complete", n)),
+    }
+    match failed.len() {
+        0 => {}
+        1 => parts.push("Download failed".to_string()),
+        n => parts.push(format!("{} downloads failed", n)),
+    }
+
+    let mut body: Vec<String> = Vec::new();
+    body.extend(list_names("", &completed));
+    body.extend(list_names("Failed: ", &failed));
+
+    let title = if parts.is_empty() {
+        "All downloads finished".to_string()
+    } else {
+        if drained {
+            body.push("Queue is empty".to_string());
+        }
+        parts.join(", ")
+    };
+    Some((title, body.join("\n")))
+}
+
+fn list_names(prefix: &str, names: &[&str]) -> Vec<String> {
+    let mut lines: Vec<String> = names
+        .iter()
+        .take(MAX_NAMES)
+        .map(|name| format!("{}{}", prefix, name))
+        .collect();
+    if names.len() > MAX_NAMES {
+        lines.push(format!("and {} more", names.len() - MAX_NAMES));
+    }
+    lines
+}
+
+/// OSC 9 (`ESC ] 9 ; text BEL`) or OSC 777 (`ESC ] 777 ; notify ; title ; body BEL`)
+fn osc_sequence(osc: u16, title: &str, body: &str) -> String {
+    let clean = |s: &str| -> String {
+        s.chars()
+            .map(|c| if c.is_control() { ' ' } else { c })
+            .collect()
+    };
+    let body = body.lines().collect::<Vec<_>>().join(", ");
+
+    if osc == 777 {
+        format!(
+            "\x1b]777;notify;{};{}\x07",
+            clean(title).replace(';', ","),
+            clean(&body)
+        )
+    } else if body.is_empty() {
+        format!("\x1b]9;{}\x07", clean(title))
+    } else {
+        format!("\x1b]9;{}: {}\x07", clean(title), clean(&body))
+    }
+}
+
+#[cfg(feature = "notifications")]
+fn show_desktop(title: &str, body: &str) -> Result<(), String> {
+    notify_rust::Notification::new()
+        .appname("tui-downloader")
+        .summary(title)
+        .body(body)
+        .show()
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+}
+
+#[cfg(not(feature = "notifications"))]
+fn show_desktop(_title: &str, _body: &str) -> Result<(), String> {
+    Err("built without the `notifications` feature".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALL: [NotifyEvent; 3] = [
+        NotifyEvent::Complete,
+        NotifyEvent::Error,
+        NotifyEvent::QueueDrained,
+    ];
+
+    fn completed(n: usize) -> Vec<Notable> {
+        (0..n)
+            .map(|i| Notable::Completed(format!("file{}.iso", i)))
+            .collect()
+    }
+
+    #[test]
+    fn test_summarize_single() {
+        let (title, body) = summarize(&completed(1), &ALL).unwrap();
+        assert_eq!(title, "Download complete");
+        assert_eq!(body, "file0.iso");
+    }
+
+    #[test]
+    fn test_summarize_batches_many_events() {
+        let mut batch = completed(50);
+        batch.push(Notable::Failed("broken.zip".to_string()));
+        batch.push(Notable::QueueDrained);
+
+        let (title, body) = summarize(&batch, &ALL).unwrap();
+        assert_eq!(title, "50 downloads complete, Download failed");
+        let lines: Vec<&str> = body.lines().collect();
+        assert_eq!(lines.len(), MAX_NAMES + 3);
+        assert_eq!(lines[MAX_NAMES], "and 45 more");
+        assert_eq!(lines[MAX_NAMES + 1], "Failed: broken.zip");
+        assert_eq!(lines[MAX_NAMES + 2], "Queue is empty");
+    }
+
+    #[test]
+    fn test_summarize_filters_by_kind() {
+        let mut batch = completed(2);
+        batch.push(Notable::QueueDrained);
+
+        assert_eq!(summarize(&batch, &[NotifyEvent::Error]), None);
+        let (title, body) = summarize(&batch, &[NotifyEvent::QueueDrained]).unwrap();
+        assert_eq!(title, "All downloads finished");
+        assert!(body.is_empty());
+    }
+
+    #[test]
+    fn test_osc_sequences() {
+        assert_eq!(
+            osc_sequence(9, "Download complete", "a.iso\nb.iso"),
+            "\x1b]9;Download complete: a.iso, b.iso\x07"
+        );
+        assert_eq!(
+            osc_sequence(777, "Done; ok", "a\x1bb"),
+            "\x1b]777;notify;Done, ok;a b\x07"
+        );
+    }
+
+    #[test]
+    fn test_terminal_alert_escape() {
+        let alert = TerminalAlert {
+            osc: None,
+            bell: true,
+        };
+        assert_eq!(alert.escape(), "\x07");
+    }
+}
@@ -0,0 +1,538 @@
+//! Command line opened with `:`
+//!
+//! Commands reach the same operations as keys. The ones below take
+//! arguments; every other action of normal mode runs by its name in
+//! `[keys.normal]` (`:pause_all`, `:open_folder`). Without arguments a
+//! command falls back to the action of the same name, so `:sort` cycles the
+//! sort order and `:add` opens the add field.
+//!
+//! ```text
+//! add URL [--dir DIR] [--out NAME]
+//! limit down|up|both SPEED
+//! pause|resume [QUERY]          # search query, e.g. status:active
+//! sort FIELD [asc|desc]
+//! set OPTION VALUE              # any aria2 global option
+//! purge
+//! theme NAME
+//! export csv FILE | export stats
+//! ```
+
+use crate::input::KeyAction;
+use crate::keymap;
+use crate::models::{Download, DownloadOptions, DownloadState, InputMode};
+use crate::query::{Query, STALLED_KEYWORD, TYPES};
+use crate::ui::utils::{expand_home, SortOrder};
+use crate::units::parse_speed_limit;
+use std::path::PathBuf;
+
+/// Most commands kept in the history
+pub const HISTORY_CAPACITY: usize = 100;
+
+/// Commands that take arguments
+const COMMANDS: &[&str] = &[
+    "add", "export", "limit", "pause", "purge", "resume", "set", "sort", "theme",
+];
+
+/// aria2 options offered by `:set` completion; others are passed on as typed
+const OPTIONS: &[&str] = &[
+    "bt-max-peers",
+    "dir",
+    "max-concurrent-downloads",
+    "max-connection-per-server",
+    "max-overall-download-limit",
+    "max-overall-upload-limit",
+    "max-tries",
+    "min-split-size",
+    "retry-wait",
+    "seed-ratio",
+    "seed-time",
+    "split",
+];
+
+/// A parsed command line
+#[derive(Clone, Debug, PartialEq)]
+pub enum PaletteCommand {
+    /// Add a download with aria2 options
+    Add {
+        url: String,
+        options: DownloadOptions,
+    },
+    /// Change the global speed limits; `None` keeps a limit as it is
+    Limit {
+        download: Option<u64>,
+        upload: Option<u64>,
+    },
+    /// Pause the downloads matching a search query, or the selection
+    Pause(Option<String>),
+    /// Resume the downloads matching a search query, or the selection
+    Resume(Option<String>),
+    Sort {
+        order: SortOrder,
+        ascending: Option<bool>, // None keeps the direction
+    },
+    /// Change one of aria2's global options
+    Set {
+        name: String,
+        value: String,
+    },
+    /// Remove completed downloads, from any tab
+    Purge,
+    Theme(String),
+    /// Write every download to a CSV file
+    ExportCsv(PathBuf),
+    /// Run a normal-mode action
+    Action(KeyAction),
+}
+
+impl PaletteCommand {
+    /// Parse a command line, without the leading `:`
+    pub fn parse(line: &str) -> Result<Self, String> {
+        let words = split_words(line)?;
+        let Some((name, args)) = words.split_first() else {
+            return Err("empty command".to_string());
+        };
+        let name = name.replace('-', "_");
+        let args: Vec<&str> = args.iter().map(String::as_str).collect();
+
+        match (name.as_str(), args.as_slice()) {
+            ("add", [_, ..]) => parse_add(&args),
+            ("limit", []) => Ok(PaletteCommand::Action(KeyAction::ShowSpeedLimit)),
+            ("limit", [direction, speed]) => parse_limit(direction, speed),
+            ("limit", _) => Err("usage: limit down|up|both SPEED".to_string()),
+            ("pause", []) => Ok(PaletteCommand::Pause(None)),
+            ("pause", _) => parse_query(&args).map(PaletteCommand::Pause),
+            ("resume", []) => Ok(PaletteCommand::Resume(None)),
+            ("resume", _) => parse_query(&args).map(PaletteCommand::Resume),
+            ("sort", [field, direction @ ..]) => parse_sort(field, direction),
+            ("set", [option, value]) => parse_set(option, value),
+            ("set", _) => Err("usage: set OPTION VALUE".to_string()),
+            ("purge", []) => Ok(PaletteCommand::Purge),
+            ("theme", [theme]) => Ok(PaletteCommand::Theme(theme.to_string())),
+            ("export", ["csv", file]) => Ok(PaletteCommand::ExportCsv(expand_home(file))),
+            ("export", ["stats"]) => Ok(PaletteCommand::Action(KeyAction::StatsExport)),
+            ("export", _) => Err("usage: export csv FILE | export stats".to_string()),
+            (name, []) => action(name)
+                .map(PaletteCommand::Action)
+                .ok_or_else(|| format!("unknown command '{}'", name)),
+            (name, _) if action(name).is_some() || COMMANDS.contains(&name) => {
+                Err(format!("{} takes no arguments", name))
+            }
+            (name, _) => Err(format!("unknown command '{}'", name)),
+        }
+    }
+}
+
+/// Normal-mode action with the given name in `[keys.normal]`
+fn action(name: &str) -> Option<KeyAction> {
+    keymap::actions(InputMode::Normal)
+        .find(|(action_name, _)| *action_name == name)
+        .map(|(_, action)| action.clone())
+}
+
+/// `add URL [--dir DIR] [--out NAME]`, options also as `--dir=DIR`
+fn parse_add(args: &[&str]) -> Result<PaletteCommand, String> {
+    let mut url = None;
+    let mut options = DownloadOptions::default();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if !arg.starts_with('-') {
+            if url.replace(arg.to_string()).is_some() {
+                return Err("add takes one URL".to_string());
+            }
+            continue;
+        }
+        let (flag, inline) = match arg.split_once('=') {
+            Some((flag, value)) => (flag, Some(value)),
+            None => (*arg, None),
+        };
+        if !matches!(flag, "--dir" | "-d" | "--out" | "-o") {
+            return Err(format!(
+                "unknown option '{}' (expected --dir or --out)",
+                flag
+            ));
+        }
+        let value = inline
+            .or_else(|| args.next().copied())
+            .filter(|value| !value.is_empty())
+            .ok_or_else(|| format!("{} needs a value", flag))?;
+        if matches!(flag, "--dir" | "-d") {
+            options.dir = Some(expand_home(value).display().to_string());
+        } else {
+            options.out = Some(value.to_string());
+        }
+    }
+    let url = url.ok_or("add needs a URL")?;
+    Ok(PaletteCommand::Add { url, options })
+}
+
+/// `limit down|up|both SPEED`, where `off` or 0 lifts the limit
+fn parse_limit(direction: &str, speed: &str) -> Result<PaletteCommand, String> {
+    let limit = if speed.eq_ignore_ascii_case("off") {
+        0
+    } else {
+        parse_speed_limit(speed).ok_or_else(|| format!("invalid speed '{}'", speed))?
+    };
+    let (download, upload) = match direction {
+        "down" => (Some(limit), None),
+        "up" => (None, Some(limit)),
+        "both" => (Some(limit), Some(limit)),
+        _ => {
+            return Err(format!(
+                "unknown direction '{}' (expected down, up or both)",
+                direction
+            ))
+        }
+    };
+    Ok(PaletteCommand::Limit { download, upload })
+}
+
+/// Search query of `pause` and `resume`, checked here so errors show before running
+fn parse_query(args: &[&str]) -> Result<Option<String>, String> {
+    let query = args.join(" ");
+    Query::parse(&query).map_err(|e| e.message)?;
+    Ok(Some(query))
+}
+
+/// `sort FIELD [asc|desc]`
+fn parse_sort(field: &str, direction: &[&str]) -> Result<PaletteCommand, String> {
+    let order = SortOrder::from_name(field).ok_or_else(|| {
+        let names: Vec<&str> = SortOrder::ALL.iter().map(SortOrder::name).collect();
+        format!(
+            "unknown sort field '{}' (expected {})",
+            field,
+            names.join(", ")
+        )
+    })?;
+    let ascending = match direction {
+        [] => None,
+        ["asc"] => Some(true),
+        ["desc"] => Some(false),
+        _ => return Err("usage: sort FIELD [asc|desc]".to_string()),
+    };
+    Ok(PaletteCommand::Sort { order, ascending })
+}
+
+/// `set OPTION VALUE`; aria2 checks the option and its value
+fn parse_set(option: &str, value: &str) -> Result<PaletteCommand, String> {
+    let valid = !option.is_empty()
+        && option
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-');
+    if !valid {
+        return Err(format!("invalid option name '{}'", option));
+    }
+    Ok(PaletteCommand::Set {
+        name: option.to_string(),
+        value: value.to_string(),
+    })
+}
+
+/// Split a command line into words; single or double quotes keep spaces
+fn split_words(line: &str) -> Result<Vec<String>, String> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut in_word = false;
+    let mut quote = None;
+    for c in line.chars() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), c) => word.push(c),
+            (None, '"' | '\'') => {
+                quote = Some(c);
+                in_word = true;
+            }
+            (None, c) if c.is_whitespace() => {
+                if in_word {
+                    words.push(std::mem::take(&mut word));
+                    in_word = false;
+                }
+            }
+            (None, c) => {
+                word.push(c);
+                in_word = true;
+            }
+        }
+    }
+    if quote.is_some() {
+        return Err("unclosed quote".to_string());
+    }
+    if in_word {
+        words.push(word);
+    }
+    Ok(words)
+}
+
+/// A completed command line and the candidates it was completed from
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Completion {
+    pub line: String,
+    pub candidates: Vec<String>, // Shown when more than one matches
+}
+
+/// Complete the last word of a command line
+///
+/// A single match is completed with a space after it; several are completed
+/// up to their common prefix.
+pub fn complete(line: &str, themes: &[&str]) -> Completion {
+    let start = line.rfind(' ').map_or(0, |i| i + 1);
+    let (head, word) = line.split_at(start);
+    let before: Vec<&str> = head.split_whitespace().collect();
+
+    let mut candidates: Vec<String> = match before.as_slice() {
+        [] => {
+            let mut names: Vec<String> = COMMANDS.iter().map(|c| c.to_string()).collect();
+            names.extend(keymap::actions(InputMode::Normal).map(|(n, _)| n.to_string()));
+            names.sort();
+            names.dedup();
+            names
+        }
+        ["sort"] => SortOrder::ALL
+            .iter()
+            .map(|o| o.name().to_string())
+            .collect(),
+        ["sort", _] => vec!["asc".to_string(), "desc".to_string()],
+        ["limit"] => ["both", "down", "up"].map(String::from).to_vec(),
+        ["export"] => ["csv", "stats"].map(String::from).to_vec(),
+        ["set"] => OPTIONS.iter().map(|o| o.to_string()).collect(),
+        ["theme"] => themes.iter().map(|t| t.to_string()).collect(),
+        ["pause" | "resume", ..] => {
+            let mut keywords: Vec<String> = DownloadState::ALL
+                .iter()
+                .map(|s| format!("status:{}", s.as_str().to_lowercase()))
+                .collect();
+            keywords.extend(TYPES.iter().map(|t| format!("type:{}", t)));
+            keywords.push(STALLED_KEYWORD.to_string());
+            keywords
+        }
+        ["add", ..] if word.starts_with('-') => ["--dir", "--out"].map(String::from).to_vec(),
+        _ => Vec::new(),
+    };
+    candidates.retain(|candidate| candidate.starts_with(word));
+
+    let line = match candidates.as_slice() {
+        [] => line.to_string(),
+        [only] => format!("{}{} ", head, only),
+        [first, rest @ ..] => {
+            let common = rest.iter().fold(first.len(), |len, candidate| {
+                first
+                    .chars()
+                    .zip(candidate.chars())
+                    .take_while(|(a, b)| a == b)
+                    .count()
+                    .min(len)
+            });
+            format!("{}{}", head, &first[..common])
+        }
+    };
+    if candidates.len() == 1 {
+        candidates.clear();
+    }
+    Completion { line, candidates }
+}
+
+/// Downloads as CSV, one row per download
+pub fn downloads_csv(downloads: &[Download]) -> String {
+    let mut csv = String::from("name,status,progress,completed,total,url,path\n");
+    for download in downloads {
+        let fields = [
+            download.name.clone(),
+            download.status.as_str().to_lowercase(),
+            format!("{:.3}", download.progress),
+            download.completed_length.to_string(),
+            download.total_length.to_string(),
+            download.url.clone().unwrap_or_default(),
+            download.file_path.clone().unwrap_or_default(),
+        ];
+        let row: Vec<String> = fields.iter().map(|field| csv_field(field)).collect();
+        csv.push_str(&row.join(","));
+        csv.push('\n');
+    }
+    csv
+}
+
+/// Quote a CSV field if it contains a separator, quote or line break
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_commands() {
+        let home = dirs::home_dir().unwrap();
+        assert_eq!(
+            PaletteCommand::parse("add https://a.com/x.iso --dir ~/iso --out x.iso"),
+            Ok(PaletteCommand::Add {
+                url: "https://a.com/x.iso".to_string(),
+                options: DownloadOptions {
+                    dir: Some(home.join("iso").display().to_string()),
+                    out: Some("x.iso".to_string()),
+                    ..Default::default()
+                },
+            })
+        );
+        assert_eq!(
+            PaletteCommand::parse("limit down 2M"),
+            Ok(PaletteCommand::Limit {
+                download: Some(2 * 1024 * 1024),
+                upload: None
+            })
+        );
+        assert_eq!(
+            PaletteCommand::parse("limit both off"),
+            Ok(PaletteCommand::Limit {
+                download: Some(0),
+                upload: Some(0)
+            })
+        );
+        assert_eq!(
+            PaletteCommand::parse("pause status:active"),
+            Ok(PaletteCommand::Pause(Some("status:active".to_string())))
+        );
+        assert_eq!(
+            PaletteCommand::parse("resume"),
+            Ok(PaletteCommand::Resume(None))
+        );
+        assert_eq!(
+            PaletteCommand::parse("sort size desc"),
+            Ok(PaletteCommand::Sort {
+                order: SortOrder::Size,
+                ascending: Some(false)
+            })
+        );
+        assert_eq!(
+            PaletteCommand::parse("set max-concurrent-downloads 3"),
+            Ok(PaletteCommand::Set {
+                name: "max-concurrent-downloads".to_string(),
+                value: "3".to_string()
+            })
+        );
+        assert_eq!(PaletteCommand::parse(" purge "), Ok(PaletteCommand::Purge));
+        assert_eq!(
+            PaletteCommand::parse("export csv '/tmp/my downloads.csv'"),
+            Ok(PaletteCommand::ExportCsv(PathBuf::from(
+                "/tmp/my downloads.csv"
+            )))
+        );
+    }
+
+    #[test]
+    fn test_bare_commands_run_actions() {
+        for (line, action) in [
+            ("pause_all", KeyAction::PauseAll),
+            ("open-folder", KeyAction::OpenFolder),
+            ("add", KeyAction::EnterEditMode),
+            ("sort", KeyAction::CycleSort),
+            ("theme", KeyAction::CycleTheme),
+            ("limit", KeyAction::ShowSpeedLimit),
+            ("export stats", KeyAction::StatsExport),
+        ] {
+            assert_eq!(
+                PaletteCommand::parse(line),
+                Ok(PaletteCommand::Action(action)),
+                "{}",
+                line
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        for (line, error) in [
+            ("", "empty command"),
+            ("fly", "unknown command 'fly'"),
+            ("quit now", "quit takes no arguments"),
+            ("purge all", "purge takes no arguments"),
+            (
+                "limit sideways 2M",
+                "unknown direction 'sideways' (expected down, up or both)",
+            ),
+            ("limit down fast", "invalid speed 'fast'"),
+            ("limit down", "usage: limit down|up|both SPEED"),
+            (
+                "sort colour",
+                "unknown sort field 'colour' (expected name, size, progress, speed, date, status)",
+            ),
+            ("sort size up", "usage: sort FIELD [asc|desc]"),
+            ("set split", "usage: set OPTION VALUE"),
+            ("set Split 4", "invalid option name 'Split'"),
+            ("add --dir /tmp", "add needs a URL"),
+            ("add a.com/x --out", "--out needs a value"),
+            (
+                "add a.com/x --force",
+                "unknown option '--force' (expected --dir or --out)",
+            ),
+            ("add a.com/x a.com/y", "add takes one URL"),
+            ("export json x", "usage: export csv FILE | export stats"),
+            ("add 'a.com/x", "unclosed quote"),
+            ("pause size:>big", "invalid number 'big'"),
+        ] {
+            assert_eq!(
+                PaletteCommand::parse(line),
+                Err(error.to_string()),
+                "{}",
+                line
+            );
+        }
+    }
+
+    #[test]
+    fn test_complete() {
+        let themes = ["dark", "light", "high-contrast"];
+        assert_eq!(complete("pur", &themes).line, "purge ");
+        let sort = complete("so", &themes);
+        assert_eq!(sort.line, "sort");
+        assert_eq!(sort.candidates, vec!["sort", "sort_direction"]);
+        assert_eq!(complete("sort si", &themes).line, "sort size ");
+        assert_eq!(complete("sort size d", &themes).line, "sort size desc ");
+        assert_eq!(complete("theme l", &themes).line, "theme light ");
+        assert_eq!(complete("set max-c", &themes).line, "set max-con");
+        assert_eq!(
+            complete("set max-c", &themes).candidates,
+            vec!["max-concurrent-downloads", "max-connection-per-server"]
+        );
+        assert_eq!(
+            complete("pause status:p", &themes).line,
+            "pause status:paused "
+        );
+        assert_eq!(complete("add x --o", &themes).line, "add x --out ");
+
+        // Nothing to complete
+        let none = complete("add https://a", &themes);
+        assert_eq!(none.line, "add https://a");
+        assert!(none.candidates.is_empty());
+
+        // Commands and action names share the first word
+        let first = complete("pa", &themes);
+        assert_eq!(first.line, "pa");
+        assert!(first.candidates.contains(&"pause".to_string()));
+        assert!(first.candidates.contains(&"pause_all".to_string()));
+    }
+
+    #[test]
+    fn test_downloads_csv_quotes_fields() {
+        let download = Download {
+            name: "a, \"b\".zip".to_string(),
+            status: DownloadState::Paused,
+            progress: 0.5,
+            url: Some("https://a.com/x".to_string()),
+            ..Default::default()
+        };
+        let csv = downloads_csv(&[download]);
+        let mut lines = csv.lines();
+        assert_eq!(
+            lines.next(),
+            Some("name,status,progress,completed,total,url,path")
+        );
+        assert_eq!(
+            lines.next(),
+            Some("\"a, \"\"b\"\".zip\",paused,0.500,0,0,https://a.com/x,")
+        );
+    }
+}
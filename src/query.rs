@@ -0,0 +1,609 @@
+//! Search query language used by `/` and by `:pause`/`:resume`
+//!
+//! A query is a list of terms that must all match. `OR` between terms
+//! matches either side and binds looser than the implicit AND, so
+//! `iso status:paused OR torrent` is `(iso AND status:paused) OR torrent`.
+//!
+//! ```text
+//! ubuntu                 name contains "ubuntu", or its letters in order
+//! "ubuntu 24"            name contains the phrase exactly
+//! /\.iso$/               name matches the regex (case-insensitive)
+//! status:error           waiting, active, paused, seeding, complete, error, removed, verifying
+//! type:torrent           http, torrent or metalink
+//! size:>1G speed:<100K   >, >=, <, <= or = a size in bytes, with K, M or G
+//! progress:<50           percent done
+//! url:github.com         URL contains the text
+//! path:~/iso             file path contains the text
+//! is:stalled             flagged by the watchdog
+//! -term                  does not match the term
+//! ```
+
+use crate::models::{Download, DownloadState, DownloadType};
+use crate::ui::utils::expand_home;
+use crate::units::parse_speed_limit;
+use regex::{Regex, RegexBuilder};
+use std::ops::Range;
+
+/// Search keyword that keeps only stalled downloads
+pub const STALLED_KEYWORD: &str = "is:stalled";
+
+/// Fields that can prefix a term, as in `status:paused`
+pub const FIELDS: &[&str] = &[
+    "is", "path", "progress", "size", "speed", "status", "type", "url",
+];
+
+/// Values of `type:`
+pub const TYPES: &[&str] = &["http", "torrent", "metalink"];
+
+/// A query that failed to parse, with the part of it to highlight
+#[derive(Clone, Debug, PartialEq)]
+pub struct QueryError {
+    pub message: String,
+    pub span: Range<usize>, // Byte range in the query
+}
+
+impl QueryError {
+    fn new(message: impl Into<String>, span: Range<usize>) -> Self {
+        Self {
+            message: message.into(),
+            span,
+        }
+    }
+}
+
+/// A parsed search query
+#[derive(Clone, Debug, Default)]
+pub struct Query {
+    groups: Vec<Vec<Term>>, // Alternatives joined by OR, each a list of terms joined by AND
+}
+
+#[derive(Clone, Debug)]
+struct Term {
+    negated: bool,
+    filter: Filter,
+}
+
+#[derive(Clone, Debug)]
+enum Filter {
+    /// Folded name text; `fuzzy` also accepts its characters in order
+    Name {
+        text: Vec<char>,
+        fuzzy: bool,
+    },
+    Regex(Regex),
+    Status(DownloadState),
+    Type(DownloadType),
+    Size(Compare),
+    Speed(Compare),
+    Progress(Compare),
+    Url(String),
+    Path(String),
+    Stalled,
+}
+
+/// A numeric comparison such as `>1G`
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Compare {
+    op: Op,
+    value: f64,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Op {
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+    Equal,
+}
+
+impl Compare {
+    fn matches(&self, value: f64) -> bool {
+        match self.op {
+            Op::Less => value < self.value,
+            Op::LessEqual => value <= self.value,
+            Op::Greater => value > self.value,
+            Op::GreaterEqual => value >= self.value,
+            Op::Equal => value == self.value,
+        }
+    }
+}
+
+impl Query {
+    /// Parse a query; an empty one matches everything
+    pub fn parse(query: &str) -> Result<Self, QueryError> {
+        let mut groups = vec![Vec::new()];
+        let mut or_span: Option<Range<usize>> = None;
+        for token in tokenize(query)? {
+            if token.text == "OR" && !token.quoted {
+                if groups.last().is_some_and(Vec::is_empty) {
+                    return Err(QueryError::new("OR needs a term before it", token.span));
+                }
+                groups.push(Vec::new());
+                or_span = Some(token.span);
+                continue;
+            }
+            let term = parse_term(&token)?;
+            groups.last_mut().expect("at least one group").push(term);
+        }
+        if let (Some(span), true) = (or_span, groups.last().is_some_and(Vec::is_empty)) {
+            return Err(QueryError::new("OR needs a term after it", span));
+        }
+        groups.retain(|group| !group.is_empty());
+        Ok(Self { groups })
+    }
+
+    /// Whether the query has no terms
+    pub fn is_empty(&self) -> bool {
+        self.groups.is_empty()
+    }
+
+    /// Whether a download matches the query
+    pub fn matches(&self, download: &Download) -> bool {
+        self.is_empty()
+            || self
+                .groups
+                .iter()
+                .any(|group| group.iter().all(|term| term.matches(download)))
+    }
+
+    /// Which characters of `name` the name terms matched, for highlighting
+    pub fn highlights(&self, name: &str) -> Vec<bool> {
+        let folded = fold(name);
+        let mut marked = vec![false; folded.len()];
+        let terms = self.groups.iter().flatten().filter(|term| !term.negated);
+        for term in terms {
+            match &term.filter {
+                Filter::Name { text, fuzzy } => {
+                    let found = mark_substrings(&folded, text, &mut marked);
+                    if !found && *fuzzy {
+                        if let Some(positions) = subsequence(&folded, text) {
+                            for i in positions {
+                                marked[i] = true;
+                            }
+                        }
+                    }
+                }
+                Filter::Regex(regex) => {
+                    for found in regex.find_iter(name) {
+                        for (i, (offset, _)) in name.char_indices().enumerate() {
+                            if found.range().contains(&offset) {
+                                marked[i] = true;
+                            }
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        marked
+    }
+}
+
+impl Term {
+    fn matches(&self, download: &Download) -> bool {
+        self.filter.matches(download, self.negated) != self.negated
+    }
+}
+
+impl Filter {
+    /// Whether the download matches; negated name terms only exclude exact
+    /// matches, since fuzzy ones would exclude far too much
+    fn matches(&self, download: &Download, negated: bool) -> bool {
+        match self {
+            Filter::Name { text, fuzzy } => {
+                let name = fold(&download.name);
+                contains(&name, text) || (*fuzzy && !negated && subsequence(&name, text).is_some())
+            }
+            Filter::Regex(regex) => regex.is_match(&download.name),
+            Filter::Status(state) => download.status == *state,
+            Filter::Type(kind) => download.download_type == *kind,
+            Filter::Size(compare) => compare.matches(download.total_length as f64),
+            Filter::Speed(compare) => {
+                compare.matches(download.speed_history.last().copied().unwrap_or(0) as f64)
+            }
+            Filter::Progress(compare) => compare.matches(download.progress * 100.0),
+            Filter::Url(text) => download
+                .url
+                .as_ref()
+                .is_some_and(|url| url.to_lowercase().contains(text)),
+            Filter::Path(text) => download
+                .file_path
+                .as_ref()
+                .is_some_and(|path| path.to_lowercase().contains(text)),
+            Filter::Stalled => download.stalled,
+        }
+    }
+}
+
+/// A word of the query, with quotes removed
+struct Token {
+    text: String,
+    span: Range<usize>,
+    quoted: bool, // Written as "..."; never a field, OR or regex
+    regex: bool,  // Written as /.../, without the slashes in `text`
+}
+
+/// Split a query into words, keeping quoted phrases and regexes together
+fn tokenize(query: &str) -> Result<Vec<Token>, QueryError> {
+    let mut tokens = Vec::new();
+    let mut chars = query.char_indices().peekable();
+    while let Some(&(start, c)) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+            continue;
+        }
+
+        let mut text = String::new();
+        let mut quoted = false;
+        if c == '-' {
+            text.push(c);
+            chars.next();
+        }
+        let regex = chars.peek().is_some_and(|&(_, c)| c == '/');
+        if regex {
+            chars.next();
+            text.push('/');
+            let mut closed = false;
+            while let Some((_, c)) = chars.next() {
+                if c == '\\' {
+                    text.push(c);
+                    if let Some((_, escaped)) = chars.next() {
+                        text.push(escaped);
+                    }
+                    continue;
+                }
+                if c == '/' {
+                    closed = true;
+                    break;
+                }
+                text.push(c);
+            }
+            if !closed {
+                return Err(QueryError::new("unclosed regex", start..query.len()));
+            }
+        } else {
+            let mut in_quote = false;
+            while let Some(&(_, c)) = chars.peek() {
+                if c.is_whitespace() && !in_quote {
+                    break;
+                }
+                chars.next();
+                if c == '"' {
+                    in_quote = !in_quote;
+                    quoted = true;
+                } else {
+                    text.push(c);
+                }
+            }
+            if in_quote {
+                return Err(QueryError::new("unclosed quote", start..query.len()));
+            }
+        }
+        let end = chars.peek().map_or(query.len(), |&(i, _)| i);
+        tokens.push(Token {
+            text,
+            span: start..end,
+            quoted,
+            regex,
+        });
+    }
+    Ok(tokens)
+}
+
+fn parse_term(token: &Token) -> Result<Term, QueryError> {
+    let error = |message: String| QueryError::new(message, token.span.clone());
+    let (negated, text) = match token.text.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, token.text.as_str()),
+    };
+    if text.is_empty() {
+        return Err(error("nothing to negate after '-'".to_string()));
+    }
+
+    let filter = if token.regex {
+        let pattern = &text[1..];
+        let regex = RegexBuilder::new(pattern)
+            .case_insensitive(true)
+            .build()
+            .map_err(|_| error(format!("invalid regex '{}'", pattern)))?;
+        Filter::Regex(regex)
+    } else if token.quoted {
+        Filter::Name {
+            text: fold(text),
+            fuzzy: false,
+        }
+    } else {
+        match field(text) {
+            Some((name, value)) => parse_field(&name, value).map_err(error)?,
+            None => Filter::Name {
+                text: fold(text),
+                fuzzy: true,
+            },
+        }
+    };
+    Ok(Term { negated, filter })
+}
+
+/// Split `name:value` when the name looks like a field
+///
+/// Words like `https://...` stay names.
+fn field(text: &str) -> Option<(String, &str)> {
+    let (name, value) = text.split_once(':')?;
+    let looks_like_field = !name.is_empty()
+        && name.chars().all(|c| c.is_ascii_alphabetic())
+        && !value.starts_with("//");
+    looks_like_field.then(|| (name.to_ascii_lowercase(), value))
+}
+
+fn parse_field(name: &str, value: &str) -> Result<Filter, String> {
+    if value.is_empty() {
+        return Err(format!("{}: needs a value", name));
+    }
+    let lower = value.to_lowercase();
+    match name {
+        "status" => DownloadState::ALL
+            .iter()
+            .find(|state| state.as_str().eq_ignore_ascii_case(value))
+            .map(|state| Filter::Status(*state))
+            .ok_or_else(|| {
+                let names: Vec<String> = DownloadState::ALL
+                    .iter()
+                    .map(|state| state.as_str().to_lowercase())
+                    .collect();
+                format!("unknown status '{}' (expected {})", value, names.join(", "))
+            }),
+        "type" => match lower.as_str() {
+            "http" => Ok(Filter::Type(DownloadType::Http)),
+            "torrent" => Ok(Filter::Type(DownloadType::Torrent)),
+            "metalink" => Ok(Filter::Type(DownloadType::Metalink)),
+            _ => Err(format!(
+                "unknown type '{}' (expected {})",
+                value,
+                TYPES.join(", ")
+            )),
+        },
+        "is" if lower == "stalled" => Ok(Filter::Stalled),
+        "is" => Err(format!("unknown flag '{}' (expected stalled)", value)),
+        "size" => parse_compare(value, parse_size).map(Filter::Size),
+        "speed" => parse_compare(value, parse_size).map(Filter::Speed),
+        "progress" => parse_compare(value, |v| {
+            v.trim_end_matches('%')
+                .parse::<f64>()
+                .ok()
+                .filter(|p| (0.0..=100.0).contains(p))
+        })
+        .map(Filter::Progress),
+        "url" => Ok(Filter::Url(lower)),
+        "path" => Ok(Filter::Path(
+            expand_home(value).display().to_string().to_lowercase(),
+        )),
+        _ => Err(format!(
+            "unknown field '{}' (expected {})",
+            name,
+            FIELDS.join(", ")
+        )),
+    }
+}
+
+/// `>1G`, `<=50`, `=0` or a bare number (equal)
+fn parse_compare(value: &str, number: impl Fn(&str) -> Option<f64>) -> Result<Compare, String> {
+    let (op, rest) = [
+        (">=", Op::GreaterEqual),
+        ("<=", Op::LessEqual),
+        (">", Op::Greater),
+        ("<", Op::Less),
+        ("=", Op::Equal),
+    ]
+    .into_iter()
+    .find_map(|(prefix, op)| value.strip_prefix(prefix).map(|rest| (op, rest)))
+    .unwrap_or((Op::Equal, value));
+    let value = number(rest).ok_or_else(|| format!("invalid number '{}'", rest))?;
+    Ok(Compare { op, value })
+}
+
+/// Bytes in a size such as `1G`, `100K` or `512`
+fn parse_size(value: &str) -> Option<f64> {
+    if !value.starts_with(|c: char| c.is_ascii_digit() || c == '.') {
+        return None;
+    }
+    parse_speed_limit(value).map(|bytes| bytes as f64)
+}
+
+/// Lowercase characters, one per character of `text`
+fn fold(text: &str) -> Vec<char> {
+    text.chars()
+        .map(|c| c.to_lowercase().next().unwrap_or(c))
+        .collect()
+}
+
+fn contains(haystack: &[char], needle: &[char]) -> bool {
+    needle.is_empty() || haystack.windows(needle.len()).any(|w| w == needle)
+}
+
+/// Mark every occurrence of `needle`, returning whether there was one
+fn mark_substrings(haystack: &[char], needle: &[char], marked: &mut [bool]) -> bool {
+    if needle.is_empty() || needle.len() > haystack.len() {
+        return false;
+    }
+    let mut found = false;
+    let mut i = 0;
+    while i + needle.len() <= haystack.len() {
+        if haystack[i..i + needle.len()] == *needle {
+            marked[i..i + needle.len()].fill(true);
+            found = true;
+            i += needle.len();
+        } else {
+            i += 1;
+        }
+    }
+    found
+}
+
+/// Positions of `needle`'s characters in order in `haystack`, if they all appear
+fn subsequence(haystack: &[char], needle: &[char]) -> Option<Vec<usize>> {
+    let mut positions = Vec::with_capacity(needle.len());
+    let mut rest = haystack.iter().enumerate();
+    for c in needle {
+        let (i, _) = rest.by_ref().find(|(_, h)| *h == c)?;
+        positions.push(i);
+    }
+    Some(positions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn download(name: &str, status: DownloadState) -> Download {
+        Download {
+            name: name.to_string(),
+            status,
+            ..Default::default()
+        }
+    }
+
+    fn matching<'a>(downloads: &'a [Download], query: &str) -> Vec<&'a str> {
+        let query = Query::parse(query).unwrap();
+        downloads
+            .iter()
+            .filter(|d| query.matches(d))
+            .map(|d| d.name.as_str())
+            .collect()
+    }
+
+    fn sample() -> Vec<Download> {
+        let mut iso = download("ubuntu-24.04.iso", DownloadState::Active);
+        iso.total_length = 5 << 30;
+        iso.progress = 0.3;
+        iso.speed_history = vec![2 << 20];
+        iso.url = Some("https://releases.ubuntu.com/x.iso".to_string());
+        iso.file_path = Some("/data/iso/ubuntu-24.04.iso".to_string());
+
+        let mut torrent = download("debian.torrent", DownloadState::Paused);
+        torrent.download_type = DownloadType::Torrent;
+        torrent.total_length = 600 << 20;
+        torrent.progress = 0.8;
+        torrent.url = Some("https://github.com/debian".to_string());
+
+        let mut zip = download("notes.zip", DownloadState::Error);
+        zip.total_length = 10 << 10;
+        zip.stalled = true;
+        vec![iso, torrent, zip]
+    }
+
+    #[test]
+    fn test_field_filters() {
+        let downloads = sample();
+        assert_eq!(matching(&downloads, "status:error"), vec!["notes.zip"]);
+        assert_eq!(matching(&downloads, "type:torrent"), vec!["debian.torrent"]);
+        assert_eq!(matching(&downloads, "size:>1G"), vec!["ubuntu-24.04.iso"]);
+        assert_eq!(
+            matching(&downloads, "size:<=600M"),
+            vec!["debian.torrent", "notes.zip"]
+        );
+        assert_eq!(
+            matching(&downloads, "speed:<100K"),
+            vec!["debian.torrent", "notes.zip"]
+        );
+        assert_eq!(
+            matching(&downloads, "progress:<50"),
+            vec!["ubuntu-24.04.iso", "notes.zip"]
+        );
+        assert_eq!(
+            matching(&downloads, "url:GITHUB.com"),
+            vec!["debian.torrent"]
+        );
+        assert_eq!(
+            matching(&downloads, "path:/data/iso"),
+            vec!["ubuntu-24.04.iso"]
+        );
+        assert_eq!(matching(&downloads, "IS:STALLED"), vec!["notes.zip"]);
+        assert_eq!(
+            matching(&downloads, "STATUS:Paused"),
+            vec!["debian.torrent"]
+        );
+    }
+
+    #[test]
+    fn test_negation_and_or() {
+        let downloads = sample();
+        assert_eq!(
+            matching(&downloads, "-status:active"),
+            vec!["debian.torrent", "notes.zip"]
+        );
+        assert_eq!(
+            matching(&downloads, "status:active OR status:error"),
+            vec!["ubuntu-24.04.iso", "notes.zip"]
+        );
+        // AND binds tighter than OR
+        assert_eq!(
+            matching(&downloads, "ubuntu status:paused OR notes"),
+            vec!["notes.zip"]
+        );
+        assert_eq!(matching(&downloads, "-zip -iso"), vec!["debian.torrent"]);
+    }
+
+    #[test]
+    fn test_name_matching() {
+        let downloads = sample();
+        assert_eq!(matching(&downloads, "UBUNTU"), vec!["ubuntu-24.04.iso"]);
+        // Fuzzy: letters in order
+        assert_eq!(matching(&downloads, "ubiso"), vec!["ubuntu-24.04.iso"]);
+        // Quoted phrases must appear as written
+        assert!(matching(&downloads, "\"ubiso\"").is_empty());
+        assert_eq!(
+            matching(&downloads, "\"24.04 \" OR \"otes.\""),
+            vec!["notes.zip"]
+        );
+        assert_eq!(
+            matching(&downloads, r"/\.(iso|zip)$/"),
+            vec!["ubuntu-24.04.iso", "notes.zip"]
+        );
+        assert_eq!(
+            matching(&downloads, "-/^u/"),
+            vec!["debian.torrent", "notes.zip"]
+        );
+        // URLs are names, not fields
+        assert!(matching(&downloads, "https://x").is_empty());
+        assert_eq!(matching(&downloads, "").len(), 3);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        for (query, message, span) in [
+            ("stauts:error", "unknown field 'stauts' (expected is, path, progress, size, speed, status, type, url)", 0..12),
+            ("iso status:done", "unknown status 'done' (expected waiting, active, paused, seeding, complete, error, removed, verifying)", 4..15),
+            ("type:ftp", "unknown type 'ftp' (expected http, torrent, metalink)", 0..8),
+            ("size:>big", "invalid number 'big'", 0..9),
+            ("progress:<150", "invalid number '150'", 0..13),
+            ("speed:", "speed: needs a value", 0..6),
+            ("is:slow", "unknown flag 'slow' (expected stalled)", 0..7),
+            ("a OR", "OR needs a term after it", 2..4),
+            ("OR a", "OR needs a term before it", 0..2),
+            ("a - b", "nothing to negate after '-'", 2..3),
+            ("/(unclosed/", "invalid regex '(unclosed'", 0..11),
+            ("x /abc", "unclosed regex", 2..6),
+            ("\"abc", "unclosed quote", 0..4),
+        ] {
+            let error = Query::parse(query).unwrap_err();
+            assert_eq!(error, QueryError::new(message, span), "{}", query);
+        }
+    }
+
+    #[test]
+    fn test_highlights() {
+        let marks = |query: &str, name: &str| -> String {
+            Query::parse(query)
+                .unwrap()
+                .highlights(name)
+                .iter()
+                .map(|&m| if m { '^' } else { ' ' })
+                .collect()
+        };
+        assert_eq!(marks("iso", "ubuntu.iso"), "       ^^^");
+        assert_eq!(marks("ubiso status:active", "ubuntu.iso"), "^^     ^^^");
+        assert_eq!(marks("/b.n/", "ubuntu.iso"), " ^^^      ");
+        assert_eq!(marks("-ubuntu size:>1", "ubuntu.iso"), "          ");
+    }
+}
@@ -0,0 +1,552 @@
+//! Time-based control of the download queue
+//!
+//! A [`Schedule`] holds weekly rules such as "weekdays 01:00-07:00 resume,
+//! pause outside" and one-off start/stop times for single downloads. The
+//! [`Scheduler`] checks it against the local clock and returns the worker
+//! commands for every boundary crossed since the previous check.
+//!
+//! Rules are written as `<days> <from>-<to> <inside>[/<outside>]`, e.g.
+//! `weekdays 01:00-07:00 resume/pause`. The same text is used in the
+//! schedule popup and in `schedule.toml`.
+
+use crate::config::Config;
+use crate::worker::Command;
+use chrono::{Datelike, Duration, NaiveDateTime, NaiveTime, Weekday};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::PathBuf;
+
+const SCHEDULE_FILE: &str = "schedule.toml";
+
+const WEEKDAYS: [Weekday; 7] = [
+    Weekday::Mon,
+    Weekday::Tue,
+    Weekday::Wed,
+    Weekday::Thu,
+    Weekday::Fri,
+    Weekday::Sat,
+    Weekday::Sun,
+];
+
+/// What a rule does to the whole queue
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum QueueAction {
+    Resume,
+    Pause,
+    Nothing,
+}
+
+impl QueueAction {
+    fn parse(s: &str) -> Result<Self, String> {
+        match s {
+            "resume" => Ok(QueueAction::Resume),
+            "pause" => Ok(QueueAction::Pause),
+            "none" => Ok(QueueAction::Nothing),
+            _ => Err(format!("unknown action '{}' (resume, pause or none)", s)),
+        }
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            QueueAction::Resume => "resume",
+            QueueAction::Pause => "pause",
+            QueueAction::Nothing => "none",
+        }
+    }
+
+    fn command(&self) -> Option<Command> {
+        match self {
+            QueueAction::Resume => Some(Command::ResumeAll),
+            QueueAction::Pause => Some(Command::PauseAll),
+            QueueAction::Nothing => None,
+        }
+    }
+
+    fn describe(&self) -> &'static str {
+        match self {
+            QueueAction::Resume => "resume all",
+            QueueAction::Pause => "pause all",
+            QueueAction::Nothing => "",
+        }
+    }
+}
+
+/// Weekly time window, e.g. `weekdays 01:00-07:00`
+#[derive(Clone, Debug, PartialEq)]
+pub struct TimeWindow {
+    pub days: Vec<Weekday>, // Days the window starts on
+    pub from: NaiveTime,
+    pub to: NaiveTime, // Earlier than `from` for windows that cross midnight
+}
+
+impl TimeWindow {
+    /// Parse `<days> <from>-<to>`
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let words: Vec<&str> = text.split_whitespace().collect();
+        let [days, range] = words[..] else {
+            return Err("expected '<days> <from>-<to>'".to_string());
+        };
+        Self::from_parts(days, range)
+    }
+
+    fn from_parts(days: &str, range: &str) -> Result<Self, String> {
+        let (from, to) = range
+            .split_once('-')
+            .ok_or_else(|| format!("expected a time range, got '{}'", range))?;
+        let window = Self {
+            days: parse_days(days)?,
+            from: parse_time(from)?,
+            to: parse_time(to)?,
+        };
+        if window.from == window.to {
+            return Err("the window is empty".to_string());
+        }
+        Ok(window)
+    }
+
+    /// Whether `now` falls inside the window
+    pub fn contains(&self, now: NaiveDateTime) -> bool {
+        let time = now.time();
+        let today = self.days.contains(&now.weekday());
+        if self.from < self.to {
+            today && time >= self.from && time < self.to
+        } else {
+            let yesterday = self.days.contains(&now.weekday().pred());
+            (today && time >= self.from) || (yesterday && time < self.to)
+        }
+    }
+
+    /// Moments after `now` at which the window may open or close, in order
+    pub fn boundaries(&self, now: NaiveDateTime) -> Vec<NaiveDateTime> {
+        let mut candidates: Vec<NaiveDateTime> = (0..=8)
+            .flat_map(|offset| {
+                let date = now.date() + Duration::days(offset);
+                [date.and_time(self.from), date.and_time(self.to)]
+            })
+            .filter(|t| *t > now)
+            .collect();
+        candidates.sort();
+        candidates
+    }
+
+    /// First moment after `now` at which the window opens or closes
+    fn next_change(&self, now: NaiveDateTime) -> Option<NaiveDateTime> {
+        let inside = self.contains(now);
+        self.boundaries(now)
+            .into_iter()
+            .find(|t| self.contains(*t) != inside)
+    }
+}
+
+impl fmt::Display for TimeWindow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {}-{}",
+            format_days(&self.days),
+            self.from.format("%H:%M"),
+            self.to.format("%H:%M")
+        )
+    }
+}
+
+/// Weekly time window with actions for entering and leaving it
+#[derive(Clone, Debug, PartialEq)]
+pub struct ScheduleRule {
+    pub window: TimeWindow,
+    pub inside: QueueAction,
+    pub outside: QueueAction,
+}
+
+impl ScheduleRule {
+    /// Parse the text form, e.g. `weekdays 01:00-07:00 resume/pause`
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let words: Vec<&str> = text.split_whitespace().collect();
+        let [days, range, actions] = words[..] else {
+            return Err("expected '<days> <from>-<to> <action>[/<action>]'".to_string());
+        };
+        let (inside, outside) = actions.split_once('/').unwrap_or((actions, "none"));
+
+        Ok(Self {
+            window: TimeWindow::from_parts(days, range)?,
+            inside: QueueAction::parse(inside)?,
+            outside: QueueAction::parse(outside)?,
+        })
+    }
+
+    /// Whether `now` falls inside the window
+    pub fn contains(&self, now: NaiveDateTime) -> bool {
+        self.window.contains(now)
+    }
+
+    /// First moment after `now` at which the rule switches sides
+    fn next_change(&self, now: NaiveDateTime) -> Option<NaiveDateTime> {
+        self.window.next_change(now)
+    }
+}
+
+impl fmt::Display for ScheduleRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.window, self.inside.as_str())?;
+        if self.outside != QueueAction::Nothing {
+            write!(f, "/{}", self.outside.as_str())?;
+        }
+        Ok(())
+    }
+}
+
+/// One-off start and stop times for a single download
+#[derive(Clone, Debug, PartialEq)]
+pub struct DownloadTimer {
+    pub gid: String,
+    pub name: String,
+    pub start_at: Option<NaiveDateTime>,
+    pub stop_at: Option<NaiveDateTime>,
+}
+
+/// Weekly rules plus per-download timers
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Schedule {
+    pub rules: Vec<ScheduleRule>,
+    pub timers: Vec<DownloadTimer>, // Not saved: GIDs do not outlive the aria2 session
+}
+
+/// On-disk form of the schedule
+#[derive(Default, Deserialize, Serialize)]
+struct ScheduleFile {
+    rules: Vec<String>,
+}
+
+impl Schedule {
+    /// Path of the file the schedule popup saves to
+    pub fn path() -> PathBuf {
+        Config::dir().join(SCHEDULE_FILE)
+    }
+
+    /// Load saved rules (an empty schedule if there is no file)
+    pub fn load() -> Result<Self, Box<dyn std::error::Error>> {
+        let path = Self::path();
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = std::fs::read_to_string(&path)?;
+        Self::parse(&content).map_err(|e| format!("{}: {}", path.display(), e).into())
+    }
+
+    /// Parse saved rules from TOML text
+    pub fn parse(content: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        let file: ScheduleFile = toml::from_str(content)?;
+        let rules = file
+            .rules
+            .iter()
+            .map(|text| ScheduleRule::parse(text).map_err(|e| format!("'{}': {}", text, e)))
+            .collect::<Result<_, _>>()?;
+        Ok(Self {
+            rules,
+            timers: Vec::new(),
+        })
+    }
+
+    /// TOML text for the rules
+    pub fn to_toml(&self) -> String {
+        let file = ScheduleFile {
+            rules: self.rules.iter().map(|r| r.to_string()).collect(),
+        };
+        toml::to_string(&file).unwrap_or_default()
+    }
+
+    /// Timer for a download, created if missing
+    pub fn timer_mut(&mut self, gid: &str, name: &str) -> &mut DownloadTimer {
+        let index = match self.timers.iter().position(|t| t.gid == gid) {
+            Some(index) => index,
+            None => {
+                self.timers.push(DownloadTimer {
+                    gid: gid.to_string(),
+                    name: name.to_string(),
+                    start_at: None,
+                    stop_at: None,
+                });
+                self.timers.len() - 1
+            }
+        };
+        &mut self.timers[index]
+    }
+}
+
+/// Applies a [`Schedule`] as time passes
+#[derive(Debug, Default)]
+pub struct Scheduler {
+    pub schedule: Schedule,
+    inside: Vec<Option<bool>>, // Per rule, `None` until the first check
+}
+
+impl Scheduler {
+    pub fn new(schedule: Schedule) -> Self {
+        let inside = vec![None; schedule.rules.len()];
+        Self { schedule, inside }
+    }
+
+    /// Apply rule changes; every rule is re-applied on the next check
+    pub fn rules_changed(&mut self) {
+        self.inside = vec![None; self.schedule.rules.len()];
+    }
+
+    /// Commands for rule boundaries and timers passed since the last check
+    ///
+    /// On the first check each rule applies the action for the side it is on.
+    pub fn check(&mut self, now: NaiveDateTime) -> Vec<Command> {
+        let mut commands = Vec::new();
+        let mut resumed = false;
+
+        for (rule, last) in self.schedule.rules.iter().zip(self.inside.iter_mut()) {
+            let inside = rule.contains(now);
+            if *last == Some(inside) {
+                continue;
+            }
+            *last = Some(inside);
+            let action = if inside { rule.inside } else { rule.outside };
+            resumed |= action == QueueAction::Resume;
+            commands.extend(action.command());
+        }
+
+        for timer in &mut self.schedule.timers {
+            if timer.start_at.is_some_and(|at| at <= now) {
+                timer.start_at = None;
+                commands.push(Command::Resume(timer.gid.clone()));
+            } else if resumed && timer.start_at.is_some() {
+                // Resuming the whole queue must not start downloads that wait for their time
+                commands.push(Command::Pause(timer.gid.clone()));
+            }
+            if timer.stop_at.is_some_and(|at| at <= now) {
+                timer.stop_at = None;
+                commands.push(Command::Pause(timer.gid.clone()));
+            }
+        }
+        self.schedule
+            .timers
+            .retain(|t| t.start_at.is_some() || t.stop_at.is_some());
+
+        commands
+    }
+
+    /// Next scheduled change as text, e.g. `pause all 07:00`
+    pub fn next_change(&self, now: NaiveDateTime) -> Option<String> {
+        let rules = self.schedule.rules.iter().filter_map(|rule| {
+            let at = rule.next_change(now)?;
+            let action = if rule.contains(at) {
+                rule.inside
+            } else {
+                rule.outside
+            };
+            (action != QueueAction::Nothing).then(|| (at, action.describe().to_string()))
+        });
+        let timers = self.schedule.timers.iter().flat_map(|t| {
+            [
+                t.start_at.map(|at| (at, format!("start {}", t.name))),
+                t.stop_at.map(|at| (at, format!("stop {}", t.name))),
+            ]
+            .into_iter()
+            .flatten()
+        });
+
+        let (at, what) = rules.chain(timers).min_by_key(|(at, _)| *at)?;
+        Some(format!("{} {}", what, format_moment(at, now)))
+    }
+}
+
+/// Next occurrence of `HH:MM` after `now`
+pub fn next_time_of_day(text: &str, now: NaiveDateTime) -> Result<NaiveDateTime, String> {
+    let time = parse_time(text)?;
+    let today = now.date().and_time(time);
+    Ok(if today > now {
+        today
+    } else {
+        today + Duration::days(1)
+    })
+}
+
+/// `07:00` for today, `Tue 07:00` for later days
+pub fn format_moment(at: NaiveDateTime, now: NaiveDateTime) -> String {
+    if at.date() == now.date() {
+        at.format("%H:%M").to_string()
+    } else {
+        at.format("%a %H:%M").to_string()
+    }
+}
+
+fn parse_time(text: &str) -> Result<NaiveTime, String> {
+    NaiveTime::parse_from_str(text, "%H:%M").map_err(|_| format!("invalid time '{}'", text))
+}
+
+fn parse_days(text: &str) -> Result<Vec<Weekday>, String> {
+    match text {
+        "daily" => return Ok(WEEKDAYS.to_vec()),
+        "weekdays" => return Ok(WEEKDAYS[..5].to_vec()),
+        "weekends" => return Ok(WEEKDAYS[5..].to_vec()),
+        _ => {}
+    }
+    text.split(',')
+        .map(|day| {
+            day.parse::<Weekday>()
+                .map_err(|_| format!("unknown day '{}'", day))
+        })
+        .collect()
+}
+
+fn format_days(days: &[Weekday]) -> String {
+    let mut sorted = days.to_vec();
+    sorted.sort_by_key(|d| d.num_days_from_monday());
+    sorted.dedup();
+    if sorted == WEEKDAYS {
+        "daily".to_string()
+    } else if sorted == WEEKDAYS[..5] {
+        "weekdays".to_string()
+    } else if sorted == WEEKDAYS[5..] {
+        "weekends".to_string()
+    } else {
+        let names: Vec<String> = sorted
+            .iter()
+            .map(|d| d.to_string().to_lowercase())
+            .collect();
+        names.join(",")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    // 2024-01-01 is a Monday
+    fn at(day: u32, hour: u32, minute: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2024, 1, day)
+            .unwrap()
+            .and_hms_opt(hour, minute, 0)
+            .unwrap()
+    }
+
+    fn night_rule() -> ScheduleRule {
+        ScheduleRule::parse("weekdays 01:00-07:00 resume/pause").unwrap()
+    }
+
+    #[test]
+    fn test_parse_and_display_rules() {
+        let rule = night_rule();
+        assert_eq!(rule.window.days.len(), 5);
+        assert_eq!(rule.inside, QueueAction::Resume);
+        assert_eq!(rule.outside, QueueAction::Pause);
+        assert_eq!(rule.to_string(), "weekdays 01:00-07:00 resume/pause");
+
+        let rule = ScheduleRule::parse("sat,sun 22:00-06:00 pause").unwrap();
+        assert_eq!(rule.to_string(), "weekends 22:00-06:00 pause");
+
+        assert!(ScheduleRule::parse("weekdays 01:00 resume").is_err());
+        assert!(ScheduleRule::parse("someday 01:00-02:00 resume").is_err());
+        assert!(ScheduleRule::parse("daily 25:00-02:00 resume").is_err());
+        assert!(ScheduleRule::parse("daily 01:00-02:00 explode").is_err());
+        assert!(ScheduleRule::parse("daily 01:00-01:00 resume").is_err());
+    }
+
+    #[test]
+    fn test_parse_time_window() {
+        let window = TimeWindow::parse("mon,fri 09:00-17:30").unwrap();
+        assert_eq!(window.days, vec![Weekday::Mon, Weekday::Fri]);
+        assert_eq!(window.to_string(), "mon,fri 09:00-17:30");
+        assert!(TimeWindow::parse("mon 09:00-17:30 resume").is_err());
+    }
+
+    #[test]
+    fn test_contains_handles_midnight() {
+        let rule = night_rule();
+        assert!(rule.contains(at(1, 1, 0)));
+        assert!(!rule.contains(at(1, 7, 0)));
+        assert!(!rule.contains(at(6, 2, 0))); // Saturday
+
+        let rule = ScheduleRule::parse("fri 22:00-06:00 resume").unwrap();
+        assert!(rule.contains(at(5, 23, 0)));
+        assert!(rule.contains(at(6, 5, 59))); // Saturday morning, started Friday
+        assert!(!rule.contains(at(4, 23, 0)));
+    }
+
+    #[test]
+    fn test_check_fires_on_boundaries_only() {
+        let mut scheduler = Scheduler::new(Schedule {
+            rules: vec![night_rule()],
+            timers: Vec::new(),
+        });
+
+        // First check applies the current side
+        assert_eq!(scheduler.check(at(1, 12, 0)), vec![Command::PauseAll]);
+        assert!(scheduler.check(at(1, 18, 0)).is_empty());
+        assert_eq!(scheduler.check(at(2, 1, 0)), vec![Command::ResumeAll]);
+        assert!(scheduler.check(at(2, 3, 0)).is_empty());
+        assert_eq!(scheduler.check(at(2, 7, 0)), vec![Command::PauseAll]);
+    }
+
+    #[test]
+    fn test_timers() {
+        let mut scheduler = Scheduler::new(Schedule {
+            rules: vec![night_rule()],
+            timers: Vec::new(),
+        });
+        scheduler.check(at(1, 0, 30));
+        let timer = scheduler.schedule.timer_mut("g1", "big.iso");
+        timer.start_at = Some(at(1, 3, 0));
+        timer.stop_at = Some(at(1, 5, 0));
+
+        // The queue resumes at 01:00 but g1 waits for 03:00
+        assert_eq!(
+            scheduler.check(at(1, 1, 0)),
+            vec![Command::ResumeAll, Command::Pause("g1".to_string())]
+        );
+        assert_eq!(
+            scheduler.check(at(1, 3, 0)),
+            vec![Command::Resume("g1".to_string())]
+        );
+        assert_eq!(
+            scheduler.check(at(1, 5, 0)),
+            vec![Command::Pause("g1".to_string())]
+        );
+        assert!(scheduler.schedule.timers.is_empty());
+    }
+
+    #[test]
+    fn test_next_change() {
+        let mut scheduler = Scheduler::new(Schedule {
+            rules: vec![night_rule()],
+            timers: Vec::new(),
+        });
+        assert_eq!(
+            scheduler.next_change(at(1, 3, 0)).as_deref(),
+            Some("pause all 07:00")
+        );
+        assert_eq!(
+            scheduler.next_change(at(5, 12, 0)).as_deref(),
+            Some("resume all Mon 01:00")
+        );
+
+        scheduler.schedule.timer_mut("g1", "big.iso").stop_at = Some(at(1, 4, 0));
+        assert_eq!(
+            scheduler.next_change(at(1, 3, 0)).as_deref(),
+            Some("stop big.iso 04:00")
+        );
+    }
+
+    #[test]
+    fn test_schedule_file_round_trip() {
+        let schedule = Schedule::parse("rules = [\"weekdays 01:00-07:00 resume/pause\"]").unwrap();
+        assert_eq!(schedule.rules, vec![night_rule()]);
+        assert_eq!(
+            Schedule::parse(&schedule.to_toml()).unwrap().rules,
+            schedule.rules
+        );
+        assert!(Schedule::parse("rules = [\"nonsense\"]").is_err());
+    }
+
+    #[test]
+    fn test_next_time_of_day() {
+        assert_eq!(next_time_of_day("03:00", at(1, 2, 0)).unwrap(), at(1, 3, 0));
+        assert_eq!(next_time_of_day("01:00", at(1, 2, 0)).unwrap(), at(2, 1, 0));
+        assert!(next_time_of_day("3pm", at(1, 2, 0)).is_err());
+    }
+}
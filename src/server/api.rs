@@ -0,0 +1,361 @@
+//! HTTP API over the download manager for scripts and automation
+//!
+//! Every request must carry the configured token. Routes:
+//!
+//! - `GET /api/downloads` - All downloads
+//! - `POST /api/downloads` - Add a download: `{"url": "...", "out": ..., "dir": ...}`
+//! - `GET /api/downloads/{gid}` - One download
+//! - `DELETE /api/downloads/{gid}` - Remove a download from the list
+//! - `POST /api/downloads/{gid}/pause` and `.../resume`
+//! - `GET /api/stats` - Global statistics
+//! - `GET /api/limits` and `PUT /api/limits` - Global speed limits in bytes/sec
+//! - `GET /api/events` - Server-sent events stream of lifecycle events
+
+use crate::config::ApiConfig;
+use crate::download_manager::DownloadManager;
+use crate::events::DownloadEvent;
+use crate::models::DownloadOptions;
+use crate::server::http::{Request, Response};
+use serde::Deserialize;
+use serde_json::json;
+use std::sync::Arc;
+use tokio::sync::{broadcast, mpsc};
+
+/// Body of `POST /api/downloads`
+#[derive(Debug, Deserialize)]
+struct AddRequest {
+    url: String,
+    #[serde(flatten)]
+    options: DownloadOptions,
+}
+
+/// Body of `PUT /api/limits`; missing fields are left unchanged
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct LimitsRequest {
+    download: Option<u64>,
+    upload: Option<u64>,
+}
+
+#[derive(Debug, PartialEq)]
+enum Route {
+    ListDownloads,
+    AddDownload,
+    GetDownload(String),
+    RemoveDownload(String),
+    PauseDownload(String),
+    ResumeDownload(String),
+    Stats,
+    GetLimits,
+    SetLimits,
+    Events,
+}
+
+/// Run the API until the listener fails
+pub async fn run(
+    config: ApiConfig,
+    download_manager: Arc<DownloadManager>,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let addr = config.address().map_err(|e| e.to_string())?;
+    let token = Arc::new(config.token);
+    let cors_origin = super::cors_origin(&config.cors_origin);
+
+    super::serve(addr, cors_origin, move |request| {
+        let token = token.clone();
+        let download_manager = download_manager.clone();
+        async move { handle(request, &token, &download_manager).await }
+    })
+    .await?;
+    Ok(())
+}
+
+async fn handle(request: Request, token: &str, download_manager: &DownloadManager) -> Response {
+    let route = match route(&request, token) {
+        Ok(route) => route,
+        Err(response) => return response,
+    };
+
+    match route {
+        Route::ListDownloads => {
+            let downloads = download_manager.get_all_downloads().await;
+            Response::json(200, &json!(downloads))
+        }
+        Route::GetDownload(gid) => match download_manager.get_download(&gid).await {
+            Some(download) => Response::json(200, &json!(download)),
+            None => Response::error(404, "Download not found"),
+        },
+        Route::AddDownload => {
+            let add: AddRequest = match serde_json::from_slice(&request.body) {
+                Ok(add) => add,
+                Err(e) => return Response::error(400, &format!("Invalid JSON: {}", e)),
+            };
+            let result = download_manager
+                .add_download_with_options(add.url.trim(), &add.options)
+                .await
+                .map_err(|e| e.to_string());
+            match result {
+                Ok(gid) => Response::json(201, &json!({ "gid": gid })),
+                Err(e) => Response::error(500, &e),
+            }
+        }
+        Route::RemoveDownload(gid) => {
+            if download_manager.get_download(&gid).await.is_none() {
+                return Response::error(404, "Download not found");
+            }
+            let result = download_manager
+                .remove_download(&gid)
+                .await
+                .map_err(|e| e.to_string());
+            ok_or_error(result)
+        }
+        Route::PauseDownload(gid) => {
+            let result = download_manager
+                .pause_download(&gid)
+                .await
+                .map_err(|e| e.to_string());
+            ok_or_error(result)
+        }
+        Route::ResumeDownload(gid) => {
+            let result = download_manager
+                .resume_download(&gid)
+                .await
+                .map_err(|e| e.to_string());
+            ok_or_error(result)
+        }
+        Route::Stats => {
+            let stats = download_manager.get_global_stats().await;
+            Response::json(200, &json!(stats))
+        }
+        Route::GetLimits => {
+            let result = download_manager
+                .get_speed_limits()
+                .await
+                .map_err(|e| e.to_string());
+            match result {
+                Ok((download, upload)) => {
+                    Response::json(200, &json!({ "download": download, "upload": upload }))
+                }
+                Err(e) => Response::error(500, &e),
+            }
+        }
+        Route::SetLimits => {
+            let limits: LimitsRequest = match serde_json::from_slice(&request.body) {
+                Ok(limits) => limits,
+                Err(e) => return Response::error(400, &format!("Invalid JSON: {}", e)),
+            };
+            if let Some(limit) = limits.download {
+                let result = download_manager
+                    .set_download_speed_limit(limit)
+                    .await
+                    .map_err(|e| e.to_string());
+                if let Err(e) = result {
+                    return Response::error(500, &e);
+                }
+            }
+            if let Some(limit) = limits.upload {
+                let result = download_manager
+                    .set_upload_speed_limit(limit)
+                    .await
+                    .map_err(|e| e.to_string());
+                if let Err(e) = result {
+                    return Response::error(500, &e);
+                }
+            }
+            Response::json(200, &json!({ "status": "ok" }))
+        }
+        Route::Events => event_stream(download_manager.subscribe()),
+    }
+}
+
+fn ok_or_error(result: Result<(), String>) -> Response {
+    match result {
+        Ok(()) => Response::json(200, &json!({ "status": "ok" })),
+        Err(e) => Response::error(500, &e),
+    }
+}
+
+/// Authenticate and route a request
+fn route(request: &Request, token: &str) -> Result<Route, Response> {
+    if !request.has_token(token) {
+        return Err(Response::error(401, "Invalid or missing token"));
+    }
+
+    let segments: Vec<&str> = request
+        .path
+        .trim_matches('/')
+        .split('/')
+        .filter(|s| !s.is_empty())
+        .collect();
+    let method = request.method.as_str();
+
+    let route = match segments.as_slice() {
+        ["api", "downloads"] => match method {
+            "GET" => Route::ListDownloads,
+            "POST" => Route::AddDownload,
+            _ => return Err(method_not_allowed()),
+        },
+        ["api", "downloads", gid] => match method {
+            "GET" => Route::GetDownload(gid.to_string()),
+            "DELETE" => Route::RemoveDownload(gid.to_string()),
+            _ => return Err(method_not_allowed()),
+        },
+        ["api", "downloads", gid, action] => match (method, *action) {
+            ("POST", "pause") => Route::PauseDownload(gid.to_string()),
+            ("POST", "resume") => Route::ResumeDownload(gid.to_string()),
+            (_, "pause") | (_, "resume") => return Err(method_not_allowed()),
+            _ => return Err(Response::error(404, "Not found")),
+        },
+        ["api", "stats"] if method == "GET" => Route::Stats,
+        ["api", "limits"] => match method {
+            "GET" => Route::GetLimits,
+            "PUT" | "POST" => Route::SetLimits,
+            _ => return Err(method_not_allowed()),
+        },
+        ["api", "events"] if method == "GET" => Route::Events,
+        ["api", "stats"] | ["api", "events"] => return Err(method_not_allowed()),
+        _ => return Err(Response::error(404, "Not found")),
+    };
+    Ok(route)
+}
+
+fn method_not_allowed() -> Response {
+    Response::error(405, "Method not allowed")
+}
+
+/// Forward lifecycle events to one SSE client until it disconnects
+fn event_stream(mut events: broadcast::Receiver<DownloadEvent>) -> Response {
+    let (tx, rx) = mpsc::channel(16);
+
+    tokio::spawn(async move {
+        if tx.send(b": connected\n\n".to_vec()).await.is_err() {
+            return;
+        }
+        loop {
+            let chunk = match events.recv().await {
+                Ok(event) => match sse_message(&event) {
+                    Some(chunk) => chunk,
+                    None => continue,
+                },
+                Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                Err(broadcast::error::RecvError::Closed) => break,
+            };
+            if tx.send(chunk.into_bytes()).await.is_err() {
+                break; // Client went away
+            }
+        }
+    });
+
+    Response::event_stream(rx)
+}
+
+/// SSE message for a lifecycle event, or nothing for events scripts don't see
+fn sse_message(event: &DownloadEvent) -> Option<String> {
+    let data = match event {
+        DownloadEvent::Progress(download, percent) => {
+            json!({ "download": download, "percent": percent })
+        }
+        DownloadEvent::BackendDown | DownloadEvent::BackendUp => json!({}),
+        DownloadEvent::Quota(_) => return None,
+        _ => json!({ "download": event.download()? }),
+    };
+    Some(format!("event: {}\ndata: {}\n\n", event.name(), data))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Download, DownloadState};
+
+    fn create_test_download(gid: &str, status: DownloadState) -> Download {
+        Download {
+            gid: Some(gid.to_string()),
+            name: format!("{}.zip", gid),
+            status,
+            ..Default::default()
+        }
+    }
+
+    fn request(method: &str, path: &str) -> Request {
+        let mut request = Request {
+            method: method.to_string(),
+            path: path.to_string(),
+            ..Default::default()
+        };
+        request
+            .headers
+            .insert("authorization".to_string(), "Bearer secret".to_string());
+        request
+    }
+
+    fn status(result: Result<Route, Response>) -> u16 {
+        result.err().unwrap().status
+    }
+
+    #[test]
+    fn test_routes() {
+        assert_eq!(
+            route(&request("GET", "/api/downloads"), "secret").unwrap(),
+            Route::ListDownloads
+        );
+        assert_eq!(
+            route(&request("POST", "/api/downloads/"), "secret").unwrap(),
+            Route::AddDownload
+        );
+        assert_eq!(
+            route(&request("DELETE", "/api/downloads/abc"), "secret").unwrap(),
+            Route::RemoveDownload("abc".to_string())
+        );
+        assert_eq!(
+            route(&request("POST", "/api/downloads/abc/resume"), "secret").unwrap(),
+            Route::ResumeDownload("abc".to_string())
+        );
+        assert_eq!(
+            route(&request("PUT", "/api/limits"), "secret").unwrap(),
+            Route::SetLimits
+        );
+        assert_eq!(
+            route(&request("GET", "/api/events"), "secret").unwrap(),
+            Route::Events
+        );
+    }
+
+    #[test]
+    fn test_route_errors() {
+        assert_eq!(status(route(&request("GET", "/api/stats"), "other")), 401);
+        assert_eq!(status(route(&request("GET", "/api/nope"), "secret")), 404);
+        assert_eq!(
+            status(route(&request("GET", "/api/downloads/abc/pause"), "secret")),
+            405
+        );
+        assert_eq!(
+            status(route(&request("POST", "/api/downloads/abc/stop"), "secret")),
+            404
+        );
+    }
+
+    #[test]
+    fn test_add_request_options() {
+        let add: AddRequest =
+            serde_json::from_str(r#"{"url": "https://a.com/x.iso", "out": "y.iso"}"#).unwrap();
+        assert_eq!(add.url, "https://a.com/x.iso");
+        assert_eq!(add.options.out.as_deref(), Some("y.iso"));
+    }
+
+    #[test]
+    fn test_sse_message() {
+        let download = create_test_download("a", DownloadState::Complete);
+        let message = sse_message(&DownloadEvent::Completed(download.clone())).unwrap();
+        assert!(message.starts_with("event: completed\ndata: {"));
+        assert!(message.contains(r#""gid":"a""#));
+        assert!(message.ends_with("}\n\n"));
+
+        let message = sse_message(&DownloadEvent::Progress(download, 50)).unwrap();
+        assert!(message.starts_with("event: progress\n"));
+        assert!(message.contains(r#""percent":50"#));
+
+        assert_eq!(
+            sse_message(&DownloadEvent::BackendDown).as_deref(),
+            Some("event: backend_down\ndata: {}\n\n")
+        );
+    }
+}
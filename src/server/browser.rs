@@ -0,0 +1,293 @@
+//! Endpoint for "download with tui-downloader" browser extensions
+//!
+//! Listens on the loopback interface only. Every request must carry the
+//! configured token. Supported routes:
+//!
+//! - `GET /ping` - Presence check, returns the application version
+//! - `POST /add` - JSON body with `url`/`urls`, `cookies`, `referer`,
+//!   `user_agent` and `filename`
+
+use crate::config::BrowserConfig;
+use crate::download_manager::DownloadManager;
+use crate::models::DownloadOptions;
+use crate::server::http::{Request, Response};
+use serde::Deserialize;
+use serde_json::json;
+use std::sync::Arc;
+use tokio::sync::mpsc::UnboundedSender;
+
+/// A download request sent by the browser
+#[derive(Clone, Debug, Default, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct BrowserDownload {
+    pub url: Option<String>,
+    pub urls: Vec<String>,
+    pub cookies: Option<String>,
+    pub referer: Option<String>,
+    #[serde(alias = "userAgent")]
+    pub user_agent: Option<String>,
+    pub filename: Option<String>,
+}
+
+impl BrowserDownload {
+    /// All URLs in the request, trimmed and without duplicates
+    pub fn all_urls(&self) -> Vec<String> {
+        let mut urls: Vec<String> = Vec::new();
+        for url in self.url.iter().chain(self.urls.iter()) {
+            let url = url.trim();
+            if !url.is_empty() && !urls.iter().any(|u| u == url) {
+                urls.push(url.to_string());
+            }
+        }
+        urls
+    }
+
+    /// aria2 options matching the browser context
+    ///
+    /// The suggested file name only makes sense for a single URL.
+    pub fn options(&self) -> DownloadOptions {
+        let mut headers = Vec::new();
+        if let Some(cookies) = self.cookies.as_deref().filter(|c| !c.is_empty()) {
+            headers.push(format!("Cookie: {}", cookies));
+        }
+
+        DownloadOptions {
+            out: if self.all_urls().len() == 1 {
+                self.filename.clone().filter(|f| !f.is_empty())
+            } else {
+                None
+            },
+            referer: self.referer.clone().filter(|r| !r.is_empty()),
+            user_agent: self.user_agent.clone().filter(|u| !u.is_empty()),
+            headers,
+            ..Default::default()
+        }
+    }
+
+    /// Short description for status messages and confirmation popups
+    pub fn describe(&self) -> String {
+        let urls = self.all_urls();
+        match (urls.len(), &self.filename) {
+            (1, Some(name)) if !name.is_empty() => name.clone(),
+            (1, _) => urls[0].clone(),
+            (n, _) => format!("{} downloads", n),
+        }
+    }
+}
+
+/// Events sent from the endpoint to the UI
+#[derive(Clone, Debug)]
+pub enum BrowserEvent {
+    /// Downloads were added without confirmation
+    Added(String),
+    /// Adding failed
+    Failed(String),
+    /// The user has to confirm before the download is added
+    Pending(BrowserDownload),
+}
+
+/// Add every URL of a browser request, returning the new GIDs
+pub async fn add_downloads(
+    download_manager: &DownloadManager,
+    request: &BrowserDownload,
+) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    let options = request.options();
+    let mut gids = Vec::new();
+
+    for url in request.all_urls() {
+        gids.push(
+            download_manager
+                .add_download_with_options(&url, &options)
+                .await?,
+        );
+    }
+
+    Ok(gids)
+}
+
+/// Run the browser endpoint until the listener fails
+pub async fn run(
+    config: BrowserConfig,
+    download_manager: Arc<DownloadManager>,
+    events: UnboundedSender<BrowserEvent>,
+) -> std::io::Result<()> {
+    let config = Arc::new(config);
+
+    let cors_origin = super::cors_origin(&config.cors_origin);
+    super::serve(super::loopback(config.port), cors_origin, move |request| {
+        let config = config.clone();
+        let download_manager = download_manager.clone();
+        let events = events.clone();
+        async move { handle(request, &config, &download_manager, &events).await }
+    })
+    .await
+}
+
+async fn handle(
+    request: Request,
+    config: &BrowserConfig,
+    download_manager: &DownloadManager,
+    events: &UnboundedSender<BrowserEvent>,
+) -> Response {
+    let download = match parse_request(&request, config) {
+        Ok(Route::Ping) => {
+            return Response::json(
+                200,
+                &json!({ "app": "tui-downloader", "version": env!("CARGO_PKG_VERSION") }),
+            )
+        }
+        Ok(Route::Add(download)) => download,
+        Err(response) => return response,
+    };
+
+    if config.confirm {
+        let _ = events.send(BrowserEvent::Pending(download));
+        return Response::json(202, &json!({ "status": "pending" }));
+    }
+
+    // Box<dyn Error> is not Send, so resolve the result before the next await
+    let result = add_downloads(download_manager, &download)
+        .await
+        .map_err(|e| e.to_string());
+
+    match result {
+        Ok(gids) => {
+            let _ = events.send(BrowserEvent::Added(download.describe()));
+            Response::json(200, &json!({ "status": "added", "gids": gids }))
+        }
+        Err(e) => {
+            let _ = events.send(BrowserEvent::Failed(e.clone()));
+            Response::error(500, &e)
+        }
+    }
+}
+
+enum Route {
+    Ping,
+    Add(BrowserDownload),
+}
+
+/// Authenticate and route a request
+fn parse_request(request: &Request, config: &BrowserConfig) -> Result<Route, Response> {
+    if !request.has_token(&config.token) {
+        return Err(Response::error(401, "Invalid or missing token"));
+    }
+
+    match (request.method.as_str(), request.path.as_str()) {
+        ("GET", "/ping") => Ok(Route::Ping),
+        ("POST", "/add") => {
+            let download: BrowserDownload = serde_json::from_slice(&request.body)
+                .map_err(|e| Response::error(400, &format!("Invalid JSON: {}", e)))?;
+            if download.all_urls().is_empty() {
+                return Err(Response::error(400, "No URL given"));
+            }
+            Ok(Route::Add(download))
+        }
+        (_, "/ping") | (_, "/add") => Err(Response::error(405, "Method not allowed")),
+        _ => Err(Response::error(404, "Not found")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> BrowserConfig {
+        BrowserConfig {
+            enabled: true,
+            token: "secret".to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn request(method: &str, path: &str, body: &str) -> Request {
+        let mut request = Request {
+            method: method.to_string(),
+            path: path.to_string(),
+            body: body.as_bytes().to_vec(),
+            ..Default::default()
+        };
+        request
+            .headers
+            .insert("x-auth-token".to_string(), "secret".to_string());
+        request
+    }
+
+    #[test]
+    fn test_all_urls_deduplicates() {
+        let download = BrowserDownload {
+            url: Some(" https://a.com/x ".to_string()),
+            urls: vec![
+                "https://a.com/x".to_string(),
+                "https://b.com/y".to_string(),
+                "".to_string(),
+            ],
+            ..Default::default()
+        };
+        assert_eq!(
+            download.all_urls(),
+            vec!["https://a.com/x", "https://b.com/y"]
+        );
+    }
+
+    #[test]
+    fn test_options_from_browser_context() {
+        let download: BrowserDownload = serde_json::from_str(
+            r#"{
+                "url": "https://example.com/dl?id=1",
+                "cookies": "session=abc",
+                "referer": "https://example.com/",
+                "userAgent": "Firefox",
+                "filename": "file.iso"
+            }"#,
+        )
+        .unwrap();
+
+        let options = download.options();
+        assert_eq!(options.out.as_deref(), Some("file.iso"));
+        assert_eq!(options.referer.as_deref(), Some("https://example.com/"));
+        assert_eq!(options.user_agent.as_deref(), Some("Firefox"));
+        assert_eq!(options.headers, vec!["Cookie: session=abc"]);
+    }
+
+    #[test]
+    fn test_filename_ignored_for_multiple_urls() {
+        let download = BrowserDownload {
+            urls: vec!["https://a.com/1".to_string(), "https://a.com/2".to_string()],
+            filename: Some("name.zip".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(download.options().out, None);
+        assert_eq!(download.describe(), "2 downloads");
+    }
+
+    #[test]
+    fn test_rejects_bad_token() {
+        let mut req = request("POST", "/add", r#"{"url": "https://a.com/x"}"#);
+        req.headers
+            .insert("x-auth-token".to_string(), "wrong".to_string());
+
+        let response = parse_request(&req, &config()).err().unwrap();
+        assert_eq!(response.status, 401);
+    }
+
+    #[test]
+    fn test_routes() {
+        let config = config();
+
+        let req = request("GET", "/ping", "");
+        assert!(matches!(parse_request(&req, &config), Ok(Route::Ping)));
+
+        let req = request("POST", "/add", r#"{"url": "https://a.com/x"}"#);
+        assert!(matches!(parse_request(&req, &config), Ok(Route::Add(_))));
+
+        let req = request("POST", "/add", r#"{"referer": "https://a.com"}"#);
+        assert_eq!(parse_request(&req, &config).err().unwrap().status, 400);
+
+        let req = request("GET", "/add", "");
+        assert_eq!(parse_request(&req, &config).err().unwrap().status, 405);
+
+        let req = request("GET", "/nope", "");
+        assert_eq!(parse_request(&req, &config).err().unwrap().status, 404);
+    }
+}
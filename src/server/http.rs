@@ -0,0 +1,343 @@
+//! Minimal HTTP/1.1 request parsing and response writing
+//!
+//! Only what the local endpoints need: one request per connection,
+//! `Content-Length` bodies and no chunked transfer encoding. Streaming
+//! responses (server-sent events) are written until the sender is dropped.
+
+use serde_json::Value;
+use std::collections::HashMap;
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::sync::mpsc;
+
+const MAX_HEADER_LINES: usize = 100;
+const MAX_BODY_SIZE: usize = 1024 * 1024; // 1 MiB is plenty for a list of URLs
+
+/// A parsed HTTP request
+#[derive(Clone, Debug, Default)]
+pub struct Request {
+    pub method: String,
+    pub path: String,
+    pub query: HashMap<String, String>,
+    pub headers: HashMap<String, String>, // Header names are lowercased
+    pub body: Vec<u8>,
+}
+
+impl Request {
+    /// Get a header value by (case-insensitive) name
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .get(&name.to_ascii_lowercase())
+            .map(|s| s.as_str())
+    }
+
+    /// Token sent as `Authorization: Bearer <token>`, `X-Auth-Token` or `?token=`
+    pub fn token(&self) -> Option<&str> {
+        self.header("authorization")
+            .and_then(|v| v.strip_prefix("Bearer "))
+            .or_else(|| self.header("x-auth-token"))
+            .or_else(|| self.query.get("token").map(|s| s.as_str()))
+    }
+
+    /// Whether the request carries the token `expected`
+    ///
+    /// The comparison takes the same time wherever the tokens differ.
+    pub fn has_token(&self, expected: &str) -> bool {
+        self.token()
+            .is_some_and(|token| constant_time_eq(token.as_bytes(), expected.as_bytes()))
+    }
+}
+
+/// Compare byte strings in time that depends only on their lengths
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+/// An HTTP response
+#[derive(Debug)]
+pub struct Response {
+    pub status: u16,
+    pub content_type: &'static str,
+    pub body: Vec<u8>,
+    pub stream: Option<mpsc::Receiver<Vec<u8>>>, // Chunks written after `body`
+}
+
+impl Response {
+    pub fn json(status: u16, value: &Value) -> Self {
+        Self {
+            status,
+            content_type: "application/json",
+            body: value.to_string().into_bytes(),
+            stream: None,
+        }
+    }
+
+    pub fn text(status: u16, text: &str) -> Self {
+        Self {
+            status,
+            content_type: "text/plain; charset=utf-8",
+            body: text.as_bytes().to_vec(),
+            stream: None,
+        }
+    }
+
+    /// `text/event-stream` response fed by `stream` until it is closed
+    pub fn event_stream(stream: mpsc::Receiver<Vec<u8>>) -> Self {
+        Self {
+            status: 200,
+            content_type: "text/event-stream",
+            body: Vec::new(),
+            stream: Some(stream),
+        }
+    }
+
+    pub fn empty(status: u16) -> Self {
+        Self::text(status, "")
+    }
+
+    /// JSON error body of the form `{"error": "..."}`
+    pub fn error(status: u16, message: &str) -> Self {
+        Self::json(status, &serde_json::json!({ "error": message }))
+    }
+}
+
+/// Read a single request from the connection
+pub async fn read_request<R: AsyncBufRead + Unpin>(
+    reader: &mut R,
+) -> Result<Request, Box<dyn std::error::Error + Send + Sync>> {
+    let mut line = String::new();
+    reader.read_line(&mut line).await?;
+
+    let mut parts = line.split_whitespace();
+    let method = parts.next().ok_or("Empty request line")?.to_uppercase();
+    let target = parts.next().ok_or("Missing request target")?;
+
+    let (path, query) = match target.split_once('?') {
+        Some((path, query)) => (path.to_string(), parse_query(query)),
+        None => (target.to_string(), HashMap::new()),
+    };
+
+    let mut headers = HashMap::new();
+    for _ in 0..MAX_HEADER_LINES {
+        line.clear();
+        if reader.read_line(&mut line).await? == 0 {
+            break;
+        }
+        let trimmed = line.trim_end();
+        if trimmed.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = trimmed.split_once(':') {
+            headers.insert(name.trim().to_ascii_lowercase(), value.trim().to_string());
+        }
+    }
+
+    let content_length: usize = headers
+        .get("content-length")
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(0);
+    if content_length > MAX_BODY_SIZE {
+        return Err("Request body too large".into());
+    }
+
+    let mut body = vec![0; content_length];
+    reader.read_exact(&mut body).await?;
+
+    Ok(Request {
+        method,
+        path,
+        query,
+        headers,
+        body,
+    })
+}
+
+/// Write a response and its body to the connection
+///
+/// Streaming responses have no `Content-Length`; the connection is closed
+/// when the stream ends. CORS headers are only sent for a configured origin.
+pub async fn write_response<W: AsyncWrite + Unpin>(
+    writer: &mut W,
+    response: &mut Response,
+    cors_origin: Option<&str>,
+) -> std::io::Result<()> {
+    let length = match response.stream {
+        Some(_) => "Cache-Control: no-cache\r\n".to_string(),
+        None => format!("Content-Length: {}\r\n", response.body.len()),
+    };
+    let cors = match cors_origin {
+        Some(origin) => format!(
+            "Access-Control-Allow-Origin: {}\r\n\
+             Access-Control-Allow-Headers: Authorization, Content-Type, X-Auth-Token\r\n\
+             Access-Control-Allow-Methods: GET, POST, PUT, DELETE, OPTIONS\r\n",
+            origin
+        ),
+        None => String::new(),
+    };
+    let head = format!(
+        "HTTP/1.1 {} {}\r\n\
+         Content-Type: {}\r\n\
+         {}\
+         {}\
+         Connection: close\r\n\r\n",
+        response.status,
+        reason_phrase(response.status),
+        response.content_type,
+        length,
+        cors
+    );
+    writer.write_all(head.as_bytes()).await?;
+    writer.write_all(&response.body).await?;
+    writer.flush().await?;
+
+    if let Some(stream) = response.stream.as_mut() {
+        while let Some(chunk) = stream.recv().await {
+            writer.write_all(&chunk).await?;
+            writer.flush().await?;
+        }
+    }
+    Ok(())
+}
+
+/// Parse a `key=value&key2=value2` query string
+pub fn parse_query(query: &str) -> HashMap<String, String> {
+    query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| match pair.split_once('=') {
+            Some((k, v)) => (percent_decode(k), percent_decode(v)),
+            None => (percent_decode(pair), String::new()),
+        })
+        .collect()
+}
+
+/// Decode `%XX` escapes and `+` in a query component
+fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        match bytes[i] {
+            b'%' if i + 2 < bytes.len() => {
+                let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).unwrap_or("");
+                match u8::from_str_radix(hex, 16) {
+                    Ok(byte) => {
+                        out.push(byte);
+                        i += 3;
+                        continue;
+                    }
+                    Err(_) => out.push(b'%'),
+                }
+            }
+            b'+' => out.push(b' '),
+            b => out.push(b),
+        }
+        i += 1;
+    }
+
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+fn reason_phrase(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        201 => "Created",
+        202 => "Accepted",
+        204 => "No Content",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        408 => "Request Timeout",
+        500 => "Internal Server Error",
+        _ => "Unknown",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_read_request() {
+        let raw = b"POST /add?token=abc HTTP/1.1\r\nHost: localhost\r\nContent-Type: application/json\r\nContent-Length: 2\r\n\r\n{}";
+        let mut reader = tokio::io::BufReader::new(&raw[..]);
+        let request = read_request(&mut reader).await.unwrap();
+
+        assert_eq!(request.method, "POST");
+        assert_eq!(request.path, "/add");
+        assert_eq!(request.query.get("token").unwrap(), "abc");
+        assert_eq!(request.header("Content-Type"), Some("application/json"));
+        assert_eq!(request.body, b"{}");
+    }
+
+    #[test]
+    fn test_token_sources() {
+        let mut request = Request::default();
+        assert_eq!(request.token(), None);
+
+        request
+            .query
+            .insert("token".to_string(), "from-query".to_string());
+        assert_eq!(request.token(), Some("from-query"));
+
+        request
+            .headers
+            .insert("x-auth-token".to_string(), "from-header".to_string());
+        assert_eq!(request.token(), Some("from-header"));
+
+        request
+            .headers
+            .insert("authorization".to_string(), "Bearer bearer".to_string());
+        assert_eq!(request.token(), Some("bearer"));
+        assert!(request.has_token("bearer"));
+        assert!(!request.has_token("bearer2"));
+        assert!(!request.has_token("bearex"));
+        assert!(!Request::default().has_token(""));
+    }
+
+    #[test]
+    fn test_parse_query() {
+        let query = parse_query("url=https%3A%2F%2Fexample.com%2Fa+b.zip&flag");
+        assert_eq!(query.get("url").unwrap(), "https://example.com/a b.zip");
+        assert_eq!(query.get("flag").unwrap(), "");
+    }
+
+    #[tokio::test]
+    async fn test_write_response() {
+        let mut out = Vec::new();
+        write_response(&mut out, &mut Response::text(200, "ok"), None)
+            .await
+            .unwrap();
+        let text = String::from_utf8(out).unwrap();
+        assert!(text.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(text.contains("Content-Length: 2"));
+        assert!(!text.contains("Access-Control"));
+        assert!(text.ends_with("\r\n\r\nok"));
+
+        let mut out = Vec::new();
+        let origin = Some("chrome-extension://abc");
+        write_response(&mut out, &mut Response::empty(204), origin)
+            .await
+            .unwrap();
+        let text = String::from_utf8(out).unwrap();
+        assert!(text.contains("Access-Control-Allow-Origin: chrome-extension://abc\r\n"));
+    }
+
+    #[tokio::test]
+    async fn test_write_event_stream() {
+        let (tx, rx) = mpsc::channel(4);
+        tx.send(b"data: one\n\n".to_vec()).await.unwrap();
+        tx.send(b"data: two\n\n".to_vec()).await.unwrap();
+        drop(tx);
+
+        let mut out = Vec::new();
+        write_response(&mut out, &mut Response::event_stream(rx), None)
+            .await
+            .unwrap();
+        let text = String::from_utf8(out).unwrap();
+        assert!(text.contains("Content-Type: text/event-stream"));
+        assert!(!text.contains("Content-Length"));
+        assert!(text.ends_with("\r\n\r\ndata: one\n\ndata: two\n\n"));
+    }
+}
@@ -0,0 +1,262 @@
+//! Prometheus metrics exporter
+//!
+//! Serves `GET /metrics` in the Prometheus text exposition format.
+
+use crate::config::MetricsConfig;
+use crate::download_manager::DownloadManager;
+use crate::models::{Download, DownloadState, DownloadType, GlobalStats};
+use crate::server::http::{Request, Response};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::Arc;
+
+const CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+const PREFIX: &str = "tui_downloader_";
+
+/// Run the exporter until the listener fails
+pub async fn run(
+    config: MetricsConfig,
+    download_manager: Arc<DownloadManager>,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let addr = config.address().map_err(|e| e.to_string())?;
+    let token = Arc::new(config.token);
+
+    super::serve(addr, None, move |request| {
+        let token = token.clone();
+        let download_manager = download_manager.clone();
+        async move { handle(request, &token, &download_manager).await }
+    })
+    .await?;
+    Ok(())
+}
+
+async fn handle(request: Request, token: &str, download_manager: &DownloadManager) -> Response {
+    if !token.is_empty() && !request.has_token(token) {
+        return Response::error(401, "Invalid or missing token");
+    }
+    if request.path != "/metrics" {
+        return Response::error(404, "Not found");
+    }
+    if request.method != "GET" {
+        return Response::error(405, "Method not allowed");
+    }
+
+    let stats = download_manager.get_global_stats().await;
+    let downloads = download_manager.get_all_downloads().await;
+    let body = render(&stats, &downloads, download_manager.is_backend_healthy());
+
+    Response {
+        content_type: CONTENT_TYPE,
+        ..Response::text(200, &body)
+    }
+}
+
+/// Render all metrics in the text exposition format
+fn render(stats: &GlobalStats, downloads: &[Download], backend_up: bool) -> String {
+    let mut out = String::new();
+
+    let backend_up = if backend_up { 1 } else { 0 };
+    for (name, kind, help, value) in [
+        (
+            "backend_up",
+            "gauge",
+            "Whether aria2c answered the last poll",
+            backend_up,
+        ),
+        (
+            "global_download_speed_bytes_per_second",
+            "gauge",
+            "Global download speed in bytes per second",
+            stats.download_speed,
+        ),
+        (
+            "global_upload_speed_bytes_per_second",
+            "gauge",
+            "Global upload speed in bytes per second",
+            stats.upload_speed,
+        ),
+        (
+            "downloaded_bytes_total",
+            "counter",
+            "Bytes downloaded since startup",
+            stats.total_downloaded,
+        ),
+        (
+            "uploaded_bytes_total",
+            "counter",
+            "Bytes uploaded since startup",
+            stats.total_uploaded,
+        ),
+    ] {
+        header(&mut out, name, kind, help);
+        sample(&mut out, name, String::new(), value);
+    }
+
+    header(
+        &mut out,
+        "downloads",
+        "gauge",
+        "Number of downloads by state",
+    );
+    for (state, count) in [
+        ("active", stats.num_active),
+        ("waiting", stats.num_waiting),
+        ("stopped", stats.num_stopped),
+    ] {
+        sample(&mut out, "downloads", format!("state=\"{}\"", state), count);
+    }
+
+    // Failed downloads grouped by aria2 exit code
+    let mut errors: BTreeMap<&str, u32> = BTreeMap::new();
+    for download in downloads
+        .iter()
+        .filter(|d| d.status == DownloadState::Error)
+    {
+        let code = download.error_code.as_deref().unwrap_or("unknown");
+        *errors.entry(code).or_default() += 1;
+    }
+    header(
+        &mut out,
+        "errors",
+        "gauge",
+        "Failed downloads by aria2 error code",
+    );
+    for (code, count) in errors {
+        sample(
+            &mut out,
+            "errors",
+            format!("code=\"{}\"", escape(code)),
+            count,
+        );
+    }
+
+    let mut downloads: Vec<&Download> = downloads.iter().filter(|d| d.gid.is_some()).collect();
+    downloads.sort_by(|a, b| a.gid.cmp(&b.gid));
+
+    header(
+        &mut out,
+        "download_progress",
+        "gauge",
+        "Download progress from 0 to 1",
+    );
+    for download in &downloads {
+        sample(
+            &mut out,
+            "download_progress",
+            labels(download),
+            download.progress,
+        );
+    }
+    header(
+        &mut out,
+        "download_speed_bytes_per_second",
+        "gauge",
+        "Download speed in bytes per second",
+    );
+    for download in &downloads {
+        let speed = download.speed_history.last().copied().unwrap_or(0);
+        sample(
+            &mut out,
+            "download_speed_bytes_per_second",
+            labels(download),
+            speed,
+        );
+    }
+
+    out
+}
+
+/// `# HELP` and `# TYPE` lines for a metric
+fn header(out: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {}{} {}", PREFIX, name, help);
+    let _ = writeln!(out, "# TYPE {}{} {}", PREFIX, name, kind);
+}
+
+fn sample(out: &mut String, name: &str, labels: String, value: impl std::fmt::Display) {
+    if labels.is_empty() {
+        let _ = writeln!(out, "{}{} {}", PREFIX, name, value);
+    } else {
+        let _ = writeln!(out, "{}{}{{{}}} {}", PREFIX, name, labels, value);
+    }
+}
+
+fn labels(download: &Download) -> String {
+    let kind = match download.download_type {
+        DownloadType::Http => "http",
+        DownloadType::Torrent => "torrent",
+        DownloadType::Metalink => "metalink",
+    };
+    format!(
+        "gid=\"{}\",name=\"{}\",type=\"{}\"",
+        escape(download.gid.as_deref().unwrap_or_default()),
+        escape(&download.name),
+        kind
+    )
+}
+
+/// Escape a label value
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_test_download(gid: &str, status: DownloadState, progress: f64) -> Download {
+        Download {
+            gid: Some(gid.to_string()),
+            name: format!("{}.iso", gid),
+            status,
+            progress,
+            speed_history: vec![100, 2048],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_render_global_metrics() {
+        let stats = GlobalStats {
+            download_speed: 1000,
+            num_active: 2,
+            num_waiting: 1,
+            total_downloaded: 123456,
+            ..Default::default()
+        };
+
+        let out = render(&stats, &[], false);
+        assert!(out.contains("# TYPE tui_downloader_backend_up gauge\n"));
+        assert!(out.contains("tui_downloader_backend_up 0\n"));
+        assert!(out.contains("tui_downloader_global_download_speed_bytes_per_second 1000\n"));
+        assert!(out.contains("tui_downloader_downloads{state=\"active\"} 2\n"));
+        assert!(out.contains("tui_downloader_downloads{state=\"waiting\"} 1\n"));
+        assert!(out.contains("tui_downloader_downloaded_bytes_total 123456\n"));
+    }
+
+    #[test]
+    fn test_render_per_download_metrics() {
+        let mut failed = create_test_download("b", DownloadState::Error, 0.0);
+        failed.error_code = Some("3".to_string());
+        let downloads = vec![
+            create_test_download("a", DownloadState::Active, 0.5),
+            failed,
+        ];
+
+        let out = render(&GlobalStats::default(), &downloads, true);
+        assert!(out.contains(
+            "tui_downloader_download_progress{gid=\"a\",name=\"a.iso\",type=\"http\"} 0.5\n"
+        ));
+        assert!(out.contains(
+            "tui_downloader_download_speed_bytes_per_second{gid=\"a\",name=\"a.iso\",type=\"http\"} 2048\n"
+        ));
+        assert!(out.contains("tui_downloader_errors{code=\"3\"} 1\n"));
+    }
+
+    #[test]
+    fn test_escape_label() {
+        assert_eq!(escape("a \"b\"\\c\nd"), "a \\\"b\\\"\\\\c\\nd");
+    }
+}
@@ -0,0 +1,75 @@
+//! Local HTTP endpoints
+//!
+//! Small, dependency-free HTTP listeners that expose the download manager
+//! to other programs on the same machine.
+//!
+//! - `http.rs` - Request parsing and response writing
+//! - `api.rs` - REST API and event stream for scripts
+//! - `browser.rs` - Endpoint for "download with tui-downloader" browser extensions
+//! - `metrics.rs` - Prometheus exporter
+
+pub mod api;
+pub mod browser;
+pub mod http;
+pub mod metrics;
+
+use http::{Request, Response};
+use std::future::Future;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::BufReader;
+use tokio::net::TcpListener;
+
+/// How long a client may take to send its request
+const READ_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Accept connections on `addr` and answer each request with `handler`
+///
+/// Runs until the listener fails; each connection is handled on its own task.
+/// CORS headers are only sent when `cors_origin` is set.
+pub async fn serve<H, F>(
+    addr: SocketAddr,
+    cors_origin: Option<String>,
+    handler: H,
+) -> std::io::Result<()>
+where
+    H: Fn(Request) -> F + Send + Sync + 'static,
+    F: Future<Output = Response> + Send + 'static,
+{
+    let listener = TcpListener::bind(addr).await?;
+    let handler = Arc::new(handler);
+    let cors_origin: Arc<Option<String>> = Arc::new(cors_origin);
+
+    loop {
+        let (stream, _) = listener.accept().await?;
+        let handler = handler.clone();
+        let cors_origin = cors_origin.clone();
+
+        tokio::spawn(async move {
+            let (read_half, mut write_half) = stream.into_split();
+            let mut reader = BufReader::new(read_half);
+
+            let request = tokio::time::timeout(READ_TIMEOUT, http::read_request(&mut reader));
+            let mut response = match request.await {
+                Ok(Ok(request)) if request.method == "OPTIONS" => Response::empty(204),
+                Ok(Ok(request)) => handler(request).await,
+                Ok(Err(e)) => Response::error(400, &e.to_string()),
+                Err(_) => Response::error(408, "Request timeout"),
+            };
+
+            let cors_origin = cors_origin.as_deref();
+            let _ = http::write_response(&mut write_half, &mut response, cors_origin).await;
+        });
+    }
+}
+
+/// Origin allowed to call a listener from a web page, if one is configured
+pub fn cors_origin(configured: &str) -> Option<String> {
+    (!configured.is_empty()).then(|| configured.to_string())
+}
+
+/// Loopback address for a local listener
+pub fn loopback(port: u16) -> SocketAddr {
+    SocketAddr::from(([127, 0, 0, 1], port))
+}
@@ -0,0 +1,462 @@
+//! Long-term transfer statistics for the statistics dashboard
+//!
+//! The speed graphs only keep the last minute of polls. [`Stats`]
+//! adds each poll's transfer into per-minute, per-hour and per-day buckets,
+//! keeping a fixed number of each, so older data is kept at a coarser
+//! resolution. Finished downloads are also counted per domain and per
+//! download type. Everything is saved to `stats.json` under the data
+//! directory and can be exported as CSV.
+
+use crate::config::Config;
+use crate::models::{Download, DownloadState};
+use crate::ui::utils::download_type_name;
+use crate::usage::Transfer;
+use chrono::{DateTime, Duration, Local, NaiveDateTime, Timelike};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+const STATS_FILE: &str = "stats.json";
+
+/// Size of the buckets a throughput series is kept in
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Resolution {
+    #[default]
+    Minute,
+    Hour,
+    Day,
+}
+
+impl Resolution {
+    pub const ALL: [Resolution; 3] = [Resolution::Minute, Resolution::Hour, Resolution::Day];
+
+    /// Buckets kept on disk
+    pub fn capacity(self) -> usize {
+        match self {
+            Resolution::Minute => 120,
+            Resolution::Hour => 72,
+            Resolution::Day => 365,
+        }
+    }
+
+    /// Buckets shown in the throughput chart
+    pub fn shown(self) -> usize {
+        match self {
+            Resolution::Minute => 60,
+            Resolution::Hour => 48,
+            Resolution::Day => 30,
+        }
+    }
+
+    /// Length of one bucket
+    pub fn step(self) -> Duration {
+        match self {
+            Resolution::Minute => Duration::minutes(1),
+            Resolution::Hour => Duration::hours(1),
+            Resolution::Day => Duration::days(1),
+        }
+    }
+
+    /// Start of the bucket `time` falls in
+    pub fn bucket(self, time: NaiveDateTime) -> NaiveDateTime {
+        let minute = time
+            .with_second(0)
+            .and_then(|t| t.with_nanosecond(0))
+            .unwrap_or(time);
+        match self {
+            Resolution::Minute => minute,
+            Resolution::Hour => minute.with_minute(0).unwrap_or(minute),
+            Resolution::Day => time.date().and_hms_opt(0, 0, 0).unwrap_or(minute),
+        }
+    }
+
+    /// The next finer or coarser resolution, wrapping around
+    pub fn next(self) -> Self {
+        match self {
+            Resolution::Minute => Resolution::Hour,
+            Resolution::Hour => Resolution::Day,
+            Resolution::Day => Resolution::Minute,
+        }
+    }
+
+    /// Name used in titles and the CSV export
+    pub fn name(self) -> &'static str {
+        match self {
+            Resolution::Minute => "minute",
+            Resolution::Hour => "hour",
+            Resolution::Day => "day",
+        }
+    }
+}
+
+/// Bytes transferred during one bucket
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Sample {
+    pub start: NaiveDateTime, // Local time the bucket starts at
+    #[serde(flatten)]
+    pub transfer: Transfer,
+}
+
+/// Finished downloads from one domain
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct DomainStats {
+    pub bytes: u64, // Downloaded, including what failed downloads got
+    pub completed: u32,
+    pub failed: u32,
+}
+
+impl DomainStats {
+    /// Share of finished downloads that completed (0.0 - 1.0)
+    pub fn success_rate(&self) -> f64 {
+        let finished = self.completed + self.failed;
+        if finished == 0 {
+            0.0
+        } else {
+            self.completed as f64 / finished as f64
+        }
+    }
+}
+
+/// Completed downloads of one type
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct TypeStats {
+    pub downloads: u32,
+    pub bytes: u64,
+    pub seconds: u64, // From being added until completed
+}
+
+impl TypeStats {
+    /// Average speed in bytes per second
+    pub fn average_speed(&self) -> u64 {
+        self.bytes / self.seconds.max(1)
+    }
+}
+
+/// Throughput series and per-domain and per-type totals
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Stats {
+    #[serde(default)]
+    pub minutes: Vec<Sample>,
+    #[serde(default)]
+    pub hours: Vec<Sample>,
+    #[serde(default)]
+    pub days: Vec<Sample>,
+    #[serde(default)]
+    pub domains: BTreeMap<String, DomainStats>,
+    #[serde(default)]
+    pub types: BTreeMap<String, TypeStats>,
+    #[serde(skip)]
+    dirty: bool, // Changed since it was last saved
+}
+
+impl Stats {
+    /// Path of the statistics file
+    pub fn path() -> PathBuf {
+        Config::data_dir().join(STATS_FILE)
+    }
+
+    /// Load the saved statistics (empty if there is no file)
+    pub fn load() -> Result<Self, Box<dyn std::error::Error>> {
+        let path = Self::path();
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = std::fs::read_to_string(&path)?;
+        Self::parse(&content).map_err(|e| format!("{}: {}", path.display(), e).into())
+    }
+
+    /// Parse saved statistics from JSON text
+    pub fn parse(content: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        Ok(serde_json::from_str(content)?)
+    }
+
+    /// JSON text for the statistics file
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap_or_default()
+    }
+
+    /// Write the statistics to disk if they changed
+    pub fn save(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        if !self.dirty {
+            return Ok(());
+        }
+        let path = Self::path();
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(&path, self.to_json())?;
+        self.dirty = false;
+        Ok(())
+    }
+
+    /// Buckets kept at one resolution, oldest first
+    pub fn series(&self, resolution: Resolution) -> &[Sample] {
+        match resolution {
+            Resolution::Minute => &self.minutes,
+            Resolution::Hour => &self.hours,
+            Resolution::Day => &self.days,
+        }
+    }
+
+    fn series_mut(&mut self, resolution: Resolution) -> &mut Vec<Sample> {
+        match resolution {
+            Resolution::Minute => &mut self.minutes,
+            Resolution::Hour => &mut self.hours,
+            Resolution::Day => &mut self.days,
+        }
+    }
+
+    /// Add bytes (downloaded, uploaded) transferred at `now`
+    pub fn record(&mut self, now: NaiveDateTime, (downloaded, uploaded): (u64, u64)) {
+        if downloaded == 0 && uploaded == 0 {
+            return;
+        }
+        for resolution in Resolution::ALL {
+            let start = resolution.bucket(now);
+            let series = self.series_mut(resolution);
+            match series.last_mut() {
+                Some(last) if last.start == start => {
+                    last.transfer.downloaded += downloaded;
+                    last.transfer.uploaded += uploaded;
+                }
+                _ => series.push(Sample {
+                    start,
+                    transfer: Transfer {
+                        downloaded,
+                        uploaded,
+                    },
+                }),
+            }
+            if series.len() > resolution.capacity() {
+                let excess = series.len() - resolution.capacity();
+                series.drain(..excess);
+            }
+        }
+        self.dirty = true;
+    }
+
+    /// Count a download that completed or failed at `finished`
+    pub fn record_finished(&mut self, download: &Download, finished: DateTime<Local>) {
+        let domain = self
+            .domains
+            .entry(domain_of(download.url.as_deref()))
+            .or_default();
+        domain.bytes += download.completed_length;
+        if download.status == DownloadState::Error {
+            domain.failed += 1;
+        } else {
+            domain.completed += 1;
+            let kind = self
+                .types
+                .entry(download_type_name(download).to_string())
+                .or_default();
+            kind.downloads += 1;
+            kind.bytes += download.total_length;
+            kind.seconds += (finished - download.added_at).num_seconds().max(1) as u64;
+        }
+        self.dirty = true;
+    }
+
+    /// The last `count` buckets up to `now`, oldest first, idle ones as zero
+    pub fn window(&self, resolution: Resolution, now: NaiveDateTime, count: usize) -> Vec<Sample> {
+        let series = self.series(resolution);
+        let newest = resolution.bucket(now);
+        (0..count)
+            .rev()
+            .map(|ago| {
+                let start = resolution.bucket(newest - resolution.step() * ago as i32);
+                let transfer = series
+                    .binary_search_by_key(&start, |s| s.start)
+                    .map(|i| series[i].transfer)
+                    .unwrap_or_default();
+                Sample { start, transfer }
+            })
+            .collect()
+    }
+
+    /// Domains with the most bytes downloaded, largest first
+    pub fn top_domains(&self, count: usize) -> Vec<(&str, &DomainStats)> {
+        let mut domains: Vec<(&str, &DomainStats)> = self
+            .domains
+            .iter()
+            .map(|(name, stats)| (name.as_str(), stats))
+            .collect();
+        domains.sort_by_key(|(_, stats)| std::cmp::Reverse(stats.bytes));
+        domains.truncate(count);
+        domains
+    }
+
+    /// CSV files for the export, as (file name, content)
+    pub fn csv_files(&self) -> Vec<(&'static str, String)> {
+        let mut throughput = String::from("resolution,start,downloaded,uploaded\n");
+        for resolution in Resolution::ALL {
+            for sample in self.series(resolution) {
+                throughput.push_str(&format!(
+                    "{},{},{},{}\n",
+                    resolution.name(),
+                    sample.start.format("%Y-%m-%d %H:%M"),
+                    sample.transfer.downloaded,
+                    sample.transfer.uploaded
+                ));
+            }
+        }
+
+        let mut domains = String::from("domain,bytes,completed,failed,success_rate\n");
+        for (name, stats) in self.top_domains(usize::MAX) {
+            domains.push_str(&format!(
+                "{},{},{},{},{:.3}\n",
+                name,
+                stats.bytes,
+                stats.completed,
+                stats.failed,
+                stats.success_rate()
+            ));
+        }
+
+        let mut types = String::from("type,downloads,bytes,average_speed\n");
+        for (name, stats) in &self.types {
+            types.push_str(&format!(
+                "{},{},{},{}\n",
+                name,
+                stats.downloads,
+                stats.bytes,
+                stats.average_speed()
+            ));
+        }
+
+        vec![
+            ("throughput.csv", throughput),
+            ("domains.csv", domains),
+            ("types.csv", types),
+        ]
+    }
+}
+
+/// Host a download came from, or what kind of source it was
+pub fn domain_of(url: Option<&str>) -> String {
+    let Some(url) = url else {
+        return "unknown".to_string();
+    };
+    if url.starts_with("magnet:") {
+        return "magnet".to_string();
+    }
+    let Some((_, rest)) = url.split_once("://") else {
+        return "local".to_string(); // Torrent and metalink files
+    };
+    let authority = rest.split(['/', '?', '#']).next().unwrap_or_default();
+    let host = authority.rsplit('@').next().unwrap_or_default();
+    let host = host.split(':').next().unwrap_or_default();
+    if host.is_empty() {
+        "local".to_string()
+    } else {
+        host.to_lowercase()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::DownloadType;
+    use chrono::NaiveDate;
+
+    fn at(day: u32, hour: u32, minute: u32, second: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2024, 3, day)
+            .unwrap()
+            .and_hms_opt(hour, minute, second)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_record_fills_every_resolution() {
+        let mut stats = Stats::default();
+        stats.record(at(4, 10, 15, 5), (100, 10));
+        stats.record(at(4, 10, 15, 50), (100, 0));
+        stats.record(at(4, 10, 16, 0), (50, 0));
+        stats.record(at(4, 10, 17, 0), (0, 0)); // Idle polls add no bucket
+
+        assert_eq!(stats.minutes.len(), 2);
+        assert_eq!(stats.minutes[0].start, at(4, 10, 15, 0));
+        assert_eq!(stats.minutes[0].transfer.downloaded, 200);
+        assert_eq!(stats.hours.len(), 1);
+        assert_eq!(stats.hours[0].transfer.downloaded, 250);
+        assert_eq!(stats.days[0].start, at(4, 0, 0, 0));
+        assert_eq!(stats.days[0].transfer.uploaded, 10);
+    }
+
+    #[test]
+    fn test_series_keep_their_capacity() {
+        let mut stats = Stats::default();
+        for minute in 0..150 {
+            stats.record(at(4, 0, 0, 0) + Duration::minutes(minute), (1, 0));
+        }
+        assert_eq!(stats.minutes.len(), Resolution::Minute.capacity());
+        assert_eq!(stats.minutes[0].start, at(4, 0, 30, 0));
+        assert_eq!(stats.hours.len(), 3);
+    }
+
+    #[test]
+    fn test_window_fills_idle_buckets() {
+        let mut stats = Stats::default();
+        stats.record(at(4, 10, 0, 0), (100, 0));
+        stats.record(at(4, 12, 30, 0), (300, 0));
+
+        let window = stats.window(Resolution::Hour, at(4, 12, 45, 0), 4);
+        let bytes: Vec<u64> = window.iter().map(|s| s.transfer.downloaded).collect();
+        assert_eq!(bytes, vec![0, 100, 0, 300]);
+        assert_eq!(window[0].start, at(4, 9, 0, 0));
+    }
+
+    #[test]
+    fn test_finished_downloads_by_domain_and_type() {
+        let mut stats = Stats::default();
+        let finished = Local::now();
+        let mut download = Download {
+            url: Some("https://user@Mirror.example.com:8443/a.iso".to_string()),
+            status: DownloadState::Complete,
+            download_type: DownloadType::Http,
+            total_length: 4096,
+            completed_length: 4096,
+            added_at: finished - Duration::seconds(4),
+            ..Default::default()
+        };
+        stats.record_finished(&download, finished);
+        download.status = DownloadState::Error;
+        download.completed_length = 1024;
+        stats.record_finished(&download, finished);
+
+        let domain = &stats.domains["mirror.example.com"];
+        assert_eq!(domain.bytes, 5120);
+        assert_eq!(domain.success_rate(), 0.5);
+        assert_eq!(stats.types["HTTP/HTTPS"].average_speed(), 1024);
+    }
+
+    #[test]
+    fn test_domain_of() {
+        assert_eq!(
+            domain_of(Some("ftp://ftp.example.org/pub")),
+            "ftp.example.org"
+        );
+        assert_eq!(domain_of(Some("magnet:?xt=urn:btih:abc")), "magnet");
+        assert_eq!(domain_of(Some("/home/me/file.torrent")), "local");
+        assert_eq!(domain_of(None), "unknown");
+    }
+
+    #[test]
+    fn test_csv_export() {
+        let mut stats = Stats::default();
+        stats.record(at(4, 10, 15, 0), (100, 10));
+        let files = stats.csv_files();
+        assert_eq!(files[0].0, "throughput.csv");
+        assert!(files[0].1.contains("minute,2024-03-04 10:15,100,10\n"));
+        assert!(files[0].1.contains("day,2024-03-04 00:00,100,10\n"));
+        assert_eq!(files[1].1, "domain,bytes,completed,failed,success_rate\n");
+    }
+
+    #[test]
+    fn test_stats_round_trip_through_json() {
+        let mut stats = Stats::default();
+        stats.record(at(4, 10, 15, 0), (100, 10));
+        let parsed = Stats::parse(&stats.to_json()).unwrap();
+        assert_eq!(parsed.to_json(), stats.to_json());
+    }
+}
@@ -2,20 +2,29 @@
 //!
 //! This module orchestrates all UI components and handles the main rendering logic.
 
-use crate::models::{Download, DownloadType, InputMode};
+use crate::history::{visible_history, HistoryEntry};
+use crate::keymap::Keymap;
+use crate::models::{Download, DownloadState, DownloadType, InputMode, LogEntry};
+use crate::scheduler::Schedule;
+use crate::stats::{Resolution, Stats};
 use crate::ui::theme::{Styles, Theme};
 use crate::ui::utils::{
-    calculate_global_stats, count_by_tab, filter_by_search, filter_by_tab, format_speed,
-    sort_downloads, GlobalStats, SortOrder,
+    calculate_global_stats, count_by_tab, filter_by_tab, format_speed, visible_downloads,
+    GlobalStats, SortOrder, HISTORY_TAB,
 };
 use crate::ui::widgets::downloads_list::render_with_search;
+use crate::ui::widgets::global_stats::{self, quota_gauge};
+use crate::ui::widgets::{history_list, shortcuts, tabs};
 use crate::ui::widgets::{
-    render_details_panel, render_help_popup, render_input_field, render_search_bar,
-    render_speed_limit_popup, render_status_bar, SpeedLimitState,
+    render_command_line, render_context_menu, render_details_panel, render_help_popup,
+    render_input_field, render_log_popup, render_schedule_popup, render_search_bar,
+    render_speed_limit_popup, render_stats_popup, render_status_bar, ContextMenu, ScheduleState,
+    SpeedLimitState,
 };
+use crate::usage::QuotaUse;
 use ratatui::symbols::border;
 use ratatui::text::{Line, Span};
-use ratatui::widgets::{Block, Borders, List, ListState as TabListState, Paragraph};
+use ratatui::widgets::{Block, Borders, Clear, List, ListState as TabListState, Paragraph};
 use ratatui::{
     layout::{Constraint, Direction, Layout, Rect},
     style::{Modifier, Style},
@@ -25,6 +34,10 @@ use ratatui::{
 /// Application version for title banner
 const APP_VERSION: &str = env!("CARGO_PKG_VERSION");
 
+/// Terminal size from which the full layout is used; below it the layout compacts
+pub const COMPACT_WIDTH: u16 = 100;
+pub const COMPACT_HEIGHT: u16 = 30;
+
 /// Main application state for rendering
 pub struct AppState<'a> {
     pub downloads: &'a [Download],
@@ -38,11 +51,28 @@ pub struct AppState<'a> {
     pub sort_ascending: bool,
     pub help_scroll: usize,
     pub speed_limit_state: Option<&'a SpeedLimitState>,
-    #[allow(dead_code)]
     pub download_limit: u64,
-    #[allow(dead_code)]
     pub upload_limit: u64,
-    pub selected_indices: &'a [usize],
+    pub selected_gids: &'a [String], // Downloads marked for batch operations
+    pub busy_gids: &'a [String],     // Downloads with a backend command in flight
+    pub hook_failed_gids: &'a [String], // Downloads whose hook command failed
+    pub log: &'a [LogEntry],
+    pub log_scroll: usize, // Lines scrolled up from the newest log entry
+    pub schedule: Option<&'a Schedule>, // Set while the schedule popup is open
+    pub schedule_state: Option<&'a ScheduleState>,
+    pub schedule_input: &'a str,
+    pub schedule_note: &'a str, // Next scheduled change, shown in the stats bar
+    pub profile_note: &'a str,  // Active speed profile, shown in the stats bar
+    pub quota: &'a [QuotaUse],  // Data-cap use, shown as a gauge in the stats bar
+    pub history: &'a [HistoryEntry], // Finished downloads, listed in the History tab
+    pub stats: Option<(&'a Stats, Resolution)>, // Set while the statistics dashboard is open
+    pub keymap: &'a Keymap,     // Active key bindings, for the help and shortcuts bar
+    pub menu: Option<&'a ContextMenu>, // Context menu of a right-clicked download
+    pub show_details: bool,     // Details overlay open, when too narrow for the side panel
+    pub command_input: &'a str, // Command line being typed after `:`
+    pub command_error: Option<&'a str>, // Why the last command line failed
+    pub command_hint: &'a str,  // Completions offered by Tab
+    pub tick: usize,            // Frame counter for animations
 }
 
 impl<'a> Default for AppState<'a> {
@@ -60,139 +90,311 @@ impl<'a> Default for AppState<'a> {
             speed_limit_state: None,
             download_limit: 0,
             upload_limit: 0,
-            selected_indices: &[],
+            selected_gids: &[],
+            busy_gids: &[],
+            hook_failed_gids: &[],
+            log: &[],
+            log_scroll: 0,
+            schedule: None,
+            schedule_state: None,
+            schedule_input: "",
+            schedule_note: "",
+            profile_note: "",
+            quota: &[],
+            history: &[],
+            stats: None,
+            keymap: Keymap::builtin(),
+            menu: None,
+            show_details: false,
+            command_input: "",
+            command_error: None,
+            command_hint: "",
+            tick: 0,
         }
     }
 }
 
+/// Screen areas of the last frame that react to the mouse
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct HitAreas {
+    pub tabs: Vec<(Rect, usize)>,   // Category tabs and their index
+    pub list: Rect,                 // Downloads or history list, for the wheel
+    pub rows: Vec<(Rect, usize)>,   // Visible list rows and their index in the list
+    pub buttons: Vec<(Rect, bool)>, // Confirmation buttons, true for yes
+    pub menu: Vec<Rect>,            // Context menu items
+}
+
+impl HitAreas {
+    /// Index of the tab at a screen position
+    pub fn tab_at(&self, x: u16, y: u16) -> Option<usize> {
+        find(&self.tabs, x, y)
+    }
+
+    /// Index of the list row at a screen position
+    pub fn row_at(&self, x: u16, y: u16) -> Option<usize> {
+        find(&self.rows, x, y)
+    }
+
+    /// Answer of the confirmation button at a screen position
+    pub fn button_at(&self, x: u16, y: u16) -> Option<bool> {
+        find(&self.buttons, x, y)
+    }
+
+    /// Index of the context menu item at a screen position
+    pub fn menu_item_at(&self, x: u16, y: u16) -> Option<usize> {
+        self.menu.iter().position(|area| contains(*area, x, y))
+    }
+
+    /// Whether a screen position is over the list
+    pub fn in_list(&self, x: u16, y: u16) -> bool {
+        contains(self.list, x, y)
+    }
+}
+
+fn contains(area: Rect, x: u16, y: u16) -> bool {
+    x >= area.left() && x < area.right() && y >= area.top() && y < area.bottom()
+}
+
+fn find<T: Copy>(areas: &[(Rect, T)], x: u16, y: u16) -> Option<T> {
+    areas
+        .iter()
+        .find(|(area, _)| contains(*area, x, y))
+        .map(|(_, value)| *value)
+}
+
 /// Render the complete application UI
-pub fn render(f: &mut Frame, state: AppState, list_state: &mut ratatui::widgets::ListState) {
+///
+/// Returns the areas of the frame that react to the mouse.
+pub fn render(
+    f: &mut Frame,
+    state: AppState,
+    list_state: &mut ratatui::widgets::ListState,
+) -> HitAreas {
+    let mut hits = HitAreas::default();
     let size = f.size();
 
+    // Breakpoints: narrow terminals drop the sidebar and the side-by-side
+    // details, short ones the banner, the input field and the boxed bottom bar
+    let wide = size.width >= COMPACT_WIDTH;
+    let tall = size.height >= COMPACT_HEIGHT;
+    let compact = !wide || !tall;
+
     // Calculate global stats
     let global_stats = calculate_global_stats(state.downloads);
 
     // Main vertical layout: content area + shortcuts + optional status
     let has_status = !state.status_message.is_empty();
+    let mut constraints = vec![Constraint::Min(5)]; // Main content area
+    if compact {
+        constraints.push(Constraint::Length(1)); // Stats line
+        constraints.push(Constraint::Length(1)); // Shortcuts line
+    } else {
+        constraints.push(Constraint::Length(3)); // Shortcuts bar
+    }
+    if has_status {
+        constraints.push(Constraint::Length(1)); // Status message
+    }
     let main_layout = Layout::default()
         .direction(Direction::Vertical)
-        .constraints(if has_status {
-            vec![
-                Constraint::Min(10),   // Main content area
-                Constraint::Length(3), // Shortcuts bar
-                Constraint::Length(1), // Status message
-            ]
-        } else {
-            vec![
-                Constraint::Min(10),   // Main content area
-                Constraint::Length(3), // Shortcuts bar
-            ]
-        })
+        .constraints(constraints)
         .split(size);
 
-    // Horizontal split: left sidebar | main content
-    let horizontal_layout = Layout::default()
-        .direction(Direction::Horizontal)
-        .constraints([
-            Constraint::Length(22), // Left sidebar
-            Constraint::Min(40),    // Main content area
-        ])
-        .split(main_layout[0]);
-
-    // Left sidebar: title banner + category tabs
-    let left_sidebar = Layout::default()
-        .direction(Direction::Vertical)
-        .constraints([
-            Constraint::Length(4), // Title banner
-            Constraint::Min(5),    // Category tabs
-        ])
-        .split(horizontal_layout[0]);
-
-    // Render title banner
-    render_title_banner(f, left_sidebar[0]);
-
-    // Render category tabs in left sidebar
     let mut tab_state = TabListState::default();
     tab_state.select(Some(state.current_tab));
-    render_category_tabs(
-        f,
-        left_sidebar[1],
-        state.downloads,
-        state.current_tab,
-        &mut tab_state,
-    );
+    let content_area = if wide {
+        // Horizontal split: left sidebar | main content
+        let horizontal_layout = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([
+                Constraint::Length(22), // Left sidebar
+                Constraint::Min(40),    // Main content area
+            ])
+            .split(main_layout[0]);
+
+        // Left sidebar: title banner (when there is room) + category tabs
+        let tabs_area = if tall {
+            let left_sidebar = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([
+                    Constraint::Length(4), // Title banner
+                    Constraint::Min(5),    // Category tabs
+                ])
+                .split(horizontal_layout[0]);
+            render_title_banner(f, left_sidebar[0]);
+            left_sidebar[1]
+        } else {
+            horizontal_layout[0]
+        };
+
+        // Render category tabs in left sidebar
+        hits.tabs = render_category_tabs(
+            f,
+            tabs_area,
+            state.downloads,
+            state.history.len(),
+            state.current_tab,
+            &mut tab_state,
+        );
+        horizontal_layout[1]
+    } else {
+        // One-line tab strip above the list
+        let strip_layout = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(1), Constraint::Min(3)])
+            .split(main_layout[0]);
+        let titles = tab_titles(state.downloads, state.history.len());
+        hits.tabs = tabs::render(
+            f,
+            strip_layout[0],
+            state.current_tab,
+            titles.iter().map(String::as_str).collect(),
+        );
+        strip_layout[1]
+    };
 
-    // Right content area: input field + downloads/details
-    let right_content = Layout::default()
-        .direction(Direction::Vertical)
-        .constraints([
-            Constraint::Length(3), // Input field
-            Constraint::Min(5),    // Downloads + Details
-        ])
-        .split(horizontal_layout[1]);
-
-    // Render input field
-    render_input_field(f, right_content[0], state.input_text, state.input_mode);
-
-    // Downloads and details split
-    let content_split = Layout::default()
-        .direction(Direction::Horizontal)
-        .constraints([
-            Constraint::Percentage(45), // Downloads list
-            Constraint::Percentage(55), // Details panel
-        ])
-        .split(right_content[1]);
+    // Right content area: input field (always when tall, else only while typing)
+    let list_area = if tall || state.input_mode == InputMode::Editing {
+        let right_content = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Length(3), // Input field
+                Constraint::Min(3),    // Downloads + Details
+            ])
+            .split(content_area);
+        render_input_field(f, right_content[0], state.input_text, state.input_mode);
+        right_content[1]
+    } else {
+        content_area
+    };
 
-    // Filter and sort downloads
-    let filtered_by_tab = filter_by_tab(state.downloads, state.current_tab);
-    let filtered_downloads = if state.search_query.is_empty() {
-        filtered_by_tab
+    // Downloads and details side by side, or details as a toggled overlay
+    let (list_area, details_area) = if wide {
+        let content_split = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([
+                Constraint::Percentage(45), // Downloads list
+                Constraint::Percentage(55), // Details panel
+            ])
+            .split(list_area);
+        (content_split[0], Some(content_split[1]))
     } else {
-        filter_by_search(&filtered_by_tab, state.search_query)
+        (list_area, state.show_details.then_some(list_area))
     };
 
-    // Sort downloads
-    let mut sorted_downloads = filtered_downloads.clone();
-    sort_downloads(
-        &mut sorted_downloads,
+    // Filter and sort downloads
+    let sorted_downloads = visible_downloads(
+        state.downloads,
+        state.current_tab,
+        state.search_query,
         state.sort_order,
         state.sort_ascending,
     );
+    let history = visible_history(state.history, state.search_query);
 
-    // Render downloads list with search highlighting
-    render_with_search(
-        f,
-        content_split[0],
-        &sorted_downloads,
-        list_state,
-        state.search_query,
-        state.selected_indices,
-    );
+    hits.list = list_area;
+    if state.current_tab == HISTORY_TAB {
+        hits.rows = history_list::render(f, list_area, &history, list_state);
+    } else {
+        // Render downloads list with search highlighting
+        hits.rows = render_with_search(
+            f,
+            list_area,
+            &sorted_downloads,
+            list_state,
+            state.search_query,
+            state.selected_gids,
+            state.busy_gids,
+            state.hook_failed_gids,
+            state.tick,
+        );
+    }
 
-    // Render details panel
-    let selected_download = get_selected_download(state.downloads, &sorted_downloads, list_state);
-    render_details_panel(f, content_split[1], &selected_download);
+    if let Some(details_area) = details_area {
+        // The overlay hides the rows, so they no longer take clicks
+        if !wide {
+            f.render_widget(Clear, details_area);
+            hits.rows.clear();
+        }
+        if state.current_tab == HISTORY_TAB {
+            let selected = list_state
+                .selected()
+                .and_then(|row| history.get(row).copied());
+            history_list::render_details(f, details_area, selected);
+        } else {
+            let selected_download =
+                get_selected_download(state.downloads, &sorted_downloads, list_state);
+            render_details_panel(f, details_area, &selected_download);
+        }
+    }
 
     // Render keyboard shortcuts bar
-    render_shortcuts_bar(f, main_layout[1], state.input_mode, &global_stats);
+    if compact {
+        global_stats::render_compact(
+            f,
+            main_layout[1],
+            &global_stats,
+            state.download_limit,
+            state.upload_limit,
+            state.quota,
+        );
+        render_shortcuts_line(f, main_layout[2], state.input_mode, state.keymap);
+    } else {
+        render_shortcuts_bar(
+            f,
+            main_layout[1],
+            state.input_mode,
+            state.keymap,
+            &global_stats,
+            state.schedule_note,
+            state.profile_note,
+            state.quota,
+        );
+    }
 
     // Render status bar if there's a message
     if has_status {
-        render_status_bar(f, main_layout[2], state.status_message);
+        render_status_bar(f, main_layout[main_layout.len() - 1], state.status_message);
     }
 
     // Render overlays/popups last so they appear on top
 
     // Search bar overlay
     if state.input_mode == InputMode::Search {
-        let result_count = sorted_downloads.len();
-        let total_count = filter_by_tab(state.downloads, state.current_tab).len();
+        let (result_count, total_count) = if state.current_tab == HISTORY_TAB {
+            (history.len(), state.history.len())
+        } else {
+            (
+                sorted_downloads.len(),
+                filter_by_tab(state.downloads, state.current_tab).len(),
+            )
+        };
         render_search_bar(f, size, state.search_query, result_count, total_count);
     }
 
     // Help popup
     if state.input_mode == InputMode::Help {
-        render_help_popup(f, size, state.help_scroll);
+        render_help_popup(f, size, state.help_scroll, state.keymap);
+    }
+
+    // Log popup
+    if state.input_mode == InputMode::Log {
+        render_log_popup(f, size, state.log, state.log_scroll);
+    }
+
+    // Statistics dashboard
+    if let Some((stats, resolution)) = state.stats {
+        render_stats_popup(
+            f,
+            size,
+            stats,
+            resolution,
+            chrono::Local::now().naive_local(),
+        );
+    }
+
+    // Schedule popup
+    if let (Some(schedule), Some(schedule_state)) = (state.schedule, state.schedule_state) {
+        render_schedule_popup(f, size, schedule, schedule_state, state.schedule_input);
     }
 
     // Speed limit popup
@@ -201,6 +403,24 @@ pub fn render(f: &mut Frame, state: AppState, list_state: &mut ratatui::widgets:
             render_speed_limit_popup(f, size, speed_state);
         }
     }
+
+    // Context menu
+    if let Some(menu) = state.menu {
+        hits.menu = render_context_menu(f, size, menu);
+    }
+
+    // Command line
+    if state.input_mode == InputMode::Command {
+        render_command_line(
+            f,
+            size,
+            state.command_input,
+            state.command_error,
+            state.command_hint,
+        );
+    }
+
+    hits
 }
 
 /// Render the title banner with decorative borders
@@ -218,27 +438,27 @@ fn render_title_banner(f: &mut Frame, area: Rect) {
             horizontal_top: "*",
             horizontal_bottom: "*",
         })
-        .border_style(Style::default().fg(Theme::SECONDARY));
+        .border_style(Style::default().fg(Theme::current().secondary));
 
     let title_text = vec![
         Line::from(vec![
             Span::styled(
                 "TUI",
                 Style::default()
-                    .fg(Theme::HIGHLIGHT)
+                    .fg(Theme::current().highlight)
                     .add_modifier(Modifier::BOLD),
             ),
             Span::styled(
                 " Downloader",
                 Style::default()
-                    .fg(Theme::TEXT)
+                    .fg(Theme::current().text)
                     .add_modifier(Modifier::BOLD),
             ),
         ]),
         Line::from(vec![Span::styled(
             format!("v{}", APP_VERSION),
             Style::default()
-                .fg(Theme::TEXT_MUTED)
+                .fg(Theme::current().text_muted)
                 .add_modifier(Modifier::ITALIC),
         )]),
     ];
@@ -255,9 +475,10 @@ fn render_category_tabs(
     f: &mut Frame,
     area: Rect,
     downloads: &[Download],
+    history_count: usize,
     current_tab: usize,
     tab_state: &mut TabListState,
-) {
+) -> Vec<(Rect, usize)> {
     let active_count = count_by_tab(downloads, 0);
     let queue_count = count_by_tab(downloads, 1);
     let completed_count = count_by_tab(downloads, 2);
@@ -266,6 +487,7 @@ fn render_category_tabs(
         format_tab_item("Active", active_count, 0, current_tab),
         format_tab_item("Queue", queue_count, 1, current_tab),
         format_tab_item("Completed", completed_count, 2, current_tab),
+        format_tab_item("History", history_count, HISTORY_TAB, current_tab),
     ];
 
     let tab_list = List::new(tabs)
@@ -278,78 +500,114 @@ fn render_category_tabs(
         )
         .highlight_style(
             Style::default()
-                .fg(Theme::HIGHLIGHT)
+                .fg(Theme::current().highlight)
                 .add_modifier(Modifier::BOLD),
         )
         .highlight_symbol(">> ");
 
+    let inner = Block::default().borders(Borders::ALL).inner(area);
     f.render_stateful_widget(tab_list, area, tab_state);
+
+    // One line per tab, from the first one scrolled into view
+    let offset = tab_state.offset();
+    (offset..=HISTORY_TAB)
+        .map(|tab| {
+            let y = inner.y + (tab - offset) as u16;
+            (Rect::new(inner.x, y, inner.width, 1), tab)
+        })
+        .take_while(|(row, _)| row.bottom() <= inner.bottom())
+        .collect()
+}
+
+/// Titles of the one-line tab strip, e.g. `Active 2`
+fn tab_titles(downloads: &[Download], history_count: usize) -> Vec<String> {
+    vec![
+        format!("Active {}", count_by_tab(downloads, 0)),
+        format!("Queue {}", count_by_tab(downloads, 1)),
+        format!("Completed {}", count_by_tab(downloads, 2)),
+        format!("History {}", history_count),
+    ]
 }
 
 /// Format a tab item with count
 fn format_tab_item(name: &str, count: usize, index: usize, current: usize) -> Line<'static> {
     let style = if index == current {
         Style::default()
-            .fg(Theme::HIGHLIGHT)
+            .fg(Theme::current().highlight)
             .add_modifier(Modifier::BOLD)
     } else {
-        Style::default().fg(Theme::TEXT_MUTED)
+        Style::default().fg(Theme::current().text_muted)
     };
 
     let icon = match index {
         0 => ">", // Active - playing
         1 => "o", // Queue - waiting
         2 => "*", // Completed - done
+        3 => "~", // History - past runs
         _ => "-",
     };
 
     Line::from(vec![
         Span::styled(format!("{} ", icon), style),
-        Span::styled(format!("{}", name), style),
+        Span::styled(name.to_string(), style),
         Span::styled(
             format!(" ({})", count),
-            Style::default().fg(Theme::TEXT_MUTED),
+            Style::default().fg(Theme::current().text_muted),
         ),
     ])
 }
 
 /// Render keyboard shortcuts bar at the bottom
-fn render_shortcuts_bar(f: &mut Frame, area: Rect, mode: InputMode, stats: &GlobalStats) {
-    let (scope_name, shortcuts) = get_shortcuts_for_mode(mode);
+#[allow(clippy::too_many_arguments)]
+fn render_shortcuts_bar(
+    f: &mut Frame,
+    area: Rect,
+    mode: InputMode,
+    keymap: &Keymap,
+    stats: &GlobalStats,
+    schedule_note: &str,
+    profile_note: &str,
+    quota: &[QuotaUse],
+) {
+    let (scope_name, shortcuts) = shortcuts::resolve(mode, keymap);
 
     // Build shortcut spans
     let mut spans: Vec<Span> = vec![Span::styled(" ", Styles::text_muted())];
+    spans.extend(shortcut_spans(&shortcuts));
 
-    for (i, (key, desc)) in shortcuts.iter().enumerate() {
-        spans.push(Span::styled(
-            format!("{}", key),
-            Style::default()
-                .fg(Theme::SECONDARY)
-                .add_modifier(Modifier::BOLD),
-        ));
-        spans.push(Span::styled(format!(" {} ", desc), Styles::text_muted()));
-
-        if i < shortcuts.len() - 1 {
-            spans.push(Span::styled(" ", Styles::text_muted()));
-        }
+    // Add the speed profile, the next scheduled change and global speed info on the right
+    let mut schedule_info = String::new();
+    if !profile_note.is_empty() {
+        schedule_info.push_str(&format!("  Limits: {} |", profile_note));
+    }
+    if !schedule_note.is_empty() {
+        schedule_info.push_str(&format!("  Next: {} |", schedule_note));
     }
-
-    // Add global speed info on the right
     let speed_info = format!(
-        "  D: {} | U: {}",
+        "{}  D: {} | U: {}",
+        schedule_info,
         format_speed(stats.total_download_speed),
         format_speed(stats.total_upload_speed)
     );
+    let gauge = quota_gauge(quota);
 
     // Calculate padding
     let left_text: String = spans.iter().map(|s| s.content.to_string()).collect();
     let left_len = left_text.chars().count();
-    let speed_len = speed_info.chars().count();
+    let gauge_len = gauge.as_ref().map_or(0, |g| g.content.chars().count() + 2);
+    let speed_len = speed_info.chars().count() + gauge_len;
     let total_width = area.width.saturating_sub(4) as usize; // Account for borders
     let padding = total_width.saturating_sub(left_len + speed_len);
 
     spans.push(Span::styled(" ".repeat(padding), Styles::text_muted()));
-    spans.push(Span::styled(speed_info, Style::default().fg(Theme::INFO)));
+    if let Some(gauge) = gauge {
+        spans.push(Span::styled("  ", Styles::text_muted()));
+        spans.push(gauge);
+    }
+    spans.push(Span::styled(
+        speed_info,
+        Style::default().fg(Theme::current().info),
+    ));
 
     let shortcuts_paragraph = Paragraph::new(Line::from(spans)).block(
         Block::default()
@@ -362,55 +620,32 @@ fn render_shortcuts_bar(f: &mut Frame, area: Rect, mode: InputMode, stats: &Glob
     f.render_widget(shortcuts_paragraph, area);
 }
 
-/// Get shortcuts based on input mode (returns scope name and key-description pairs)
-fn get_shortcuts_for_mode(mode: InputMode) -> (&'static str, Vec<(&'static str, &'static str)>) {
-    match mode {
-        InputMode::Editing => (
-            "Add Download",
-            vec![("Enter", "submit"), ("Esc", "cancel"), ("Ctrl+U", "clear")],
-        ),
-        InputMode::Search => (
-            "Search",
-            vec![
-                ("Enter", "apply"),
-                ("Esc", "clear"),
-                ("Backspace", "delete"),
-            ],
-        ),
-        InputMode::SpeedLimit => (
-            "Speed Limit",
-            vec![
-                ("Tab", "switch"),
-                ("j/k", "adjust"),
-                ("Enter", "apply"),
-                ("Esc", "cancel"),
-            ],
-        ),
-        InputMode::Help => (
-            "Help",
-            vec![("j/k", "scroll"), ("Esc", "close"), ("q", "close")],
-        ),
-        InputMode::Confirmation => (
-            "Confirm",
-            vec![("y", "yes"), ("n", "no"), ("Esc", "cancel")],
-        ),
-        InputMode::Settings => (
-            "Settings",
-            vec![("j/k", "navigate"), ("Enter", "edit"), ("Esc", "close")],
-        ),
-        InputMode::Normal => (
-            "Downloads",
-            vec![
-                ("i", "add"),
-                ("/", "search"),
-                ("Space", "pause"),
-                ("d", "delete"),
-                ("1-3", "tabs"),
-                ("?", "help"),
-                ("q", "quit"),
-            ],
-        ),
+/// Render the keyboard shortcuts on one unboxed line, for small terminals
+fn render_shortcuts_line(f: &mut Frame, area: Rect, mode: InputMode, keymap: &Keymap) {
+    let (scope_name, shortcuts) = shortcuts::resolve(mode, keymap);
+
+    let mut spans = vec![Span::styled(format!("[{}] ", scope_name), Styles::border())];
+    spans.extend(shortcut_spans(&shortcuts));
+    f.render_widget(Paragraph::new(Line::from(spans)), area);
+}
+
+/// Key and description spans of the shortcuts
+fn shortcut_spans(shortcuts: &[(String, &str)]) -> Vec<Span<'static>> {
+    let mut spans = Vec::new();
+    for (i, (key, desc)) in shortcuts.iter().enumerate() {
+        spans.push(Span::styled(
+            key.to_string(),
+            Style::default()
+                .fg(Theme::current().secondary)
+                .add_modifier(Modifier::BOLD),
+        ));
+        spans.push(Span::styled(format!(" {} ", desc), Styles::text_muted()));
+
+        if i < shortcuts.len() - 1 {
+            spans.push(Span::styled(" ", Styles::text_muted()));
+        }
     }
+    spans
 }
 
 /// Get the currently selected download or a placeholder
@@ -438,7 +673,7 @@ fn create_placeholder_download() -> Download {
         url: None,
         progress: 0.0,
         speed: "N/A".to_string(),
-        status: "IDLE".to_string(),
+        status: DownloadState::Waiting,
         total_length: 0,
         completed_length: 0,
         download_type: DownloadType::Http,
@@ -448,11 +683,18 @@ fn create_placeholder_download() -> Download {
         connections: 0,
         file_path: None,
         error_message: None,
-        added_at: std::time::Instant::now(),
+        error_code: None,
+        uploaded_length: 0,
+        added_at: chrono::Local::now(),
         seeds: 0,
         peers: 0,
         bitfield: None,
         num_pieces: 0,
+        options: Default::default(),
+        retry_attempts: 0,
+        next_retry: None,
+        stalled: false,
+        slow_since: None,
     }
 }
 
@@ -460,14 +702,14 @@ fn create_placeholder_download() -> Download {
 mod tests {
     use super::*;
 
-    fn create_test_download(name: &str, status: &str, progress: f64) -> Download {
+    fn create_test_download(name: &str, status: DownloadState, progress: f64) -> Download {
         Download {
             gid: Some(format!("gid_{}", name)),
             name: name.to_string(),
             url: None,
             progress,
             speed: "0 B/s".to_string(),
-            status: status.to_string(),
+            status,
             total_length: 1024,
             completed_length: (1024.0 * progress) as u64,
             download_type: DownloadType::Http,
@@ -477,11 +719,18 @@ mod tests {
             connections: 0,
             file_path: None,
             error_message: None,
-            added_at: std::time::Instant::now(),
+            error_code: None,
+            uploaded_length: 0,
+            added_at: chrono::Local::now(),
             seeds: 0,
             peers: 0,
             bitfield: None,
             num_pieces: 0,
+            options: Default::default(),
+            retry_attempts: 0,
+            next_retry: None,
+            stalled: false,
+            slow_since: None,
         }
     }
 
@@ -489,7 +738,7 @@ mod tests {
     fn test_placeholder_download() {
         let placeholder = create_placeholder_download();
         assert_eq!(placeholder.name, "No downloads");
-        assert_eq!(placeholder.status, "IDLE");
+        assert_eq!(placeholder.status, DownloadState::Waiting);
         assert_eq!(placeholder.progress, 0.0);
     }
 
@@ -507,8 +756,8 @@ mod tests {
     #[test]
     fn test_get_selected_download_with_selection() {
         let downloads = vec![
-            create_test_download("file1.txt", "ACTIVE", 0.5),
-            create_test_download("file2.txt", "WAITING", 0.0),
+            create_test_download("file1.txt", DownloadState::Active, 0.5),
+            create_test_download("file2.txt", DownloadState::Waiting, 0.0),
         ];
         let filtered: Vec<&Download> = downloads.iter().collect();
         let mut state = ratatui::widgets::ListState::default();
@@ -520,7 +769,11 @@ mod tests {
 
     #[test]
     fn test_get_selected_download_out_of_bounds() {
-        let downloads = vec![create_test_download("file1.txt", "ACTIVE", 0.5)];
+        let downloads = vec![create_test_download(
+            "file1.txt",
+            DownloadState::Active,
+            0.5,
+        )];
         let filtered: Vec<&Download> = vec![];
         let mut state = ratatui::widgets::ListState::default();
         state.select(Some(10));
@@ -561,12 +814,18 @@ mod tests {
             InputMode::Search,
             InputMode::SpeedLimit,
             InputMode::Help,
+            InputMode::Log,
+            InputMode::Stats,
+            InputMode::Schedule,
+            InputMode::ScheduleEdit,
             InputMode::Confirmation,
+            InputMode::Menu,
+            InputMode::Command,
             InputMode::Settings,
         ];
 
         for mode in modes {
-            let (scope, shortcuts) = get_shortcuts_for_mode(mode);
+            let (scope, shortcuts) = shortcuts::resolve(mode, &Keymap::default());
             assert!(
                 !scope.is_empty(),
                 "Mode {:?} should have a scope name",
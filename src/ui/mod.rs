@@ -6,7 +6,7 @@
 //!
 //! - `app.rs` - Main application rendering orchestration
 //! - `widgets/` - Reusable UI components
-//! - `theme.rs` - Consistent color scheme and styles
+//! - `theme.rs` - Themes loaded from TOML, and the styles built from them
 //! - `utils.rs` - Helper functions for filtering, sorting, and formatting
 //!
 //! # Features
@@ -15,7 +15,9 @@
 //! - Search/filter functionality
 //! - Sorting by name, size, progress, speed, status
 //! - Help popup with all keybindings
+//! - Statistics dashboard with long-term throughput charts
 //! - Speed limit configuration popup
+//! - Schedule popup for queue rules and download timers
 //! - ETA (estimated time of arrival) display
 //! - Enhanced torrent info (seeds/peers)
 //!
@@ -48,13 +50,18 @@ pub mod utils;
 pub mod widgets;
 
 // Re-export main rendering function and types
-pub use app::AppState;
+pub use app::{AppState, HitAreas};
 
 // Re-export utilities used by main
-pub use utils::{filter_by_tab, format_speed, SortOrder};
+pub use utils::{
+    filter_by_search, format_speed, visible_downloads, SortOrder, COMPLETED_TAB, HISTORY_TAB,
+};
 
 // Re-export popup functions and types
-pub use widgets::{render_popup, render_size_warning, PopupType, SpeedLimitState};
+pub use widgets::{
+    render_popup, render_size_warning, LimitScope, PopupType, ScheduleEdit, ScheduleState,
+    SpeedLimitState,
+};
 
 /// Main render function for the application
 ///
@@ -127,12 +134,14 @@ pub fn render_app(
 /// * `f` - Frame to render to
 /// * `state` - Complete application state
 /// * `list_state` - Mutable list state for selection tracking
+///
+/// Returns the areas of the frame that react to the mouse.
 pub fn render_app_full(
     f: &mut ratatui::Frame,
     state: AppState,
     list_state: &mut ratatui::widgets::ListState,
-) {
-    app::render(f, state, list_state);
+) -> HitAreas {
+    app::render(f, state, list_state)
 }
 
 #[cfg(test)]
@@ -152,6 +161,7 @@ mod tests {
     #[test]
     fn test_app_state_with_values() {
         let downloads: Vec<crate::models::Download> = vec![];
+        let gids = vec!["a".to_string(), "b".to_string(), "c".to_string()];
         let state = AppState {
             downloads: &downloads,
             current_tab: 1,
@@ -165,7 +175,26 @@ mod tests {
             speed_limit_state: None,
             download_limit: 1024,
             upload_limit: 512,
-            selected_indices: &[0, 1, 2],
+            selected_gids: &gids,
+            busy_gids: &[],
+            hook_failed_gids: &[],
+            log: &[],
+            log_scroll: 0,
+            schedule: None,
+            schedule_state: None,
+            schedule_input: "",
+            schedule_note: "",
+            profile_note: "",
+            quota: &[],
+            history: &[],
+            stats: None,
+            keymap: crate::keymap::Keymap::builtin(),
+            menu: None,
+            show_details: false,
+            command_input: "",
+            command_error: None,
+            command_hint: "",
+            tick: 0,
         };
 
         assert_eq!(state.current_tab, 1);
@@ -176,7 +205,7 @@ mod tests {
         assert_eq!(state.help_scroll, 5);
         assert_eq!(state.download_limit, 1024);
         assert_eq!(state.upload_limit, 512);
-        assert_eq!(state.selected_indices.len(), 3);
+        assert_eq!(state.selected_gids.len(), 3);
     }
 
     #[test]
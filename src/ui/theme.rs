@@ -1,50 +1,425 @@
-//! Theme and styling constants for the TUI
+//! Themes and common styles for the TUI
+//!
+//! Themes are TOML files naming a color for each role:
+//!
+//! ```toml
+//! extends = "light"        # roles not listed here come from this theme
+//!
+//! [colors]
+//! highlight = "#d7005f"
+//! text_muted = "gray"
+//! ```
+//!
+//! Colors are `#rrggbb`, a 256-color index or a basic color name. The dark,
+//! light and high-contrast presets are built in; files in the `themes`
+//! directory next to the configuration add more. Colors are reduced to what
+//! the terminal supports, and `NO_COLOR` turns them off.
 
+use crate::models::DownloadState;
+use indexmap::IndexMap;
 use ratatui::style::{Color, Modifier, Style};
+use serde::Deserialize;
+use std::cell::Cell;
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::str::FromStr;
 
-/// Application color scheme
-pub struct Theme;
+/// Built-in themes, by name
+const PRESETS: &[(&str, &str)] = &[
+    ("dark", include_str!("themes/dark.toml")),
+    ("light", include_str!("themes/light.toml")),
+    ("high-contrast", include_str!("themes/high-contrast.toml")),
+];
 
-impl Theme {
+/// Theme that user themes extend unless they name another
+const DEFAULT_THEME: &str = "dark";
+
+/// Role names in `[colors]`, in the order of [`Theme::colors_mut`]
+const ROLES: [&str; 26] = [
+    "primary",
+    "secondary",
+    "success",
+    "warning",
+    "error",
+    "info",
+    "border",
+    "border_focused",
+    "text",
+    "text_muted",
+    "background",
+    "status_active",
+    "status_paused",
+    "status_waiting",
+    "status_complete",
+    "status_error",
+    "status_seeding",
+    "status_verifying",
+    "status_removed",
+    "status_idle",
+    "progress_active",
+    "progress_paused",
+    "progress_complete",
+    "highlight",
+    "selected",
+    "cmd_color",
+];
+
+thread_local! {
+    // The UI is drawn from one thread; tests each get their own theme
+    static CURRENT: Cell<Theme> = Cell::new(Theme::preset(DEFAULT_THEME));
+}
+
+/// Application color scheme
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Theme {
     // Primary colors
-    #[allow(dead_code)]
-    pub const PRIMARY: Color = Color::Rgb(139, 233, 253);
-    pub const SECONDARY: Color = Color::Rgb(255, 255, 85);
-    pub const SUCCESS: Color = Color::Rgb(5, 255, 55);
-    pub const WARNING: Color = Color::Rgb(255, 199, 119);
-    pub const ERROR: Color = Color::Rgb(199, 55, 44);
-    pub const INFO: Color = Color::LightBlue;
+    pub primary: Color,
+    pub secondary: Color,
+    pub success: Color,
+    pub warning: Color,
+    pub error: Color,
+    pub info: Color,
 
     // UI element colors
-    pub const BORDER: Color = Color::Gray;
-    pub const BORDER_FOCUSED: Color = Color::LightBlue;
-    pub const TEXT: Color = Color::Rgb(204, 224, 208);
-    pub const TEXT_MUTED: Color = Color::DarkGray;
-    pub const BACKGROUND: Color = Color::Black;
+    pub border: Color,
+    pub border_focused: Color,
+    pub text: Color,
+    pub text_muted: Color,
+    pub background: Color,
 
     // Download status colors
-    pub const STATUS_ACTIVE: Color = Color::Rgb(5, 255, 55);
-    pub const STATUS_PAUSED: Color = Color::Rgb(255, 255, 85);
-    pub const STATUS_WAITING: Color = Color::LightBlue;
-    pub const STATUS_COMPLETE: Color = Color::Blue;
-    pub const STATUS_ERROR: Color = Color::Rgb(199, 55, 44);
-    #[allow(dead_code)]
-    pub const STATUS_IDLE: Color = Color::DarkGray;
+    pub status_active: Color,
+    pub status_paused: Color,
+    pub status_waiting: Color,
+    pub status_complete: Color,
+    pub status_error: Color,
+    pub status_seeding: Color,
+    pub status_verifying: Color,
+    pub status_removed: Color,
+    pub status_idle: Color,
 
     // Progress colors
-    #[allow(dead_code)]
-    pub const PROGRESS_ACTIVE: Color = Color::Rgb(5, 255, 55);
-    #[allow(dead_code)]
-    pub const PROGRESS_PAUSED: Color = Color::Rgb(255, 255, 85);
-    #[allow(dead_code)]
-    pub const PROGRESS_COMPLETE: Color = Color::Blue;
+    pub progress_active: Color,
+    pub progress_paused: Color,
+    pub progress_complete: Color,
 
     // Highlight colors
-    pub const HIGHLIGHT: Color = Color::Rgb(255, 255, 85);
-    pub const SELECTED: Color = Color::LightBlue;
+    pub highlight: Color,
+    pub selected: Color,
 
     // Category colors
-    pub const CMD_COLOR: Color = Color::Rgb(204, 224, 208);
+    pub cmd_color: Color,
+}
+
+/// A theme file
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct ThemeFile {
+    extends: Option<String>,
+    colors: BTreeMap<String, String>,
+}
+
+impl Theme {
+    /// The theme the UI is drawn with
+    pub fn current() -> Theme {
+        CURRENT.with(Cell::get)
+    }
+
+    /// Draw the UI with `theme` from now on
+    pub fn set(theme: Theme) {
+        CURRENT.with(|current| current.set(theme));
+    }
+
+    /// A built-in theme
+    fn preset(name: &str) -> Theme {
+        let (_, text) = PRESETS
+            .iter()
+            .find(|(preset, _)| *preset == name)
+            .expect("unknown preset");
+        let file: ThemeFile = toml::from_str(text).expect("invalid preset");
+        Theme::default()
+            .with_colors(&file.colors)
+            .expect("invalid preset")
+    }
+
+    /// This theme with the roles in `colors` replaced
+    fn with_colors(mut self, colors: &BTreeMap<String, String>) -> Result<Theme, String> {
+        for (role, value) in colors {
+            let index = ROLES
+                .iter()
+                .position(|r| r == role)
+                .ok_or_else(|| format!("unknown color role '{}'", role))?;
+            *self.colors_mut()[index] =
+                Color::from_str(value).map_err(|_| format!("invalid color '{}'", value))?;
+        }
+        Ok(self)
+    }
+
+    fn colors_mut(&mut self) -> [&mut Color; 26] {
+        [
+            &mut self.primary,
+            &mut self.secondary,
+            &mut self.success,
+            &mut self.warning,
+            &mut self.error,
+            &mut self.info,
+            &mut self.border,
+            &mut self.border_focused,
+            &mut self.text,
+            &mut self.text_muted,
+            &mut self.background,
+            &mut self.status_active,
+            &mut self.status_paused,
+            &mut self.status_waiting,
+            &mut self.status_complete,
+            &mut self.status_error,
+            &mut self.status_seeding,
+            &mut self.status_verifying,
+            &mut self.status_removed,
+            &mut self.status_idle,
+            &mut self.progress_active,
+            &mut self.progress_paused,
+            &mut self.progress_complete,
+            &mut self.highlight,
+            &mut self.selected,
+            &mut self.cmd_color,
+        ]
+    }
+
+    /// This theme with every color reduced to what `support` can show
+    pub fn adapted(mut self, support: ColorSupport) -> Theme {
+        for color in self.colors_mut() {
+            *color = support.adapt(*color);
+        }
+        self
+    }
+
+    /// Color for a download state
+    pub fn status_color(&self, state: DownloadState) -> Color {
+        match state {
+            DownloadState::Active => self.status_active,
+            DownloadState::Paused => self.status_paused,
+            DownloadState::Waiting => self.status_waiting,
+            DownloadState::Seeding => self.status_seeding,
+            DownloadState::Complete => self.status_complete,
+            DownloadState::Error => self.status_error,
+            DownloadState::Removed => self.status_removed,
+            DownloadState::Verifying => self.status_verifying,
+        }
+    }
+
+    /// Color for a progress bar
+    pub fn progress_color(&self, progress: f64, state: DownloadState) -> Color {
+        if progress >= 1.0 {
+            self.progress_complete
+        } else if state == DownloadState::Paused {
+            self.progress_paused
+        } else {
+            self.progress_active
+        }
+    }
+}
+
+/// Colors a terminal can show
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ColorSupport {
+    /// `NO_COLOR` is set or the terminal is dumb
+    None,
+    Ansi16,
+    Ansi256,
+    TrueColor,
+}
+
+/// The basic colors with xterm's default values, for picking the closest
+const ANSI16: [(Color, (u8, u8, u8)); 16] = [
+    (Color::Black, (0, 0, 0)),
+    (Color::Red, (205, 0, 0)),
+    (Color::Green, (0, 205, 0)),
+    (Color::Yellow, (205, 205, 0)),
+    (Color::Blue, (0, 0, 238)),
+    (Color::Magenta, (205, 0, 205)),
+    (Color::Cyan, (0, 205, 205)),
+    (Color::Gray, (229, 229, 229)),
+    (Color::DarkGray, (127, 127, 127)),
+    (Color::LightRed, (255, 0, 0)),
+    (Color::LightGreen, (0, 255, 0)),
+    (Color::LightYellow, (255, 255, 0)),
+    (Color::LightBlue, (92, 92, 255)),
+    (Color::LightMagenta, (255, 0, 255)),
+    (Color::LightCyan, (0, 255, 255)),
+    (Color::White, (255, 255, 255)),
+];
+
+/// Channel values of the 6x6x6 cube in the 256-color palette
+const CUBE: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+impl ColorSupport {
+    /// Support of the terminal the app runs in
+    pub fn detect() -> Self {
+        let var = |name| std::env::var(name).ok();
+        Self::from_env(
+            var("NO_COLOR").as_deref(),
+            var("COLORTERM").as_deref(),
+            var("TERM").as_deref(),
+        )
+    }
+
+    /// Support given the `NO_COLOR`, `COLORTERM` and `TERM` variables
+    pub fn from_env(no_color: Option<&str>, colorterm: Option<&str>, term: Option<&str>) -> Self {
+        if no_color.is_some_and(|v| !v.is_empty()) || term == Some("dumb") {
+            ColorSupport::None
+        } else if matches!(colorterm, Some("truecolor" | "24bit")) {
+            ColorSupport::TrueColor
+        } else if term.is_some_and(|t| t.contains("256color")) {
+            ColorSupport::Ansi256
+        } else {
+            ColorSupport::Ansi16
+        }
+    }
+
+    /// The closest color this support can show
+    pub fn adapt(self, color: Color) -> Color {
+        match (self, color) {
+            (ColorSupport::None, _) => Color::Reset,
+            (ColorSupport::Ansi256, Color::Rgb(r, g, b)) => Color::Indexed(nearest_256(r, g, b)),
+            (ColorSupport::Ansi16, Color::Rgb(r, g, b)) => nearest_16((r, g, b)),
+            (ColorSupport::Ansi16, Color::Indexed(i)) => nearest_16(indexed_rgb(i)),
+            (_, color) => color,
+        }
+    }
+}
+
+fn distance(a: (u8, u8, u8), b: (u8, u8, u8)) -> u32 {
+    let d = |x: u8, y: u8| (x as i32 - y as i32).pow(2) as u32;
+    d(a.0, b.0) + d(a.1, b.1) + d(a.2, b.2)
+}
+
+fn nearest_16(rgb: (u8, u8, u8)) -> Color {
+    ANSI16
+        .iter()
+        .min_by_key(|(_, value)| distance(rgb, *value))
+        .map(|(color, _)| *color)
+        .unwrap_or(Color::Reset)
+}
+
+/// Closest entry of the color cube or the gray ramp
+fn nearest_256(r: u8, g: u8, b: u8) -> u8 {
+    let level = |v: u8| {
+        (0..CUBE.len())
+            .min_by_key(|&i| (CUBE[i] as i32 - v as i32).abs())
+            .unwrap_or(0)
+    };
+    let (ri, gi, bi) = (level(r), level(g), level(b));
+    let cube = (16 + 36 * ri + 6 * gi + bi) as u8;
+
+    let average = (r as u32 + g as u32 + b as u32) / 3;
+    let gray = (232 + (average.saturating_sub(3) / 10).min(23)) as u8;
+
+    if distance((r, g, b), indexed_rgb(gray)) < distance((r, g, b), indexed_rgb(cube)) {
+        gray
+    } else {
+        cube
+    }
+}
+
+/// Default value of a 256-color palette entry
+fn indexed_rgb(index: u8) -> (u8, u8, u8) {
+    match index {
+        0..=15 => ANSI16[index as usize].1,
+        16..=231 => {
+            let i = (index - 16) as usize;
+            (CUBE[i / 36], CUBE[i / 6 % 6], CUBE[i % 6])
+        }
+        _ => {
+            let v = 8 + 10 * (index - 232);
+            (v, v, v)
+        }
+    }
+}
+
+/// Built-in and user themes, by name
+pub struct Themes {
+    themes: IndexMap<String, Theme>,
+    support: ColorSupport,
+}
+
+impl Default for Themes {
+    fn default() -> Self {
+        Self::new(ColorSupport::TrueColor)
+    }
+}
+
+impl Themes {
+    /// The presets, shown with `support`
+    pub fn new(support: ColorSupport) -> Self {
+        let themes = PRESETS
+            .iter()
+            .map(|(name, _)| (name.to_string(), Theme::preset(name)))
+            .collect();
+        Self { themes, support }
+    }
+
+    /// The presets and the `*.toml` files in `dir`, in name order
+    pub fn load(dir: &Path, support: ColorSupport) -> Result<Self, String> {
+        let mut themes = Self::new(support);
+        let Ok(entries) = std::fs::read_dir(dir) else {
+            return Ok(themes);
+        };
+        let mut paths: Vec<_> = entries
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|path| path.extension().is_some_and(|ext| ext == "toml"))
+            .collect();
+        paths.sort();
+
+        for path in paths {
+            let name = path
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().to_string())
+                .unwrap_or_default();
+            std::fs::read_to_string(&path)
+                .map_err(|e| e.to_string())
+                .and_then(|text| themes.add(&name, &text))
+                .map_err(|e| format!("{}: {}", path.display(), e))?;
+        }
+        Ok(themes)
+    }
+
+    /// Add a theme from the text of a theme file
+    pub fn add(&mut self, name: &str, text: &str) -> Result<(), String> {
+        let file: ThemeFile = toml::from_str(text).map_err(|e| e.to_string())?;
+        let base = file.extends.as_deref().unwrap_or(DEFAULT_THEME);
+        let theme = self
+            .themes
+            .get(base)
+            .ok_or_else(|| format!("unknown theme '{}' to extend", base))?
+            .with_colors(&file.colors)?;
+        self.themes.insert(name.to_string(), theme);
+        Ok(())
+    }
+
+    /// A theme by name, with its colors reduced to what the terminal can show
+    pub fn get(&self, name: &str) -> Option<Theme> {
+        self.themes
+            .get(name)
+            .map(|theme| theme.adapted(self.support))
+    }
+
+    /// Names of the themes, presets first
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.themes.keys().map(String::as_str)
+    }
+
+    /// The theme after `name`, wrapping around
+    pub fn next(&self, name: &str) -> &str {
+        let index = self
+            .themes
+            .get_index_of(name)
+            .map_or(0, |i| (i + 1) % self.themes.len());
+        self.themes
+            .get_index(index)
+            .map(|(name, _)| name.as_str())
+            .unwrap_or(DEFAULT_THEME)
+    }
 }
 
 /// Common styles used throughout the application
@@ -54,104 +429,85 @@ pub struct Styles;
 impl Styles {
     /// Default text style
     pub fn text() -> Style {
-        Style::default().fg(Theme::TEXT)
+        Style::default().fg(Theme::current().text)
     }
 
     /// Muted/secondary text style
     pub fn text_muted() -> Style {
-        Style::default().fg(Theme::TEXT_MUTED)
+        Style::default().fg(Theme::current().text_muted)
     }
 
     /// Highlighted text style
     pub fn highlight() -> Style {
         Style::default()
-            .fg(Theme::HIGHLIGHT)
+            .fg(Theme::current().highlight)
             .add_modifier(Modifier::BOLD)
     }
 
     /// Selected item style
     pub fn selected() -> Style {
         Style::default()
-            .fg(Theme::SELECTED)
+            .fg(Theme::current().selected)
             .add_modifier(Modifier::BOLD)
     }
 
     /// Error text style
     pub fn error() -> Style {
         Style::default()
-            .fg(Theme::ERROR)
+            .fg(Theme::current().error)
             .add_modifier(Modifier::BOLD)
     }
 
     /// Success text style
     pub fn success() -> Style {
         Style::default()
-            .fg(Theme::SUCCESS)
+            .fg(Theme::current().success)
             .add_modifier(Modifier::BOLD)
     }
 
     /// Warning text style
     pub fn warning() -> Style {
         Style::default()
-            .fg(Theme::WARNING)
+            .fg(Theme::current().warning)
             .add_modifier(Modifier::BOLD)
     }
 
     /// Info text style
     pub fn info() -> Style {
-        Style::default().fg(Theme::INFO)
+        Style::default().fg(Theme::current().info)
     }
 
     /// Border style
     pub fn border() -> Style {
-        Style::default().fg(Theme::BORDER)
+        Style::default().fg(Theme::current().border)
     }
 
     /// Focused border style
     pub fn border_focused() -> Style {
         Style::default()
-            .fg(Theme::BORDER_FOCUSED)
+            .fg(Theme::current().border_focused)
             .add_modifier(Modifier::BOLD)
     }
 
     /// Status-specific style
-    pub fn status(status: &str) -> Style {
-        let color = match status {
-            "ACTIVE" => Theme::STATUS_ACTIVE,
-            "PAUSED" => Theme::STATUS_PAUSED,
-            "WAITING" => Theme::STATUS_WAITING,
-            "COMPLETE" => Theme::STATUS_COMPLETE,
-            "ERROR" => Theme::STATUS_ERROR,
-            _ => Theme::STATUS_IDLE,
-        };
-
-        Style::default().fg(color).add_modifier(Modifier::BOLD)
+    pub fn status(state: DownloadState) -> Style {
+        Style::default()
+            .fg(Theme::current().status_color(state))
+            .add_modifier(Modifier::BOLD)
     }
 
     /// Progress bar style based on status
-    pub fn progress(status: &str, complete: bool) -> Style {
-        let color = if complete {
-            Theme::PROGRESS_COMPLETE
-        } else if status == "PAUSED" {
-            Theme::PROGRESS_PAUSED
-        } else {
-            Theme::PROGRESS_ACTIVE
-        };
-
-        Style::default().fg(color).bg(Theme::BACKGROUND)
+    pub fn progress(state: DownloadState, complete: bool) -> Style {
+        let progress = if complete { 1.0 } else { 0.0 };
+        Self::gauge(progress, state)
     }
 
     /// Gauge style for progress bars
-    pub fn gauge(progress: f64, status: &str) -> Style {
-        let color = if progress >= 1.0 {
-            Theme::PROGRESS_COMPLETE
-        } else if status == "PAUSED" {
-            Theme::PROGRESS_PAUSED
-        } else {
-            Theme::PROGRESS_ACTIVE
-        };
-
-        Style::default().fg(color).bg(Theme::BACKGROUND)
+    pub fn gauge(progress: f64, state: DownloadState) -> Style {
+        let theme = Theme::current();
+        Style::default()
+            .fg(theme.progress_color(progress, state))
+            .bg(theme.background)
     }
 }
 
@@ -163,12 +519,105 @@ impl KeyStyle {
     /// Style for key labels in shortcuts
     pub fn key() -> Style {
         Style::default()
-            .fg(Theme::SECONDARY)
+            .fg(Theme::current().secondary)
             .add_modifier(Modifier::BOLD)
     }
 
     /// Style for key descriptions
     pub fn description() -> Style {
-        Style::default().fg(Theme::TEXT_MUTED)
+        Style::default().fg(Theme::current().text_muted)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_presets_set_every_role() {
+        for (name, text) in PRESETS {
+            let file: ThemeFile = toml::from_str(text).unwrap();
+            assert_eq!(file.colors.len(), ROLES.len(), "{} misses roles", name);
+        }
+        let dark = Theme::preset("dark");
+        assert_eq!(dark.primary, Color::Rgb(139, 233, 253));
+        assert_eq!(dark.border, Color::Gray);
+        assert_eq!(dark.text_muted, Color::DarkGray);
+    }
+
+    #[test]
+    fn test_user_theme_extends_a_preset() {
+        let mut themes = Themes::default();
+        themes
+            .add(
+                "paper",
+                "extends = \"light\"\n[colors]\nhighlight = \"#d7005f\"",
+            )
+            .unwrap();
+        let paper = themes.get("paper").unwrap();
+        assert_eq!(paper.highlight, Color::Rgb(0xd7, 0x00, 0x5f));
+        assert_eq!(paper.text, Theme::preset("light").text);
+
+        assert!(themes.add("bad", "[colors]\nshadow = \"red\"").is_err());
+        assert!(themes.add("bad", "[colors]\ntext = \"#12\"").is_err());
+        assert!(themes.add("bad", "extends = \"nope\"").is_err());
+    }
+
+    #[test]
+    fn test_next_wraps_around() {
+        let themes = Themes::default();
+        assert_eq!(themes.next("dark"), "light");
+        assert_eq!(themes.next("high-contrast"), "dark");
+        assert_eq!(themes.next("missing"), "dark");
+    }
+
+    #[test]
+    fn test_color_support_from_env() {
+        let support = ColorSupport::from_env;
+        assert_eq!(
+            support(Some("1"), Some("truecolor"), None),
+            ColorSupport::None
+        );
+        assert_eq!(
+            support(Some(""), Some("truecolor"), None),
+            ColorSupport::TrueColor
+        );
+        assert_eq!(
+            support(None, None, Some("xterm-256color")),
+            ColorSupport::Ansi256
+        );
+        assert_eq!(support(None, None, Some("xterm")), ColorSupport::Ansi16);
+        assert_eq!(support(None, None, Some("dumb")), ColorSupport::None);
+    }
+
+    #[test]
+    fn test_colors_fall_back_to_the_palette() {
+        let red = Color::Rgb(199, 55, 44);
+        assert_eq!(ColorSupport::TrueColor.adapt(red), red);
+        assert_eq!(ColorSupport::Ansi256.adapt(red), Color::Indexed(166));
+        assert_eq!(ColorSupport::Ansi16.adapt(red), Color::Red);
+        assert_eq!(
+            ColorSupport::Ansi256.adapt(Color::Rgb(128, 128, 128)),
+            Color::Indexed(244)
+        );
+        assert_eq!(
+            ColorSupport::Ansi16.adapt(Color::Indexed(231)),
+            Color::White
+        );
+        assert_eq!(
+            ColorSupport::Ansi16.adapt(Color::LightBlue),
+            Color::LightBlue
+        );
+        assert_eq!(ColorSupport::None.adapt(Color::LightBlue), Color::Reset);
+
+        let plain = Theme::preset("dark").adapted(ColorSupport::None);
+        assert_eq!(plain, Theme::default());
+    }
+
+    #[test]
+    fn test_current_theme_switches() {
+        let light = Themes::default().get("light").unwrap();
+        Theme::set(light);
+        assert_eq!(Styles::text().fg, Some(light.text));
     }
 }
@@ -1,6 +1,8 @@
 //! Utility functions for UI operations
 
-use crate::models::Download;
+use crate::models::{Download, DownloadState};
+use crate::query::Query;
+use std::path::PathBuf;
 
 /// Filter downloads based on tab index
 ///
@@ -11,47 +13,112 @@ use crate::models::Download;
 /// # Returns
 /// Vector of references to downloads matching the tab criteria
 pub fn filter_by_tab(downloads: &[Download], tab: usize) -> Vec<&Download> {
-    downloads
-        .iter()
-        .filter(|d| match tab {
-            0 => is_active(d),
-            1 => is_queued(d),
-            2 => is_completed(d),
-            _ => false,
-        })
-        .collect()
+    downloads.iter().filter(|d| tab_of(d) == tab).collect()
 }
 
-/// Filter downloads by search query (case-insensitive name matching)
+/// Tab listing finished, failed and removed downloads
+pub const COMPLETED_TAB: usize = 2;
+
+/// Tab listing the download history instead of aria2's downloads
+pub const HISTORY_TAB: usize = 3;
+
+/// Index of the tab that lists a download (0 = Active, 1 = Queue, 2 = Completed)
+pub fn tab_of(download: &Download) -> usize {
+    match download.status {
+        DownloadState::Active | DownloadState::Seeding | DownloadState::Verifying => 0,
+        DownloadState::Waiting | DownloadState::Paused => 1,
+        DownloadState::Complete | DownloadState::Error | DownloadState::Removed => COMPLETED_TAB,
+    }
+}
+
+/// Filter downloads by a search query (see [`crate::query`])
+///
+/// A query that does not parse filters nothing; the search bar shows the error.
 pub fn filter_by_search<'a>(downloads: &[&'a Download], query: &str) -> Vec<&'a Download> {
-    if query.is_empty() {
-        return downloads.to_vec();
-    }
-    let query_lower = query.to_lowercase();
-    downloads
-        .iter()
-        .filter(|d| d.name.to_lowercase().contains(&query_lower))
-        .copied()
-        .collect()
+    match Query::parse(query) {
+        Ok(query) if !query.is_empty() => downloads
+            .iter()
+            .filter(|d| query.matches(d))
+            .copied()
+            .collect(),
+        _ => downloads.to_vec(),
+    }
+}
+
+/// Replace a leading `~` with the home directory
+pub fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix('~'), dirs::home_dir()) {
+        (Some(rest), Some(home)) if rest.is_empty() || rest.starts_with('/') => {
+            home.join(rest.trim_start_matches('/'))
+        }
+        _ => PathBuf::from(path),
+    }
+}
+
+/// Downloads shown in the list: filtered by tab and search, then sorted
+///
+/// The sort is stable, so downloads that compare equal keep the order in
+/// which they were added.
+pub fn visible_downloads<'a>(
+    downloads: &'a [Download],
+    tab: usize,
+    query: &str,
+    order: SortOrder,
+    ascending: bool,
+) -> Vec<&'a Download> {
+    let mut visible = filter_by_search(&filter_by_tab(downloads, tab), query);
+    sort_downloads(&mut visible, order, ascending);
+    visible
 }
 
 /// Sort order for downloads
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
 pub enum SortOrder {
+    #[default]
     Name,
     Size,
     Progress,
     Speed,
+    Date, // When the download was added
     Status,
 }
 
 impl SortOrder {
+    pub const ALL: [SortOrder; 6] = [
+        SortOrder::Name,
+        SortOrder::Size,
+        SortOrder::Progress,
+        SortOrder::Speed,
+        SortOrder::Date,
+        SortOrder::Status,
+    ];
+
+    /// Short lowercase name, as typed in `:sort`
+    pub fn name(&self) -> &'static str {
+        match self {
+            SortOrder::Name => "name",
+            SortOrder::Size => "size",
+            SortOrder::Progress => "progress",
+            SortOrder::Speed => "speed",
+            SortOrder::Date => "date",
+            SortOrder::Status => "status",
+        }
+    }
+
+    /// Sort order with the given short name
+    pub fn from_name(name: &str) -> Option<SortOrder> {
+        Self::ALL
+            .into_iter()
+            .find(|order| order.name().eq_ignore_ascii_case(name))
+    }
+
     pub fn as_str(&self) -> &'static str {
         match self {
             SortOrder::Name => "Name",
             SortOrder::Size => "Size",
             SortOrder::Progress => "Progress",
             SortOrder::Speed => "Speed",
+            SortOrder::Date => "Date Added",
             SortOrder::Status => "Status",
         }
     }
@@ -61,7 +128,8 @@ impl SortOrder {
             SortOrder::Name => SortOrder::Size,
             SortOrder::Size => SortOrder::Progress,
             SortOrder::Progress => SortOrder::Speed,
-            SortOrder::Speed => SortOrder::Status,
+            SortOrder::Speed => SortOrder::Date,
+            SortOrder::Date => SortOrder::Status,
             SortOrder::Status => SortOrder::Name,
         }
     }
@@ -73,19 +141,14 @@ impl SortOrder {
             SortOrder::Size => SortOrder::Name,
             SortOrder::Progress => SortOrder::Size,
             SortOrder::Speed => SortOrder::Progress,
-            SortOrder::Status => SortOrder::Speed,
+            SortOrder::Date => SortOrder::Speed,
+            SortOrder::Status => SortOrder::Date,
         }
     }
 }
 
-impl Default for SortOrder {
-    fn default() -> Self {
-        SortOrder::Name
-    }
-}
-
 /// Sort downloads by the given order
-pub fn sort_downloads<'a>(downloads: &mut [&'a Download], order: SortOrder, ascending: bool) {
+pub fn sort_downloads(downloads: &mut [&Download], order: SortOrder, ascending: bool) {
     downloads.sort_by(|a, b| {
         let cmp = match order {
             SortOrder::Name => a.name.to_lowercase().cmp(&b.name.to_lowercase()),
@@ -99,7 +162,8 @@ pub fn sort_downloads<'a>(downloads: &mut [&'a Download], order: SortOrder, asce
                 let speed_b = parse_speed(&b.speed);
                 speed_a.cmp(&speed_b)
             }
-            SortOrder::Status => a.status.cmp(&b.status),
+            SortOrder::Date => a.added_at.cmp(&b.added_at),
+            SortOrder::Status => a.status.as_str().cmp(b.status.as_str()),
         };
         if ascending {
             cmp
@@ -130,36 +194,44 @@ fn parse_speed(speed: &str) -> u64 {
 
 /// Check if a download is active
 pub fn is_active(download: &Download) -> bool {
-    download.status == "ACTIVE"
-        || (download.progress > 0.0 && download.progress < 1.0 && download.status != "WAITING")
+    download.status.is_running()
 }
 
 /// Check if a download is queued
 pub fn is_queued(download: &Download) -> bool {
-    download.status == "WAITING" || download.status == "PAUSED" || download.progress == 0.0
+    matches!(
+        download.status,
+        DownloadState::Waiting | DownloadState::Paused
+    )
 }
 
 /// Check if a download is completed
 pub fn is_completed(download: &Download) -> bool {
-    download.progress >= 1.0 || download.status == "COMPLETE"
+    download.status == DownloadState::Complete
 }
 
 /// Check if a download has an error
 pub fn is_error(download: &Download) -> bool {
-    download.status == "ERROR" || download.status.to_lowercase().contains("error")
+    download.status == DownloadState::Error
 }
 
 /// Count downloads by tab
 pub fn count_by_tab(downloads: &[Download], tab: usize) -> usize {
-    downloads
-        .iter()
-        .filter(|d| match tab {
-            0 => is_active(d),
-            1 => is_queued(d),
-            2 => is_completed(d),
-            _ => false,
-        })
-        .count()
+    downloads.iter().filter(|d| tab_of(d) == tab).count()
+}
+
+/// Icon shown next to a download's state
+pub fn state_icon(state: DownloadState) -> &'static str {
+    match state {
+        DownloadState::Active => ">",
+        DownloadState::Paused => "||",
+        DownloadState::Waiting => "o",
+        DownloadState::Seeding => "^",
+        DownloadState::Complete => "*",
+        DownloadState::Error => "x",
+        DownloadState::Removed => "-",
+        DownloadState::Verifying => "?",
+    }
 }
 
 /// Format file size in human-readable format
@@ -199,6 +271,19 @@ pub fn format_speed(speed_bytes: u64) -> String {
     }
 }
 
+/// Current speed followed by the download's own limit, if it has one
+pub fn format_capped_speed(speed: &str, cap: u64) -> String {
+    if cap == 0 {
+        speed.to_string()
+    } else {
+        format!(
+            "{} (cap {})",
+            speed,
+            crate::ui::widgets::speed_limit_popup::format_speed_limit(cap)
+        )
+    }
+}
+
 /// Format ETA (Estimated Time of Arrival) from remaining bytes and speed
 pub fn format_eta(remaining_bytes: u64, speed_bytes_per_sec: u64) -> String {
     if speed_bytes_per_sec == 0 {
@@ -215,16 +300,13 @@ pub fn format_download_eta(download: &Download) -> String {
         return "Complete".to_string();
     }
 
-    if download.status == "PAUSED" {
-        return "Paused".to_string();
-    }
-
-    if download.status == "WAITING" {
-        return "Waiting".to_string();
-    }
-
-    if download.status == "ERROR" || download.status.to_lowercase().contains("error") {
-        return "Error".to_string();
+    match download.status {
+        DownloadState::Paused => return "Paused".to_string(),
+        DownloadState::Waiting => return "Waiting".to_string(),
+        DownloadState::Error => return "Error".to_string(),
+        DownloadState::Removed => return "Removed".to_string(),
+        DownloadState::Verifying => return "Verifying".to_string(),
+        _ => {}
     }
 
     // Get average speed from history for more stable ETA
@@ -333,14 +415,14 @@ mod tests {
     use super::*;
     use crate::models::{Download, DownloadType};
 
-    fn create_test_download(status: &str, progress: f64) -> Download {
+    fn create_test_download(status: DownloadState, progress: f64) -> Download {
         Download {
             gid: Some("test".to_string()),
             name: "test.txt".to_string(),
             url: None,
             progress,
             speed: "0 B/s".to_string(),
-            status: status.to_string(),
+            status,
             total_length: 0,
             completed_length: 0,
             download_type: DownloadType::Http,
@@ -348,13 +430,20 @@ mod tests {
             connections: 0,
             file_path: None,
             error_message: None,
+            error_code: None,
+            uploaded_length: 0,
             upload_speed: "".to_string(),
             upload_speed_history: vec![0, 0],
-            added_at: std::time::Instant::now(),
+            added_at: chrono::Local::now(),
             seeds: 0,
             peers: 0,
             bitfield: None,
             num_pieces: 0,
+            options: Default::default(),
+            retry_attempts: 0,
+            next_retry: None,
+            stalled: false,
+            slow_since: None,
         }
     }
 
@@ -375,6 +464,15 @@ mod tests {
         assert_eq!(format_speed(1048576), "1.00 MB/s");
     }
 
+    #[test]
+    fn test_format_capped_speed() {
+        assert_eq!(format_capped_speed("1.00 MB/s", 0), "1.00 MB/s");
+        assert_eq!(
+            format_capped_speed("400.00 KB/s", 500 * 1024),
+            "400.00 KB/s (cap 500 KB/s)"
+        );
+    }
+
     #[test]
     fn test_format_eta() {
         assert_eq!(format_eta(1024, 0), "∞");
@@ -401,44 +499,85 @@ mod tests {
 
     #[test]
     fn test_is_active() {
-        let download = create_test_download("ACTIVE", 0.5);
+        let download = create_test_download(DownloadState::Active, 0.5);
         assert!(is_active(&download));
 
-        let download = create_test_download("WAITING", 0.0);
+        let download = create_test_download(DownloadState::Waiting, 0.0);
         assert!(!is_active(&download));
     }
 
     #[test]
     fn test_is_queued() {
-        let download = create_test_download("WAITING", 0.0);
+        let download = create_test_download(DownloadState::Waiting, 0.0);
         assert!(is_queued(&download));
 
-        let download = create_test_download("PAUSED", 0.5);
+        let download = create_test_download(DownloadState::Paused, 0.5);
         assert!(is_queued(&download));
     }
 
     #[test]
     fn test_is_completed() {
-        let download = create_test_download("COMPLETE", 1.0);
+        let download = create_test_download(DownloadState::Complete, 1.0);
         assert!(is_completed(&download));
 
-        let download = create_test_download("ACTIVE", 0.5);
+        let download = create_test_download(DownloadState::Active, 0.5);
         assert!(!is_completed(&download));
     }
 
+    #[test]
+    fn test_tab_of_follows_state() {
+        // Paused downloads stay in the queue however far they got
+        assert_eq!(tab_of(&create_test_download(DownloadState::Paused, 0.0)), 1);
+        assert_eq!(tab_of(&create_test_download(DownloadState::Paused, 0.5)), 1);
+        assert_eq!(
+            tab_of(&create_test_download(DownloadState::Seeding, 1.0)),
+            0
+        );
+        assert_eq!(
+            tab_of(&create_test_download(DownloadState::Verifying, 0.0)),
+            0
+        );
+        assert_eq!(tab_of(&create_test_download(DownloadState::Error, 0.3)), 2);
+        assert_eq!(
+            tab_of(&create_test_download(DownloadState::Removed, 0.0)),
+            2
+        );
+    }
+
     #[test]
     fn test_is_error() {
-        let download = create_test_download("ERROR", 0.0);
+        let download = create_test_download(DownloadState::Error, 0.0);
         assert!(is_error(&download));
 
-        let download = create_test_download("ACTIVE", 0.5);
+        let download = create_test_download(DownloadState::Active, 0.5);
         assert!(!is_error(&download));
     }
 
+    #[test]
+    fn test_visible_downloads_keeps_insertion_order_for_ties() {
+        let mut downloads = vec![
+            create_test_download(DownloadState::Active, 0.5),
+            create_test_download(DownloadState::Active, 0.3),
+            create_test_download(DownloadState::Active, 0.4),
+        ];
+        for (i, d) in downloads.iter_mut().enumerate() {
+            d.gid = Some(format!("g{}", i));
+        }
+
+        // All names are equal, so the sort leaves the order untouched
+        let visible = visible_downloads(&downloads, 0, "", SortOrder::Name, true);
+        let gids: Vec<_> = visible.iter().map(|d| d.gid.as_deref().unwrap()).collect();
+        assert_eq!(gids, vec!["g0", "g1", "g2"]);
+
+        let visible = visible_downloads(&downloads, 0, "", SortOrder::Progress, false);
+        let gids: Vec<_> = visible.iter().map(|d| d.gid.as_deref().unwrap()).collect();
+        assert_eq!(gids, vec!["g0", "g2", "g1"]);
+    }
+
     #[test]
     fn test_filter_by_search() {
-        let d1 = create_test_download("ACTIVE", 0.5);
-        let mut d2 = create_test_download("ACTIVE", 0.3);
+        let d1 = create_test_download(DownloadState::Active, 0.5);
+        let mut d2 = create_test_download(DownloadState::Active, 0.3);
         d2.name = "other_file.zip".to_string();
 
         let downloads: Vec<&Download> = vec![&d1, &d2];
@@ -453,11 +592,66 @@ mod tests {
         assert_eq!(filtered.len(), 1); // Case insensitive
     }
 
+    #[test]
+    fn test_filter_stalled_keyword() {
+        let d1 = create_test_download(DownloadState::Active, 0.5);
+        let mut d2 = create_test_download(DownloadState::Active, 0.3);
+        d2.name = "other_file.zip".to_string();
+        d2.stalled = true;
+
+        let downloads: Vec<&Download> = vec![&d1, &d2];
+
+        let filtered = filter_by_search(&downloads, "is:stalled");
+        assert_eq!(filtered.len(), 1);
+        assert_eq!(filtered[0].name, "other_file.zip");
+
+        assert!(filter_by_search(&downloads, "test is:stalled").is_empty());
+        assert_eq!(filter_by_search(&downloads, "IS:STALLED other").len(), 1);
+    }
+
+    #[test]
+    fn test_filter_status_keyword() {
+        let d1 = create_test_download(DownloadState::Active, 0.5);
+        let mut d2 = create_test_download(DownloadState::Paused, 0.3);
+        d2.name = "other_file.zip".to_string();
+        let d3 = create_test_download(DownloadState::Error, 0.1);
+
+        let downloads: Vec<&Download> = vec![&d1, &d2, &d3];
+
+        let filtered = filter_by_search(&downloads, "status:paused");
+        assert_eq!(filtered.len(), 1);
+        assert_eq!(filtered[0].name, "other_file.zip");
+        assert_eq!(
+            filter_by_search(&downloads, "STATUS:Active OR status:error").len(),
+            2
+        );
+        assert!(filter_by_search(&downloads, "other status:active").is_empty());
+        assert_eq!(filter_by_search(&downloads, "status:paused other").len(), 1);
+        // A query with an error filters nothing
+        assert_eq!(filter_by_search(&downloads, "status:done").len(), 3);
+    }
+
     #[test]
     fn test_sort_order() {
         assert_eq!(SortOrder::Name.next(), SortOrder::Size);
         assert_eq!(SortOrder::Status.next(), SortOrder::Name);
         assert_eq!(SortOrder::Name.prev(), SortOrder::Status);
+        assert_eq!(SortOrder::Speed.next(), SortOrder::Date);
+        assert_eq!(SortOrder::from_name("SIZE"), Some(SortOrder::Size));
+        assert_eq!(SortOrder::from_name("date"), Some(SortOrder::Date));
+        assert_eq!(SortOrder::from_name("colour"), None);
+    }
+
+    #[test]
+    fn test_sort_by_date_added() {
+        let mut older = create_test_download(DownloadState::Active, 0.5);
+        older.name = "older".to_string();
+        older.added_at = chrono::Local::now() - chrono::Duration::hours(1);
+        let newer = create_test_download(DownloadState::Active, 0.5);
+
+        let mut downloads = vec![&newer, &older];
+        sort_downloads(&mut downloads, SortOrder::Date, true);
+        assert_eq!(downloads[0].name, "older");
     }
 
     #[test]
@@ -470,9 +664,9 @@ mod tests {
 
     #[test]
     fn test_global_stats() {
-        let d1 = create_test_download("ACTIVE", 0.5);
-        let d2 = create_test_download("COMPLETE", 1.0);
-        let d3 = create_test_download("WAITING", 0.0);
+        let d1 = create_test_download(DownloadState::Active, 0.5);
+        let d2 = create_test_download(DownloadState::Complete, 1.0);
+        let d3 = create_test_download(DownloadState::Waiting, 0.0);
 
         let downloads = vec![d1, d2, d3];
         let stats = calculate_global_stats(&downloads);
@@ -0,0 +1,86 @@
+//! Command line opened with `:`, drawn over the bottom of the screen
+
+use crate::ui::theme::Theme;
+use ratatui::{
+    layout::Rect,
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Clear, Paragraph},
+    Frame,
+};
+
+/// Render the command line
+///
+/// # Arguments
+/// * `f` - Frame to render to
+/// * `area` - Full screen area
+/// * `text` - Command being typed, without the `:`
+/// * `error` - Why the last command failed, shown above the line
+/// * `hint` - Completions offered by Tab, shown above the line when there is no error
+pub fn render(f: &mut Frame, area: Rect, text: &str, error: Option<&str>, hint: &str) {
+    let lines = build_lines(text, error, hint);
+    let height = (lines.len() as u16).min(area.height);
+    let line_area = Rect::new(
+        area.x,
+        area.bottom().saturating_sub(height),
+        area.width,
+        height,
+    );
+
+    f.render_widget(Clear, line_area);
+    f.render_widget(Paragraph::new(lines), line_area);
+}
+
+/// The message line, if any, followed by the prompt
+fn build_lines(text: &str, error: Option<&str>, hint: &str) -> Vec<Line<'static>> {
+    let theme = Theme::current();
+    let mut lines = Vec::new();
+    if let Some(error) = error {
+        lines.push(Line::from(Span::styled(
+            error.to_string(),
+            Style::default().fg(theme.error),
+        )));
+    } else if !hint.is_empty() {
+        lines.push(Line::from(Span::styled(
+            hint.to_string(),
+            Style::default().fg(theme.text_muted),
+        )));
+    }
+
+    lines.push(Line::from(vec![
+        Span::styled(
+            ":",
+            Style::default()
+                .fg(theme.highlight)
+                .add_modifier(Modifier::BOLD),
+        ),
+        Span::styled(text.to_string(), Style::default().fg(theme.cmd_color)),
+        Span::styled(
+            "_",
+            Style::default()
+                .fg(theme.highlight)
+                .add_modifier(Modifier::SLOW_BLINK),
+        ),
+    ]));
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(line: &Line) -> String {
+        line.spans.iter().map(|s| s.content.as_ref()).collect()
+    }
+
+    #[test]
+    fn test_error_shows_above_the_prompt() {
+        let lines = build_lines("sort sise", Some("unknown sort 'sise'"), "name  size");
+        assert_eq!(lines.len(), 2);
+        assert_eq!(text(&lines[0]), "unknown sort 'sise'");
+        assert_eq!(text(&lines[1]), ":sort sise_");
+
+        let lines = build_lines("so", None, "");
+        assert_eq!(lines.len(), 1);
+    }
+}
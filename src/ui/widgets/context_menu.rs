@@ -0,0 +1,62 @@
+//! Context menu opened by right-clicking a download
+
+use crate::input::KeyAction;
+use crate::ui::theme::Styles;
+use ratatui::{
+    layout::Rect,
+    symbols::border,
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph},
+    Frame,
+};
+
+/// An open context menu
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ContextMenu {
+    pub x: u16, // Screen position of the click that opened it
+    pub y: u16,
+    pub items: Vec<(String, KeyAction)>, // Labels and the actions they run
+    pub selected: usize,
+}
+
+/// Render the menu next to where it was opened, kept on screen
+///
+/// Returns the area of each item.
+pub fn render(f: &mut Frame, area: Rect, menu: &ContextMenu) -> Vec<Rect> {
+    let label_width = menu
+        .items
+        .iter()
+        .map(|(label, _)| label.len())
+        .max()
+        .unwrap_or(0) as u16;
+    let width = (label_width + 4).min(area.width);
+    let height = (menu.items.len() as u16 + 2).min(area.height);
+    let x = menu.x.min(area.right().saturating_sub(width));
+    let y = menu.y.min(area.bottom().saturating_sub(height));
+    let popup_area = Rect::new(x, y, width, height);
+
+    f.render_widget(Clear, popup_area);
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_set(border::ROUNDED)
+        .border_style(Styles::border_focused());
+    let inner = block.inner(popup_area);
+    f.render_widget(block, popup_area);
+
+    let mut areas = Vec::new();
+    for (i, (label, _)) in menu.items.iter().enumerate().take(inner.height as usize) {
+        let row = Rect::new(inner.x, inner.y + i as u16, inner.width, 1);
+        let style = if i == menu.selected {
+            Styles::selected().add_modifier(ratatui::style::Modifier::REVERSED)
+        } else {
+            Styles::text()
+        };
+        let line = Line::from(Span::styled(
+            format!(" {:<w$}", label, w = label_width as usize + 1),
+            style,
+        ));
+        f.render_widget(Paragraph::new(line), row);
+        areas.push(row);
+    }
+    areas
+}
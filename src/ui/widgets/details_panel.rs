@@ -1,8 +1,10 @@
 //! Details panel widget for displaying download information
 
-use crate::models::{Download, DownloadType};
+use crate::models::{Download, DownloadState, DownloadType};
 use crate::ui::theme::{Styles, Theme};
-use crate::ui::utils::{download_type_name, format_download_eta, format_size};
+use crate::ui::utils::{
+    download_type_name, format_capped_speed, format_download_eta, format_size, state_icon,
+};
 use ratatui::{
     layout::{Constraint, Direction, Layout, Rect},
     style::Style,
@@ -11,6 +13,7 @@ use ratatui::{
     widgets::{Block, Borders, Gauge, Paragraph, Sparkline},
     Frame,
 };
+use std::time::Instant;
 
 /// Render the complete details panel
 ///
@@ -19,7 +22,7 @@ use ratatui::{
 /// * `area` - Area to render in
 /// * `download` - Download to display details for
 pub fn render(f: &mut Frame, area: Rect, download: &Download) {
-    let has_error = download.status == "ERROR" || download.status.to_lowercase().contains("error");
+    let has_error = download.status == DownloadState::Error;
 
     // Create main container with rounded border
     let block = Block::default()
@@ -31,9 +34,9 @@ pub fn render(f: &mut Frame, area: Rect, download: &Download) {
             " Details "
         })
         .border_style(if has_error {
-            Style::default().fg(Theme::ERROR)
+            Style::default().fg(Theme::current().error)
         } else {
-            Style::default().fg(Theme::BORDER)
+            Style::default().fg(Theme::current().border)
         });
 
     let inner = block.inner(area);
@@ -109,6 +112,21 @@ fn render_empty_state(f: &mut Frame, area: Rect) {
     f.render_widget(message, area);
 }
 
+/// Attempt count and countdown of automatic retries, if any
+fn retry_text(download: &Download, now: Instant) -> Option<String> {
+    match download.next_retry {
+        Some(at) => Some(format!(
+            "Retry {} in {}s",
+            download.retry_attempts + 1,
+            at.saturating_duration_since(now).as_secs()
+        )),
+        None if download.retry_attempts > 0 => {
+            Some(format!("Retried {}x", download.retry_attempts))
+        }
+        None => None,
+    }
+}
+
 /// Render download information section (compact, includes seeds/peers inline for torrents)
 fn render_info_section(
     f: &mut Frame,
@@ -120,21 +138,14 @@ fn render_info_section(
     let mut info_lines = vec![];
 
     // Status line with icon (no emoji, simple characters)
-    let status_icon = match download.status.as_str() {
-        "ACTIVE" => ">",
-        "PAUSED" => "||",
-        "WAITING" => "o",
-        "COMPLETE" => "*",
-        "ERROR" => "x",
-        _ => "-",
-    };
+    let status_icon = state_icon(download.status);
 
-    info_lines.push(Line::from(vec![
+    let mut status_spans = vec![
         Span::styled(
             format!(" {} ", status_icon),
-            Styles::status(&download.status),
+            Styles::status(download.status),
         ),
-        Span::styled(&download.status, Styles::status(&download.status)),
+        Span::styled(download.status.as_str(), Styles::status(download.status)),
         Span::styled("  ETA: ", Styles::text_muted()),
         Span::styled(
             format_download_eta(download),
@@ -144,7 +155,11 @@ fn render_info_section(
                 Styles::text_muted()
             },
         ),
-    ]));
+    ];
+    if let Some(retry) = retry_text(download, Instant::now()) {
+        status_spans.push(Span::styled(format!("  {}", retry), Styles::warning()));
+    }
+    info_lines.push(Line::from(status_spans));
 
     // Error message if present
     if has_error {
@@ -247,7 +262,7 @@ fn render_progress_box(f: &mut Frame, area: Rect, download: &Download, has_error
         .borders(Borders::ALL)
         .border_set(border::ROUNDED)
         .title(" Progress ")
-        .border_style(Style::default().fg(Theme::BORDER));
+        .border_style(Style::default().fg(Theme::current().border));
 
     let inner = block.inner(area);
     f.render_widget(block, area);
@@ -260,13 +275,8 @@ fn render_progress_box(f: &mut Frame, area: Rect, download: &Download, has_error
 
     let label = format!("{:.1}%", download.progress * 100.0);
 
-    let gauge_style = if download.progress >= 1.0 {
-        Style::default().fg(Theme::STATUS_COMPLETE)
-    } else if download.status == "PAUSED" {
-        Style::default().fg(Theme::STATUS_PAUSED)
-    } else {
-        Style::default().fg(Theme::SUCCESS)
-    };
+    let gauge_style =
+        Style::default().fg(Theme::current().progress_color(download.progress, download.status));
 
     let gauge = Gauge::default()
         .ratio(download.progress)
@@ -281,8 +291,11 @@ fn render_download_speed_box(f: &mut Frame, area: Rect, download: &Download) {
     let block = Block::default()
         .borders(Borders::ALL)
         .border_set(border::ROUNDED)
-        .title(format!(" Down: {} ", download.speed))
-        .border_style(Style::default().fg(Theme::SUCCESS));
+        .title(format!(
+            " Down: {} ",
+            format_capped_speed(&download.speed, download.options.download_limit)
+        ))
+        .border_style(Style::default().fg(Theme::current().success));
 
     let inner = block.inner(area);
     f.render_widget(block, area);
@@ -309,7 +322,7 @@ fn render_download_speed_box(f: &mut Frame, area: Rect, download: &Download) {
 
     let sparkline = Sparkline::default()
         .data(&data)
-        .style(Style::default().fg(Theme::SUCCESS));
+        .style(Style::default().fg(Theme::current().success));
     f.render_widget(sparkline, inner);
 }
 
@@ -318,8 +331,11 @@ fn render_upload_speed_box(f: &mut Frame, area: Rect, download: &Download) {
     let block = Block::default()
         .borders(Borders::ALL)
         .border_set(border::ROUNDED)
-        .title(format!(" Up: {} ", download.upload_speed))
-        .border_style(Style::default().fg(Theme::INFO));
+        .title(format!(
+            " Up: {} ",
+            format_capped_speed(&download.upload_speed, download.options.upload_limit)
+        ))
+        .border_style(Style::default().fg(Theme::current().info));
 
     let inner = block.inner(area);
     f.render_widget(block, area);
@@ -351,7 +367,7 @@ fn render_upload_speed_box(f: &mut Frame, area: Rect, download: &Download) {
 
     let sparkline = Sparkline::default()
         .data(&data)
-        .style(Style::default().fg(Theme::INFO));
+        .style(Style::default().fg(Theme::current().info));
     f.render_widget(sparkline, inner);
 }
 
@@ -365,7 +381,7 @@ fn render_pieces_box(f: &mut Frame, area: Rect, download: &Download) {
             count_completed_pieces(download),
             download.num_pieces
         ))
-        .border_style(Style::default().fg(Theme::BORDER));
+        .border_style(Style::default().fg(Theme::current().border));
 
     let inner = block.inner(area);
     f.render_widget(block, area);
@@ -421,7 +437,7 @@ fn render_bitfield_visualization(bitfield: &str, num_pieces: u32, width: usize)
 
     // Calculate how many pieces each display block represents
     let display_width = width.saturating_sub(2).max(1); // Leave some margin
-    let pieces_per_block = ((num_pieces as usize) + display_width - 1) / display_width;
+    let pieces_per_block = (num_pieces as usize).div_ceil(display_width);
 
     let mut spans: Vec<Span<'static>> = Vec::new();
     spans.push(Span::styled(" ", Styles::text_muted()));
@@ -441,19 +457,19 @@ fn render_bitfield_visualization(bitfield: &str, num_pieces: u32, width: usize)
 
         // Choose character based on completion ratio
         let (ch, style) = if total == 0 {
-            ('-', Style::default().fg(Theme::TEXT_MUTED))
+            ('-', Style::default().fg(Theme::current().text_muted))
         } else {
             let ratio = completed as f64 / total as f64;
             if ratio >= 1.0 {
-                ('#', Style::default().fg(Theme::SUCCESS)) // Fully complete
+                ('#', Style::default().fg(Theme::current().success)) // Fully complete
             } else if ratio >= 0.75 {
-                ('=', Style::default().fg(Theme::SUCCESS)) // Mostly complete
+                ('=', Style::default().fg(Theme::current().success)) // Mostly complete
             } else if ratio >= 0.5 {
-                ('+', Style::default().fg(Theme::WARNING)) // Half complete
+                ('+', Style::default().fg(Theme::current().warning)) // Half complete
             } else if ratio > 0.0 {
-                ('.', Style::default().fg(Theme::WARNING)) // Partially complete
+                ('.', Style::default().fg(Theme::current().warning)) // Partially complete
             } else {
-                ('-', Style::default().fg(Theme::TEXT_MUTED)) // Empty
+                ('-', Style::default().fg(Theme::current().text_muted)) // Empty
             }
         };
 
@@ -523,14 +539,14 @@ mod tests {
     use super::*;
     use crate::models::DownloadType;
 
-    fn create_test_download(name: &str, status: &str, progress: f64) -> Download {
+    fn create_test_download(name: &str, status: DownloadState, progress: f64) -> Download {
         Download {
             gid: Some("test123".to_string()),
             name: name.to_string(),
             url: Some("https://example.com/file.zip".to_string()),
             progress,
             speed: "1.5 MB/s".to_string(),
-            status: status.to_string(),
+            status,
             total_length: 1024 * 1024 * 100,
             completed_length: (1024 * 1024 * 100) as u64 * progress as u64 / 100,
             download_type: DownloadType::Http,
@@ -540,16 +556,23 @@ mod tests {
             connections: 4,
             file_path: Some("/downloads/file.zip".to_string()),
             error_message: None,
-            added_at: std::time::Instant::now(),
+            error_code: None,
+            uploaded_length: 0,
+            added_at: chrono::Local::now(),
             seeds: 0,
             peers: 0,
             bitfield: None,
             num_pieces: 0,
+            options: Default::default(),
+            retry_attempts: 0,
+            next_retry: None,
+            stalled: false,
+            slow_since: None,
         }
     }
 
     fn create_torrent_download() -> Download {
-        let mut dl = create_test_download("ubuntu.torrent", "ACTIVE", 50.0);
+        let mut dl = create_test_download("ubuntu.torrent", DownloadState::Active, 50.0);
         dl.download_type = DownloadType::Torrent;
         dl.seeds = 15;
         dl.peers = 42;
@@ -558,9 +581,9 @@ mod tests {
 
     #[test]
     fn test_download_creation() {
-        let download = create_test_download("test.zip", "ACTIVE", 0.5);
+        let download = create_test_download("test.zip", DownloadState::Active, 0.5);
         assert_eq!(download.name, "test.zip");
-        assert_eq!(download.status, "ACTIVE");
+        assert_eq!(download.status, DownloadState::Active);
     }
 
     #[test]
@@ -573,23 +596,37 @@ mod tests {
 
     #[test]
     fn test_error_download() {
-        let mut download = create_test_download("test.zip", "ERROR", 0.0);
+        let mut download = create_test_download("test.zip", DownloadState::Error, 0.0);
         download.error_message = Some("Connection refused".to_string());
-        assert_eq!(download.status, "ERROR");
+        assert_eq!(download.status, DownloadState::Error);
         assert!(download.error_message.is_some());
     }
 
+    #[test]
+    fn test_retry_text() {
+        let now = Instant::now();
+        let mut download = create_test_download("test.zip", DownloadState::Error, 0.0);
+        assert_eq!(retry_text(&download, now), None);
+
+        download.retry_attempts = 1;
+        download.next_retry = Some(now + std::time::Duration::from_secs(20));
+        assert_eq!(retry_text(&download, now).unwrap(), "Retry 2 in 20s");
+
+        download.next_retry = None;
+        assert_eq!(retry_text(&download, now).unwrap(), "Retried 1x");
+    }
+
     #[test]
     fn test_completed_download() {
-        let download = create_test_download("test.zip", "COMPLETE", 1.0);
-        assert_eq!(download.status, "COMPLETE");
+        let download = create_test_download("test.zip", DownloadState::Complete, 1.0);
+        assert_eq!(download.status, DownloadState::Complete);
         assert_eq!(download.progress, 1.0);
     }
 
     #[test]
     fn test_paused_download() {
-        let download = create_test_download("test.zip", "PAUSED", 0.5);
-        assert_eq!(download.status, "PAUSED");
+        let download = create_test_download("test.zip", DownloadState::Paused, 0.5);
+        assert_eq!(download.status, DownloadState::Paused);
     }
 
     #[test]
@@ -602,7 +639,7 @@ mod tests {
 
     #[test]
     fn test_count_completed_pieces() {
-        let mut download = create_test_download("test.zip", "ACTIVE", 0.5);
+        let mut download = create_test_download("test.zip", DownloadState::Active, 0.5);
         download.bitfield = Some("ff".to_string()); // 8 bits set
         download.num_pieces = 8;
         assert_eq!(count_completed_pieces(&download), 8);
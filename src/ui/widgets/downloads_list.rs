@@ -1,8 +1,9 @@
 //! Downloads list widget for displaying download items
 
-use crate::models::Download;
+use crate::models::{Download, DownloadState};
+use crate::query::Query;
 use crate::ui::theme::{Styles, Theme};
-use crate::ui::utils::{format_download_eta, truncate_text};
+use crate::ui::utils::{format_download_eta, state_icon, truncate_text};
 use ratatui::{
     layout::{Constraint, Direction, Layout, Rect},
     style::{Modifier, Style},
@@ -12,6 +13,12 @@ use ratatui::{
     Frame,
 };
 
+/// Spinner frames shown in place of the status icon while a command is in flight
+const SPINNER: [&str; 4] = ["|", "/", "-", "\\"];
+
+/// Shortest name shown before the ETA is dropped from a row
+const MIN_NAME_WIDTH: usize = 12;
+
 /// Render the downloads list widget
 ///
 /// # Arguments
@@ -21,7 +28,7 @@ use ratatui::{
 /// * `list_state` - Mutable list state for selection tracking
 #[allow(dead_code)]
 pub fn render(f: &mut Frame, area: Rect, downloads: &[&Download], list_state: &mut ListState) {
-    render_with_search(f, area, downloads, list_state, "", &[])
+    render_with_search(f, area, downloads, list_state, "", &[], &[], &[], 0);
 }
 
 /// Render the downloads list widget with search highlighting
@@ -32,15 +39,24 @@ pub fn render(f: &mut Frame, area: Rect, downloads: &[&Download], list_state: &m
 /// * `downloads` - Slice of downloads to display
 /// * `list_state` - Mutable list state for selection tracking
 /// * `search_query` - Current search query for highlighting
-/// * `selected_indices` - Indices of selected items for batch operations
+/// * `selected_gids` - GIDs of selected items for batch operations
+/// * `busy_gids` - GIDs with a backend command in flight
+/// * `hook_failed_gids` - GIDs whose hook command failed
+/// * `tick` - Frame counter driving the busy spinner
+///
+/// Returns the area of each row on screen with its index in `downloads`.
+#[allow(clippy::too_many_arguments)]
 pub fn render_with_search(
     f: &mut Frame,
     area: Rect,
     downloads: &[&Download],
     list_state: &mut ListState,
     search_query: &str,
-    selected_indices: &[usize],
-) {
+    selected_gids: &[String],
+    busy_gids: &[String],
+    hook_failed_gids: &[String],
+    tick: usize,
+) -> Vec<(Rect, usize)> {
     // Validate and adjust list state
     validate_selection(list_state, downloads.len());
 
@@ -92,10 +108,11 @@ pub fn render_with_search(
             ]),
         ]);
         f.render_widget(empty_msg, inner);
-        return;
+        return Vec::new();
     }
 
     // Render items
+    let mut rows = Vec::new();
     let mut current_y = inner.top();
 
     for (idx, download) in downloads.iter().enumerate() {
@@ -113,7 +130,19 @@ pub fn render_with_search(
         }
 
         let is_selected = idx == selected_index;
-        let is_batch_selected = selected_indices.contains(&idx);
+        let is_batch_selected = download
+            .gid
+            .as_ref()
+            .is_some_and(|gid| selected_gids.contains(gid));
+        let busy_frame = download
+            .gid
+            .as_ref()
+            .filter(|gid| busy_gids.contains(gid))
+            .map(|_| SPINNER[tick % SPINNER.len()]);
+        let hook_failed = download
+            .gid
+            .as_ref()
+            .is_some_and(|gid| hook_failed_gids.contains(gid));
 
         // Create layout for this item (2 rows)
         let item_area = Rect {
@@ -130,7 +159,10 @@ pub fn render_with_search(
             is_selected,
             is_batch_selected,
             search_query,
+            busy_frame,
+            hook_failed,
         );
+        rows.push((item_area, idx));
 
         current_y += 2;
 
@@ -144,15 +176,17 @@ pub fn render_with_search(
             };
             let separator = Line::from(vec![Span::styled(
                 "─".repeat(separator_area.width as usize),
-                Style::default().fg(Theme::BORDER),
+                Style::default().fg(Theme::current().border),
             )]);
             f.render_widget(Paragraph::new(separator), separator_area);
             current_y += 1;
         }
     }
+    rows
 }
 
 /// Render a single download item
+#[allow(clippy::too_many_arguments)]
 fn render_download_item(
     f: &mut Frame,
     area: Rect,
@@ -160,6 +194,8 @@ fn render_download_item(
     is_selected: bool,
     is_batch_selected: bool,
     search_query: &str,
+    busy_frame: Option<&str>,
+    hook_failed: bool,
 ) {
     let item_layout = Layout::default()
         .direction(Direction::Vertical)
@@ -167,17 +203,10 @@ fn render_download_item(
         .split(area);
 
     // Determine if download has error
-    let has_error = download.status == "ERROR" || download.status.to_lowercase().contains("error");
-
-    // Status indicator icons
-    let status_icon = match download.status.as_str() {
-        "ACTIVE" => ">",
-        "PAUSED" => "||",
-        "WAITING" => "o",
-        "COMPLETE" => "*",
-        "ERROR" => "x",
-        _ => "-",
-    };
+    let has_error = download.status == DownloadState::Error;
+
+    // Status indicator icons (spinner while a command is in flight)
+    let status_icon = busy_frame.unwrap_or(state_icon(download.status));
 
     // Selection marker
     let selection_marker = if is_selected {
@@ -193,36 +222,38 @@ fn render_download_item(
         Styles::error()
     } else if is_selected {
         Style::default()
-            .fg(Theme::HIGHLIGHT)
+            .fg(Theme::current().highlight)
             .add_modifier(Modifier::BOLD)
     } else if is_batch_selected {
         Style::default()
-            .fg(Theme::SELECTED)
+            .fg(Theme::current().selected)
             .add_modifier(Modifier::BOLD)
+    } else if download.stalled {
+        Style::default()
+            .fg(Theme::current().warning)
+            .add_modifier(Modifier::ITALIC)
     } else if download.progress >= 1.0 {
-        Style::default().fg(Theme::STATUS_COMPLETE)
+        Style::default().fg(Theme::current().status_complete)
     } else {
-        Style::default().fg(Theme::CMD_COLOR)
+        Style::default().fg(Theme::current().cmd_color)
     };
 
     let icon_style = if has_error {
         Styles::error()
     } else {
-        match download.status.as_str() {
-            "ACTIVE" => Style::default().fg(Theme::SUCCESS),
-            "PAUSED" => Style::default().fg(Theme::WARNING),
-            "WAITING" => Style::default().fg(Theme::TEXT_MUTED),
-            "COMPLETE" => Style::default().fg(Theme::STATUS_COMPLETE),
-            _ => Style::default().fg(Theme::TEXT_MUTED),
-        }
+        Style::default().fg(Theme::current().status_color(download.status))
     };
 
-    // Truncate name if needed - leave room for status and ETA
-    let max_name_len = area.width.saturating_sub(20) as usize;
-    let display_name = truncate_text(&download.name, max_name_len);
-
-    // Calculate ETA
+    // Truncate name if needed - leave room for the marker, icon, flags and ETA.
+    // Narrow rows drop the ETA before the name gets too short.
     let eta = format_download_eta(download);
+    let reserved = 8 + if download.stalled { 11 } else { 0 } + if hook_failed { 15 } else { 0 };
+    let eta_width = eta.chars().count() + 2;
+    let show_eta = area.width as usize >= reserved + eta_width + MIN_NAME_WIDTH;
+    let max_name_len = (area.width as usize)
+        .saturating_sub(reserved + if show_eta { eta_width } else { 0 })
+        .max(1);
+    let display_name = truncate_text(&download.name, max_name_len);
 
     // Build name line with search highlighting
     let name_spans = if !search_query.is_empty() {
@@ -234,12 +265,12 @@ fn render_download_item(
     // Build first line: marker + icon + name + ETA
     let marker_style = if is_selected {
         Style::default()
-            .fg(Theme::HIGHLIGHT)
+            .fg(Theme::current().highlight)
             .add_modifier(Modifier::BOLD)
     } else if is_batch_selected {
-        Style::default().fg(Theme::SELECTED)
+        Style::default().fg(Theme::current().selected)
     } else {
-        Style::default().fg(Theme::TEXT_MUTED)
+        Style::default().fg(Theme::current().text_muted)
     };
 
     let mut name_line_spans = vec![
@@ -248,11 +279,22 @@ fn render_download_item(
     ];
     name_line_spans.extend(name_spans);
 
+    if download.stalled {
+        name_line_spans.push(Span::styled("  [stalled]", Styles::warning()));
+    }
+    if hook_failed {
+        name_line_spans.push(Span::styled("  [hook failed]", Styles::error()));
+    }
+
     // Add ETA on the right side for active downloads
-    if download.progress < 1.0 && download.status != "COMPLETE" && !eta.is_empty() {
+    if show_eta
+        && download.progress < 1.0
+        && download.status != DownloadState::Complete
+        && !eta.is_empty()
+    {
         name_line_spans.push(Span::styled(
             format!("  {}", eta),
-            Style::default().fg(Theme::TEXT_MUTED),
+            Style::default().fg(Theme::current().text_muted),
         ));
     }
 
@@ -281,19 +323,9 @@ fn render_download_item(
         f.render_widget(Paragraph::new(error_line), progress_area);
     } else {
         // Progress bar with inline stats
-        let progress_label = build_progress_label(download);
-
-        let gauge_style = if download.progress >= 1.0 {
-            Style::default()
-                .fg(Theme::STATUS_COMPLETE)
-                .bg(Theme::BACKGROUND)
-        } else if download.status == "PAUSED" {
-            Style::default()
-                .fg(Theme::STATUS_PAUSED)
-                .bg(Theme::BACKGROUND)
-        } else {
-            Style::default().fg(Theme::SUCCESS).bg(Theme::BACKGROUND)
-        };
+        let progress_label = build_progress_label(download, progress_area.width as usize);
+
+        let gauge_style = Styles::gauge(download.progress, download.status);
 
         let gauge = Gauge::default()
             .ratio(download.progress)
@@ -304,66 +336,64 @@ fn render_download_item(
     }
 }
 
-/// Build a clean progress label
-fn build_progress_label(download: &Download) -> String {
+/// Build a clean progress label that fits in `width` columns
+///
+/// Seeds/peers go first, then the size, then the speed.
+fn build_progress_label(download: &Download, width: usize) -> String {
     let percent = format!("{:.0}%", download.progress * 100.0);
     let size = crate::ui::utils::format_size(download.completed_length);
+    let speed =
+        crate::ui::utils::format_capped_speed(&download.speed, download.options.download_limit);
 
+    let mut labels = Vec::new();
     // For torrents, include seeds/peers
     if download.download_type == crate::models::DownloadType::Torrent {
-        format!(
+        labels.push(format!(
             "{} | {} | {} | S:{} P:{}",
-            percent, download.speed, size, download.seeds, download.peers
-        )
-    } else {
-        format!("{} | {} | {}", percent, download.speed, size)
+            percent, speed, size, download.seeds, download.peers
+        ));
     }
+    labels.push(format!("{} | {} | {}", percent, speed, size));
+    labels.push(format!("{} | {}", percent, speed));
+    labels
+        .into_iter()
+        .find(|label| label.chars().count() <= width)
+        .unwrap_or(percent)
 }
 
-/// Highlight search matches in text
+/// Highlight the characters matched by the name terms of a search query
 fn highlight_search(
     text: &str,
     query: &str,
     base_style: ratatui::style::Style,
     _is_selected: bool,
 ) -> Vec<Span<'static>> {
-    if query.is_empty() {
+    let marked = match Query::parse(query) {
+        Ok(query) => query.highlights(text),
+        Err(_) => Vec::new(),
+    };
+    if !marked.contains(&true) {
         return vec![Span::styled(text.to_string(), base_style)];
     }
 
-    let text_lower = text.to_lowercase();
-    let query_lower = query.to_lowercase();
-
-    let mut spans = Vec::new();
-    let mut last_end = 0;
-
-    for (start, _) in text_lower.match_indices(&query_lower) {
-        // Add text before match
-        if start > last_end {
-            spans.push(Span::styled(text[last_end..start].to_string(), base_style));
+    let match_style = Style::default()
+        .fg(Theme::current().highlight)
+        .add_modifier(Modifier::BOLD | Modifier::UNDERLINED);
+
+    // One span per run of matched or unmatched characters
+    let mut spans: Vec<Span<'static>> = Vec::new();
+    let mut run = String::new();
+    let mut run_marked = marked[0];
+    for (c, is_marked) in text.chars().zip(marked) {
+        if is_marked != run_marked {
+            let style = if run_marked { match_style } else { base_style };
+            spans.push(Span::styled(std::mem::take(&mut run), style));
+            run_marked = is_marked;
         }
-
-        // Add highlighted match (preserve original case)
-        let match_text = &text[start..start + query.len()];
-        spans.push(Span::styled(
-            match_text.to_string(),
-            Style::default()
-                .fg(Theme::HIGHLIGHT)
-                .add_modifier(Modifier::BOLD | Modifier::UNDERLINED),
-        ));
-
-        last_end = start + query.len();
-    }
-
-    // Add remaining text
-    if last_end < text.len() {
-        spans.push(Span::styled(text[last_end..].to_string(), base_style));
-    }
-
-    if spans.is_empty() {
-        spans.push(Span::styled(text.to_string(), base_style));
+        run.push(c);
     }
-
+    let style = if run_marked { match_style } else { base_style };
+    spans.push(Span::styled(run, style));
     spans
 }
 
@@ -396,14 +426,14 @@ mod tests {
     use super::*;
     use crate::models::DownloadType;
 
-    fn create_test_download(name: &str, status: &str, progress: f64) -> Download {
+    fn create_test_download(name: &str, status: DownloadState, progress: f64) -> Download {
         Download {
             gid: Some("test".to_string()),
             name: name.to_string(),
             url: Some("https://example.com/file.zip".to_string()),
             progress,
             speed: "1.5 MB/s".to_string(),
-            status: status.to_string(),
+            status,
             total_length: 1024 * 1024 * 100,
             completed_length: (1024 * 1024 * 100) as u64 * progress as u64,
             download_type: DownloadType::Http,
@@ -413,11 +443,18 @@ mod tests {
             connections: 4,
             file_path: None,
             error_message: None,
-            added_at: std::time::Instant::now(),
+            error_code: None,
+            uploaded_length: 0,
+            added_at: chrono::Local::now(),
             seeds: 0,
             peers: 0,
             bitfield: None,
             num_pieces: 0,
+            options: Default::default(),
+            retry_attempts: 0,
+            next_retry: None,
+            stalled: false,
+            slow_since: None,
         }
     }
 
@@ -481,6 +518,18 @@ mod tests {
         assert!(spans.len() >= 2); // Should find match despite case difference
     }
 
+    #[test]
+    fn test_highlight_search_name_terms_only() {
+        let text = |spans: Vec<Span>| -> Vec<String> {
+            spans.iter().map(|s| s.content.to_string()).collect()
+        };
+        // Fields are not highlighted; fuzzy terms mark the letters they matched
+        let spans = highlight_search("ubuntu.iso", "status:active ubiso", Styles::text(), false);
+        assert_eq!(text(spans), vec!["ub", "untu.", "iso"]);
+        let spans = highlight_search("ubuntu.iso", "/n.u/ -iso", Styles::text(), false);
+        assert_eq!(text(spans), vec!["ubu", "ntu", ".iso"]);
+    }
+
     #[test]
     fn test_highlight_search_empty_query() {
         let spans = highlight_search("test file.zip", "", Styles::text(), false);
@@ -489,21 +538,37 @@ mod tests {
 
     #[test]
     fn test_download_item_status_icons() {
-        let download = create_test_download("test.zip", "ACTIVE", 0.5);
-        assert_eq!(download.status, "ACTIVE");
+        let download = create_test_download("test.zip", DownloadState::Active, 0.5);
+        assert_eq!(download.status, DownloadState::Active);
 
-        let download = create_test_download("test.zip", "PAUSED", 0.5);
-        assert_eq!(download.status, "PAUSED");
+        let download = create_test_download("test.zip", DownloadState::Paused, 0.5);
+        assert_eq!(download.status, DownloadState::Paused);
 
-        let download = create_test_download("test.zip", "COMPLETE", 1.0);
-        assert_eq!(download.status, "COMPLETE");
+        let download = create_test_download("test.zip", DownloadState::Complete, 1.0);
+        assert_eq!(download.status, DownloadState::Complete);
     }
 
     #[test]
     fn test_build_progress_label() {
-        let download = create_test_download("test.zip", "ACTIVE", 0.5);
-        let label = build_progress_label(&download);
+        let download = create_test_download("test.zip", DownloadState::Active, 0.5);
+        let label = build_progress_label(&download, 80);
         assert!(label.contains("50%"));
         assert!(label.contains("1.5 MB/s"));
+        assert!(!label.contains("cap"));
+
+        let mut capped = download.clone();
+        capped.options.download_limit = 2 * 1024 * 1024;
+        assert!(build_progress_label(&capped, 80).contains("1.5 MB/s (cap 2.0 MB/s)"));
+    }
+
+    #[test]
+    fn test_build_progress_label_narrow() {
+        let download = create_test_download("test.zip", DownloadState::Active, 0.5);
+        let wide = build_progress_label(&download, 80);
+        let narrow = build_progress_label(&download, 16);
+        assert!(narrow.chars().count() <= 16);
+        assert!(narrow.starts_with("50%"));
+        assert!(wide.len() > narrow.len());
+        assert_eq!(build_progress_label(&download, 3), "50%");
     }
 }
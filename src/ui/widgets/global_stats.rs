@@ -4,6 +4,7 @@
 
 use crate::ui::theme::Styles;
 use crate::ui::utils::GlobalStats;
+use crate::usage::QuotaUse;
 use ratatui::{
     layout::{Alignment, Constraint, Direction, Layout, Rect},
     text::{Line, Span},
@@ -19,28 +20,34 @@ use ratatui::{
 /// * `stats` - Global statistics to display
 /// * `download_limit` - Current download speed limit (0 = unlimited)
 /// * `upload_limit` - Current upload speed limit (0 = unlimited)
+/// * `quota` - Data-cap quotas, the most used one shown as a gauge
 pub fn render(
     f: &mut Frame,
     area: Rect,
     stats: &GlobalStats,
     download_limit: u64,
     upload_limit: u64,
+    quota: &[QuotaUse],
 ) {
     // Single line of stats with subtle separator below
-    let stats_line = build_stats_line(stats, download_limit, upload_limit);
+    let stats_line = build_stats_line(stats, download_limit, upload_limit, quota);
     let paragraph = Paragraph::new(stats_line).alignment(Alignment::Center);
     f.render_widget(paragraph, area);
 }
 
 /// Render a compact version of the stats bar (single line, no border)
+///
+/// Fits in about 60 columns: short speeds, one-character count icons and
+/// the most used quota as a percentage.
 pub fn render_compact(
     f: &mut Frame,
     area: Rect,
     stats: &GlobalStats,
     download_limit: u64,
     upload_limit: u64,
+    quota: &[QuotaUse],
 ) {
-    let stats_line = build_stats_line(stats, download_limit, upload_limit);
+    let stats_line = build_compact_line(stats, download_limit, upload_limit, quota);
     let paragraph = Paragraph::new(stats_line).alignment(Alignment::Center);
     f.render_widget(paragraph, area);
 }
@@ -75,7 +82,12 @@ pub fn render_expanded(
 }
 
 /// Build the main stats line
-fn build_stats_line(stats: &GlobalStats, download_limit: u64, upload_limit: u64) -> Line<'static> {
+fn build_stats_line(
+    stats: &GlobalStats,
+    download_limit: u64,
+    upload_limit: u64,
+    quota: &[QuotaUse],
+) -> Line<'static> {
     let mut spans = vec![];
 
     // Download speed with icon
@@ -155,9 +167,116 @@ fn build_stats_line(stats: &GlobalStats, download_limit: u64, upload_limit: u64)
         spans.push(Span::styled(" errors", Styles::error()));
     }
 
+    // Data-cap usage
+    if let Some(gauge) = quota_gauge(quota) {
+        spans.push(Span::styled("   ·   ", Styles::text_muted()));
+        spans.push(gauge);
+    }
+
     Line::from(spans)
 }
 
+/// Build the single-line stats for small terminals
+fn build_compact_line(
+    stats: &GlobalStats,
+    download_limit: u64,
+    upload_limit: u64,
+    quota: &[QuotaUse],
+) -> Line<'static> {
+    let mut spans = vec![Span::styled(
+        format!("D {}", format_speed_compact(stats.total_download_speed)),
+        Styles::success(),
+    )];
+    if download_limit > 0 {
+        spans.push(Span::styled(
+            format!(" [{}]", format_speed_short(download_limit)),
+            Styles::text_muted(),
+        ));
+    }
+
+    spans.push(Span::styled(
+        format!("  U {}", format_speed_compact(stats.total_upload_speed)),
+        Styles::info(),
+    ));
+    if upload_limit > 0 {
+        spans.push(Span::styled(
+            format!(" [{}]", format_speed_short(upload_limit)),
+            Styles::text_muted(),
+        ));
+    }
+
+    // Counts with the same icons as the category tabs
+    spans.push(Span::styled(" · ", Styles::text_muted()));
+    spans.push(Span::styled(
+        format!(">{}", stats.active_count),
+        if stats.active_count > 0 {
+            Styles::success()
+        } else {
+            Styles::text_muted()
+        },
+    ));
+    spans.push(Span::styled(
+        format!(" o{}", stats.waiting_count),
+        if stats.waiting_count > 0 {
+            Styles::warning()
+        } else {
+            Styles::text_muted()
+        },
+    ));
+    spans.push(Span::styled(
+        format!(" *{}", stats.completed_count),
+        Styles::text_muted(),
+    ));
+    if stats.error_count > 0 {
+        spans.push(Span::styled(
+            format!(" x{}", stats.error_count),
+            Styles::error(),
+        ));
+    }
+
+    if let Some(usage) = most_used(quota) {
+        spans.push(Span::styled(" · ", Styles::text_muted()));
+        spans.push(Span::styled(
+            format!("{} {}%", usage.period.label(), usage.percent()),
+            quota_style(usage),
+        ));
+    }
+
+    Line::from(spans)
+}
+
+/// Gauge for the most used data-cap quota, e.g. `Month ████░░░░ 52%`
+pub fn quota_gauge(quota: &[QuotaUse]) -> Option<Span<'static>> {
+    const CELLS: usize = 8;
+
+    let usage = most_used(quota)?;
+    let filled = (usage.ratio() * CELLS as f64).round() as usize;
+    let text = format!(
+        "{} {}{} {}%",
+        usage.period.label(),
+        "█".repeat(filled),
+        "░".repeat(CELLS - filled),
+        usage.percent()
+    );
+    Some(Span::styled(text, quota_style(usage)))
+}
+
+/// The quota closest to its limit
+fn most_used(quota: &[QuotaUse]) -> Option<&QuotaUse> {
+    quota.iter().max_by(|a, b| a.ratio().total_cmp(&b.ratio()))
+}
+
+/// Color of a quota: red once reached, yellow from 80%
+fn quota_style(usage: &QuotaUse) -> ratatui::style::Style {
+    if usage.is_reached() {
+        Styles::error()
+    } else if usage.ratio() >= 0.8 {
+        Styles::warning()
+    } else {
+        Styles::info()
+    }
+}
+
 /// Build the speed-focused line for expanded view
 fn build_speed_line(stats: &GlobalStats, download_limit: u64, upload_limit: u64) -> Line<'static> {
     let mut spans = vec![];
@@ -214,15 +333,12 @@ fn build_speed_line(stats: &GlobalStats, download_limit: u64, upload_limit: u64)
 
 /// Build the count-focused line for expanded view
 fn build_count_line(stats: &GlobalStats) -> Line<'static> {
-    let mut spans = vec![];
-
     // Active
-    spans.push(Span::styled("● ", Styles::success()));
-    spans.push(Span::styled(
-        stats.active_count.to_string(),
-        Styles::success(),
-    ));
-    spans.push(Span::styled(" active", Styles::text_muted()));
+    let mut spans = vec![
+        Span::styled("● ", Styles::success()),
+        Span::styled(stats.active_count.to_string(), Styles::success()),
+        Span::styled(" active", Styles::text_muted()),
+    ];
 
     spans.push(Span::styled("    ", Styles::text_muted()));
 
@@ -306,6 +422,23 @@ fn format_speed_short(speed_bytes: u64) -> String {
     }
 }
 
+/// Format speed with one decimal and a one-letter unit (for compact display)
+fn format_speed_compact(speed_bytes: u64) -> String {
+    const KB: u64 = 1024;
+    const MB: u64 = KB * 1024;
+    const GB: u64 = MB * 1024;
+
+    if speed_bytes >= GB {
+        format!("{:.1}G/s", speed_bytes as f64 / GB as f64)
+    } else if speed_bytes >= MB {
+        format!("{:.1}M/s", speed_bytes as f64 / MB as f64)
+    } else if speed_bytes >= KB {
+        format!("{:.0}K/s", speed_bytes as f64 / KB as f64)
+    } else {
+        format!("{}B/s", speed_bytes)
+    }
+}
+
 /// Format file size in human-readable format
 fn format_size(bytes: u64) -> String {
     const KB: u64 = 1024;
@@ -348,6 +481,42 @@ mod tests {
         assert_eq!(format_speed_short(1073741824), "1G");
     }
 
+    #[test]
+    fn test_format_speed_compact() {
+        assert_eq!(format_speed_compact(0), "0B/s");
+        assert_eq!(format_speed_compact(1536), "2K/s");
+        assert_eq!(format_speed_compact(1572864), "1.5M/s");
+        assert_eq!(format_speed_compact(1073741824), "1.0G/s");
+    }
+
+    #[test]
+    fn test_build_compact_line_fits_small_terminals() {
+        use crate::usage::QuotaPeriod;
+
+        let stats = GlobalStats {
+            total_download_speed: 1572864,
+            total_upload_speed: 524288,
+            active_count: 12,
+            waiting_count: 34,
+            completed_count: 567,
+            error_count: 8,
+            total_downloaded: 0,
+            total_size: 0,
+        };
+        let quota = [QuotaUse {
+            period: QuotaPeriod::Monthly,
+            used: 50,
+            limit: 100,
+        }];
+        let line = build_compact_line(&stats, 5 * 1024 * 1024, 1024 * 1024, &quota);
+        let text: String = line.spans.iter().map(|s| s.content.to_string()).collect();
+        assert_eq!(
+            text,
+            "D 1.5M/s [5M]  U 512K/s [1M] · >12 o34 *567 x8 · Month 50%"
+        );
+        assert!(line.width() <= 60);
+    }
+
     #[test]
     fn test_format_size() {
         assert_eq!(format_size(0), "0 B");
@@ -369,14 +538,14 @@ mod tests {
             total_size: 0,
         };
 
-        let line = build_stats_line(&stats, 0, 0);
+        let line = build_stats_line(&stats, 0, 0, &[]);
         assert!(!line.spans.is_empty());
     }
 
     #[test]
     fn test_build_stats_line_with_limits() {
         let stats = GlobalStats::default();
-        let line = build_stats_line(&stats, 5 * 1024 * 1024, 1024 * 1024);
+        let line = build_stats_line(&stats, 5 * 1024 * 1024, 1024 * 1024, &[]);
 
         // Should contain limit indicators
         let text: String = line.spans.iter().map(|s| s.content.to_string()).collect();
@@ -397,12 +566,33 @@ mod tests {
             total_size: 0,
         };
 
-        let line = build_stats_line(&stats, 0, 0);
+        let line = build_stats_line(&stats, 0, 0, &[]);
         let text: String = line.spans.iter().map(|s| s.content.to_string()).collect();
         assert!(text.contains("errors"));
         assert!(text.contains("2"));
     }
 
+    #[test]
+    fn test_quota_gauge_shows_most_used_quota() {
+        use crate::usage::QuotaPeriod;
+
+        assert!(quota_gauge(&[]).is_none());
+        let quota = [
+            QuotaUse {
+                period: QuotaPeriod::Daily,
+                used: 1,
+                limit: 10,
+            },
+            QuotaUse {
+                period: QuotaPeriod::Monthly,
+                used: 50,
+                limit: 100,
+            },
+        ];
+        let gauge = quota_gauge(&quota).unwrap();
+        assert_eq!(gauge.content, "Month ████░░░░ 50%");
+    }
+
     #[test]
     fn test_global_stats_default() {
         let stats = GlobalStats::default();
@@ -1,5 +1,8 @@
 //! Help popup widget showing all keybindings
 
+use crate::input::KeyAction;
+use crate::keymap::Keymap;
+use crate::models::InputMode;
 use crate::ui::theme::Theme;
 use ratatui::{
     layout::{Alignment, Constraint, Direction, Layout, Rect},
@@ -13,7 +16,7 @@ use ratatui::{
 };
 
 /// Render the help popup
-pub fn render(f: &mut Frame, area: Rect, scroll_offset: usize) {
+pub fn render(f: &mut Frame, area: Rect, scroll_offset: usize, keymap: &Keymap) {
     // Calculate popup size (centered, 70% width, 80% height)
     let popup_area = centered_rect(70, 80, area);
 
@@ -26,13 +29,13 @@ pub fn render(f: &mut Frame, area: Rect, scroll_offset: usize) {
         .border_set(border::ROUNDED)
         .title(" Help - Keyboard Shortcuts ")
         .title_alignment(Alignment::Center)
-        .border_style(Style::default().fg(Theme::INFO));
+        .border_style(Style::default().fg(Theme::current().info));
 
     let inner = block.inner(popup_area);
     f.render_widget(block, popup_area);
 
     // Build help content
-    let help_content = build_help_content();
+    let help_content = build_help_content(keymap);
     let total_lines = help_content.len();
     let visible_lines = inner.height as usize;
 
@@ -72,156 +75,446 @@ pub fn render(f: &mut Frame, area: Rect, scroll_offset: usize) {
         height: 1,
     };
 
+    let close = keymap
+        .label(InputMode::Help, &KeyAction::HelpClose)
+        .unwrap_or_default();
+    let scroll_keys: Vec<String> = [KeyAction::HelpScrollDown, KeyAction::HelpScrollUp]
+        .iter()
+        .filter_map(|action| keymap.label(InputMode::Help, action))
+        .collect();
     let footer = Paragraph::new(Line::from(vec![
-        Span::styled("Press ", Style::default().fg(Theme::TEXT_MUTED)),
+        Span::styled("Press ", Style::default().fg(Theme::current().text_muted)),
         Span::styled(
-            "Esc",
+            close,
             Style::default()
-                .fg(Theme::SECONDARY)
+                .fg(Theme::current().secondary)
                 .add_modifier(Modifier::BOLD),
         ),
-        Span::styled(" or ", Style::default().fg(Theme::TEXT_MUTED)),
         Span::styled(
-            "?",
-            Style::default()
-                .fg(Theme::SECONDARY)
-                .add_modifier(Modifier::BOLD),
+            " to close  |  ",
+            Style::default().fg(Theme::current().text_muted),
         ),
-        Span::styled(" to close  |  ", Style::default().fg(Theme::TEXT_MUTED)),
         Span::styled(
-            "j/k",
+            scroll_keys.join("/"),
             Style::default()
-                .fg(Theme::SECONDARY)
+                .fg(Theme::current().secondary)
                 .add_modifier(Modifier::BOLD),
         ),
-        Span::styled(" to scroll", Style::default().fg(Theme::TEXT_MUTED)),
+        Span::styled(
+            " to scroll",
+            Style::default().fg(Theme::current().text_muted),
+        ),
     ]))
     .alignment(Alignment::Center);
 
     f.render_widget(footer, footer_area);
 }
 
-/// Build the help content
-fn build_help_content() -> Vec<Line<'static>> {
-    let mut lines = vec![];
-
-    // Header
-    lines.push(Line::from(""));
-    lines.push(section_header("Navigation"));
-    lines.push(key_desc("k / Up", "Move selection up"));
-    lines.push(key_desc("j / Down", "Move selection down"));
-    lines.push(key_desc("g / Home", "Go to first item"));
-    lines.push(key_desc("G / End", "Go to last item"));
-    lines.push(key_desc("Page Up / Ctrl+U", "Page up"));
-    lines.push(key_desc("Page Down / Ctrl+D", "Page down"));
-    lines.push(key_desc(
-        "1 / 2 / 3",
-        "Switch to Active/Queue/Completed tab",
-    ));
-
-    lines.push(Line::from(""));
-    lines.push(section_header("Download Management"));
-    lines.push(key_desc("i", "Add new download (enter URL)"));
-    lines.push(key_desc("Space / p", "Pause/Resume selected download"));
-    lines.push(key_desc("d", "Remove download from list"));
-    lines.push(key_desc(
-        "Shift+Delete",
-        "Delete download AND file from disk",
-    ));
-    lines.push(key_desc("r", "Retry failed download"));
-    lines.push(key_desc("x", "Purge all completed downloads"));
-    lines.push(key_desc("Shift+P", "Pause all downloads"));
-    lines.push(key_desc("Shift+R", "Resume all downloads"));
-
-    lines.push(Line::from(""));
-    lines.push(section_header("Queue Management"));
-    lines.push(key_desc("Shift+K / Shift+Up", "Move download up in queue"));
-    lines.push(key_desc(
-        "Shift+J / Shift+Down",
-        "Move download down in queue",
-    ));
-
-    lines.push(Line::from(""));
-    lines.push(section_header("Search & Filter"));
-    lines.push(key_desc("/", "Enter search mode"));
-    lines.push(key_desc("Esc", "Clear search / Cancel"));
+/// A help section: title, then the actions it lists with the mode they are
+/// bound in, then an optional note
+type Section = (
+    &'static str,
+    &'static [(InputMode, KeyAction, &'static str)],
+    Option<&'static str>,
+);
+
+const SECTIONS: &[Section] = &[
+    (
+        "Navigation",
+        &[
+            (InputMode::Normal, KeyAction::MoveUp, "Move selection up"),
+            (
+                InputMode::Normal,
+                KeyAction::MoveDown,
+                "Move selection down",
+            ),
+            (InputMode::Normal, KeyAction::MoveToTop, "Go to first item"),
+            (
+                InputMode::Normal,
+                KeyAction::MoveToBottom,
+                "Go to last item",
+            ),
+            (InputMode::Normal, KeyAction::PageUp, "Page up"),
+            (InputMode::Normal, KeyAction::PageDown, "Page down"),
+            (
+                InputMode::Normal,
+                KeyAction::ToggleDetails,
+                "Show/hide details (small terminals)",
+            ),
+            (
+                InputMode::Normal,
+                KeyAction::SelectTab(0),
+                "Switch to Active tab",
+            ),
+            (
+                InputMode::Normal,
+                KeyAction::SelectTab(1),
+                "Switch to Queue tab",
+            ),
+            (
+                InputMode::Normal,
+                KeyAction::SelectTab(2),
+                "Switch to Completed tab",
+            ),
+            (
+                InputMode::Normal,
+                KeyAction::SelectTab(3),
+                "Switch to History tab",
+            ),
+        ],
+        None,
+    ),
+    (
+        "Download Management",
+        &[
+            (
+                InputMode::Normal,
+                KeyAction::EnterEditMode,
+                "Add new download (enter URL)",
+            ),
+            (
+                InputMode::Normal,
+                KeyAction::PauseResume,
+                "Pause/Resume selected download",
+            ),
+            (
+                InputMode::Normal,
+                KeyAction::Delete,
+                "Remove download from list",
+            ),
+            (
+                InputMode::Normal,
+                KeyAction::DeleteFile,
+                "Delete download AND file from disk",
+            ),
+            (
+                InputMode::Normal,
+                KeyAction::RetryDownload,
+                "Retry failed download",
+            ),
+            (
+                InputMode::Normal,
+                KeyAction::PurgeCompleted,
+                "Purge all completed downloads",
+            ),
+            (
+                InputMode::Normal,
+                KeyAction::PauseAll,
+                "Pause all downloads",
+            ),
+            (
+                InputMode::Normal,
+                KeyAction::ResumeAll,
+                "Resume all downloads",
+            ),
+        ],
+        None,
+    ),
+    (
+        "Queue Management",
+        &[
+            (
+                InputMode::Normal,
+                KeyAction::MoveQueueUp,
+                "Move download up in queue",
+            ),
+            (
+                InputMode::Normal,
+                KeyAction::MoveQueueDown,
+                "Move download down in queue",
+            ),
+        ],
+        None,
+    ),
+    (
+        "Search & Filter",
+        &[
+            (
+                InputMode::Normal,
+                KeyAction::EnterSearchMode,
+                "Enter search mode",
+            ),
+            (InputMode::Normal, KeyAction::ClearSearch, "Clear search"),
+        ],
+        Some("Filters: status:error type:torrent size:>1G speed:<100K progress:<50 url: path: is:stalled, -term, OR, /regex/"),
+    ),
+    (
+        "Sorting",
+        &[
+            (
+                InputMode::Normal,
+                KeyAction::CycleSort,
+                "Cycle sort field (Name -> Size -> Progress -> Speed -> Date -> Status)",
+            ),
+            (
+                InputMode::Normal,
+                KeyAction::ToggleSortDirection,
+                "Toggle sort direction (Ascending/Descending)",
+            ),
+        ],
+        None,
+    ),
+    (
+        "Speed Limits",
+        &[
+            (
+                InputMode::Normal,
+                KeyAction::ShowSpeedLimit,
+                "Open speed limit settings",
+            ),
+            (
+                InputMode::Normal,
+                KeyAction::ToggleTurtle,
+                "Toggle turtle mode (slow speed profile)",
+            ),
+            (
+                InputMode::SpeedLimit,
+                KeyAction::SpeedLimitToggleField,
+                "Switch field",
+            ),
+            (
+                InputMode::SpeedLimit,
+                KeyAction::SpeedLimitToggleScope,
+                "Switch between global and selected-download limits",
+            ),
+            (
+                InputMode::SpeedLimit,
+                KeyAction::SpeedLimitIncrease,
+                "Raise the limit",
+            ),
+            (
+                InputMode::SpeedLimit,
+                KeyAction::SpeedLimitDecrease,
+                "Lower the limit",
+            ),
+            (InputMode::SpeedLimit, KeyAction::SpeedLimitConfirm, "Apply"),
+        ],
+        None,
+    ),
+    (
+        "Scheduler",
+        &[
+            (
+                InputMode::Normal,
+                KeyAction::ShowSchedule,
+                "Open the schedule",
+            ),
+            (InputMode::Schedule, KeyAction::ScheduleAdd, "Add a rule"),
+            (
+                InputMode::Schedule,
+                KeyAction::ScheduleEdit,
+                "Edit the selected rule",
+            ),
+            (
+                InputMode::Schedule,
+                KeyAction::ScheduleDelete,
+                "Delete the selected rule",
+            ),
+            (
+                InputMode::Schedule,
+                KeyAction::ScheduleStartAt,
+                "Start selected downloads at a time",
+            ),
+            (
+                InputMode::Schedule,
+                KeyAction::ScheduleStopAt,
+                "Stop selected downloads at a time",
+            ),
+        ],
+        Some("Rules look like: weekdays 01:00-07:00 resume/pause"),
+    ),
+    (
+        "Command Line",
+        &[(
+            InputMode::Normal,
+            KeyAction::EnterCommandMode,
+            "Open the command line (Tab completes, Up/Down recall)",
+        )],
+        Some("Try :add URL --dir DIR, :limit down 2M, :pause status:active, :sort size desc"),
+    ),
+    (
+        "File Operations",
+        &[
+            (
+                InputMode::Normal,
+                KeyAction::OpenFile,
+                "Open downloaded file",
+            ),
+            (
+                InputMode::Normal,
+                KeyAction::OpenFolder,
+                "Open containing folder",
+            ),
+            (
+                InputMode::Normal,
+                KeyAction::CopyUrl,
+                "Copy download URL to clipboard",
+            ),
+            (
+                InputMode::Normal,
+                KeyAction::CopyPath,
+                "Copy file path to clipboard",
+            ),
+        ],
+        None,
+    ),
+    (
+        "History",
+        &[
+            (
+                InputMode::Normal,
+                KeyAction::SelectTab(3),
+                "Finished and failed downloads, newest first",
+            ),
+            (
+                InputMode::Normal,
+                KeyAction::RetryDownload,
+                "Download the entry's URL again",
+            ),
+        ],
+        Some("Open and copy work on history entries as on downloads"),
+    ),
+    (
+        "Selection (Batch Operations)",
+        &[
+            (
+                InputMode::Normal,
+                KeyAction::ToggleSelect,
+                "Toggle selection on current item",
+            ),
+            (
+                InputMode::Normal,
+                KeyAction::SelectAll,
+                "Select all in current tab",
+            ),
+            (InputMode::Normal, KeyAction::DeselectAll, "Deselect all"),
+        ],
+        Some("Actions on the current item apply to every selected item"),
+    ),
+    (
+        "Statistics",
+        &[
+            (
+                InputMode::Normal,
+                KeyAction::ShowStats,
+                "Statistics dashboard",
+            ),
+            (
+                InputMode::Stats,
+                KeyAction::StatsCycleResolution,
+                "Cycle minute/hour/day resolution",
+            ),
+            (
+                InputMode::Stats,
+                KeyAction::StatsExport,
+                "Export the statistics as CSV",
+            ),
+        ],
+        None,
+    ),
+    (
+        "General",
+        &[
+            (InputMode::Normal, KeyAction::ShowHelp, "Show this help"),
+            (
+                InputMode::Normal,
+                KeyAction::ShowLog,
+                "Show log (hook output)",
+            ),
+            (
+                InputMode::Normal,
+                KeyAction::CycleTheme,
+                "Switch to the next theme",
+            ),
+            (InputMode::Normal, KeyAction::Quit, "Quit application"),
+        ],
+        None,
+    ),
+];
+
+/// Build the help content from the active key bindings
+///
+/// Actions without a key are left out.
+fn build_help_content(keymap: &Keymap) -> Vec<Line<'static>> {
+    let mut lines = vec![Line::from("")];
+    for (i, (title, entries, note)) in SECTIONS.iter().enumerate() {
+        if i > 0 {
+            lines.push(Line::from(""));
+        }
+        lines.push(section_header(title));
+        for (mode, action, desc) in entries.iter() {
+            let keys = keymap.keys(*mode, action);
+            if keys.is_empty() {
+                continue;
+            }
+            let keys: Vec<String> = keys.iter().map(|key| key.to_string()).collect();
+            lines.push(key_desc(keys.join(" / "), desc));
+        }
+        if let Some(note) = note {
+            lines.push(Line::from(Span::styled(
+                format!("  {}", note),
+                Style::default().fg(Theme::current().text_muted),
+            )));
+        }
+    }
 
     lines.push(Line::from(""));
-    lines.push(section_header("Sorting"));
-    lines.push(key_desc(
-        "s",
-        "Cycle sort field (Name -> Size -> Progress -> Speed -> Status)",
-    ));
+    lines.push(section_header("Input Mode (Adding URLs)"));
+    lines.push(key_desc("Enter".to_string(), "Submit URL"));
+    lines.push(key_desc("Esc".to_string(), "Cancel input"));
+    lines.push(key_desc("Ctrl+U".to_string(), "Clear input line"));
+    lines.push(key_desc("Ctrl+W".to_string(), "Delete word backwards"));
     lines.push(key_desc(
-        "S",
-        "Toggle sort direction (Ascending/Descending)",
+        "Ctrl+A / Home".to_string(),
+        "Move cursor to start",
     ));
-
-    lines.push(Line::from(""));
-    lines.push(section_header("Speed Limits"));
-    lines.push(key_desc("l", "Open speed limit settings"));
-
-    lines.push(Line::from(""));
-    lines.push(section_header("File Operations"));
-    lines.push(key_desc("o", "Open downloaded file"));
-    lines.push(key_desc("O", "Open containing folder"));
-    lines.push(key_desc("c", "Copy download URL to clipboard"));
-    lines.push(key_desc("C", "Copy file path to clipboard"));
-
-    lines.push(Line::from(""));
-    lines.push(section_header("Selection (Batch Operations)"));
-    lines.push(key_desc("v", "Toggle selection on current item"));
-    lines.push(key_desc("Ctrl+A", "Select all in current tab"));
-    lines.push(key_desc("Ctrl+D", "Deselect all"));
-
-    lines.push(Line::from(""));
-    lines.push(section_header("Input Mode (Adding URLs)"));
-    lines.push(key_desc("Enter", "Submit URL"));
-    lines.push(key_desc("Esc", "Cancel input"));
-    lines.push(key_desc("Ctrl+U", "Clear input line"));
-    lines.push(key_desc("Ctrl+W", "Delete word backwards"));
-    lines.push(key_desc("Ctrl+A / Home", "Move cursor to start"));
-    lines.push(key_desc("Ctrl+E / End", "Move cursor to end"));
-    lines.push(key_desc("<- / ->", "Move cursor left/right"));
-
-    lines.push(Line::from(""));
-    lines.push(section_header("General"));
-    lines.push(key_desc("?", "Show this help"));
-    lines.push(key_desc("F1", "Show this help"));
-    lines.push(key_desc("q", "Quit application"));
+    lines.push(key_desc("Ctrl+E / End".to_string(), "Move cursor to end"));
+    lines.push(key_desc("<- / ->".to_string(), "Move cursor left/right"));
 
     lines.push(Line::from(""));
     lines.push(section_header("Supported Formats"));
     lines.push(Line::from(vec![
-        Span::styled("  * ", Style::default().fg(Theme::INFO)),
-        Span::styled("HTTP/HTTPS URLs: ", Style::default().fg(Theme::CMD_COLOR)),
+        Span::styled("  * ", Style::default().fg(Theme::current().info)),
+        Span::styled(
+            "HTTP/HTTPS URLs: ",
+            Style::default().fg(Theme::current().cmd_color),
+        ),
         Span::styled(
             "https://example.com/file.zip",
-            Style::default().fg(Theme::TEXT_MUTED),
+            Style::default().fg(Theme::current().text_muted),
         ),
     ]));
     lines.push(Line::from(vec![
-        Span::styled("  * ", Style::default().fg(Theme::INFO)),
-        Span::styled("Magnet links: ", Style::default().fg(Theme::CMD_COLOR)),
+        Span::styled("  * ", Style::default().fg(Theme::current().info)),
+        Span::styled(
+            "Magnet links: ",
+            Style::default().fg(Theme::current().cmd_color),
+        ),
         Span::styled(
             "magnet:?xt=urn:btih:...",
-            Style::default().fg(Theme::TEXT_MUTED),
+            Style::default().fg(Theme::current().text_muted),
         ),
     ]));
     lines.push(Line::from(vec![
-        Span::styled("  * ", Style::default().fg(Theme::INFO)),
-        Span::styled("Torrent files: ", Style::default().fg(Theme::CMD_COLOR)),
+        Span::styled("  * ", Style::default().fg(Theme::current().info)),
+        Span::styled(
+            "Torrent files: ",
+            Style::default().fg(Theme::current().cmd_color),
+        ),
         Span::styled(
             "/path/to/file.torrent",
-            Style::default().fg(Theme::TEXT_MUTED),
+            Style::default().fg(Theme::current().text_muted),
         ),
     ]));
     lines.push(Line::from(vec![
-        Span::styled("  * ", Style::default().fg(Theme::INFO)),
-        Span::styled("Metalink files: ", Style::default().fg(Theme::CMD_COLOR)),
+        Span::styled("  * ", Style::default().fg(Theme::current().info)),
+        Span::styled(
+            "Metalink files: ",
+            Style::default().fg(Theme::current().cmd_color),
+        ),
         Span::styled(
             "/path/to/file.metalink",
-            Style::default().fg(Theme::TEXT_MUTED),
+            Style::default().fg(Theme::current().text_muted),
         ),
     ]));
 
@@ -234,28 +527,28 @@ fn build_help_content() -> Vec<Line<'static>> {
 /// Create a section header line
 fn section_header(title: &'static str) -> Line<'static> {
     Line::from(vec![
-        Span::styled("  -- ", Style::default().fg(Theme::TEXT_MUTED)),
+        Span::styled("  -- ", Style::default().fg(Theme::current().text_muted)),
         Span::styled(
             title,
             Style::default()
-                .fg(Theme::HIGHLIGHT)
+                .fg(Theme::current().highlight)
                 .add_modifier(Modifier::BOLD),
         ),
-        Span::styled(" --", Style::default().fg(Theme::TEXT_MUTED)),
+        Span::styled(" --", Style::default().fg(Theme::current().text_muted)),
     ])
 }
 
 /// Create a key-description line
-fn key_desc(key: &'static str, desc: &'static str) -> Line<'static> {
+fn key_desc(key: String, desc: &'static str) -> Line<'static> {
     Line::from(vec![
         Span::raw("    "),
         Span::styled(
             format!("{:20}", key),
             Style::default()
-                .fg(Theme::SECONDARY)
+                .fg(Theme::current().secondary)
                 .add_modifier(Modifier::BOLD),
         ),
-        Span::styled(desc, Style::default().fg(Theme::CMD_COLOR)),
+        Span::styled(desc, Style::default().fg(Theme::current().cmd_color)),
     ])
 }
 
@@ -286,10 +579,36 @@ mod tests {
 
     #[test]
     fn test_help_content_not_empty() {
-        let content = build_help_content();
+        let content = build_help_content(&Keymap::default());
         assert!(!content.is_empty());
     }
 
+    fn text(lines: &[Line]) -> String {
+        lines
+            .iter()
+            .map(|line| {
+                line.spans
+                    .iter()
+                    .map(|s| s.content.as_ref())
+                    .collect::<String>()
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    #[test]
+    fn test_help_follows_the_keymap() {
+        let help = text(&build_help_content(&Keymap::default()));
+        assert!(help.contains("k / Up"));
+        assert!(help.contains("Shift+Delete"));
+
+        let config: crate::config::KeysConfig =
+            toml::from_str("[normal]\ndelete = \"D\"\ndelete_file = []").unwrap();
+        let help = text(&build_help_content(&Keymap::from_config(&config).unwrap()));
+        assert!(help.contains("D                   Remove download from list"));
+        assert!(!help.contains("Delete download AND file from disk"));
+    }
+
     #[test]
     fn test_centered_rect() {
         let area = Rect::new(0, 0, 100, 100);
@@ -0,0 +1,203 @@
+//! History tab: finished and failed downloads from this and earlier runs
+
+use crate::history::{HistoryEntry, HistoryResult};
+use crate::ui::theme::{Styles, Theme};
+use crate::ui::utils::{format_size, format_speed, truncate_text};
+use ratatui::{
+    layout::Rect,
+    style::{Modifier, Style},
+    symbols::border,
+    text::{Line, Span},
+    widgets::{Block, Borders, List, ListItem, ListState, Paragraph, Wrap},
+    Frame,
+};
+
+/// Timestamp format used in the list and details
+const TIME_FORMAT: &str = "%Y-%m-%d %H:%M";
+
+/// Render the history list
+///
+/// `entries` are already filtered and ordered newest first.
+///
+/// Returns the area of each row on screen with its index in `entries`.
+pub fn render(
+    f: &mut Frame,
+    area: Rect,
+    entries: &[&HistoryEntry],
+    list_state: &mut ListState,
+) -> Vec<(Rect, usize)> {
+    let title = match list_state.selected() {
+        Some(row) if !entries.is_empty() => format!(" History [{}/{}] ", row + 1, entries.len()),
+        _ => " History ".to_string(),
+    };
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_set(border::ROUNDED)
+        .title(title)
+        .border_style(Styles::border());
+
+    if entries.is_empty() {
+        let empty = Paragraph::new(Line::from(Span::styled(
+            "No finished downloads yet",
+            Styles::text_muted(),
+        )))
+        .block(block);
+        f.render_widget(empty, area);
+        return Vec::new();
+    }
+
+    let name_width = area.width.saturating_sub(6) as usize;
+    let items: Vec<ListItem> = entries
+        .iter()
+        .map(|entry| ListItem::new(build_item_lines(entry, name_width)))
+        .collect();
+
+    let inner = block.inner(area);
+    let list = List::new(items)
+        .block(block)
+        .highlight_style(Styles::selected())
+        .highlight_symbol("> ");
+    f.render_stateful_widget(list, area, list_state);
+
+    // Every entry takes two lines
+    (list_state.offset()..entries.len())
+        .map(|index| {
+            let y = inner.y + 2 * (index - list_state.offset()) as u16;
+            (Rect::new(inner.x, y, inner.width, 2), index)
+        })
+        .take_while(|(row, _)| row.bottom() <= inner.bottom())
+        .collect()
+}
+
+/// Name on the first line, finish time, size and speed on the second
+fn build_item_lines(entry: &HistoryEntry, name_width: usize) -> Vec<Line<'static>> {
+    let (icon, style) = match entry.result {
+        HistoryResult::Complete => ("*", Style::default().fg(Theme::current().status_complete)),
+        HistoryResult::Error => ("!", Styles::error()),
+    };
+    vec![
+        Line::from(vec![
+            Span::styled(format!("{} ", icon), style),
+            Span::styled(
+                truncate_text(&entry.name, name_width),
+                Style::default()
+                    .fg(Theme::current().text)
+                    .add_modifier(Modifier::BOLD),
+            ),
+        ]),
+        Line::from(Span::styled(
+            format!(
+                "  {}  {}  {}",
+                entry.finished.format(TIME_FORMAT),
+                format_size(entry.size),
+                format_speed(entry.average_speed)
+            ),
+            Styles::text_muted(),
+        )),
+    ]
+}
+
+/// Render the details of the selected history entry
+pub fn render_details(f: &mut Frame, area: Rect, entry: Option<&HistoryEntry>) {
+    let has_error = entry.is_some_and(|e| e.result == HistoryResult::Error);
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_set(border::ROUNDED)
+        .title(if has_error {
+            " Details [!] "
+        } else {
+            " Details "
+        })
+        .border_style(if has_error {
+            Style::default().fg(Theme::current().error)
+        } else {
+            Style::default().fg(Theme::current().border)
+        });
+
+    let lines = match entry {
+        Some(entry) => build_detail_lines(entry),
+        None => vec![Line::from(Span::styled(
+            "Select an entry to see its details",
+            Styles::text_muted(),
+        ))],
+    };
+    let paragraph = Paragraph::new(lines)
+        .block(block)
+        .wrap(Wrap { trim: false });
+    f.render_widget(paragraph, area);
+}
+
+/// Label/value lines for one history entry
+fn build_detail_lines(entry: &HistoryEntry) -> Vec<Line<'static>> {
+    let field = |label: &str, value: String, style: Style| {
+        Line::from(vec![
+            Span::styled(format!("{:<9}", label), Styles::text_muted()),
+            Span::styled(value, style),
+        ])
+    };
+    let result = match entry.result {
+        HistoryResult::Complete => field("Result", "Complete".to_string(), Styles::success()),
+        HistoryResult::Error => field("Result", "Error".to_string(), Styles::error()),
+    };
+    let not_available = || "-".to_string();
+
+    let mut lines = vec![
+        field("Name", entry.name.clone(), Styles::text()),
+        result,
+        field(
+            "URL",
+            entry.url.clone().unwrap_or_else(not_available),
+            Styles::text(),
+        ),
+        field(
+            "Path",
+            entry.path.clone().unwrap_or_else(not_available),
+            Styles::text(),
+        ),
+        field("Size", format_size(entry.size), Styles::text()),
+        field(
+            "Started",
+            entry.started.format(TIME_FORMAT).to_string(),
+            Styles::text(),
+        ),
+        field(
+            "Finished",
+            entry.finished.format(TIME_FORMAT).to_string(),
+            Styles::text(),
+        ),
+        field("Speed", format_speed(entry.average_speed), Styles::text()),
+    ];
+    if let Some(error) = &entry.error {
+        lines.push(field("Error", error.clone(), Styles::error()));
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Local, TimeZone};
+
+    #[test]
+    fn test_detail_lines_include_error() {
+        let entry = HistoryEntry {
+            gid: Some("g1".to_string()),
+            name: "broken.iso".to_string(),
+            url: None,
+            path: None,
+            size: 0,
+            started: Local.with_ymd_and_hms(2024, 3, 4, 9, 0, 0).unwrap(),
+            finished: Local.with_ymd_and_hms(2024, 3, 4, 9, 30, 0).unwrap(),
+            average_speed: 0,
+            result: HistoryResult::Error,
+            error: Some("Connection refused".to_string()),
+        };
+        let text: Vec<String> = build_detail_lines(&entry)
+            .iter()
+            .map(|line| line.spans.iter().map(|s| s.content.as_ref()).collect())
+            .collect();
+        assert!(text.contains(&"URL      -".to_string()));
+        assert!(text.contains(&"Finished 2024-03-04 09:30".to_string()));
+        assert_eq!(text.last().unwrap(), "Error    Connection refused");
+    }
+}
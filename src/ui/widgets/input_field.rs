@@ -23,9 +23,9 @@ pub fn render(f: &mut Frame, area: Rect, text: &str, mode: InputMode) {
     let is_editing = mode == InputMode::Editing;
 
     let border_style = if is_editing {
-        Style::default().fg(Theme::BORDER_FOCUSED)
+        Style::default().fg(Theme::current().border_focused)
     } else {
-        Style::default().fg(Theme::BORDER)
+        Style::default().fg(Theme::current().border)
     };
 
     let prefix = if is_editing { ">> " } else { "   " };
@@ -36,40 +36,43 @@ pub fn render(f: &mut Frame, area: Rect, text: &str, mode: InputMode) {
             Span::styled(
                 prefix,
                 Style::default()
-                    .fg(Theme::HIGHLIGHT)
+                    .fg(Theme::current().highlight)
                     .add_modifier(Modifier::BOLD),
             ),
             Span::styled(
                 "URL, magnet link, or .torrent/.metalink file path",
-                Style::default().fg(Theme::TEXT_MUTED),
+                Style::default().fg(Theme::current().text_muted),
             ),
             Span::styled(
                 "_",
                 Style::default()
-                    .fg(Theme::HIGHLIGHT)
+                    .fg(Theme::current().highlight)
                     .add_modifier(Modifier::SLOW_BLINK),
             ), // Cursor
         ])
     } else if text.is_empty() {
         Line::from(vec![
-            Span::styled(prefix, Style::default().fg(Theme::TEXT_MUTED)),
-            Span::styled("Press ", Style::default().fg(Theme::TEXT_MUTED)),
+            Span::styled(prefix, Style::default().fg(Theme::current().text_muted)),
+            Span::styled("Press ", Style::default().fg(Theme::current().text_muted)),
             Span::styled(
                 "i",
                 Style::default()
-                    .fg(Theme::HIGHLIGHT)
+                    .fg(Theme::current().highlight)
                     .add_modifier(Modifier::BOLD),
             ),
-            Span::styled(" to add a download", Style::default().fg(Theme::TEXT_MUTED)),
+            Span::styled(
+                " to add a download",
+                Style::default().fg(Theme::current().text_muted),
+            ),
         ])
     } else {
         // Validate and colorize input
         let text_style = if is_valid_input(text) {
-            Style::default().fg(Theme::SUCCESS)
+            Style::default().fg(Theme::current().success)
         } else if is_editing {
-            Style::default().fg(Theme::WARNING)
+            Style::default().fg(Theme::current().warning)
         } else {
-            Style::default().fg(Theme::CMD_COLOR)
+            Style::default().fg(Theme::current().cmd_color)
         };
 
         // Truncate long URLs to prevent performance issues
@@ -87,10 +90,10 @@ pub fn render(f: &mut Frame, area: Rect, text: &str, mode: InputMode) {
                 prefix,
                 if is_editing {
                     Style::default()
-                        .fg(Theme::HIGHLIGHT)
+                        .fg(Theme::current().highlight)
                         .add_modifier(Modifier::BOLD)
                 } else {
-                    Style::default().fg(Theme::TEXT_MUTED)
+                    Style::default().fg(Theme::current().text_muted)
                 },
             ),
             Span::styled(display_str, text_style),
@@ -101,7 +104,7 @@ pub fn render(f: &mut Frame, area: Rect, text: &str, mode: InputMode) {
             spans.push(Span::styled(
                 "_",
                 Style::default()
-                    .fg(Theme::HIGHLIGHT)
+                    .fg(Theme::current().highlight)
                     .add_modifier(Modifier::SLOW_BLINK),
             ));
         }
@@ -0,0 +1,135 @@
+//! Log popup widget showing hook output and other background messages
+
+use crate::models::LogEntry;
+use crate::ui::theme::{Styles, Theme};
+use ratatui::{
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
+    style::Style,
+    symbols::border,
+    text::{Line, Span},
+    widgets::{
+        Block, Borders, Clear, Paragraph, Scrollbar, ScrollbarOrientation, ScrollbarState, Wrap,
+    },
+    Frame,
+};
+
+/// Render the log popup
+///
+/// `scroll_back` counts lines from the bottom, so the newest entries are
+/// shown until the user scrolls up.
+pub fn render(f: &mut Frame, area: Rect, entries: &[LogEntry], scroll_back: usize) {
+    let popup_area = centered_rect(80, 70, area);
+
+    f.render_widget(Clear, popup_area);
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_set(border::ROUNDED)
+        .title(" Log (UTC) ")
+        .title_alignment(Alignment::Center)
+        .border_style(Style::default().fg(Theme::current().info));
+
+    let inner = block.inner(popup_area);
+    f.render_widget(block, popup_area);
+
+    let lines = build_log_lines(entries);
+    let total_lines = lines.len();
+    let visible_lines = inner.height as usize;
+
+    let max_scroll = total_lines.saturating_sub(visible_lines);
+    let scroll = max_scroll - scroll_back.min(max_scroll);
+
+    let paragraph = Paragraph::new(lines)
+        .scroll((scroll as u16, 0))
+        .wrap(Wrap { trim: false });
+    f.render_widget(paragraph, inner);
+
+    if total_lines > visible_lines {
+        let scrollbar_area = Rect {
+            x: popup_area.right() - 1,
+            y: popup_area.y + 1,
+            width: 1,
+            height: popup_area.height.saturating_sub(2),
+        };
+
+        let mut scrollbar_state = ScrollbarState::new(max_scroll).position(scroll);
+        let scrollbar = Scrollbar::new(ScrollbarOrientation::VerticalRight)
+            .begin_symbol(Some("^"))
+            .end_symbol(Some("v"));
+
+        f.render_stateful_widget(scrollbar, scrollbar_area, &mut scrollbar_state);
+    }
+}
+
+/// One line per log message, continuation lines indented under the time
+fn build_log_lines(entries: &[LogEntry]) -> Vec<Line<'static>> {
+    if entries.is_empty() {
+        return vec![Line::from(Span::styled(
+            "Nothing logged yet",
+            Styles::text_muted(),
+        ))];
+    }
+
+    let mut lines = Vec::new();
+    for entry in entries {
+        let style = if entry.is_error {
+            Styles::error()
+        } else {
+            Style::default().fg(Theme::current().cmd_color)
+        };
+
+        for (i, text) in entry.message.lines().enumerate() {
+            let prefix = if i == 0 {
+                format!("{} ", entry.time)
+            } else {
+                " ".repeat(entry.time.len() + 1)
+            };
+            let text_style = if i == 0 { style } else { Styles::text_muted() };
+            lines.push(Line::from(vec![
+                Span::styled(prefix, Styles::text_muted()),
+                Span::styled(text.to_string(), text_style),
+            ]));
+        }
+    }
+    lines
+}
+
+/// Create a centered rectangle with percentage-based dimensions
+fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
+    let popup_layout = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Percentage((100 - percent_y) / 2),
+            Constraint::Percentage(percent_y),
+            Constraint::Percentage((100 - percent_y) / 2),
+        ])
+        .split(r);
+
+    Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([
+            Constraint::Percentage((100 - percent_x) / 2),
+            Constraint::Percentage(percent_x),
+            Constraint::Percentage((100 - percent_x) / 2),
+        ])
+        .split(popup_layout[1])[1]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_multiline_messages_are_indented() {
+        let entries = vec![LogEntry {
+            time: "12:00:00".to_string(),
+            message: "hook clamscan: exit 1\nInfected".to_string(),
+            is_error: true,
+        }];
+        let lines = build_log_lines(&entries);
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0].spans[0].content, "12:00:00 ");
+        assert_eq!(lines[1].spans[0].content, "         ");
+        assert_eq!(lines[1].spans[1].content, "Infected");
+    }
+}
@@ -29,6 +29,8 @@ pub enum PopupType {
 /// * `message` - Message to display
 /// * `popup_type` - Type of popup (affects styling)
 /// * `show_buttons` - Whether to show confirmation buttons
+///
+/// Returns the area of each button with the answer it gives: true for yes.
 pub fn render(
     f: &mut Frame,
     area: Rect,
@@ -36,7 +38,7 @@ pub fn render(
     message: &str,
     popup_type: PopupType,
     show_buttons: bool,
-) {
+) -> Vec<(Rect, bool)> {
     // Calculate popup size (centered, 60% width, auto height)
     let popup_area = centered_rect(60, 40, area);
 
@@ -45,10 +47,10 @@ pub fn render(
 
     // Determine colors based on popup type
     let (border_color, icon) = match popup_type {
-        PopupType::Confirmation => (Theme::WARNING, "[!]"),
-        PopupType::Warning => (Theme::WARNING, "[!]"),
-        PopupType::Error => (Theme::ERROR, "[x]"),
-        PopupType::Info => (Theme::INFO, "[i]"),
+        PopupType::Confirmation => (Theme::current().warning, "[!]"),
+        PopupType::Warning => (Theme::current().warning, "[!]"),
+        PopupType::Error => (Theme::current().error, "[x]"),
+        PopupType::Info => (Theme::current().info, "[i]"),
     };
 
     // Create the popup block with rounded borders
@@ -83,7 +85,7 @@ pub fn render(
         .map(|line| {
             Line::from(vec![Span::styled(
                 line,
-                Style::default().fg(Theme::CMD_COLOR),
+                Style::default().fg(Theme::current().cmd_color),
             )])
         })
         .collect();
@@ -94,35 +96,46 @@ pub fn render(
 
     f.render_widget(message_paragraph, layout[0]);
 
-    // Render buttons if needed
-    if show_buttons {
-        let buttons = Line::from(vec![
-            Span::styled(
-                "Y",
-                Style::default()
-                    .fg(Theme::SUCCESS)
-                    .add_modifier(Modifier::BOLD),
-            ),
-            Span::styled(" yes  ", Style::default().fg(Theme::TEXT_MUTED)),
-            Span::styled(
-                "N",
-                Style::default()
-                    .fg(Theme::ERROR)
-                    .add_modifier(Modifier::BOLD),
-            ),
-            Span::styled(" no  ", Style::default().fg(Theme::TEXT_MUTED)),
-            Span::styled(
-                "Esc",
-                Style::default()
-                    .fg(Theme::SECONDARY)
-                    .add_modifier(Modifier::BOLD),
-            ),
-            Span::styled(" cancel", Style::default().fg(Theme::TEXT_MUTED)),
-        ]);
+    if !show_buttons {
+        return Vec::new();
+    }
 
-        let buttons_paragraph = Paragraph::new(buttons).alignment(Alignment::Center);
-        f.render_widget(buttons_paragraph, layout[1]);
+    // Buttons: key, label, answer
+    let theme = Theme::current();
+    let buttons = [
+        ("Y", " yes", theme.success, true),
+        ("N", " no", theme.error, false),
+        ("Esc", " cancel", theme.secondary, false),
+    ];
+    let gap = "  ";
+    let width: usize = buttons
+        .iter()
+        .map(|(key, label, _, _)| key.len() + label.len())
+        .sum::<usize>()
+        + gap.len() * (buttons.len() - 1);
+
+    let row = layout[1];
+    let mut x = row.x + row.width.saturating_sub(width as u16) / 2;
+    let mut spans = Vec::new();
+    let mut areas = Vec::new();
+    for (i, (key, label, color, answer)) in buttons.into_iter().enumerate() {
+        if i > 0 {
+            spans.push(Span::raw(gap));
+            x += gap.len() as u16;
+        }
+        spans.push(Span::styled(
+            key,
+            Style::default().fg(color).add_modifier(Modifier::BOLD),
+        ));
+        spans.push(Span::styled(label, Style::default().fg(theme.text_muted)));
+        let button_width = (key.len() + label.len()) as u16;
+        areas.push((Rect::new(x, row.y, button_width, 1), answer));
+        x += button_width;
     }
+
+    let buttons_paragraph = Paragraph::new(Line::from(spans)).alignment(Alignment::Center);
+    f.render_widget(buttons_paragraph, row);
+    areas
 }
 
 /// Render a size warning overlay
@@ -153,7 +166,7 @@ pub fn render_size_warning(
         .borders(Borders::ALL)
         .border_set(border::ROUNDED)
         .title(" [!] Terminal Too Small ")
-        .border_style(Style::default().fg(Theme::WARNING));
+        .border_style(Style::default().fg(Theme::current().warning));
 
     let inner = block.inner(popup_area);
     f.render_widget(block, popup_area);
@@ -164,43 +177,52 @@ pub fn render_size_warning(
         Line::from(vec![Span::styled(
             "Terminal size is too small!",
             Style::default()
-                .fg(Theme::WARNING)
+                .fg(Theme::current().warning)
                 .add_modifier(Modifier::BOLD),
         )]),
         Line::from(""),
         Line::from(vec![
-            Span::styled("Current: ", Style::default().fg(Theme::TEXT_MUTED)),
+            Span::styled(
+                "Current: ",
+                Style::default().fg(Theme::current().text_muted),
+            ),
             Span::styled(
                 format!("{}x{}", current_width, current_height),
                 Style::default()
-                    .fg(Theme::ERROR)
+                    .fg(Theme::current().error)
                     .add_modifier(Modifier::BOLD),
             ),
         ]),
         Line::from(vec![
-            Span::styled("Required: ", Style::default().fg(Theme::TEXT_MUTED)),
+            Span::styled(
+                "Required: ",
+                Style::default().fg(Theme::current().text_muted),
+            ),
             Span::styled(
                 format!("{}x{}", min_width, min_height),
                 Style::default()
-                    .fg(Theme::SUCCESS)
+                    .fg(Theme::current().success)
                     .add_modifier(Modifier::BOLD),
             ),
         ]),
         Line::from(""),
         Line::from(vec![Span::styled(
             "Please resize your terminal",
-            Style::default().fg(Theme::TEXT_MUTED),
+            Style::default().fg(Theme::current().text_muted),
         )]),
         Line::from(""),
         Line::from(vec![
-            Span::styled("Press ", Style::default().fg(Theme::TEXT_MUTED)),
+            Span::styled("Press ", Style::default().fg(Theme::current().text_muted)),
             Span::styled(
                 "Q",
                 Style::default()
-                    .fg(Theme::ERROR)
+                    .fg(Theme::current().error)
                     .add_modifier(Modifier::BOLD),
             ),
-            Span::styled(" to quit anyway", Style::default().fg(Theme::TEXT_MUTED)),
+            Span::styled(
+                " to quit anyway",
+                Style::default().fg(Theme::current().text_muted),
+            ),
         ]),
     ];
 
@@ -0,0 +1,227 @@
+//! Schedule popup widget for editing queue rules and per-download timers
+
+use crate::scheduler::Schedule;
+use crate::ui::theme::{Styles, Theme};
+use ratatui::{
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
+    style::{Modifier, Style},
+    symbols::border,
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph},
+    Frame,
+};
+
+/// What the text field of the popup is editing
+#[derive(Clone, Debug, PartialEq)]
+pub enum ScheduleEdit {
+    Rule(Option<usize>),  // Index of the rule, `None` for a new one
+    StartAt(Vec<String>), // GIDs to start at the entered time
+    StopAt(Vec<String>),  // GIDs to stop at the entered time
+}
+
+impl ScheduleEdit {
+    fn prompt(&self) -> &'static str {
+        match self {
+            ScheduleEdit::Rule(_) => "Rule (e.g. weekdays 01:00-07:00 resume/pause)",
+            ScheduleEdit::StartAt(_) => "Start at HH:MM (empty to clear)",
+            ScheduleEdit::StopAt(_) => "Stop at HH:MM (empty to clear)",
+        }
+    }
+}
+
+/// Schedule popup state
+#[derive(Clone, Debug, Default)]
+pub struct ScheduleState {
+    pub selected: usize, // Row in the rules list followed by the timers
+    pub editing: Option<ScheduleEdit>,
+    pub error: Option<String>, // Why the last entry was rejected
+}
+
+impl ScheduleState {
+    /// Move the selection by `delta` rows within `rows`
+    pub fn move_selection(&mut self, delta: isize, rows: usize) {
+        let last = rows.saturating_sub(1) as isize;
+        self.selected = (self.selected as isize + delta).clamp(0, last) as usize;
+    }
+}
+
+/// Render the schedule popup
+///
+/// `input` is the text being typed while `state.editing` is set.
+pub fn render(f: &mut Frame, area: Rect, schedule: &Schedule, state: &ScheduleState, input: &str) {
+    let popup_area = centered_rect(70, 60, area);
+
+    f.render_widget(Clear, popup_area);
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_set(border::ROUNDED)
+        .title(" Schedule ")
+        .title_alignment(Alignment::Center)
+        .border_style(Style::default().fg(Theme::current().info));
+
+    let inner = block.inner(popup_area);
+    f.render_widget(block, popup_area);
+
+    let layout = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Min(3),    // Rules and timers
+            Constraint::Length(3), // Input field
+            Constraint::Length(1), // Error or hint
+        ])
+        .split(inner);
+
+    let lines = build_rows(schedule, state.selected);
+    f.render_widget(Paragraph::new(lines), layout[0]);
+
+    if let Some(edit) = &state.editing {
+        let field = Paragraph::new(Line::from(vec![
+            Span::styled(
+                input.to_string(),
+                Style::default().fg(Theme::current().text),
+            ),
+            Span::styled("_", Style::default().fg(Theme::current().highlight)),
+        ]))
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_type(ratatui::widgets::BorderType::Rounded)
+                .title(format!(" {} ", edit.prompt()))
+                .border_style(Style::default().fg(Theme::current().highlight)),
+        );
+        f.render_widget(field, layout[1]);
+    }
+
+    let footer = match &state.error {
+        Some(error) => Line::from(Span::styled(error.clone(), Styles::error())),
+        None => Line::from(Span::styled(
+            "Days: daily, weekdays, weekends or mon,tue,...  Actions: resume, pause, none",
+            Styles::text_muted(),
+        )),
+    };
+    f.render_widget(
+        Paragraph::new(footer).alignment(Alignment::Center),
+        layout[2],
+    );
+}
+
+/// Rules followed by download timers, the selected row highlighted
+fn build_rows(schedule: &Schedule, selected: usize) -> Vec<Line<'static>> {
+    let mut lines = vec![section("Queue rules")];
+    let mut rows: Vec<String> = schedule.rules.iter().map(|r| r.to_string()).collect();
+    if rows.is_empty() {
+        lines.push(Line::from(Span::styled(
+            "  No rules (press a to add one)",
+            Styles::text_muted(),
+        )));
+    }
+
+    let timers = schedule.timers.iter().map(|t| {
+        let mut text = t.name.clone();
+        if let Some(at) = t.start_at {
+            text.push_str(&format!("  start {}", at.format("%a %H:%M")));
+        }
+        if let Some(at) = t.stop_at {
+            text.push_str(&format!("  stop {}", at.format("%a %H:%M")));
+        }
+        text
+    });
+    rows.extend(timers);
+
+    for (i, text) in rows.into_iter().enumerate() {
+        if i == schedule.rules.len() {
+            lines.push(Line::from(""));
+            lines.push(section("Download timers"));
+        }
+        let line = if i == selected {
+            Line::from(Span::styled(
+                format!("> {}", text),
+                Style::default()
+                    .fg(Theme::current().highlight)
+                    .add_modifier(Modifier::BOLD),
+            ))
+        } else {
+            Line::from(Span::styled(
+                format!("  {}", text),
+                Style::default().fg(Theme::current().text),
+            ))
+        };
+        lines.push(line);
+    }
+    lines
+}
+
+fn section(title: &'static str) -> Line<'static> {
+    Line::from(Span::styled(
+        title,
+        Style::default()
+            .fg(Theme::current().secondary)
+            .add_modifier(Modifier::BOLD),
+    ))
+}
+
+/// Create a centered rectangle with percentage-based dimensions
+fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
+    let popup_layout = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Percentage((100 - percent_y) / 2),
+            Constraint::Percentage(percent_y),
+            Constraint::Percentage((100 - percent_y) / 2),
+        ])
+        .split(r);
+
+    Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([
+            Constraint::Percentage((100 - percent_x) / 2),
+            Constraint::Percentage(percent_x),
+            Constraint::Percentage((100 - percent_x) / 2),
+        ])
+        .split(popup_layout[1])[1]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scheduler::ScheduleRule;
+
+    #[test]
+    fn test_rows_list_rules_then_timers() {
+        let mut schedule = Schedule {
+            rules: vec![ScheduleRule::parse("daily 01:00-07:00 resume/pause").unwrap()],
+            timers: Vec::new(),
+        };
+        let start = chrono::NaiveDate::from_ymd_opt(2024, 1, 1)
+            .unwrap()
+            .and_hms_opt(3, 0, 0)
+            .unwrap();
+        schedule.timer_mut("g1", "big.iso").start_at = Some(start);
+
+        let lines = build_rows(&schedule, 1);
+        let text: Vec<String> = lines
+            .iter()
+            .map(|l| l.spans.iter().map(|s| s.content.as_ref()).collect())
+            .collect();
+        assert_eq!(
+            text,
+            vec![
+                "Queue rules",
+                "  daily 01:00-07:00 resume/pause",
+                "",
+                "Download timers",
+                "> big.iso  start Mon 03:00",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_move_selection_is_clamped() {
+        let mut state = ScheduleState::default();
+        state.move_selection(-1, 3);
+        assert_eq!(state.selected, 0);
+        state.move_selection(5, 3);
+        assert_eq!(state.selected, 2);
+    }
+}
@@ -1,5 +1,6 @@
 //! Search bar widget for filtering downloads
 
+use crate::query::{Query, QueryError};
 use crate::ui::theme::Theme;
 use ratatui::{
     layout::{Alignment, Constraint, Direction, Layout, Rect},
@@ -12,6 +13,9 @@ use ratatui::{
 
 /// Render the search bar overlay
 ///
+/// A query that does not parse has the faulty part highlighted and the
+/// error shown under the bar.
+///
 /// # Arguments
 /// * `f` - Frame to render to
 /// * `area` - Full screen area
@@ -25,12 +29,22 @@ pub fn render(f: &mut Frame, area: Rect, query: &str, result_count: usize, total
     // Clear the area behind the search bar
     f.render_widget(Clear, search_area);
 
+    let error = Query::parse(query).err();
+
     // Create the search bar block with rounded borders
-    let block = Block::default()
+    let mut block = Block::default()
         .borders(Borders::ALL)
         .border_set(border::ROUNDED)
         .title(" Search ")
-        .border_style(Style::default().fg(Theme::HIGHLIGHT));
+        .border_style(Style::default().fg(Theme::current().highlight));
+    if let Some(error) = &error {
+        block = block
+            .border_style(Style::default().fg(Theme::current().error))
+            .title_bottom(Line::styled(
+                format!(" {} ", error.message),
+                Style::default().fg(Theme::current().error),
+            ));
+    }
 
     let inner = block.inner(search_area);
     f.render_widget(block, search_area);
@@ -46,7 +60,7 @@ pub fn render(f: &mut Frame, area: Rect, query: &str, result_count: usize, total
         .split(inner);
 
     // Render query section (left)
-    let query_line = build_query_section(query);
+    let query_line = build_query_section(query, error.as_ref());
     let query_para = Paragraph::new(query_line).alignment(Alignment::Left);
     f.render_widget(query_para, layout[0]);
 
@@ -62,14 +76,14 @@ pub fn render(f: &mut Frame, area: Rect, query: &str, result_count: usize, total
 }
 
 /// Build the query section of the search bar
-fn build_query_section(query: &str) -> Line<'static> {
+fn build_query_section(query: &str, error: Option<&QueryError>) -> Line<'static> {
     let mut spans = vec![];
 
     // Search prompt
     spans.push(Span::styled(
         ">> ",
         Style::default()
-            .fg(Theme::HIGHLIGHT)
+            .fg(Theme::current().highlight)
             .add_modifier(Modifier::BOLD),
     ));
 
@@ -77,17 +91,28 @@ fn build_query_section(query: &str) -> Line<'static> {
     if query.is_empty() {
         spans.push(Span::styled(
             "Type to search...",
-            Style::default().fg(Theme::TEXT_MUTED),
+            Style::default().fg(Theme::current().text_muted),
         ));
     } else {
-        spans.push(Span::styled(
-            query.to_string(),
-            Style::default().fg(Theme::CMD_COLOR),
-        ));
+        let style = Style::default().fg(Theme::current().cmd_color);
+        match error {
+            Some(error) => {
+                let span = error.span.clone();
+                spans.push(Span::styled(query[..span.start].to_string(), style));
+                spans.push(Span::styled(
+                    query[span.clone()].to_string(),
+                    Style::default()
+                        .fg(Theme::current().error)
+                        .add_modifier(Modifier::UNDERLINED),
+                ));
+                spans.push(Span::styled(query[span.end..].to_string(), style));
+            }
+            None => spans.push(Span::styled(query.to_string(), style)),
+        }
         spans.push(Span::styled(
             "_",
             Style::default()
-                .fg(Theme::HIGHLIGHT)
+                .fg(Theme::current().highlight)
                 .add_modifier(Modifier::SLOW_BLINK),
         )); // Cursor
     }
@@ -99,25 +124,28 @@ fn build_query_section(query: &str) -> Line<'static> {
 fn build_results_section(result_count: usize, total_count: usize) -> Line<'static> {
     let mut spans = vec![];
 
-    spans.push(Span::styled("| ", Style::default().fg(Theme::TEXT_MUTED)));
+    spans.push(Span::styled(
+        "| ",
+        Style::default().fg(Theme::current().text_muted),
+    ));
 
     if result_count == total_count {
         spans.push(Span::styled(
             format!("{} items", total_count),
-            Style::default().fg(Theme::TEXT_MUTED),
+            Style::default().fg(Theme::current().text_muted),
         ));
     } else {
         spans.push(Span::styled(
             format!("{}", result_count),
             if result_count > 0 {
-                Style::default().fg(Theme::SUCCESS)
+                Style::default().fg(Theme::current().success)
             } else {
-                Style::default().fg(Theme::ERROR)
+                Style::default().fg(Theme::current().error)
             },
         ));
         spans.push(Span::styled(
             format!("/{}", total_count),
-            Style::default().fg(Theme::TEXT_MUTED),
+            Style::default().fg(Theme::current().text_muted),
         ));
     }
 
@@ -130,17 +158,17 @@ fn build_hints_section() -> Line<'static> {
         Span::styled(
             "Enter",
             Style::default()
-                .fg(Theme::SECONDARY)
+                .fg(Theme::current().secondary)
                 .add_modifier(Modifier::BOLD),
         ),
-        Span::styled(" apply ", Style::default().fg(Theme::TEXT_MUTED)),
+        Span::styled(" apply ", Style::default().fg(Theme::current().text_muted)),
         Span::styled(
             "Esc",
             Style::default()
-                .fg(Theme::SECONDARY)
+                .fg(Theme::current().secondary)
                 .add_modifier(Modifier::BOLD),
         ),
-        Span::styled(" clear", Style::default().fg(Theme::TEXT_MUTED)),
+        Span::styled(" clear", Style::default().fg(Theme::current().text_muted)),
     ])
 }
 
@@ -184,19 +212,33 @@ mod tests {
 
     #[test]
     fn test_build_query_section_empty() {
-        let line = build_query_section("");
+        let line = build_query_section("", None);
         let text: String = line.spans.iter().map(|s| s.content.to_string()).collect();
         assert!(text.contains("Type to search"));
     }
 
     #[test]
     fn test_build_query_section_with_query() {
-        let line = build_query_section("test");
+        let line = build_query_section("test", None);
         let text: String = line.spans.iter().map(|s| s.content.to_string()).collect();
         assert!(text.contains("test"));
         assert!(text.contains("_")); // Cursor
     }
 
+    #[test]
+    fn test_build_query_section_marks_the_error() {
+        let query = "iso status:done -x";
+        let error = Query::parse(query).unwrap_err();
+        let line = build_query_section(query, Some(&error));
+        let marked: Vec<&str> = line
+            .spans
+            .iter()
+            .filter(|s| s.style.add_modifier.contains(Modifier::UNDERLINED))
+            .map(|s| s.content.as_ref())
+            .collect();
+        assert_eq!(marked, vec!["status:done"]);
+    }
+
     #[test]
     fn test_build_results_section_all_match() {
         let line = build_results_section(10, 10);
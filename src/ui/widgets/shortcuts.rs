@@ -1,7 +1,12 @@
 //! Shortcuts guide widget for displaying keyboard shortcuts
+//!
+//! The hints for each mode name actions rather than keys; their labels come
+//! from the active [`Keymap`], so a rebound key shows up here too.
 
 #![allow(dead_code)]
 
+use crate::input::KeyAction;
+use crate::keymap::Keymap;
 use crate::models::InputMode;
 use crate::ui::theme::Theme;
 use ratatui::{
@@ -12,14 +17,169 @@ use ratatui::{
     Frame,
 };
 
+/// One entry of a shortcuts guide
+pub enum Hint {
+    /// Actions labelled with their first key, joined by `/`; three or more
+    /// show as a range such as `1-4`
+    Keys(&'static [KeyAction], &'static str),
+    /// Editing keys that cannot be rebound
+    Fixed(&'static str, &'static str),
+}
+
+const NORMAL_HINTS: &[Hint] = &[
+    Hint::Keys(&[KeyAction::EnterEditMode], "add"),
+    Hint::Keys(&[KeyAction::EnterSearchMode], "search"),
+    Hint::Keys(&[KeyAction::PauseResume], "pause"),
+    Hint::Keys(&[KeyAction::Delete], "delete"),
+    Hint::Keys(
+        &[
+            KeyAction::SelectTab(0),
+            KeyAction::SelectTab(1),
+            KeyAction::SelectTab(2),
+            KeyAction::SelectTab(3),
+        ],
+        "tabs",
+    ),
+    Hint::Keys(&[KeyAction::ShowHelp], "help"),
+    Hint::Keys(&[KeyAction::Quit], "quit"),
+];
+
+const EDITING_HINTS: &[Hint] = &[
+    Hint::Fixed("Enter", "submit"),
+    Hint::Fixed("Esc", "cancel"),
+    Hint::Fixed("Ctrl+U", "clear"),
+];
+
+const SEARCH_HINTS: &[Hint] = &[
+    Hint::Fixed("Enter", "apply"),
+    Hint::Fixed("Esc", "clear"),
+    Hint::Fixed("Backspace", "delete"),
+];
+
+const SPEED_LIMIT_HINTS: &[Hint] = &[
+    Hint::Keys(&[KeyAction::SpeedLimitToggleField], "switch"),
+    Hint::Keys(&[KeyAction::SpeedLimitToggleScope], "scope"),
+    Hint::Keys(
+        &[KeyAction::SpeedLimitDecrease, KeyAction::SpeedLimitIncrease],
+        "adjust",
+    ),
+    Hint::Keys(&[KeyAction::SpeedLimitConfirm], "apply"),
+    Hint::Keys(&[KeyAction::SpeedLimitCancel], "cancel"),
+];
+
+const HELP_HINTS: &[Hint] = &[
+    Hint::Keys(
+        &[KeyAction::HelpScrollDown, KeyAction::HelpScrollUp],
+        "scroll",
+    ),
+    Hint::Keys(&[KeyAction::HelpClose], "close"),
+];
+
+const LOG_HINTS: &[Hint] = &[
+    Hint::Keys(
+        &[KeyAction::LogScrollDown, KeyAction::LogScrollUp],
+        "scroll",
+    ),
+    Hint::Keys(&[KeyAction::LogClose], "close"),
+];
+
+const STATS_HINTS: &[Hint] = &[
+    Hint::Keys(&[KeyAction::StatsCycleResolution], "resolution"),
+    Hint::Keys(&[KeyAction::StatsExport], "export CSV"),
+    Hint::Keys(&[KeyAction::StatsClose], "close"),
+];
+
+const SCHEDULE_HINTS: &[Hint] = &[
+    Hint::Keys(&[KeyAction::ScheduleAdd], "add"),
+    Hint::Keys(&[KeyAction::ScheduleEdit], "edit"),
+    Hint::Keys(&[KeyAction::ScheduleDelete], "delete"),
+    Hint::Keys(
+        &[KeyAction::ScheduleStartAt, KeyAction::ScheduleStopAt],
+        "start/stop at",
+    ),
+    Hint::Keys(&[KeyAction::ScheduleClose], "close"),
+];
+
+const SCHEDULE_EDIT_HINTS: &[Hint] = &[Hint::Fixed("Enter", "save"), Hint::Fixed("Esc", "cancel")];
+
+const COMMAND_HINTS: &[Hint] = &[
+    Hint::Fixed("Enter", "run"),
+    Hint::Fixed("Tab", "complete"),
+    Hint::Fixed("Up/Down", "history"),
+    Hint::Fixed("Esc", "cancel"),
+];
+
+const CONFIRMATION_HINTS: &[Hint] = &[
+    Hint::Keys(&[KeyAction::ConfirmYes], "yes"),
+    Hint::Keys(&[KeyAction::ConfirmNo], "no"),
+];
+
+const MENU_HINTS: &[Hint] = &[
+    Hint::Keys(&[KeyAction::MenuDown, KeyAction::MenuUp], "move"),
+    Hint::Keys(&[KeyAction::MenuSelect], "run"),
+    Hint::Keys(&[KeyAction::MenuClose], "close"),
+];
+
+const SETTINGS_HINTS: &[Hint] = &[
+    Hint::Fixed("j/k", "navigate"),
+    Hint::Fixed("Enter", "edit"),
+    Hint::Fixed("Esc", "close"),
+];
+
+/// Scope name and hints of a mode
+fn hints(mode: InputMode) -> (&'static str, &'static [Hint]) {
+    match mode {
+        InputMode::Normal => ("Downloads", NORMAL_HINTS),
+        InputMode::Editing => ("Add Download", EDITING_HINTS),
+        InputMode::Search => ("Search", SEARCH_HINTS),
+        InputMode::SpeedLimit => ("Speed Limit", SPEED_LIMIT_HINTS),
+        InputMode::Help => ("Help", HELP_HINTS),
+        InputMode::Log => ("Log", LOG_HINTS),
+        InputMode::Stats => ("Statistics", STATS_HINTS),
+        InputMode::Schedule => ("Schedule", SCHEDULE_HINTS),
+        InputMode::ScheduleEdit => ("Edit Schedule", SCHEDULE_EDIT_HINTS),
+        InputMode::Confirmation => ("Confirm", CONFIRMATION_HINTS),
+        InputMode::Menu => ("Menu", MENU_HINTS),
+        InputMode::Command => ("Command", COMMAND_HINTS),
+        InputMode::Settings => ("Settings", SETTINGS_HINTS),
+    }
+}
+
+/// Scope name and (key, description) pairs of a mode under `keymap`
+///
+/// Hints whose actions have no key are left out.
+pub fn resolve(mode: InputMode, keymap: &Keymap) -> (&'static str, Vec<(String, &'static str)>) {
+    let (scope, hints) = hints(mode);
+    let pairs = hints
+        .iter()
+        .filter_map(|hint| match hint {
+            Hint::Fixed(key, desc) => Some((key.to_string(), *desc)),
+            Hint::Keys(actions, desc) => {
+                let labels: Vec<String> = actions
+                    .iter()
+                    .filter_map(|action| keymap.label(mode, action))
+                    .collect();
+                let key = match labels.as_slice() {
+                    [] => return None,
+                    [first, .., last] if labels.len() > 2 => format!("{}-{}", first, last),
+                    _ => labels.join("/"),
+                };
+                Some((key, *desc))
+            }
+        })
+        .collect();
+    (scope, pairs)
+}
+
 /// Render the shortcuts guide widget
 ///
 /// # Arguments
 /// * `f` - Frame to render to
 /// * `area` - Area to render in
 /// * `mode` - Current input mode (determines which shortcuts to show)
-pub fn render(f: &mut Frame, area: Rect, mode: InputMode) {
-    render_with_search(f, area, mode, false)
+/// * `keymap` - Active key bindings
+pub fn render(f: &mut Frame, area: Rect, mode: InputMode, keymap: &Keymap) {
+    render_with_search(f, area, mode, false, keymap)
 }
 
 /// Render the shortcuts guide widget with optional search indicator
@@ -29,275 +189,107 @@ pub fn render(f: &mut Frame, area: Rect, mode: InputMode) {
 /// * `area` - Area to render in
 /// * `mode` - Current input mode (determines which shortcuts to show)
 /// * `has_search` - Whether a search filter is active
-pub fn render_with_search(f: &mut Frame, area: Rect, mode: InputMode, has_search: bool) {
-    let shortcuts = get_shortcuts_for_mode(mode, has_search);
+/// * `keymap` - Active key bindings
+pub fn render_with_search(
+    f: &mut Frame,
+    area: Rect,
+    mode: InputMode,
+    has_search: bool,
+    keymap: &Keymap,
+) {
+    let shortcuts = get_shortcuts_for_mode(mode, has_search, keymap);
 
     let paragraph = Paragraph::new(shortcuts).alignment(Alignment::Center);
 
     f.render_widget(paragraph, area);
 }
 
-/// Get shortcuts based on input mode
-fn get_shortcuts_for_mode(mode: InputMode, has_search: bool) -> Vec<Line<'static>> {
-    match mode {
-        InputMode::Editing => editing_mode_shortcuts(),
-        InputMode::Search => search_mode_shortcuts(),
-        InputMode::SpeedLimit => speed_limit_mode_shortcuts(),
-        InputMode::Help => help_mode_shortcuts(),
-        InputMode::Confirmation => confirmation_mode_shortcuts(),
-        InputMode::Settings => settings_mode_shortcuts(),
-        InputMode::Normal => normal_mode_shortcuts(has_search),
+/// Get shortcuts based on input mode, laid out in two rows
+fn get_shortcuts_for_mode(
+    mode: InputMode,
+    has_search: bool,
+    keymap: &Keymap,
+) -> Vec<Line<'static>> {
+    let (scope, mut pairs) = resolve(mode, keymap);
+    if has_search && mode == InputMode::Normal {
+        if let Some(key) = keymap.label(mode, &KeyAction::ClearSearch) {
+            pairs.insert(2, (key, "clear"));
+        }
     }
-}
 
-/// Shortcuts for normal mode
-fn normal_mode_shortcuts(has_search: bool) -> Vec<Line<'static>> {
-    if has_search {
-        vec![
-            Line::from(vec![
-                key("i"),
-                desc(" add   "),
-                key("/"),
-                desc(" search   "),
-                key("Esc"),
-                desc(" clear   "),
-                key("j/k"),
-                desc(" move   "),
-                key("Space"),
-                desc(" pause   "),
-                key("?"),
-                desc(" help"),
-            ]),
-            Line::from(vec![
-                key("d"),
-                desc(" delete   "),
-                key("r"),
-                desc(" retry   "),
-                key("s"),
-                desc(" sort   "),
-                key("l"),
-                desc(" limits   "),
-                key("q"),
-                desc(" quit"),
-            ]),
-        ]
-    } else {
-        vec![
-            Line::from(vec![
-                key("i"),
-                desc(" add   "),
-                key("/"),
-                desc(" search   "),
-                key("Space"),
-                desc(" pause   "),
-                key("d"),
-                desc(" delete   "),
-                key("?"),
-                desc(" help"),
-            ]),
-            Line::from(vec![
-                key("r"),
-                desc(" retry   "),
-                key("s"),
-                desc(" sort   "),
-                key("l"),
-                desc(" limits   "),
-                key("o"),
-                desc(" open   "),
-                key("1-3"),
-                desc(" tabs   "),
-                key("q"),
-                desc(" quit"),
-            ]),
-        ]
+    let mut first = vec![Span::styled(
+        format!("[{}] ", scope),
+        Style::default().fg(Theme::current().info),
+    )];
+    let mut second = Vec::new();
+    let split = pairs.len().div_ceil(2);
+    for (i, (text, description)) in pairs.into_iter().enumerate() {
+        let row = if i < split { &mut first } else { &mut second };
+        row.push(key(text));
+        row.push(desc(format!(" {}   ", description)));
     }
-}
-
-/// Shortcuts for editing mode
-fn editing_mode_shortcuts() -> Vec<Line<'static>> {
-    vec![
-        Line::from(vec![
-            key("Enter"),
-            desc(" submit   "),
-            key("Esc"),
-            desc(" cancel   "),
-            key("Ctrl+U"),
-            desc(" clear   "),
-            key("Ctrl+W"),
-            desc(" del word"),
-        ]),
-        Line::from(vec![
-            key("<- ->"),
-            desc(" move   "),
-            key("Home/End"),
-            desc(" start/end   "),
-            key("Backspace"),
-            desc(" delete   "),
-            key("Ctrl+V"),
-            desc(" paste"),
-        ]),
-    ]
-}
-
-/// Shortcuts for search mode
-fn search_mode_shortcuts() -> Vec<Line<'static>> {
-    vec![
-        Line::from(vec![
-            Span::styled("[/] ", Style::default().fg(Theme::HIGHLIGHT)),
-            desc("Type to filter   "),
-            key("Enter"),
-            desc(" apply   "),
-            key("Esc"),
-            desc(" clear & exit   "),
-            key("Backspace"),
-            desc(" delete"),
-        ]),
-        Line::from(vec![]),
-    ]
-}
-
-/// Shortcuts for speed limit mode
-fn speed_limit_mode_shortcuts() -> Vec<Line<'static>> {
-    vec![
-        Line::from(vec![
-            Span::styled("[!] ", Style::default().fg(Theme::WARNING)),
-            desc("Set bandwidth   "),
-            key("Tab"),
-            desc(" switch DL/UL   "),
-            key("j/k"),
-            desc(" adjust   "),
-            key("Enter"),
-            desc(" apply   "),
-            key("Esc"),
-            desc(" cancel"),
-        ]),
-        Line::from(vec![]),
-    ]
-}
-
-/// Shortcuts for help mode
-fn help_mode_shortcuts() -> Vec<Line<'static>> {
-    vec![
-        Line::from(vec![
-            Span::styled("[?] ", Style::default().fg(Theme::INFO)),
-            desc("Viewing help   "),
-            key("j/k"),
-            desc(" scroll   "),
-            key("Esc/?/q/Enter"),
-            desc(" close"),
-        ]),
-        Line::from(vec![]),
-    ]
-}
-
-/// Shortcuts for confirmation mode
-fn confirmation_mode_shortcuts() -> Vec<Line<'static>> {
-    vec![
-        Line::from(vec![
-            Span::styled("[!] ", Style::default().fg(Theme::WARNING)),
-            desc("Confirm action   "),
-            key("y"),
-            desc(" yes   "),
-            key("n/Esc"),
-            desc(" no"),
-        ]),
-        Line::from(vec![]),
-    ]
-}
-
-/// Shortcuts for settings mode
-fn settings_mode_shortcuts() -> Vec<Line<'static>> {
-    vec![
-        Line::from(vec![
-            Span::styled("[=] ", Style::default().fg(Theme::INFO)),
-            desc("Settings   "),
-            key("j/k"),
-            desc(" navigate   "),
-            key("Enter"),
-            desc(" edit   "),
-            key("Esc"),
-            desc(" close"),
-        ]),
-        Line::from(vec![]),
-    ]
+    vec![Line::from(first), Line::from(second)]
 }
 
 /// Create a styled key span
-fn key(text: &'static str) -> Span<'static> {
+fn key(text: String) -> Span<'static> {
     Span::styled(
         text,
         Style::default()
-            .fg(Theme::SECONDARY)
+            .fg(Theme::current().secondary)
             .add_modifier(Modifier::BOLD),
     )
 }
 
 /// Create a styled description span
-fn desc(text: &'static str) -> Span<'static> {
-    Span::styled(text, Style::default().fg(Theme::TEXT_MUTED))
+fn desc(text: String) -> Span<'static> {
+    Span::styled(text, Style::default().fg(Theme::current().text_muted))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::config::KeysConfig;
+
+    fn text(lines: &[Line]) -> String {
+        lines
+            .iter()
+            .flat_map(|line| line.spans.iter().map(|s| s.content.to_string()))
+            .collect()
+    }
 
     #[test]
     fn test_normal_mode_has_shortcuts() {
-        let shortcuts = normal_mode_shortcuts(false);
+        let shortcuts = get_shortcuts_for_mode(InputMode::Normal, false, &Keymap::default());
         assert!(!shortcuts.is_empty());
         assert_eq!(shortcuts.len(), 2); // Two rows of shortcuts
     }
 
     #[test]
     fn test_normal_mode_with_search() {
-        let shortcuts = normal_mode_shortcuts(true);
-        assert!(!shortcuts.is_empty());
-        assert_eq!(shortcuts.len(), 2);
-    }
-
-    #[test]
-    fn test_editing_mode_has_shortcuts() {
-        let shortcuts = editing_mode_shortcuts();
-        assert!(!shortcuts.is_empty());
-        assert_eq!(shortcuts.len(), 2);
-    }
-
-    #[test]
-    fn test_search_mode_has_shortcuts() {
-        let shortcuts = search_mode_shortcuts();
+        let shortcuts = get_shortcuts_for_mode(InputMode::Normal, true, &Keymap::default());
         assert!(!shortcuts.is_empty());
         assert_eq!(shortcuts.len(), 2);
+        assert!(text(&shortcuts).contains("Esc clear"));
     }
 
     #[test]
-    fn test_speed_limit_mode_has_shortcuts() {
-        let shortcuts = speed_limit_mode_shortcuts();
-        assert!(!shortcuts.is_empty());
-    }
+    fn test_labels_follow_the_keymap() {
+        let (_, pairs) = resolve(InputMode::Normal, &Keymap::default());
+        assert!(pairs.contains(&("1-4".to_string(), "tabs")));
+        assert!(pairs.contains(&("Space".to_string(), "pause")));
 
-    #[test]
-    fn test_help_mode_has_shortcuts() {
-        let shortcuts = help_mode_shortcuts();
-        assert!(!shortcuts.is_empty());
-    }
-
-    #[test]
-    fn test_confirmation_mode_has_shortcuts() {
-        let shortcuts = confirmation_mode_shortcuts();
-        assert!(!shortcuts.is_empty());
-    }
-
-    #[test]
-    fn test_settings_mode_has_shortcuts() {
-        let shortcuts = settings_mode_shortcuts();
-        assert!(!shortcuts.is_empty());
+        let config: KeysConfig = toml::from_str("[normal]\ndelete = \"D\"").unwrap();
+        let (_, pairs) = resolve(InputMode::Normal, &Keymap::from_config(&config).unwrap());
+        assert!(pairs.contains(&("D".to_string(), "delete")));
     }
 
     #[test]
     fn test_mode_switching() {
-        let normal = get_shortcuts_for_mode(InputMode::Normal, false);
-        let editing = get_shortcuts_for_mode(InputMode::Editing, false);
-        let search = get_shortcuts_for_mode(InputMode::Search, false);
+        let keymap = Keymap::default();
+        let normal = get_shortcuts_for_mode(InputMode::Normal, false, &keymap);
+        let editing = get_shortcuts_for_mode(InputMode::Editing, false, &keymap);
+        let search = get_shortcuts_for_mode(InputMode::Search, false, &keymap);
 
         // They should all have content
         assert!(!normal.is_empty());
@@ -313,17 +305,20 @@ mod tests {
             InputMode::Search,
             InputMode::SpeedLimit,
             InputMode::Help,
+            InputMode::Log,
+            InputMode::Stats,
+            InputMode::Schedule,
+            InputMode::ScheduleEdit,
             InputMode::Confirmation,
+            InputMode::Menu,
+            InputMode::Command,
             InputMode::Settings,
         ];
 
+        let keymap = Keymap::default();
         for mode in modes {
-            let shortcuts = get_shortcuts_for_mode(mode, false);
-            assert!(
-                !shortcuts.is_empty(),
-                "Mode {:?} should have shortcuts",
-                mode
-            );
+            let (_, pairs) = resolve(mode, &keymap);
+            assert!(!pairs.is_empty(), "Mode {:?} should have shortcuts", mode);
         }
     }
 }
@@ -3,6 +3,7 @@
 #![allow(dead_code)]
 
 use crate::ui::theme::Theme;
+use crate::units::parse_speed_limit;
 use ratatui::{
     layout::{Alignment, Constraint, Direction, Layout, Rect},
     style::{Modifier, Style},
@@ -12,6 +13,14 @@ use ratatui::{
     Frame,
 };
 
+/// Which limits the popup edits
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum LimitScope {
+    #[default]
+    Global, // aria2's global limits
+    Selected, // Own limits of the selected download(s)
+}
+
 /// Speed limit settings state
 #[derive(Clone, Debug)]
 pub struct SpeedLimitState {
@@ -19,16 +28,16 @@ pub struct SpeedLimitState {
     pub upload_limit: u64,
     pub editing_download: bool, // true = editing download, false = editing upload
     pub input_buffer: String,
+    pub scope: LimitScope,
+    pub targets: Vec<String>, // GIDs the per-download limits apply to
+    pub target_label: String, // File name, or "N downloads"
+    pub download_cap: u64,    // Per-download limits, 0 = only the global limit
+    pub upload_cap: u64,
 }
 
 impl Default for SpeedLimitState {
     fn default() -> Self {
-        Self {
-            download_limit: 0,
-            upload_limit: 0,
-            editing_download: true,
-            input_buffer: String::new(),
-        }
+        Self::new(0, 0)
     }
 }
 
@@ -39,6 +48,38 @@ impl SpeedLimitState {
             upload_limit,
             editing_download: true,
             input_buffer: String::new(),
+            scope: LimitScope::Global,
+            targets: Vec::new(),
+            target_label: String::new(),
+            download_cap: 0,
+            upload_cap: 0,
+        }
+    }
+
+    /// Downloads that can get their own limits, with their current caps
+    pub fn with_targets(mut self, targets: Vec<String>, label: String, caps: (u64, u64)) -> Self {
+        self.targets = targets;
+        self.target_label = label;
+        (self.download_cap, self.upload_cap) = caps;
+        self
+    }
+
+    /// Switch between global and per-download limits
+    ///
+    /// Stays global when no download is selected.
+    pub fn toggle_scope(&mut self) {
+        self.scope = match self.scope {
+            LimitScope::Global if !self.targets.is_empty() => LimitScope::Selected,
+            _ => LimitScope::Global,
+        };
+        self.input_buffer.clear();
+    }
+
+    /// Download and upload limits of the current scope
+    pub fn limits(&self) -> (u64, u64) {
+        match self.scope {
+            LimitScope::Global => (self.download_limit, self.upload_limit),
+            LimitScope::Selected => (self.download_cap, self.upload_cap),
         }
     }
 
@@ -48,19 +89,22 @@ impl SpeedLimitState {
     }
 
     pub fn get_current_limit(&self) -> u64 {
+        let (download, upload) = self.limits();
         if self.editing_download {
-            self.download_limit
+            download
         } else {
-            self.upload_limit
+            upload
         }
     }
 
     pub fn set_current_limit(&mut self, limit: u64) {
-        if self.editing_download {
-            self.download_limit = limit;
-        } else {
-            self.upload_limit = limit;
-        }
+        let field = match (self.scope, self.editing_download) {
+            (LimitScope::Global, true) => &mut self.download_limit,
+            (LimitScope::Global, false) => &mut self.upload_limit,
+            (LimitScope::Selected, true) => &mut self.download_cap,
+            (LimitScope::Selected, false) => &mut self.upload_cap,
+        };
+        *field = limit;
     }
 
     /// Increase limit by preset amount
@@ -125,7 +169,7 @@ pub fn render(f: &mut Frame, area: Rect, state: &SpeedLimitState) {
         .border_set(border::ROUNDED)
         .title(" Speed Limits ")
         .title_alignment(Alignment::Center)
-        .border_style(Style::default().fg(Theme::WARNING));
+        .border_style(Style::default().fg(Theme::current().warning));
 
     let inner = block.inner(popup_area);
     f.render_widget(block, popup_area);
@@ -146,10 +190,11 @@ pub fn render(f: &mut Frame, area: Rect, state: &SpeedLimitState) {
 
     // Render description
     let desc = Paragraph::new(vec![Line::from(vec![Span::styled(
-        "Set bandwidth limits (0 = unlimited)",
-        Style::default().fg(Theme::TEXT_MUTED),
+        describe_scope(state),
+        Style::default().fg(Theme::current().text_muted),
     )])])
     .alignment(Alignment::Center);
+    let (download_limit, upload_limit) = state.limits();
     f.render_widget(desc, layout[0]);
 
     // Render download limit
@@ -157,7 +202,7 @@ pub fn render(f: &mut Frame, area: Rect, state: &SpeedLimitState) {
         f,
         layout[1],
         "Download",
-        state.download_limit,
+        download_limit,
         state.editing_download,
         &state.input_buffer,
         true,
@@ -168,7 +213,7 @@ pub fn render(f: &mut Frame, area: Rect, state: &SpeedLimitState) {
         f,
         layout[3],
         "Upload",
-        state.upload_limit,
+        upload_limit,
         !state.editing_download,
         &state.input_buffer,
         false,
@@ -177,84 +222,117 @@ pub fn render(f: &mut Frame, area: Rect, state: &SpeedLimitState) {
     // Render input hint
     let input_hint = if !state.input_buffer.is_empty() {
         Line::from(vec![
-            Span::styled("  Input: ", Style::default().fg(Theme::TEXT_MUTED)),
+            Span::styled(
+                "  Input: ",
+                Style::default().fg(Theme::current().text_muted),
+            ),
             Span::styled(
                 state.input_buffer.clone(),
                 Style::default()
-                    .fg(Theme::HIGHLIGHT)
+                    .fg(Theme::current().highlight)
                     .add_modifier(Modifier::BOLD),
             ),
             Span::styled(
                 " (e.g., 5m, 500k, 0)",
-                Style::default().fg(Theme::TEXT_MUTED),
+                Style::default().fg(Theme::current().text_muted),
             ),
         ])
     } else {
         Line::from(vec![
             Span::styled(
                 "  Type a value (e.g., ",
-                Style::default().fg(Theme::TEXT_MUTED),
+                Style::default().fg(Theme::current().text_muted),
             ),
             Span::styled(
                 "5m",
                 Style::default()
-                    .fg(Theme::HIGHLIGHT)
+                    .fg(Theme::current().highlight)
                     .add_modifier(Modifier::BOLD),
             ),
-            Span::styled(", ", Style::default().fg(Theme::TEXT_MUTED)),
+            Span::styled(", ", Style::default().fg(Theme::current().text_muted)),
             Span::styled(
                 "500k",
                 Style::default()
-                    .fg(Theme::HIGHLIGHT)
+                    .fg(Theme::current().highlight)
                     .add_modifier(Modifier::BOLD),
             ),
-            Span::styled(", ", Style::default().fg(Theme::TEXT_MUTED)),
+            Span::styled(", ", Style::default().fg(Theme::current().text_muted)),
             Span::styled(
                 "0",
                 Style::default()
-                    .fg(Theme::HIGHLIGHT)
+                    .fg(Theme::current().highlight)
                     .add_modifier(Modifier::BOLD),
             ),
-            Span::styled(" for unlimited)", Style::default().fg(Theme::TEXT_MUTED)),
+            Span::styled(
+                " for unlimited)",
+                Style::default().fg(Theme::current().text_muted),
+            ),
         ])
     };
     f.render_widget(Paragraph::new(input_hint), layout[5]);
 
     // Render footer
-    let footer = Paragraph::new(vec![Line::from(vec![
+    let mut footer_spans = Vec::new();
+    if !state.targets.is_empty() {
+        footer_spans.push(Span::styled(
+            "s",
+            Style::default()
+                .fg(Theme::current().secondary)
+                .add_modifier(Modifier::BOLD),
+        ));
+        footer_spans.push(Span::styled(
+            " global/selected  ",
+            Style::default().fg(Theme::current().text_muted),
+        ));
+    }
+    footer_spans.extend([
         Span::styled(
             "^/v",
             Style::default()
-                .fg(Theme::SECONDARY)
+                .fg(Theme::current().secondary)
                 .add_modifier(Modifier::BOLD),
         ),
-        Span::styled(" switch  ", Style::default().fg(Theme::TEXT_MUTED)),
+        Span::styled(
+            " switch  ",
+            Style::default().fg(Theme::current().text_muted),
+        ),
         Span::styled(
             "</> ",
             Style::default()
-                .fg(Theme::SECONDARY)
+                .fg(Theme::current().secondary)
                 .add_modifier(Modifier::BOLD),
         ),
-        Span::styled(" adjust  ", Style::default().fg(Theme::TEXT_MUTED)),
+        Span::styled(
+            " adjust  ",
+            Style::default().fg(Theme::current().text_muted),
+        ),
         Span::styled(
             "Enter",
             Style::default()
-                .fg(Theme::SECONDARY)
+                .fg(Theme::current().secondary)
                 .add_modifier(Modifier::BOLD),
         ),
-        Span::styled(" apply  ", Style::default().fg(Theme::TEXT_MUTED)),
+        Span::styled(" apply  ", Style::default().fg(Theme::current().text_muted)),
         Span::styled(
             "Esc",
             Style::default()
-                .fg(Theme::SECONDARY)
+                .fg(Theme::current().secondary)
                 .add_modifier(Modifier::BOLD),
         ),
-        Span::styled(" cancel", Style::default().fg(Theme::TEXT_MUTED)),
-    ])])
-    .alignment(Alignment::Center);
+        Span::styled(" cancel", Style::default().fg(Theme::current().text_muted)),
+    ]);
+    let footer = Paragraph::new(vec![Line::from(footer_spans)]).alignment(Alignment::Center);
     f.render_widget(footer, layout[6]);
 }
 
+/// Description line naming what the limits apply to
+fn describe_scope(state: &SpeedLimitState) -> String {
+    match state.scope {
+        LimitScope::Global => "Global bandwidth limits (0 = unlimited)".to_string(),
+        LimitScope::Selected => format!("Limits for {} (0 = global only)", state.target_label),
+    }
+}
+
 /// Render a speed limit field with gauge
 fn render_limit_field(
     f: &mut Frame,
@@ -277,10 +355,10 @@ fn render_limit_field(
     // Label with arrow indicator
     let label_style = if is_selected {
         Style::default()
-            .fg(Theme::HIGHLIGHT)
+            .fg(Theme::current().highlight)
             .add_modifier(Modifier::BOLD)
     } else {
-        Style::default().fg(Theme::TEXT_MUTED)
+        Style::default().fg(Theme::current().text_muted)
     };
 
     let arrow = if is_download { "v" } else { "^" };
@@ -291,9 +369,9 @@ fn render_limit_field(
         Span::styled(
             arrow,
             if is_download {
-                Style::default().fg(Theme::SUCCESS)
+                Style::default().fg(Theme::current().success)
             } else {
-                Style::default().fg(Theme::INFO)
+                Style::default().fg(Theme::current().info)
             },
         ),
         Span::styled(format!(" {}", label), label_style),
@@ -309,11 +387,17 @@ fn render_limit_field(
     };
 
     let gauge_style = if is_selected {
-        Style::default().fg(Theme::HIGHLIGHT).bg(Theme::BACKGROUND)
+        Style::default()
+            .fg(Theme::current().highlight)
+            .bg(Theme::current().background)
     } else if is_download {
-        Style::default().fg(Theme::SUCCESS).bg(Theme::BACKGROUND)
+        Style::default()
+            .fg(Theme::current().success)
+            .bg(Theme::current().background)
     } else {
-        Style::default().fg(Theme::INFO).bg(Theme::BACKGROUND)
+        Style::default()
+            .fg(Theme::current().info)
+            .bg(Theme::current().background)
     };
 
     let gauge = Gauge::default()
@@ -332,10 +416,10 @@ fn render_limit_field(
 
     let value_style = if is_selected {
         Style::default()
-            .fg(Theme::HIGHLIGHT)
+            .fg(Theme::current().highlight)
             .add_modifier(Modifier::BOLD)
     } else {
-        Style::default().fg(Theme::CMD_COLOR)
+        Style::default().fg(Theme::current().cmd_color)
     };
 
     let value_paragraph = Paragraph::new(Line::from(vec![Span::styled(value_text, value_style)]))
@@ -369,48 +453,6 @@ fn format_speed(speed_bytes: u64) -> String {
     }
 }
 
-/// Parse speed limit from user input
-pub fn parse_speed_limit(input: &str) -> Option<u64> {
-    let input = input.trim().to_lowercase();
-
-    if input.is_empty() || input == "0" || input == "unlimited" || input == "none" {
-        return Some(0);
-    }
-
-    // Parse formats like "5m", "5mb", "5 mb/s", "5000k", etc.
-    let mut num_str = String::new();
-    let mut unit_str = String::new();
-    let mut in_unit = false;
-
-    for c in input.chars() {
-        if c.is_ascii_digit() || c == '.' {
-            if !in_unit {
-                num_str.push(c);
-            }
-        } else if c.is_alphabetic() {
-            in_unit = true;
-            unit_str.push(c);
-        }
-    }
-
-    let num: f64 = num_str.parse().ok()?;
-
-    let multiplier: u64 = if unit_str.starts_with('g') {
-        1024 * 1024 * 1024
-    } else if unit_str.starts_with('m') {
-        1024 * 1024
-    } else if unit_str.starts_with('k') {
-        1024
-    } else if unit_str.is_empty() {
-        // Assume MB/s if no unit
-        1024 * 1024
-    } else {
-        1 // bytes
-    };
-
-    Some((num * multiplier as f64) as u64)
-}
-
 /// Helper function to create a centered rectangle
 fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
     let popup_layout = Layout::default()
@@ -436,20 +478,6 @@ fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
 mod tests {
     use super::*;
 
-    #[test]
-    fn test_parse_speed_limit() {
-        assert_eq!(parse_speed_limit("5m"), Some(5 * 1024 * 1024));
-        assert_eq!(parse_speed_limit("5mb"), Some(5 * 1024 * 1024));
-        assert_eq!(parse_speed_limit("5 MB/s"), Some(5 * 1024 * 1024));
-        assert_eq!(parse_speed_limit("500k"), Some(500 * 1024));
-        assert_eq!(parse_speed_limit("500kb"), Some(500 * 1024));
-        assert_eq!(parse_speed_limit("1g"), Some(1024 * 1024 * 1024));
-        assert_eq!(parse_speed_limit("0"), Some(0));
-        assert_eq!(parse_speed_limit("unlimited"), Some(0));
-        assert_eq!(parse_speed_limit(""), Some(0));
-        assert_eq!(parse_speed_limit("5"), Some(5 * 1024 * 1024)); // Assume MB
-    }
-
     #[test]
     fn test_format_speed_limit() {
         assert_eq!(format_speed_limit(0), "Unlimited");
@@ -488,6 +516,35 @@ mod tests {
         assert_eq!(state.download_limit, 400 * 1024); // Should decrease by 100 KB
     }
 
+    #[test]
+    fn test_selected_scope_edits_caps() {
+        let mut state = SpeedLimitState::new(5 * 1024 * 1024, 0).with_targets(
+            vec!["g1".to_string()],
+            "big.iso".to_string(),
+            (0, 64 * 1024),
+        );
+        state.toggle_scope();
+        assert_eq!(state.scope, LimitScope::Selected);
+        assert_eq!(
+            describe_scope(&state),
+            "Limits for big.iso (0 = global only)"
+        );
+
+        state.increase_limit();
+        assert_eq!(state.limits(), (1024 * 1024, 64 * 1024));
+        assert_eq!(state.download_limit, 5 * 1024 * 1024); // Global untouched
+
+        state.toggle_scope();
+        assert_eq!(state.scope, LimitScope::Global);
+    }
+
+    #[test]
+    fn test_scope_needs_targets() {
+        let mut state = SpeedLimitState::new(0, 0);
+        state.toggle_scope();
+        assert_eq!(state.scope, LimitScope::Global);
+    }
+
     #[test]
     fn test_apply_input() {
         let mut state = SpeedLimitState::new(0, 0);
@@ -0,0 +1,320 @@
+//! Statistics dashboard: long-term throughput, daily totals, domains and types
+
+use crate::stats::{Resolution, Sample, Stats};
+use crate::ui::theme::{Styles, Theme};
+use crate::ui::utils::{format_size, format_speed, truncate_text};
+use chrono::NaiveDateTime;
+use ratatui::{
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
+    style::Style,
+    symbols::{self, border},
+    text::{Line, Span},
+    widgets::{
+        Axis, Bar, BarChart, BarGroup, Block, Borders, Chart, Clear, Dataset, GraphType, Paragraph,
+    },
+    Frame,
+};
+
+/// Domains listed in the dashboard
+const TOP_DOMAINS: usize = 5;
+
+/// Width of one bar in the daily totals chart
+const BAR_WIDTH: u16 = 6;
+
+/// Render the statistics dashboard
+pub fn render(
+    f: &mut Frame,
+    area: Rect,
+    stats: &Stats,
+    resolution: Resolution,
+    now: NaiveDateTime,
+) {
+    let popup_area = centered_rect(90, 85, area);
+
+    f.render_widget(Clear, popup_area);
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_set(border::ROUNDED)
+        .title(" Statistics ")
+        .title_alignment(Alignment::Center)
+        .border_style(Style::default().fg(Theme::current().info));
+
+    let inner = block.inner(popup_area);
+    f.render_widget(block, popup_area);
+
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Percentage(55), Constraint::Percentage(45)])
+        .split(inner);
+    let bottom = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+        .split(rows[1]);
+
+    render_throughput(f, rows[0], stats, resolution, now);
+    render_daily_totals(f, bottom[0], stats, now);
+    render_breakdown(f, bottom[1], stats);
+}
+
+/// Line chart of the average download and upload speed per bucket
+fn render_throughput(
+    f: &mut Frame,
+    area: Rect,
+    stats: &Stats,
+    resolution: Resolution,
+    now: NaiveDateTime,
+) {
+    let window = stats.window(resolution, now, resolution.shown());
+    let seconds = resolution.step().num_seconds().max(1) as u64;
+    let points = |bytes: fn(&Sample) -> u64| -> Vec<(f64, f64)> {
+        window
+            .iter()
+            .enumerate()
+            .map(|(i, sample)| (i as f64, (bytes(sample) / seconds) as f64))
+            .collect()
+    };
+    let downloaded = points(|s| s.transfer.downloaded);
+    let uploaded = points(|s| s.transfer.uploaded);
+    let peak = downloaded
+        .iter()
+        .chain(&uploaded)
+        .map(|(_, y)| *y)
+        .fold(0.0, f64::max)
+        .max(1.0);
+
+    let datasets = vec![
+        Dataset::default()
+            .name("Download")
+            .marker(symbols::Marker::Braille)
+            .graph_type(GraphType::Line)
+            .style(Style::default().fg(Theme::current().success))
+            .data(&downloaded),
+        Dataset::default()
+            .name("Upload")
+            .marker(symbols::Marker::Braille)
+            .graph_type(GraphType::Line)
+            .style(Style::default().fg(Theme::current().info))
+            .data(&uploaded),
+    ];
+
+    let time_format = match resolution {
+        Resolution::Minute | Resolution::Hour => "%H:%M",
+        Resolution::Day => "%m-%d",
+    };
+    let first = window
+        .first()
+        .map(|s| s.start.format(time_format).to_string())
+        .unwrap_or_default();
+    let last = window
+        .last()
+        .map(|s| s.start.format(time_format).to_string())
+        .unwrap_or_default();
+
+    let chart = Chart::new(datasets)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_set(border::ROUNDED)
+                .title(format!(
+                    " Throughput per {} [{}] ",
+                    resolution.name(),
+                    resolution_tabs(resolution)
+                ))
+                .border_style(Styles::border()),
+        )
+        .x_axis(
+            Axis::default()
+                .style(Styles::text_muted())
+                .bounds([0.0, window.len().saturating_sub(1).max(1) as f64])
+                .labels(vec![Span::raw(first), Span::raw(last)]),
+        )
+        .y_axis(
+            Axis::default()
+                .style(Styles::text_muted())
+                .bounds([0.0, peak])
+                .labels(vec![
+                    Span::raw("0"),
+                    Span::raw(format_speed((peak / 2.0) as u64)),
+                    Span::raw(format_speed(peak as u64)),
+                ]),
+        );
+    f.render_widget(chart, area);
+}
+
+/// The three resolutions, the shown one in capitals
+fn resolution_tabs(current: Resolution) -> String {
+    Resolution::ALL
+        .iter()
+        .map(|r| {
+            if *r == current {
+                r.name().to_uppercase()
+            } else {
+                r.name().to_string()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" | ")
+}
+
+/// Bar chart of bytes downloaded per day, as many days as fit
+fn render_daily_totals(f: &mut Frame, area: Rect, stats: &Stats, now: NaiveDateTime) {
+    let days = (area.width.saturating_sub(2) / (BAR_WIDTH + 1)).clamp(1, 14) as usize;
+    let window = stats.window(Resolution::Day, now, days);
+    let bars: Vec<Bar> = window
+        .iter()
+        .map(|sample| {
+            Bar::default()
+                .value(sample.transfer.downloaded)
+                .text_value(format_size(sample.transfer.downloaded))
+                .label(Line::from(sample.start.format("%a").to_string()))
+                .style(Style::default().fg(Theme::current().primary))
+        })
+        .collect();
+
+    let chart = BarChart::default()
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_set(border::ROUNDED)
+                .title(" Downloaded per day ")
+                .border_style(Styles::border()),
+        )
+        .bar_width(BAR_WIDTH)
+        .bar_gap(1)
+        .value_style(
+            Style::default()
+                .fg(Theme::current().background)
+                .bg(Theme::current().primary),
+        )
+        .data(BarGroup::default().bars(&bars));
+    f.render_widget(chart, area);
+}
+
+/// Top domains with their success rate, then average speed per download type
+fn render_breakdown(f: &mut Frame, area: Rect, stats: &Stats) {
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_set(border::ROUNDED)
+        .title(" Finished downloads ")
+        .border_style(Styles::border());
+    let width = block.inner(area).width as usize;
+    let paragraph = Paragraph::new(build_breakdown_lines(stats, width)).block(block);
+    f.render_widget(paragraph, area);
+}
+
+/// Lines for the domain and download type tables
+fn build_breakdown_lines(stats: &Stats, width: usize) -> Vec<Line<'static>> {
+    let name_width = width.saturating_sub(24).max(8);
+    let mut lines = vec![Line::from(Span::styled("Top domains", Styles::highlight()))];
+
+    let domains = stats.top_domains(TOP_DOMAINS);
+    if domains.is_empty() {
+        lines.push(Line::from(Span::styled("  None yet", Styles::text_muted())));
+    }
+    for (name, domain) in domains {
+        let rate = domain.success_rate();
+        let rate_style = if rate >= 0.9 {
+            Styles::success()
+        } else if rate >= 0.5 {
+            Styles::warning()
+        } else {
+            Styles::error()
+        };
+        lines.push(Line::from(vec![
+            Span::styled(
+                format!("  {:<w$}", truncate_text(name, name_width), w = name_width),
+                Styles::text(),
+            ),
+            Span::styled(
+                format!(" {:>10}", format_size(domain.bytes)),
+                Styles::text(),
+            ),
+            Span::styled(format!(" {:>4.0}% ok", rate * 100.0), rate_style),
+        ]));
+    }
+
+    lines.push(Line::from(""));
+    lines.push(Line::from(Span::styled(
+        "Average speed by type",
+        Styles::highlight(),
+    )));
+    if stats.types.is_empty() {
+        lines.push(Line::from(Span::styled("  None yet", Styles::text_muted())));
+    }
+    for (name, kind) in &stats.types {
+        lines.push(Line::from(vec![
+            Span::styled(
+                format!("  {:<w$}", truncate_text(name, name_width), w = name_width),
+                Styles::text(),
+            ),
+            Span::styled(
+                format!(" {:>10}", format_speed(kind.average_speed())),
+                Styles::text(),
+            ),
+            Span::styled(format!(" {:>5} done", kind.downloads), Styles::text_muted()),
+        ]));
+    }
+    lines
+}
+
+/// Create a centered rectangle with percentage-based dimensions
+fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
+    let popup_layout = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Percentage((100 - percent_y) / 2),
+            Constraint::Percentage(percent_y),
+            Constraint::Percentage((100 - percent_y) / 2),
+        ])
+        .split(r);
+
+    Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([
+            Constraint::Percentage((100 - percent_x) / 2),
+            Constraint::Percentage(percent_x),
+            Constraint::Percentage((100 - percent_x) / 2),
+        ])
+        .split(popup_layout[1])[1]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stats::DomainStats;
+
+    #[test]
+    fn test_breakdown_lists_domains_by_bytes() {
+        let mut stats = Stats::default();
+        stats.domains.insert(
+            "small.example".to_string(),
+            DomainStats {
+                bytes: 1024,
+                completed: 1,
+                failed: 0,
+            },
+        );
+        stats.domains.insert(
+            "big.example".to_string(),
+            DomainStats {
+                bytes: 1024 * 1024,
+                completed: 1,
+                failed: 1,
+            },
+        );
+        let text: Vec<String> = build_breakdown_lines(&stats, 40)
+            .iter()
+            .map(|line| line.spans.iter().map(|s| s.content.as_ref()).collect())
+            .collect();
+        assert!(text[1].starts_with("  big.example"));
+        assert!(text[1].ends_with("50% ok"));
+        assert!(text[2].starts_with("  small.example"));
+        assert_eq!(text.last().unwrap(), "  None yet");
+    }
+
+    #[test]
+    fn test_resolution_tabs() {
+        assert_eq!(resolution_tabs(Resolution::Hour), "minute | HOUR | day");
+    }
+}
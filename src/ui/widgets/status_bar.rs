@@ -24,7 +24,7 @@ pub fn render(f: &mut Frame, area: Rect, message: &str) {
 
     let formatted_message = Line::from(vec![
         Span::styled(icon, style),
-        Span::styled(" ", Style::default().fg(Theme::TEXT_MUTED)),
+        Span::styled(" ", Style::default().fg(Theme::current().text_muted)),
         Span::styled(message, style),
     ]);
 
@@ -40,7 +40,7 @@ fn determine_message_style(message: &str) -> (Style, &'static str) {
     if lower.contains("error") || lower.contains("failed") {
         (
             Style::default()
-                .fg(Theme::ERROR)
+                .fg(Theme::current().error)
                 .add_modifier(Modifier::BOLD),
             "[x]",
         )
@@ -51,19 +51,19 @@ fn determine_message_style(message: &str) -> (Style, &'static str) {
     {
         (
             Style::default()
-                .fg(Theme::SUCCESS)
+                .fg(Theme::current().success)
                 .add_modifier(Modifier::BOLD),
             "[*]",
         )
     } else if lower.contains("warning") {
         (
             Style::default()
-                .fg(Theme::WARNING)
+                .fg(Theme::current().warning)
                 .add_modifier(Modifier::BOLD),
             "[!]",
         )
     } else {
-        (Style::default().fg(Theme::INFO), "[i]")
+        (Style::default().fg(Theme::current().info), "[i]")
     }
 }
 
@@ -74,28 +74,28 @@ mod tests {
     #[test]
     fn test_error_message_style() {
         let (style, icon) = determine_message_style("Error: download failed");
-        assert_eq!(style.fg, Some(Theme::ERROR));
+        assert_eq!(style.fg, Some(Theme::current().error));
         assert_eq!(icon, "[x]");
     }
 
     #[test]
     fn test_success_message_style() {
         let (style, icon) = determine_message_style("Successfully added download");
-        assert_eq!(style.fg, Some(Theme::SUCCESS));
+        assert_eq!(style.fg, Some(Theme::current().success));
         assert_eq!(icon, "[*]");
     }
 
     #[test]
     fn test_warning_message_style() {
         let (style, icon) = determine_message_style("Warning: low disk space");
-        assert_eq!(style.fg, Some(Theme::WARNING));
+        assert_eq!(style.fg, Some(Theme::current().warning));
         assert_eq!(icon, "[!]");
     }
 
     #[test]
     fn test_info_message_style() {
         let (style, icon) = determine_message_style("Download in progress");
-        assert_eq!(style.fg, Some(Theme::INFO));
+        assert_eq!(style.fg, Some(Theme::current().info));
         assert_eq!(icon, "[i]");
     }
 
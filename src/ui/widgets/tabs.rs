@@ -10,6 +10,9 @@ use ratatui::{
     Frame,
 };
 
+/// Separator between two tabs
+const SEPARATOR: &str = "  ";
+
 /// Render the tabs widget (minimal, sleek design)
 ///
 /// # Arguments
@@ -17,16 +20,25 @@ use ratatui::{
 /// * `area` - Area to render in
 /// * `current_tab` - Index of currently selected tab
 /// * `tab_titles` - Slice of tab title strings
-pub fn render(f: &mut Frame, area: Rect, current_tab: usize, tab_titles: Vec<&str>) {
+///
+/// Returns the area of each tab and its index.
+pub fn render(
+    f: &mut Frame,
+    area: Rect,
+    current_tab: usize,
+    tab_titles: Vec<&str>,
+) -> Vec<(Rect, usize)> {
     let mut spans = vec![];
+    let mut widths = vec![];
 
     for (i, title) in tab_titles.iter().enumerate() {
         let is_selected = i == current_tab;
 
         // Add separator between tabs
         if i > 0 {
-            spans.push(Span::styled("    ", Styles::text_muted()));
+            spans.push(Span::styled(SEPARATOR, Styles::text_muted()));
         }
+        widths.push(title.chars().count() as u16 + 2);
 
         if is_selected {
             // Selected tab: highlighted with indicator
@@ -40,9 +52,23 @@ pub fn render(f: &mut Frame, area: Rect, current_tab: usize, tab_titles: Vec<&st
     }
 
     let tabs_line = Line::from(spans);
+    let line_width = tabs_line.width() as u16;
     let paragraph = Paragraph::new(tabs_line).alignment(Alignment::Center);
 
     f.render_widget(paragraph, area);
+
+    // Same offset as the centered line, clipped to the area
+    let mut x = area.x + area.width.saturating_sub(line_width) / 2;
+    let mut areas = vec![];
+    for (i, width) in widths.into_iter().enumerate() {
+        let width = width.min(area.right().saturating_sub(x));
+        if width == 0 {
+            break;
+        }
+        areas.push((Rect::new(x, area.y, width, area.height.min(1)), i));
+        x += width + SEPARATOR.len() as u16;
+    }
+    areas
 }
 
 /// Format tab title with count
@@ -62,6 +88,24 @@ pub fn format_tab_title(name: &str, number: usize, count: usize) -> String {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_render_returns_tab_areas() {
+        use ratatui::{backend::TestBackend, Terminal};
+
+        let mut terminal = Terminal::new(TestBackend::new(40, 1)).unwrap();
+        let mut areas = vec![];
+        terminal
+            .draw(|f| areas = render(f, f.size(), 1, vec!["One", "Two"]))
+            .unwrap();
+
+        // "  One  ▸ Two" is 12 columns wide, centered in 40
+        assert_eq!(areas.len(), 2);
+        assert_eq!(areas[0], (Rect::new(14, 0, 5, 1), 0));
+        assert_eq!(areas[1], (Rect::new(21, 0, 5, 1), 1));
+        let buffer = terminal.backend().buffer();
+        assert_eq!(buffer.get(23, 0).symbol(), "T");
+    }
+
     #[test]
     fn test_format_tab_title() {
         assert_eq!(format_tab_title("Active", 1, 5), "1 Active (5)");
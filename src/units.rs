@@ -0,0 +1,67 @@
+//! Sizes and speeds typed by the user
+//!
+//! Shared by the configuration, quotas, search queries and the speed limit
+//! popup, so `5m` means the same everywhere.
+
+/// Parse a speed limit or size such as `5m`, `500 KB/s` or `1g` into bytes
+///
+/// A bare number is taken as MB; empty, `0`, `none` and `unlimited` mean no limit.
+pub fn parse_speed_limit(input: &str) -> Option<u64> {
+    let input = input.trim().to_lowercase();
+
+    if input.is_empty() || input == "0" || input == "unlimited" || input == "none" {
+        return Some(0);
+    }
+
+    // Parse formats like "5m", "5mb", "5 mb/s", "5000k", etc.
+    let mut num_str = String::new();
+    let mut unit_str = String::new();
+    let mut in_unit = false;
+
+    for c in input.chars() {
+        if c.is_ascii_digit() || c == '.' {
+            if !in_unit {
+                num_str.push(c);
+            }
+        } else if c.is_alphabetic() {
+            in_unit = true;
+            unit_str.push(c);
+        }
+    }
+
+    let num: f64 = num_str.parse().ok()?;
+
+    let multiplier: u64 = if unit_str.starts_with('g') {
+        1024 * 1024 * 1024
+    } else if unit_str.starts_with('m') {
+        1024 * 1024
+    } else if unit_str.starts_with('k') {
+        1024
+    } else if unit_str.is_empty() {
+        // Assume MB/s if no unit
+        1024 * 1024
+    } else {
+        1 // bytes
+    };
+
+    Some((num * multiplier as f64) as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_speed_limit() {
+        assert_eq!(parse_speed_limit("5m"), Some(5 * 1024 * 1024));
+        assert_eq!(parse_speed_limit("5mb"), Some(5 * 1024 * 1024));
+        assert_eq!(parse_speed_limit("5 MB/s"), Some(5 * 1024 * 1024));
+        assert_eq!(parse_speed_limit("500k"), Some(500 * 1024));
+        assert_eq!(parse_speed_limit("500kb"), Some(500 * 1024));
+        assert_eq!(parse_speed_limit("1g"), Some(1024 * 1024 * 1024));
+        assert_eq!(parse_speed_limit("0"), Some(0));
+        assert_eq!(parse_speed_limit("unlimited"), Some(0));
+        assert_eq!(parse_speed_limit(""), Some(0));
+        assert_eq!(parse_speed_limit("5"), Some(5 * 1024 * 1024)); // Assume MB
+    }
+}
@@ -0,0 +1,395 @@
+//! Data-cap accounting for metered connections
+//!
+//! [`Usage`] adds up the bytes transferred per day and per calendar month,
+//! from the same per-poll deltas that feed the global statistics. It is saved
+//! to `usage.toml` in the data directory so the totals survive restarts.
+//! A [`Meter`] checks the totals against the `[quota]` limits: crossing a
+//! `warn_at` percentage produces a warning, and once a quota is used up every
+//! download is paused. Nothing is resumed when a new day or month starts; the
+//! user resumes the downloads by hand.
+
+use crate::config::{Config, QuotaConfig};
+use crate::ui::utils::format_size;
+use crate::units::parse_speed_limit;
+use chrono::{Datelike, NaiveDate};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::PathBuf;
+
+const USAGE_FILE: &str = "usage.toml";
+
+/// Bytes transferred during one period
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Transfer {
+    pub downloaded: u64,
+    pub uploaded: u64,
+}
+
+/// Totals for the current day and month
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Usage {
+    pub day: NaiveDate,
+    pub today: Transfer,
+    pub month: NaiveDate, // First day of the month
+    pub this_month: Transfer,
+}
+
+impl Usage {
+    /// Path of the usage file
+    pub fn path() -> PathBuf {
+        Config::data_dir().join(USAGE_FILE)
+    }
+
+    /// Load the saved totals (nothing used yet if there is no file)
+    pub fn load() -> Result<Self, Box<dyn std::error::Error>> {
+        let path = Self::path();
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = std::fs::read_to_string(&path)?;
+        Self::parse(&content).map_err(|e| format!("{}: {}", path.display(), e).into())
+    }
+
+    /// Parse saved totals from TOML text
+    pub fn parse(content: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        Ok(toml::from_str(content)?)
+    }
+
+    /// TOML text for the totals
+    pub fn to_toml(&self) -> String {
+        toml::to_string(self).unwrap_or_default()
+    }
+
+    /// Write the totals to the usage file
+    pub fn save(&self) -> Result<(), Box<dyn std::error::Error>> {
+        let path = Self::path();
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(&path, self.to_toml())?;
+        Ok(())
+    }
+
+    /// Add bytes (downloaded, uploaded) transferred on `date`
+    ///
+    /// A new day or month starts its total from zero.
+    pub fn record(&mut self, date: NaiveDate, (downloaded, uploaded): (u64, u64)) {
+        if date != self.day {
+            self.day = date;
+            self.today = Transfer::default();
+        }
+        let month = date.with_day(1).unwrap_or(date);
+        if month != self.month {
+            self.month = month;
+            self.this_month = Transfer::default();
+        }
+        for total in [&mut self.today, &mut self.this_month] {
+            total.downloaded += downloaded;
+            total.uploaded += uploaded;
+        }
+    }
+}
+
+/// Period a quota applies to
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum QuotaPeriod {
+    Daily,
+    Monthly,
+}
+
+impl QuotaPeriod {
+    /// Short label for the stats bar
+    pub fn label(&self) -> &'static str {
+        match self {
+            QuotaPeriod::Daily => "Today",
+            QuotaPeriod::Monthly => "Month",
+        }
+    }
+}
+
+impl fmt::Display for QuotaPeriod {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            QuotaPeriod::Daily => "Daily",
+            QuotaPeriod::Monthly => "Monthly",
+        })
+    }
+}
+
+/// Limits in bytes per day and month (0 = no quota)
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Quota {
+    pub daily: u64,
+    pub monthly: u64,
+    pub count_upload: bool,
+    pub warn_at: Vec<u8>, // Ascending percentages
+}
+
+impl Quota {
+    /// Build from configuration, checking sizes and warning thresholds
+    pub fn from_config(config: &QuotaConfig) -> Result<Self, String> {
+        let limit = |name: &str, text: &str| {
+            parse_speed_limit(text).ok_or_else(|| format!("invalid {} quota '{}'", name, text))
+        };
+        if let Some(percent) = config.warn_at.iter().find(|p| !(1..=99).contains(*p)) {
+            return Err(format!("warn_at {} is not between 1 and 99", percent));
+        }
+        let mut warn_at = config.warn_at.clone();
+        warn_at.sort_unstable();
+        warn_at.dedup();
+
+        Ok(Self {
+            daily: limit("daily", &config.daily)?,
+            monthly: limit("monthly", &config.monthly)?,
+            count_upload: config.count_upload,
+            warn_at,
+        })
+    }
+
+    /// Bytes of a transfer that count toward the quotas
+    fn counted(&self, transfer: Transfer) -> u64 {
+        if self.count_upload {
+            transfer.downloaded + transfer.uploaded
+        } else {
+            transfer.downloaded
+        }
+    }
+}
+
+/// How much of one quota is used
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct QuotaUse {
+    pub period: QuotaPeriod,
+    pub used: u64,
+    pub limit: u64,
+}
+
+impl QuotaUse {
+    /// Used share of the quota, at most 1.0
+    pub fn ratio(&self) -> f64 {
+        (self.used as f64 / self.limit as f64).min(1.0)
+    }
+
+    pub fn percent(&self) -> u64 {
+        self.used.saturating_mul(100) / self.limit
+    }
+
+    pub fn is_reached(&self) -> bool {
+        self.used >= self.limit
+    }
+}
+
+impl fmt::Display for QuotaUse {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} of {}",
+            format_size(self.used),
+            format_size(self.limit)
+        )
+    }
+}
+
+/// A quota threshold that was just crossed
+#[derive(Clone, Debug, PartialEq)]
+pub enum QuotaAlert {
+    Warning(QuotaUse, u8), // Threshold percentage crossed
+    Reached(QuotaUse),
+}
+
+impl fmt::Display for QuotaAlert {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            QuotaAlert::Warning(usage, percent) => {
+                write!(f, "{} quota at {}%: {} used", usage.period, percent, usage)
+            }
+            QuotaAlert::Reached(usage) => write!(
+                f,
+                "{} quota of {} used up: downloads paused",
+                usage.period,
+                format_size(usage.limit)
+            ),
+        }
+    }
+}
+
+/// Usage checked against the quotas
+#[derive(Clone, Debug, Default)]
+pub struct Meter {
+    pub usage: Usage,
+    quota: Quota,
+    warned: [u8; 2], // Highest percentage announced (daily, monthly), 100 once used up
+    dirty: bool,     // Changed since it was last saved
+}
+
+impl Meter {
+    pub fn new(quota: Quota, usage: Usage) -> Self {
+        Self {
+            usage,
+            quota,
+            warned: [0; 2],
+            dirty: false,
+        }
+    }
+
+    /// Add a poll's transfer made on `date`, returning the thresholds it crossed
+    pub fn record(&mut self, date: NaiveDate, transferred: (u64, u64)) -> Vec<QuotaAlert> {
+        let (day, month) = (self.usage.day, self.usage.month);
+        self.usage.record(date, transferred);
+        if self.usage.day != day {
+            self.warned[0] = 0;
+        }
+        if self.usage.month != month {
+            self.warned[1] = 0;
+        }
+        if transferred != (0, 0) {
+            self.dirty = true;
+        }
+
+        let mut alerts = Vec::new();
+        for usage in self.uses() {
+            let warned = &mut self.warned[usage.period as usize];
+            if usage.is_reached() {
+                if *warned < 100 {
+                    *warned = 100;
+                    alerts.push(QuotaAlert::Reached(usage));
+                }
+                continue;
+            }
+            let crossed = self
+                .quota
+                .warn_at
+                .iter()
+                .rev()
+                .find(|p| u64::from(**p) <= usage.percent());
+            if let Some(&percent) = crossed.filter(|p| **p > *warned) {
+                *warned = percent;
+                alerts.push(QuotaAlert::Warning(usage, percent));
+            }
+        }
+        alerts
+    }
+
+    /// Use of each configured quota
+    pub fn uses(&self) -> Vec<QuotaUse> {
+        [
+            (QuotaPeriod::Daily, self.quota.daily, self.usage.today),
+            (
+                QuotaPeriod::Monthly,
+                self.quota.monthly,
+                self.usage.this_month,
+            ),
+        ]
+        .into_iter()
+        .filter(|(_, limit, _)| *limit > 0)
+        .map(|(period, limit, transfer)| QuotaUse {
+            period,
+            used: self.quota.counted(transfer),
+            limit,
+        })
+        .collect()
+    }
+
+    /// Whether `transferred` counted toward a quota that is used up
+    ///
+    /// Downloads resumed by hand are paused again as soon as they move data.
+    pub fn over_quota(&self, transferred: (u64, u64)) -> bool {
+        let transfer = Transfer {
+            downloaded: transferred.0,
+            uploaded: transferred.1,
+        };
+        self.quota.counted(transfer) > 0 && self.uses().iter().any(QuotaUse::is_reached)
+    }
+
+    /// Write the usage file if the totals changed since the last save
+    pub fn save(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        if self.dirty {
+            self.usage.save()?;
+            self.dirty = false;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GB: u64 = 1024 * 1024 * 1024;
+
+    fn date(month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, month, day).unwrap()
+    }
+
+    fn quota(toml: &str) -> Quota {
+        let config = Config::parse(toml).unwrap();
+        Quota::from_config(&config.quota).unwrap()
+    }
+
+    #[test]
+    fn test_record_rolls_over_days_and_months() {
+        let mut usage = Usage::default();
+        usage.record(date(1, 31), (100, 10));
+        usage.record(date(1, 31), (50, 0));
+        assert_eq!(usage.today.downloaded, 150);
+        assert_eq!(usage.this_month.uploaded, 10);
+
+        usage.record(date(2, 1), (1, 0));
+        assert_eq!(usage.today.downloaded, 1);
+        assert_eq!(usage.this_month.downloaded, 1);
+        assert_eq!(usage.month, date(2, 1));
+
+        usage.record(date(2, 2), (1, 0));
+        assert_eq!(usage.today.downloaded, 1);
+        assert_eq!(usage.this_month.downloaded, 2);
+    }
+
+    #[test]
+    fn test_usage_round_trips_through_toml() {
+        let mut usage = Usage::default();
+        usage.record(date(3, 5), (GB, 2 * GB));
+        assert_eq!(Usage::parse(&usage.to_toml()).unwrap(), usage);
+    }
+
+    #[test]
+    fn test_warnings_are_announced_once() {
+        let mut meter = Meter::new(quota("[quota]\ndaily = \"10G\""), Usage::default());
+
+        assert!(meter.record(date(1, 1), (7 * GB, 0)).is_empty());
+        let alerts = meter.record(date(1, 1), (GB + GB / 2, 0));
+        assert_eq!(alerts.len(), 1);
+        assert_eq!(
+            alerts[0].to_string(),
+            "Daily quota at 80%: 8.50 GB of 10.00 GB used"
+        );
+        assert!(meter.record(date(1, 1), (GB / 10, 0)).is_empty());
+
+        let alerts = meter.record(date(1, 1), (2 * GB, 0));
+        assert!(matches!(alerts[..], [QuotaAlert::Reached(_)]));
+        assert!(meter.over_quota((1, 0)));
+        assert!(!meter.over_quota((0, 0)));
+
+        // A new day starts over
+        assert!(meter.record(date(1, 2), (0, 0)).is_empty());
+        assert!(!meter.over_quota((1, 0)));
+    }
+
+    #[test]
+    fn test_uploads_can_be_left_out() {
+        let mut meter = Meter::new(
+            quota("[quota]\nmonthly = \"1G\"\ncount_upload = false"),
+            Usage::default(),
+        );
+        assert!(meter.record(date(1, 1), (0, 2 * GB)).is_empty());
+        assert_eq!(meter.uses()[0].used, 0);
+        assert!(!meter.over_quota((0, 1)));
+    }
+
+    #[test]
+    fn test_invalid_quota() {
+        assert!(Config::parse("[quota]\ndaily = \"lots\"").is_err());
+        assert!(Config::parse("[quota]\nwarn_at = [0]").is_err());
+        assert!(Config::parse("[quota]\nwarn_at = [100]").is_err());
+        assert!(Meter::new(quota(""), Usage::default()).uses().is_empty());
+    }
+}
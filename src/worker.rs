@@ -0,0 +1,292 @@
+//! Background worker for backend calls
+//!
+//! The UI sends [`Command`]s over a channel and keeps drawing while the
+//! worker talks to aria2. Every command produces one [`WorkerEvent`] that
+//! carries the status bar message and the GIDs it was about, so the UI can
+//! show a spinner on those rows until the command finishes.
+
+use crate::download_manager::DownloadManager;
+use crate::models::{BatchAction, DownloadOptions};
+use crate::server::browser::{self, BrowserDownload};
+use crate::ui::format_speed;
+use std::sync::Arc;
+use tokio::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
+
+/// A user action that needs the backend
+#[derive(Clone, Debug, PartialEq)]
+pub enum Command {
+    Add(String),
+    AddWithOptions(String, DownloadOptions),
+    AddFromBrowser(BrowserDownload),
+    Pause(String),
+    Resume(String),
+    Remove {
+        gid: String,
+        name: String,
+    },
+    DeleteFile(String),
+    Retry(String),
+    PurgeCompleted,
+    PauseAll,
+    ResumeAll,
+    MoveUp(String),
+    MoveDown(String),
+    FetchSpeedLimits,
+    SetSpeedLimits {
+        download: u64,
+        upload: u64,
+    },
+    SetDownloadLimits {
+        gids: Vec<String>,
+        download: u64,
+        upload: u64,
+    },
+    Batch(BatchAction, Vec<String>),
+    /// Change one of aria2's global options
+    SetOption {
+        name: String,
+        value: String,
+    },
+}
+
+impl Command {
+    /// GIDs of the downloads the command acts on
+    pub fn gids(&self) -> Vec<&str> {
+        match self {
+            Command::Pause(gid)
+            | Command::Resume(gid)
+            | Command::Remove { gid, .. }
+            | Command::DeleteFile(gid)
+            | Command::Retry(gid)
+            | Command::MoveUp(gid)
+            | Command::MoveDown(gid) => vec![gid],
+            Command::Batch(_, gids) | Command::SetDownloadLimits { gids, .. } => {
+                gids.iter().map(|gid| gid.as_str()).collect()
+            }
+            _ => Vec::new(),
+        }
+    }
+}
+
+/// Result of a command, sent back to the UI
+#[derive(Clone, Debug, PartialEq)]
+pub enum WorkerEvent {
+    /// A command finished; `message` goes to the status bar
+    Done {
+        gids: Vec<String>,
+        message: Option<String>,
+    },
+    /// Current global speed limits (download, upload)
+    SpeedLimits(u64, u64),
+}
+
+/// Start the worker, returning the command sender and event receiver
+///
+/// Commands run one at a time in the order they were sent.
+pub fn spawn(
+    download_manager: Arc<DownloadManager>,
+) -> (UnboundedSender<Command>, UnboundedReceiver<WorkerEvent>) {
+    let (command_tx, mut command_rx) = mpsc::unbounded_channel::<Command>();
+    let (event_tx, event_rx) = mpsc::unbounded_channel();
+
+    tokio::spawn(async move {
+        while let Some(command) = command_rx.recv().await {
+            let event = execute(&download_manager, command).await;
+            if event_tx.send(event).is_err() {
+                break; // UI has gone away
+            }
+        }
+    });
+
+    (command_tx, event_rx)
+}
+
+/// Run a single command against the download manager
+async fn execute(download_manager: &DownloadManager, command: Command) -> WorkerEvent {
+    let gids: Vec<String> = command.gids().into_iter().map(String::from).collect();
+
+    let message = match command {
+        Command::Add(url) => match download_manager.add_download(&url).await {
+            Ok(_) => Some("Download added".to_string()),
+            Err(e) => Some(format!("Failed to add download: {}", e)),
+        },
+        Command::AddWithOptions(url, options) => {
+            match download_manager
+                .add_download_with_options(&url, &options)
+                .await
+            {
+                Ok(_) => Some("Download added".to_string()),
+                Err(e) => Some(format!("Failed to add download: {}", e)),
+            }
+        }
+        Command::AddFromBrowser(download) => {
+            match browser::add_downloads(download_manager, &download).await {
+                Ok(_) => Some(format!("Browser: added {}", download.describe())),
+                Err(e) => Some(format!("Browser: failed to add: {}", e)),
+            }
+        }
+        Command::Pause(gid) => download_manager
+            .pause_download(&gid)
+            .await
+            .err()
+            .map(|e| format!("Failed to pause: {}", e)),
+        Command::Resume(gid) => download_manager
+            .resume_download(&gid)
+            .await
+            .err()
+            .map(|e| format!("Failed to resume: {}", e)),
+        Command::Remove { gid, name } => match download_manager.remove_download(&gid).await {
+            Ok(_) => Some(format!("Deleted: {}", name)),
+            Err(e) => Some(format!("Delete failed: {}", e)),
+        },
+        Command::DeleteFile(gid) => match download_manager.delete_file(&gid).await {
+            Ok(msg) => Some(msg),
+            Err(e) => Some(format!("Failed to delete file: {}", e)),
+        },
+        Command::Retry(gid) => match download_manager.retry_download(&gid).await {
+            Ok(_) => Some("Download restarted".to_string()),
+            Err(e) => Some(format!("Failed to retry: {}", e)),
+        },
+        Command::PurgeCompleted => match download_manager.purge_completed().await {
+            Ok(count) => Some(format!("Purged {} completed download(s)", count)),
+            Err(e) => Some(format!("Purge failed: {}", e)),
+        },
+        Command::PauseAll => match download_manager.pause_all().await {
+            Ok(_) => Some("All downloads paused".to_string()),
+            Err(e) => Some(format!("Failed to pause all: {}", e)),
+        },
+        Command::ResumeAll => match download_manager.resume_all().await {
+            Ok(_) => Some("All downloads resumed".to_string()),
+            Err(e) => Some(format!("Failed to resume all: {}", e)),
+        },
+        Command::MoveUp(gid) => download_manager
+            .move_up(&gid)
+            .await
+            .err()
+            .map(|e| e.to_string()),
+        Command::MoveDown(gid) => download_manager
+            .move_down(&gid)
+            .await
+            .err()
+            .map(|e| e.to_string()),
+        Command::FetchSpeedLimits => {
+            return match download_manager.get_speed_limits().await {
+                Ok((download, upload)) => WorkerEvent::SpeedLimits(download, upload),
+                Err(e) => WorkerEvent::Done {
+                    gids,
+                    message: Some(format!("Failed to read speed limits: {}", e)),
+                },
+            };
+        }
+        Command::Batch(action, gids) => match download_manager.run_batch(action, &gids).await {
+            Ok(result) => Some(result.summary(action)),
+            Err(e) => Some(format!("{} failed: {}", action.past_tense(), e)),
+        },
+        Command::SetSpeedLimits { download, upload } => {
+            let mut result = download_manager
+                .set_download_speed_limit(download)
+                .await
+                .map_err(|e| e.to_string());
+            if result.is_ok() {
+                result = download_manager
+                    .set_upload_speed_limit(upload)
+                    .await
+                    .map_err(|e| e.to_string());
+            }
+            match result {
+                Ok(_) => Some(format!(
+                    "Speed limits set: D:{} U:{}",
+                    format_limit(download),
+                    format_limit(upload)
+                )),
+                Err(e) => Some(format!("Failed to set speed limits: {}", e)),
+            }
+        }
+        Command::SetOption { name, value } => {
+            match download_manager.set_global_option(&name, &value).await {
+                Ok(_) => Some(format!("Set {} = {}", name, value)),
+                Err(e) => Some(format!("Failed to set {}: {}", name, e)),
+            }
+        }
+        Command::SetDownloadLimits {
+            gids: targets,
+            download,
+            upload,
+        } => {
+            let limits = format!("D:{} U:{}", format_cap(download), format_cap(upload));
+            match download_manager
+                .set_download_limits(&targets, download, upload)
+                .await
+            {
+                Ok(result) if result.failed == 0 => {
+                    Some(format!("Download limits set: {}", limits))
+                }
+                Ok(result) => Some(format!(
+                    "Download limits set: {} ({} failed)",
+                    limits, result.failed
+                )),
+                Err(e) => Some(format!("Failed to set download limits: {}", e)),
+            }
+        }
+    };
+
+    WorkerEvent::Done { gids, message }
+}
+
+fn format_limit(limit: u64) -> String {
+    if limit == 0 {
+        "Unlimited".to_string()
+    } else {
+        format_speed(limit)
+    }
+}
+
+/// Per-download limits fall back to the global ones when unset
+fn format_cap(limit: u64) -> String {
+    if limit == 0 {
+        "Global".to_string()
+    } else {
+        format_speed(limit)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_command_gids() {
+        assert_eq!(Command::Pause("abc".to_string()).gids(), vec!["abc"]);
+        assert_eq!(
+            Command::Remove {
+                gid: "abc".to_string(),
+                name: "file.zip".to_string()
+            }
+            .gids(),
+            vec!["abc"]
+        );
+        assert_eq!(
+            Command::Batch(BatchAction::Pause, vec!["a".to_string(), "b".to_string()]).gids(),
+            vec!["a", "b"]
+        );
+        assert!(Command::Add("https://a.com/x".to_string())
+            .gids()
+            .is_empty());
+        assert!(Command::PauseAll.gids().is_empty());
+        assert_eq!(
+            Command::SetDownloadLimits {
+                gids: vec!["a".to_string()],
+                download: 1024,
+                upload: 0
+            }
+            .gids(),
+            vec!["a"]
+        );
+    }
+
+    #[test]
+    fn test_format_limit() {
+        assert_eq!(format_limit(0), "Unlimited");
+        assert_eq!(format_limit(1024), format_speed(1024));
+    }
+}